@@ -0,0 +1,320 @@
+//! Provides structures to parse Cargo JSON data
+
+use crate::{
+	config::LintLevels,
+	github::{Annotation, SortOrder},
+};
+use std::{
+	borrow::Cow,
+	fmt::{self, Write as FmtWrite},
+	io::{self, Write as IoWrite},
+	path::Path,
+};
+
+/// Options of the parsers and summary writers
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+	/// Whether to emit notices at the locations of the attached diagnostics (e.g. notes or suggestions)
+	pub child_notices: bool,
+	/// Whether to annotate the wrap-up diagnostics (e.g. `aborting due to 2 previous errors`)
+	pub meta_diagnostics: bool,
+	/// Whether to write the diagnostics as rendered by rustc in the summary
+	pub summary_snippets: bool,
+	/// Whether to draw charts of the diagnostics in the summary
+	pub summary_charts: bool,
+	/// Style of the messages of the annotations of rustc's diagnostics
+	pub message_style: MessageStyle,
+	/// Order of the diagnostics in the summary (the order of the output if absent)
+	pub sort_order: Option<SortOrder>,
+	/// Kinds of annotation to emit instead of others, and lints whose diagnostics are neither annotated nor summarized
+	pub lint_levels: LintLevels,
+	/// Workspace whose packages are compiled
+	///
+	/// The diagnostics of the other packages (e.g. dependencies) are only summarized,
+	/// and the paths of the diagnostics are resolved against the root of the workspace.
+	/// All the diagnostics are annotated if it is absent.
+	pub workspace: Option<Metadata>,
+}
+
+/// Converts this struct into a [`Vec<Annotation>`] and a [`Vec<Summary>`]
+pub trait HandleMessage<'m> {
+	/// Type used to store summary data
+	type Summary;
+
+	/// Converts `self` into a list of [`Annotation`]
+	fn into_annotations(self, options: &Options) -> Vec<Annotation<'m>>;
+
+	/// Returns the message as rendered by the tool, possibly with colors
+	///
+	/// The default implementation returns [`None`], in which case the messages of the annotations are printed in the logs instead.
+	#[inline]
+	fn rendered(&self) -> Option<&str> {
+		None
+	}
+
+	#[allow(unused_variables)]
+	/// Extracts summaries
+	///
+	/// The default implementation returns no summary.
+	#[inline]
+	fn summarize(&self, options: &Options) -> Vec<Self::Summary> {
+		Vec::new()
+	}
+}
+
+/// Enables types to be written as job summaries
+pub trait SummaryWriter: Sized {
+	/// Type used to store summary data
+	type Summary;
+
+	/// Writes the given `summary`
+	///
+	/// # Errors
+	/// This function returns an error if the summary could not be written.
+	fn write_summary(&mut self, summary: Self::Summary, content: &mut dyn FmtWrite) -> fmt::Result;
+
+	#[allow(unused_variables)]
+	/// Writes the preamble
+	///
+	/// This function is meant to be called after all calls to [`write_summary`](Self::write_summary).
+	///
+	/// # Errors
+	/// This function returns an error if the preamble could not be written.
+	#[inline]
+	fn write_preamble(&self, file: &mut dyn IoWrite, options: &Options) -> io::Result<()> {
+		Ok(())
+	}
+
+	#[allow(unused_variables)]
+	/// Writes the "postamble"
+	///
+	/// This function is meant to be called after all calls to [`write_summary`](Self::write_summary).
+	///
+	/// # Errors
+	/// This function returns an error if the postamble could not be written.
+	#[inline]
+	fn write_postamble(self, file: &mut dyn IoWrite, options: &Options) -> io::Result<()> {
+		Ok(())
+	}
+}
+impl SummaryWriter for () {
+	type Summary = ();
+
+	#[inline]
+	fn write_summary(
+		&mut self,
+		_summary: Self::Summary,
+		_content: &mut dyn FmtWrite,
+	) -> fmt::Result {
+		Ok(())
+	}
+}
+
+/// Removes the ANSI escape sequences (e.g. colors) from `text`
+pub fn strip_ansi(text: &str) -> Cow<'_, str> {
+	if !text.contains('\x1b') {
+		return Cow::Borrowed(text);
+	}
+	let mut stripped = String::with_capacity(text.len());
+	let mut chars = text.chars();
+	while let Some(c) = chars.next() {
+		if c != '\x1b' {
+			stripped.push(c);
+			continue;
+		}
+		match chars.next() {
+			// Control sequences end with a byte in the range `@`..=`~`
+			Some('[') => {
+				for c in chars.by_ref() {
+					if ('@'..='~').contains(&c) {
+						break;
+					}
+				}
+			}
+			// Operating system commands (e.g. hyperlinks) end with BEL or ST (`ESC \`)
+			Some(']') => {
+				while let Some(c) = chars.next() {
+					if c == '\x07' {
+						break;
+					}
+					if c == '\x1b' {
+						chars.next();
+						break;
+					}
+				}
+			}
+			// Character sets are designated by a single byte (e.g. `ESC ( B`, output by the `term` crate)
+			Some('(' | ')') => {
+				chars.next();
+			}
+			_ => {}
+		}
+	}
+	Cow::Owned(stripped)
+}
+
+/// Errors of Cargo caused by the network or the registry, which may not happen again
+const SPURIOUS_FAILURES: &[&str] = &[
+	"spurious network error",
+	"failed to download",
+	"failed to fetch",
+	"failed to update registry",
+	"failed to query replaced source registry",
+	"failed to load source for dependency",
+	"download of config.json failed",
+	"HTTP status server error",
+	"Couldn't resolve host",
+	"Connection reset",
+	"Timeout was reached",
+];
+
+/// Returns `true` if the line of the standard error of Cargo tells of a failure caused by the network or the registry
+pub fn is_spurious_failure(line: &str) -> bool {
+	let line = strip_ansi(line);
+	SPURIOUS_FAILURES
+		.iter()
+		.any(|pattern| line.contains(pattern))
+}
+
+/// Returns `path` relative to the root of the repository
+///
+/// Relative paths are resolved against the current directory.
+#[inline]
+fn relative_path(path: &Path) -> String {
+	crate::path::repository_path(path, None)
+}
+
+mod audit;
+mod bench;
+mod coverage;
+mod custom;
+mod deny;
+mod fix;
+mod geiger;
+mod hack;
+mod machete;
+mod manifest;
+mod merge;
+mod message;
+mod metadata;
+mod miri;
+mod msrv;
+mod outdated;
+mod rustc;
+mod rustdoc;
+mod rustfmt;
+mod semver;
+mod test;
+mod udeps;
+
+pub use self::rustfmt::*;
+pub use audit::*;
+pub use bench::*;
+pub use coverage::*;
+pub use custom::*;
+pub use deny::*;
+pub use fix::*;
+pub use geiger::*;
+pub use hack::*;
+pub use machete::*;
+pub use merge::*;
+pub use message::*;
+pub use metadata::*;
+pub use miri::*;
+pub use msrv::*;
+pub use outdated::*;
+pub use rustc::*;
+pub use rustdoc::*;
+pub use semver::*;
+pub use test::*;
+pub use udeps::*;
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+	use std::{env, fs, path::PathBuf, process};
+
+	/// Temporary workspace of a single package `lintfix`, removed when dropped
+	pub(crate) struct Workspace(pub(crate) Metadata);
+	impl Workspace {
+		/// Creates the workspace `name` containing the `files` (path and content)
+		pub(crate) fn new(name: &str, files: &[(&str, &str)]) -> Self {
+			let root = env::temp_dir().join(format!("ghannotate-{name}-{}", process::id()));
+			for (path, content) in files {
+				let path = root.join(path);
+				fs::create_dir_all(path.parent().unwrap()).unwrap();
+				fs::write(path, content).unwrap();
+			}
+			Self(Metadata {
+				packages: vec![Package {
+					name: "lintfix".to_owned(),
+					manifest_path: root.join("Cargo.toml"),
+					rust_version: None,
+					targets: Vec::new(),
+				}],
+				workspace_members: vec!["path+file:///lintfix#0.1.0".to_owned()],
+				target_directory: root.join("target"),
+				workspace_root: root,
+			})
+		}
+
+		/// Returns the path to the `file` of the workspace
+		pub(crate) fn path(&self, file: &str) -> PathBuf {
+			self.0.workspace_root.join(file)
+		}
+
+		/// Returns the annotated path of the `file` of the workspace
+		pub(crate) fn annotated_path(&self, file: &str) -> String {
+			relative_path(&self.path(file))
+		}
+	}
+	impl Drop for Workspace {
+		fn drop(&mut self) {
+			let _ = fs::remove_dir_all(&self.0.workspace_root);
+		}
+	}
+
+	/// Writes the summary of the `messages` with a default `W`, as in the job summary
+	pub(crate) fn summary<'m, M, W>(messages: &[M], options: &Options) -> String
+	where
+		M: HandleMessage<'m>,
+		W: SummaryWriter<Summary = M::Summary> + Default,
+	{
+		let mut writer = W::default();
+		let mut content = String::new();
+		for message in messages {
+			for summary in message.summarize(options) {
+				writer.write_summary(summary, &mut content).unwrap();
+			}
+		}
+		let mut summary = Vec::new();
+		writer.write_preamble(&mut summary, options).unwrap();
+		summary.extend_from_slice(content.as_bytes());
+		writer.write_postamble(&mut summary, options).unwrap();
+		String::from_utf8(summary).unwrap()
+	}
+
+	#[test]
+	fn ansi() {
+		assert!(matches!(
+			strip_ansi("plain text"),
+			Cow::Borrowed("plain text")
+		));
+		// Control sequence (colors)
+		assert_eq!(
+			strip_ansi("\x1b[0m\x1b[1m\x1b[33mwarning\x1b[0m: unused"),
+			"warning: unused"
+		);
+		// Operating system commands (hyperlinks) ended by BEL and by ST
+		assert_eq!(
+			strip_ansi("see \x1b]8;;https://example.com\x07the docs\x1b]8;;\x07"),
+			"see the docs"
+		);
+		assert_eq!(
+			strip_ansi("see \x1b]8;;https://example.com\x1b\\the docs\x1b]8;;\x1b\\"),
+			"see the docs"
+		);
+		// Character set designation of the `term` crate
+		assert_eq!(strip_ansi("\x1b(B\x1b[mwarning"), "warning");
+	}
+}
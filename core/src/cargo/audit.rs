@@ -0,0 +1,463 @@
+//! Provides the structs to work with cargo-audit's output
+
+use super::{HandleMessage, Metadata, Options, SummaryWriter};
+use crate::github::{Annotation, AnnotationKind};
+use serde::Deserialize;
+use std::{
+	borrow::Cow,
+	fmt::{self, Write as FmtWrite},
+	io::{self, Write as IoWrite},
+};
+
+/// Message output by cargo-audit
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuditReport {
+	/// Vulnerabilities found in the dependencies
+	pub vulnerabilities: Vulnerabilities,
+	/// Informational advisories found in the dependencies
+	#[serde(default)]
+	pub warnings: Warnings,
+}
+impl AuditReport {
+	/// Locates the declarations of the vulnerable dependencies in the workspace
+	pub fn locate(self, metadata: &Metadata) -> LocatedAuditReport {
+		let advisories = self
+			.vulnerabilities
+			.list
+			.into_iter()
+			.map(|vulnerability| (AnnotationKind::Error, vulnerability))
+			.chain(
+				[
+					self.warnings.unmaintained,
+					self.warnings.unsound,
+					self.warnings.yanked,
+					self.warnings.notice,
+				]
+				.into_iter()
+				.flatten()
+				.map(|warning| (AnnotationKind::Warning, warning)),
+			)
+			.map(|(kind, finding)| LocatedAdvisory {
+				kind,
+				location: metadata.locate_package(&finding.package.name, &finding.package.version),
+				finding,
+			})
+			.collect();
+		LocatedAuditReport { advisories }
+	}
+}
+
+/// Vulnerabilities found by cargo-audit
+#[derive(Debug, Clone, Deserialize)]
+pub struct Vulnerabilities {
+	/// List of vulnerabilities
+	pub list: Vec<Finding>,
+}
+
+/// Informational advisories found by cargo-audit
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Warnings {
+	/// Unmaintained dependencies
+	#[serde(default)]
+	pub unmaintained: Vec<Finding>,
+	/// Unsound dependencies
+	#[serde(default)]
+	pub unsound: Vec<Finding>,
+	/// Yanked dependencies
+	#[serde(default)]
+	pub yanked: Vec<Finding>,
+	/// Dependencies with a notice
+	#[serde(default)]
+	pub notice: Vec<Finding>,
+}
+
+/// Advisory concerning a dependency
+#[derive(Debug, Clone, Deserialize)]
+pub struct Finding {
+	/// The advisory (absent for yanked dependencies)
+	pub advisory: Option<Advisory>,
+	/// Affected versions
+	pub versions: Option<AdvisoryVersions>,
+	/// Affected package
+	pub package: AuditedPackage,
+	/// Kind of warning
+	pub kind: Option<String>,
+}
+
+/// [RustSec](https://rustsec.org) advisory
+#[derive(Debug, Clone, Deserialize)]
+pub struct Advisory {
+	/// Identifier of the advisory (e.g. `RUSTSEC-2020-0071`)
+	pub id: String,
+	/// Title of the advisory
+	pub title: String,
+	/// Description of the advisory
+	pub description: String,
+	/// CVSS vector of the advisory
+	pub cvss: Option<String>,
+	/// URL with more information
+	pub url: Option<String>,
+}
+
+/// Versions affected by an advisory
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdvisoryVersions {
+	/// Version requirements of the patched versions
+	pub patched: Vec<String>,
+}
+
+/// Package audited by cargo-audit
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuditedPackage {
+	/// Name of the package
+	pub name: String,
+	/// Version of the package
+	pub version: String,
+}
+
+/// [`AuditReport`] whose advisories have been located
+#[derive(Debug, Clone)]
+pub struct LocatedAuditReport {
+	/// Located advisories
+	advisories: Vec<LocatedAdvisory>,
+}
+impl<'m> HandleMessage<'m> for LocatedAuditReport {
+	type Summary = AdvisorySummary;
+
+	fn into_annotations(self, _options: &Options) -> Vec<Annotation<'m>> {
+		self.advisories
+			.into_iter()
+			.filter_map(|located| {
+				let (file, line) = located.location?;
+				let package = located.finding.package;
+				let code = located
+					.finding
+					.advisory
+					.as_ref()
+					.map(|advisory| Cow::Owned(advisory.id.clone()));
+				let (title, message) = match located.finding.advisory {
+					Some(advisory) => (
+						format!("{}: {}", advisory.id, advisory.title),
+						format!(
+							"`{}` {} is affected by {}\n\n{}",
+							package.name, package.version, advisory.id, advisory.description
+						),
+					),
+					None => (
+						format!(
+							"Dependency {}",
+							located.finding.kind.as_deref().unwrap_or("warning")
+						),
+						format!(
+							"`{}` {} is {}",
+							package.name,
+							package.version,
+							located
+								.finding
+								.kind
+								.as_deref()
+								.unwrap_or("affected by a warning")
+						),
+					),
+				};
+				Some(Annotation {
+					kind: located.kind,
+					file: Cow::Owned(file),
+					line,
+					end_line: None,
+					col: None,
+					end_column: None,
+					code,
+					suggestions: Vec::new(),
+					title: Some(Cow::Owned(title)),
+					message: Cow::Owned(message),
+				})
+			})
+			.collect()
+	}
+
+	fn summarize(&self, _options: &Options) -> Vec<Self::Summary> {
+		self.advisories
+			.iter()
+			.map(|located| {
+				let advisory = located.finding.advisory.as_ref();
+				AdvisorySummary {
+					kind: located.kind,
+					id: advisory.map(|advisory| match &advisory.url {
+						Some(url) => format!("[{}]({url})", advisory.id),
+						None => format!(
+							"[{0}](https://rustsec.org/advisories/{0}.html)",
+							advisory.id
+						),
+					}),
+					package: format!(
+						"`{}` {}",
+						located.finding.package.name, located.finding.package.version
+					),
+					severity: advisory
+						.and_then(|advisory| advisory.cvss.as_deref())
+						.and_then(cvss_severity)
+						.map(ToOwned::to_owned)
+						.or_else(|| located.finding.kind.clone()),
+					patched: located
+						.finding
+						.versions
+						.as_ref()
+						.map(|versions| versions.patched.join(", "))
+						.unwrap_or_default(),
+				}
+			})
+			.collect()
+	}
+}
+
+/// [`Finding`] with the location of the declaration of the affected package
+#[derive(Debug, Clone)]
+struct LocatedAdvisory {
+	/// The advisory
+	finding: Finding,
+	/// Kind of annotation to emit
+	kind: AnnotationKind,
+	/// Location of the declaration of the affected package
+	location: Option<(String, usize)>,
+}
+
+/// Computes the severity of a CVSS v3 vector
+fn cvss_severity(vector: &str) -> Option<&'static str> {
+	let mut metrics = vector
+		.split('/')
+		.filter_map(|metric| metric.split_once(':'));
+	if !metrics.next()?.1.starts_with('3') {
+		return None;
+	}
+	let metrics: Vec<_> = metrics.collect();
+	let metric = |name: &str| {
+		metrics
+			.iter()
+			.find_map(|(key, value)| (*key == name).then_some(*value))
+	};
+	let scope_changed = metric("S")? == "C";
+	let impact = |name: &str| match metric(name) {
+		Some("H") => Some(0.56),
+		Some("L") => Some(0.22),
+		Some("N") => Some(0.0),
+		_ => None,
+	};
+	let attack_vector = match metric("AV")? {
+		"N" => 0.85,
+		"A" => 0.62,
+		"L" => 0.55,
+		"P" => 0.2,
+		_ => return None,
+	};
+	let attack_complexity = match metric("AC")? {
+		"L" => 0.77,
+		"H" => 0.44,
+		_ => return None,
+	};
+	let privileges_required = match (metric("PR")?, scope_changed) {
+		("N", _) => 0.85,
+		("L", false) => 0.62,
+		("L", true) => 0.68,
+		("H", false) => 0.27,
+		("H", true) => 0.5,
+		_ => return None,
+	};
+	let user_interaction = match metric("UI")? {
+		"N" => 0.85,
+		"R" => 0.62,
+		_ => return None,
+	};
+
+	let unimpacted = (1.0 - impact("C")?) * (1.0 - impact("I")?) * (1.0 - impact("A")?);
+	let impact_sub_score = 1.0 - unimpacted;
+	let impact = if scope_changed {
+		7.52f64.mul_add(
+			impact_sub_score - 0.029,
+			-3.25 * (impact_sub_score - 0.02).powi(15),
+		)
+	} else {
+		6.42 * impact_sub_score
+	};
+	let exploitability =
+		8.22 * attack_vector * attack_complexity * privileges_required * user_interaction;
+	let score = if impact <= 0.0 {
+		0.0
+	} else if scope_changed {
+		(1.08 * (impact + exploitability)).min(10.0)
+	} else {
+		(impact + exploitability).min(10.0)
+	};
+	// Round up to one decimal
+	let score = score.mul_add(10.0, -1e-6).ceil() / 10.0;
+
+	Some(if score <= 0.0 {
+		"None"
+	} else if score < 4.0 {
+		"Low"
+	} else if score < 7.0 {
+		"Medium"
+	} else if score < 9.0 {
+		"High"
+	} else {
+		"Critical"
+	})
+}
+
+/// Summary info of [`Finding`]
+#[derive(Debug, Clone)]
+pub struct AdvisorySummary {
+	/// Kind of annotation
+	kind: AnnotationKind,
+	/// Link to the advisory
+	id: Option<String>,
+	/// Affected package
+	package: String,
+	/// Severity of the advisory
+	severity: Option<String>,
+	/// Patched versions
+	patched: String,
+}
+
+/// [`SummaryWriter`] for [`AdvisorySummary`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AdvisorySummaryWriter {
+	/// Counter of vulnerabilities
+	vulnerabilities: usize,
+	/// Counter of warnings
+	warnings: usize,
+}
+impl SummaryWriter for AdvisorySummaryWriter {
+	type Summary = AdvisorySummary;
+
+	fn write_summary(&mut self, summary: Self::Summary, content: &mut dyn FmtWrite) -> fmt::Result {
+		if summary.kind == AnnotationKind::Error {
+			self.vulnerabilities += 1;
+		} else {
+			self.warnings += 1;
+		}
+		writeln!(
+			content,
+			"|{}|{}|{}|{}|{}|",
+			summary.kind,
+			summary.id.unwrap_or_default(),
+			summary.package,
+			summary.severity.unwrap_or_default(),
+			summary.patched
+		)
+	}
+
+	fn write_preamble(&self, file: &mut dyn IoWrite, _options: &Options) -> io::Result<()> {
+		writeln!(
+			file,
+			"> **TOTAL:** {} vulnerabilities, {} warnings\n",
+			self.vulnerabilities, self.warnings
+		)?;
+		writeln!(file, "|Level|Advisory|Package|Severity|Patched versions|")?;
+		writeln!(file, "|:--|:--|:--|:--|:--|")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::cargo::tests::{summary, Workspace};
+	use std::slice;
+
+	/// Report of cargo-audit, with a vulnerability, an unmaintained and a yanked dependency
+	const REPORT: &str = r#"{"database":{"advisory-count":872,"last-commit":"a4e8d1e3ed9b5b5d5ed9e4c5c1d3b2c24c1f4e61","last-updated":"2024-06-01T12:00:00Z"},"lockfile":{"dependency-count":6},"settings":{"target_arch":[],"target_os":[],"severity":null,"ignore":[],"informational_warnings":["unmaintained","unsound","notice"]},"vulnerabilities":{"found":true,"count":1,"list":[{"advisory":{"id":"RUSTSEC-2020-0071","package":"time","title":"Potential segfault in the time crate","description":"Unix-like operating systems may segfault due to dereferencing a dangling pointer in specific circumstances.","date":"2020-11-18","aliases":["CVE-2020-26235","GHSA-wcg3-cvx6-7396"],"related":[],"collection":"crates","categories":["code-execution","memory-corruption"],"keywords":["segfault"],"cvss":"CVSS:3.1/AV:L/AC:H/PR:N/UI:N/S:U/C:N/I:N/A:H","informational":null,"references":[],"source":null,"url":"https://github.com/time-rs/time/issues/293","withdrawn":null,"license":"CC0-1.0"},"versions":{"patched":[">=0.2.23"],"unaffected":["=0.2.0","=0.2.1","=0.2.2","=0.2.3","=0.2.4","=0.2.5","=0.2.6"]},"affected":{"arch":[],"os":[],"functions":{"time::at_utc":["<0.2.23"]}},"package":{"name":"time","version":"0.1.45","source":"registry+https://github.com/rust-lang/crates.io-index","checksum":"1b797afad3f312d1c66a56d11d0316f916356d11bd158fbc6ca6389ff6bf805a","dependencies":[{"name":"libc","version":"0.2.155","source":"registry+https://github.com/rust-lang/crates.io-index"}],"replace":null}}]},"warnings":{"unmaintained":[{"kind":"unmaintained","package":{"name":"atty","version":"0.2.14","source":"registry+https://github.com/rust-lang/crates.io-index","checksum":"d9b39be18770d11421cdb1b9947a45dd3f37e93092cbf377614828a319d5fee8","dependencies":[{"name":"libc","version":"0.2.155","source":"registry+https://github.com/rust-lang/crates.io-index"}],"replace":null},"advisory":{"id":"RUSTSEC-2024-0375","package":"atty","title":"`atty` is unmaintained","description":"The maintainer of `atty` has published an official notice that the crate is no longer under development.","date":"2024-09-25","aliases":[],"related":[],"collection":"crates","categories":[],"keywords":["terminal","tty"],"cvss":null,"informational":"unmaintained","references":[],"source":null,"url":null,"withdrawn":null,"license":"CC0-1.0"},"affected":null,"versions":{"patched":[],"unaffected":[]}}],"yanked":[{"kind":"yanked","package":{"name":"hermit-abi","version":"0.1.19","source":"registry+https://github.com/rust-lang/crates.io-index","checksum":"62b467343b94ba476dcb2500d242dadbb39557df889310ac77c5d99100aaac33","dependencies":[],"replace":null},"advisory":null,"affected":null,"versions":null}]}}"#;
+
+	#[test]
+	fn advisories() {
+		let workspace = Workspace::new(
+			"audit",
+			&[
+				(
+					"Cargo.toml",
+					"[package]\nname = \"lintfix\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\ntime = \"0.1\"\n",
+				),
+				(
+					"Cargo.lock",
+					"# This file is automatically @generated by Cargo.\n# It is not intended for manual editing.\nversion = 4\n\n[[package]]\nname = \"atty\"\nversion = \"0.2.14\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\nchecksum = \"d9b39be18770d11421cdb1b9947a45dd3f37e93092cbf377614828a319d5fee8\"\ndependencies = [\n \"hermit-abi\",\n \"libc\",\n]\n\n[[package]]\nname = \"hermit-abi\"\nversion = \"0.1.19\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\nchecksum = \"62b467343b94ba476dcb2500d242dadbb39557df889310ac77c5d99100aaac33\"\n",
+				),
+			],
+		);
+		let report = serde_json::from_str::<AuditReport>(REPORT)
+			.unwrap()
+			.locate(&workspace.0);
+		let options = Options::default();
+		let manifest = workspace.annotated_path("Cargo.toml");
+		let lockfile = workspace.annotated_path("Cargo.lock");
+		assert_eq!(
+			summary::<_, AdvisorySummaryWriter>(slice::from_ref(&report), &options),
+			"> **TOTAL:** 1 vulnerabilities, 2 warnings
+
+|Level|Advisory|Package|Severity|Patched versions|
+|:--|:--|:--|:--|:--|
+|:x: Error|[RUSTSEC-2020-0071](https://github.com/time-rs/time/issues/293)|`time` 0.1.45|Medium|>=0.2.23|
+|:warning: Warning|[RUSTSEC-2024-0375](https://rustsec.org/advisories/RUSTSEC-2024-0375.html)|`atty` 0.2.14|unmaintained||
+|:warning: Warning||`hermit-abi` 0.1.19|yanked||
+"
+		);
+		let annotations: Vec<_> = report
+			.into_annotations(&options)
+			.into_iter()
+			.map(|annotation| {
+				(
+					annotation.kind,
+					format!("{}:{}", annotation.file, annotation.line),
+					annotation.code,
+					annotation.title.unwrap(),
+					annotation.message,
+				)
+			})
+			.collect();
+		assert_eq!(
+			annotations,
+			[
+				(
+					AnnotationKind::Error,
+					format!("{manifest}:7"),
+					Some(Cow::Borrowed("RUSTSEC-2020-0071")),
+					Cow::Borrowed("RUSTSEC-2020-0071: Potential segfault in the time crate"),
+					Cow::Borrowed("`time` 0.1.45 is affected by RUSTSEC-2020-0071\n\nUnix-like operating systems may segfault due to dereferencing a dangling pointer in specific circumstances."),
+				),
+				(
+					AnnotationKind::Warning,
+					format!("{lockfile}:6"),
+					Some(Cow::Borrowed("RUSTSEC-2024-0375")),
+					Cow::Borrowed("RUSTSEC-2024-0375: `atty` is unmaintained"),
+					Cow::Borrowed("`atty` 0.2.14 is affected by RUSTSEC-2024-0375\n\nThe maintainer of `atty` has published an official notice that the crate is no longer under development."),
+				),
+				(
+					AnnotationKind::Warning,
+					format!("{lockfile}:16"),
+					None,
+					Cow::Borrowed("Dependency yanked"),
+					Cow::Borrowed("`hermit-abi` 0.1.19 is yanked"),
+				),
+			]
+		);
+	}
+
+	#[test]
+	fn severity() {
+		assert_eq!(
+			cvss_severity("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"),
+			Some("Critical")
+		);
+		assert_eq!(
+			cvss_severity("CVSS:3.1/AV:N/AC:L/PR:N/UI:R/S:C/C:L/I:L/A:N"),
+			Some("Medium")
+		);
+		assert_eq!(
+			cvss_severity("CVSS:3.0/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:H"),
+			Some("High")
+		);
+		assert_eq!(
+			cvss_severity("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:N"),
+			Some("None")
+		);
+		assert_eq!(cvss_severity("CVSS:2.0/AV:N/AC:L/Au:N/C:P/I:P/A:P"), None);
+	}
+}
@@ -0,0 +1,364 @@
+//! Provides the structs to work with the results of criterion benchmarks
+
+use super::{relative_path, HandleMessage, Metadata, Options, SummaryWriter};
+use crate::github::{Annotation, AnnotationKind};
+use serde::Deserialize;
+use std::{
+	borrow::Cow,
+	fmt::{self, Write as FmtWrite},
+	fs, io,
+	io::Write as IoWrite,
+	path::{Path, PathBuf},
+	time::{Duration, SystemTime},
+};
+
+/// Returns the directories of the benchmarks measured by criterion since `since`
+///
+/// Criterion stores the results of each benchmark in a directory of `target/criterion`
+/// which contains the `new` measurement and, if a baseline was available, its `change`.
+pub fn criterion_benchmarks(metadata: &Metadata, since: SystemTime) -> Vec<PathBuf> {
+	/// Recursively walks `dir`
+	fn walk(dir: &Path, since: SystemTime, benchmarks: &mut Vec<PathBuf>) {
+		let benchmark = dir.join("new").join("benchmark.json");
+		if fs::metadata(&benchmark)
+			.and_then(|metadata| metadata.modified())
+			.is_ok_and(|modified| modified >= since)
+		{
+			benchmarks.push(dir.to_owned());
+		}
+		let Ok(entries) = fs::read_dir(dir) else {
+			return;
+		};
+		for entry in entries.flatten() {
+			let path = entry.path();
+			if path.is_dir()
+				&& !["new", "base", "change", "report"]
+					.iter()
+					.any(|name| path.ends_with(name))
+			{
+				walk(&path, since, benchmarks);
+			}
+		}
+	}
+
+	let mut benchmarks = Vec::new();
+	// File timestamps may be coarser than the system clock
+	let since = since.checked_sub(Duration::from_secs(1)).unwrap_or(since);
+	walk(
+		&metadata.target_directory.join("criterion"),
+		since,
+		&mut benchmarks,
+	);
+	benchmarks.sort();
+	benchmarks
+}
+
+/// Results of a criterion benchmark
+#[derive(Debug, Clone)]
+pub struct BenchmarkResult {
+	/// Identifier of the benchmark
+	benchmark: CriterionBenchmark,
+	/// Estimates of the new measurement
+	estimates: CriterionEstimates,
+	/// Estimates of the change relatively to the baseline
+	change: Option<CriterionEstimates>,
+}
+impl BenchmarkResult {
+	/// Loads the results stored in the directory `dir`
+	///
+	/// # Errors
+	/// This function returns an error if the results could not be read or are invalid.
+	pub fn load(dir: &Path) -> io::Result<Self> {
+		/// Reads and parses the JSON file `dir/name`
+		fn read<T: for<'de> Deserialize<'de>>(dir: &Path, name: &str) -> io::Result<T> {
+			serde_json::from_slice(&fs::read(dir.join(name))?).map_err(io::Error::from)
+		}
+
+		Ok(Self {
+			benchmark: read(dir, "new/benchmark.json")?,
+			estimates: read(dir, "new/estimates.json")?,
+			change: read(dir, "change/estimates.json").ok(),
+		})
+	}
+
+	/// Locates the definition of the benchmark in the `benches` directories of the workspace
+	///
+	/// Benchmarks exceeding the `threshold` (in percents) are regressions.
+	pub fn locate(self, metadata: &Metadata, threshold: f64) -> LocatedBenchmarkResult {
+		let literal = format!("\"{}\"", self.benchmark.group_id);
+		let location = metadata.packages.iter().find_map(|package| {
+			let benches = package.manifest_path.parent()?.join("benches");
+			find_literal(&benches, &literal)
+		});
+		let regression = self
+			.change
+			.as_ref()
+			.is_some_and(|change| change.mean.point_estimate * 100.0 > threshold);
+		LocatedBenchmarkResult {
+			result: self,
+			regression,
+			location,
+		}
+	}
+}
+
+/// Finds the first line containing `literal` in the Rust files of `dir`
+fn find_literal(dir: &Path, literal: &str) -> Option<(String, usize)> {
+	let mut entries: Vec<_> = fs::read_dir(dir)
+		.ok()?
+		.flatten()
+		.map(|entry| entry.path())
+		.collect();
+	entries.sort();
+	entries.into_iter().find_map(|path| {
+		if path.is_dir() {
+			find_literal(&path, literal)
+		} else if path.extension().is_some_and(|extension| extension == "rs") {
+			let content = fs::read_to_string(&path).ok()?;
+			content
+				.lines()
+				.position(|line| line.contains(literal))
+				.map(|index| (relative_path(&path), index + 1))
+		} else {
+			None
+		}
+	})
+}
+
+/// Identifier of a criterion benchmark
+#[derive(Debug, Clone, Deserialize)]
+struct CriterionBenchmark {
+	/// Name of the benchmark group (or of the benchmark itself)
+	group_id: String,
+	/// Full identifier of the benchmark
+	full_id: String,
+}
+
+/// Statistical estimates computed by criterion
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct CriterionEstimates {
+	/// Estimate of the mean
+	mean: CriterionEstimate,
+}
+
+/// Statistical estimate
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct CriterionEstimate {
+	/// Point estimate (in nanoseconds for measurements, as a ratio for changes)
+	point_estimate: f64,
+}
+
+/// [`BenchmarkResult`] whose definition has been located
+#[derive(Debug, Clone)]
+pub struct LocatedBenchmarkResult {
+	/// The results
+	result: BenchmarkResult,
+	/// Whether the benchmark has regressed beyond the threshold
+	regression: bool,
+	/// Location of the definition of the benchmark
+	location: Option<(String, usize)>,
+}
+impl<'m> HandleMessage<'m> for LocatedBenchmarkResult {
+	type Summary = BenchmarkSummary;
+
+	fn into_annotations(self, _options: &Options) -> Vec<Annotation<'m>> {
+		let (Some(change), true, Some((file, line))) =
+			(self.result.change, self.regression, self.location)
+		else {
+			return Vec::new();
+		};
+		vec![Annotation {
+			kind: AnnotationKind::Warning,
+			file: Cow::Owned(file),
+			line,
+			end_line: None,
+			col: None,
+			end_column: None,
+			code: None,
+			suggestions: Vec::new(),
+			title: Some(Cow::Borrowed("Benchmark regression")),
+			message: Cow::Owned(format!(
+				"`{}` has slowed down by {:.2}% (mean time: {})",
+				self.result.benchmark.full_id,
+				change.mean.point_estimate * 100.0,
+				format_duration(self.result.estimates.mean.point_estimate)
+			)),
+		}]
+	}
+
+	#[inline]
+	fn summarize(&self, _options: &Options) -> Vec<Self::Summary> {
+		vec![BenchmarkSummary {
+			id: self.result.benchmark.full_id.clone(),
+			mean: self.result.estimates.mean.point_estimate,
+			change: self
+				.result
+				.change
+				.map(|change| change.mean.point_estimate * 100.0),
+			regression: self.regression,
+		}]
+	}
+}
+
+/// Formats a duration given in nanoseconds
+fn format_duration(nanoseconds: f64) -> String {
+	if nanoseconds < 1e3 {
+		format!("{nanoseconds:.2} ns")
+	} else if nanoseconds < 1e6 {
+		format!("{:.2} µs", nanoseconds / 1e3)
+	} else if nanoseconds < 1e9 {
+		format!("{:.2} ms", nanoseconds / 1e6)
+	} else {
+		format!("{:.2} s", nanoseconds / 1e9)
+	}
+}
+
+/// Summary info of [`BenchmarkResult`]
+#[derive(Debug, Clone)]
+pub struct BenchmarkSummary {
+	/// Full identifier of the benchmark
+	id: String,
+	/// Mean time (in nanoseconds)
+	mean: f64,
+	/// Change of the mean time relatively to the baseline (in percents)
+	change: Option<f64>,
+	/// Whether the benchmark has regressed beyond the threshold
+	regression: bool,
+}
+
+/// [`SummaryWriter`] for [`BenchmarkSummary`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BenchmarkSummaryWriter {
+	/// Counter of benchmarks
+	benchmarks: usize,
+	/// Counter of regressions
+	regressions: usize,
+}
+impl SummaryWriter for BenchmarkSummaryWriter {
+	type Summary = BenchmarkSummary;
+
+	fn write_summary(&mut self, summary: Self::Summary, content: &mut dyn FmtWrite) -> fmt::Result {
+		self.benchmarks += 1;
+		if summary.regression {
+			self.regressions += 1;
+		}
+		writeln!(
+			content,
+			"|{}|`{}`|{}|{}|",
+			if summary.regression {
+				AnnotationKind::Warning.emoji()
+			} else {
+				""
+			},
+			summary.id,
+			format_duration(summary.mean),
+			summary
+				.change
+				.map(|change| format!("{change:+.2}%"))
+				.unwrap_or_default()
+		)
+	}
+
+	fn write_preamble(&self, file: &mut dyn IoWrite, _options: &Options) -> io::Result<()> {
+		writeln!(
+			file,
+			"> **TOTAL:** {} benchmarks, {} regressions\n",
+			self.benchmarks, self.regressions
+		)?;
+		writeln!(file, "| |Benchmark|Mean time|Change|")?;
+		writeln!(file, "|:-:|:--|--:|--:|")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::cargo::tests::{summary, Workspace};
+
+	/// Estimates of a criterion measurement, with a mean of `mean` nanoseconds
+	fn estimates(mean: &str, median: &str) -> String {
+		format!(
+			r#"{{"mean":{{"confidence_interval":{{"confidence_level":0.95,"lower_bound":{mean},"upper_bound":{mean}}},"point_estimate":{mean},"standard_error":0.0}},"median":{{"confidence_interval":{{"confidence_level":0.95,"lower_bound":{median},"upper_bound":{median}}},"point_estimate":{median},"standard_error":0.0}}}}"#
+		)
+	}
+
+	#[test]
+	fn regression() {
+		let fib_estimates = estimates("14723.018", "14690.5");
+		let fib_change = estimates("0.0837", "0.0812");
+		let sort_estimates = estimates("2104816.3", "2098700.0");
+		let workspace = Workspace::new(
+			"bench",
+			&[
+				(
+					"benches/bench.rs",
+					"use criterion::{criterion_group, criterion_main, Criterion};\n\nfn bench(c: &mut Criterion) {\n\tlet mut group = c.benchmark_group(\"fib\");\n\tgroup.bench_function(\"20\", |b| b.iter(|| lintfix::fib(20)));\n\tgroup.finish();\n\tc.bench_function(\"sort\", |b| b.iter(|| lintfix::sort()));\n}\n\ncriterion_group!(benches, bench);\ncriterion_main!(benches);\n",
+				),
+				(
+					"target/criterion/fib/20/new/benchmark.json",
+					r#"{"group_id":"fib","function_id":"20","value_str":null,"throughput":null,"full_id":"fib/20","directory_name":"fib/20","title":"fib/20"}"#,
+				),
+				("target/criterion/fib/20/new/estimates.json", &fib_estimates),
+				("target/criterion/fib/20/change/estimates.json", &fib_change),
+				(
+					"target/criterion/sort/new/benchmark.json",
+					r#"{"group_id":"sort","function_id":null,"value_str":null,"throughput":null,"full_id":"sort","directory_name":"sort","title":"sort"}"#,
+				),
+				("target/criterion/sort/new/estimates.json", &sort_estimates),
+				("target/criterion/report/index.html", "<!DOCTYPE html>"),
+			],
+		);
+		let benchmarks = criterion_benchmarks(&workspace.0, SystemTime::UNIX_EPOCH);
+		assert_eq!(
+			benchmarks,
+			[
+				workspace.path("target/criterion/fib/20"),
+				workspace.path("target/criterion/sort")
+			]
+		);
+		let results: Vec<_> = benchmarks
+			.iter()
+			.map(|dir| {
+				BenchmarkResult::load(dir)
+					.unwrap()
+					.locate(&workspace.0, 5.0)
+			})
+			.collect();
+
+		let options = Options::default();
+		let annotations: Vec<_> = results
+			.iter()
+			.cloned()
+			.flat_map(|result| result.into_annotations(&options))
+			.collect();
+		assert_eq!(
+			annotations,
+			[Annotation {
+				kind: AnnotationKind::Warning,
+				file: Cow::Owned(workspace.annotated_path("benches/bench.rs")),
+				line: 4,
+				end_line: None,
+				col: None,
+				end_column: None,
+				code: None,
+				suggestions: Vec::new(),
+				title: Some(Cow::Borrowed("Benchmark regression")),
+				message: Cow::Borrowed("`fib/20` has slowed down by 8.37% (mean time: 14.72 µs)"),
+			}]
+		);
+		assert_eq!(
+			summary::<_, BenchmarkSummaryWriter>(&results, &options),
+			"> **TOTAL:** 2 benchmarks, 1 regressions
+
+| |Benchmark|Mean time|Change|
+|:-:|:--|--:|--:|
+|:warning:|`fib/20`|14.72 µs|+8.37%|
+||`sort`|2.10 ms||
+"
+		);
+		assert!(results
+			.into_iter()
+			.map(|result| result.result.locate(&workspace.0, 10.0))
+			.all(|result| result.into_annotations(&options).is_empty()));
+	}
+}
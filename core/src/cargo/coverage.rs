@@ -0,0 +1,365 @@
+//! Provides the structs to work with cargo-llvm-cov's output
+
+use super::{relative_path, HandleMessage, Options, SummaryWriter};
+use crate::{
+	git::ChangedLines,
+	github::{Annotation, AnnotationKind},
+};
+use serde::Deserialize;
+use std::{
+	borrow::Cow,
+	fmt::{self, Write as FmtWrite},
+	io::{self, Write as IoWrite},
+	ops::RangeInclusive,
+	path::PathBuf,
+};
+
+/// Coverage report exported by `cargo llvm-cov --json`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CoverageReport {
+	/// Coverage data of each binary
+	pub data: Vec<CoverageData>,
+}
+impl CoverageReport {
+	/// Returns the percentage of covered lines
+	#[inline]
+	pub fn percent(&self) -> Option<f64> {
+		self.data.first().map(|data| data.totals.lines.percent)
+	}
+
+	/// Finds the uncovered regions of the files
+	///
+	/// If `changed_lines` is given, only the regions overlapping with changed lines are kept.
+	pub fn locate(
+		self,
+		changed_lines: Option<&ChangedLines>,
+		fail_under: Option<f64>,
+	) -> LocatedCoverageReport {
+		let mut files = Vec::new();
+		let mut uncovered = Vec::new();
+		let mut total = None;
+		for data in self.data {
+			total.get_or_insert(data.totals.lines);
+			for coverage in data.files {
+				let file = relative_path(&coverage.filename);
+				uncovered.extend(
+					coverage
+						.uncovered_lines()
+						.into_iter()
+						.filter(|lines| {
+							changed_lines
+								.is_none_or(|changed_lines| changed_lines.contains(&file, lines))
+						})
+						.map(|lines| (file.clone(), lines)),
+				);
+				files.push(CoverageSummary::File {
+					file,
+					lines: coverage.summary.lines,
+				});
+			}
+		}
+		files.extend(total.map(|lines| CoverageSummary::Total { lines, fail_under }));
+		LocatedCoverageReport {
+			summaries: files,
+			uncovered,
+		}
+	}
+}
+
+/// Coverage data of a binary
+#[derive(Debug, Clone, Deserialize)]
+pub struct CoverageData {
+	/// Coverage of each file
+	pub files: Vec<FileCoverage>,
+	/// Total coverage
+	pub totals: CoverageTotals,
+}
+
+/// Coverage of a file
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileCoverage {
+	/// Path to the file
+	pub filename: PathBuf,
+	/// Coverage segments (sorted by position)
+	pub segments: Vec<CoverageSegment>,
+	/// Coverage totals of the file
+	pub summary: CoverageTotals,
+}
+impl FileCoverage {
+	/// Returns the ranges of lines which have not been executed
+	fn uncovered_lines(&self) -> Vec<RangeInclusive<usize>> {
+		let mut uncovered: Vec<RangeInclusive<usize>> = Vec::new();
+		let mut start = None;
+		for segment in &self.segments {
+			let is_uncovered = segment.has_count && segment.count == 0 && !segment.is_gap_region;
+			match start {
+				None if is_uncovered => start = Some(segment.line),
+				Some(start_line) if !is_uncovered => {
+					start = None;
+					// A region ending at the start of a line does not cover it
+					let end_line = if segment.col <= 1 {
+						segment.line.saturating_sub(1)
+					} else {
+						segment.line
+					}
+					.max(start_line);
+					match uncovered.last_mut() {
+						Some(last) if *last.end() + 1 >= start_line => {
+							*last = *last.start()..=end_line.max(*last.end());
+						}
+						_ => uncovered.push(start_line..=end_line),
+					}
+				}
+				_ => {}
+			}
+		}
+		uncovered
+	}
+}
+
+/// Coverage segment (`[line, col, count, has_count, is_region_entry, is_gap_region]`)
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct CoverageSegment {
+	/// Line where the segment starts (1-based)
+	line: usize,
+	/// Column where the segment starts (1-based)
+	col: usize,
+	/// Execution count of the segment
+	count: u64,
+	/// Whether the segment has an execution count
+	has_count: bool,
+	/// Whether the segment starts a region
+	_is_region_entry: bool,
+	/// Whether the segment is a gap between regions
+	#[serde(default)]
+	is_gap_region: bool,
+}
+
+/// Coverage totals of a file or binary
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct CoverageTotals {
+	/// Line coverage
+	pub lines: CoverageCount,
+}
+
+/// Coverage count
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct CoverageCount {
+	/// Number of items
+	pub count: usize,
+	/// Number of covered items
+	pub covered: usize,
+	/// Percentage of covered items
+	pub percent: f64,
+}
+
+/// [`CoverageReport`] whose uncovered regions have been found
+#[derive(Debug, Clone)]
+pub struct LocatedCoverageReport {
+	/// Coverage of each file, then the total coverage
+	summaries: Vec<CoverageSummary>,
+	/// Uncovered lines of each file
+	uncovered: Vec<(String, RangeInclusive<usize>)>,
+}
+impl<'m> HandleMessage<'m> for LocatedCoverageReport {
+	type Summary = CoverageSummary;
+
+	fn into_annotations(self, _options: &Options) -> Vec<Annotation<'m>> {
+		self.uncovered
+			.into_iter()
+			.map(|(file, lines)| Annotation {
+				kind: AnnotationKind::Notice,
+				file: Cow::Owned(file),
+				line: *lines.start(),
+				end_line: (lines.end() > lines.start()).then_some(*lines.end()),
+				col: None,
+				end_column: None,
+				code: None,
+				suggestions: Vec::new(),
+				title: Some(Cow::Borrowed("Uncovered code")),
+				message: Cow::Owned(if lines.end() > lines.start() {
+					format!(
+						"Lines {}-{} are not covered by tests",
+						lines.start(),
+						lines.end()
+					)
+				} else {
+					format!("Line {} is not covered by tests", lines.start())
+				}),
+			})
+			.collect()
+	}
+
+	#[inline]
+	fn summarize(&self, _options: &Options) -> Vec<Self::Summary> {
+		self.summaries.clone()
+	}
+}
+
+/// Summary info of [`CoverageReport`]
+#[derive(Debug, Clone)]
+pub enum CoverageSummary {
+	/// Coverage of a file
+	File {
+		/// Path to the file
+		file: String,
+		/// Line coverage
+		lines: CoverageCount,
+	},
+	/// Total coverage
+	Total {
+		/// Line coverage
+		lines: CoverageCount,
+		/// Minimum percentage of covered lines
+		fail_under: Option<f64>,
+	},
+}
+
+/// [`SummaryWriter`] for [`CoverageSummary`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CoverageSummaryWriter {
+	/// Total line coverage and minimum percentage
+	total: Option<(CoverageCount, Option<f64>)>,
+	/// Whether the table header has been written
+	header: bool,
+}
+impl SummaryWriter for CoverageSummaryWriter {
+	type Summary = CoverageSummary;
+
+	fn write_summary(&mut self, summary: Self::Summary, content: &mut dyn FmtWrite) -> fmt::Result {
+		match summary {
+			CoverageSummary::File { file, lines } => {
+				if !self.header {
+					self.header = true;
+					writeln!(content, "|File|Covered lines|Lines|Coverage|")?;
+					writeln!(content, "|:--|--:|--:|--:|")?;
+				}
+				writeln!(
+					content,
+					"|`{file}`|{}|{}|{:.2}%|",
+					lines.covered, lines.count, lines.percent
+				)
+			}
+			CoverageSummary::Total { lines, fail_under } => {
+				self.total = Some((lines, fail_under));
+				Ok(())
+			}
+		}
+	}
+
+	fn write_preamble(&self, file: &mut dyn IoWrite, _options: &Options) -> io::Result<()> {
+		let Some((lines, fail_under)) = self.total else {
+			return Ok(());
+		};
+		writeln!(
+			file,
+			"> **TOTAL:** {:.2}% line coverage ({}/{} lines)\n",
+			lines.percent, lines.covered, lines.count
+		)?;
+		match fail_under {
+			Some(fail_under) if lines.percent < fail_under => {
+				writeln!(file, ":x: Below the threshold of {fail_under}%\n")
+			}
+			Some(fail_under) => {
+				writeln!(
+					file,
+					":white_check_mark: Above the threshold of {fail_under}%\n"
+				)
+			}
+			None => Ok(()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::cargo::tests::summary;
+
+	/// Report of `cargo llvm-cov --json`, with an uncovered function and an uncovered branch
+	const REPORT: &str = r#"{"data":[{"files":[{"branches":[],"expansions":[],"filename":"/home/user/lintfix/src/lib.rs","segments":[[1,35,1,true,true,false],[3,2,0,false,false,false],[5,33,0,true,true,false],[9,2,0,false,false,false],[10,31,1,true,true,false],[11,10,0,true,true,false],[11,26,1,true,false,false],[12,1,0,true,false,true],[13,2,0,false,false,false]],"summary":{"branches":{"count":0,"covered":0,"notcovered":0,"percent":0.0},"functions":{"count":3,"covered":2,"percent":66.66666666666666},"instantiations":{"count":3,"covered":2,"percent":66.66666666666666},"lines":{"count":12,"covered":6,"percent":50.0},"regions":{"count":6,"covered":3,"notcovered":3,"percent":50.0}}}],"functions":[],"totals":{"branches":{"count":0,"covered":0,"notcovered":0,"percent":0.0},"functions":{"count":3,"covered":2,"percent":66.66666666666666},"instantiations":{"count":3,"covered":2,"percent":66.66666666666666},"lines":{"count":12,"covered":6,"percent":50.0},"regions":{"count":6,"covered":3,"notcovered":3,"percent":50.0}}}],"type":"llvm.coverage.json.export","version":"2.0.1","cargo_llvm_cov":{"version":"0.6.10","manifest_path":"/home/user/lintfix/Cargo.toml"}}"#;
+
+	/// Annotated file
+	const FILE: &str = "/home/user/lintfix/src/lib.rs";
+
+	#[test]
+	fn uncovered_lines() {
+		let report: CoverageReport = serde_json::from_str(REPORT).unwrap();
+		assert_eq!(report.percent(), Some(50.0));
+		let options = Options::default();
+		let annotations: Vec<_> = report
+			.clone()
+			.locate(None, None)
+			.into_annotations(&options)
+			.into_iter()
+			.map(|annotation| {
+				(
+					annotation.file,
+					annotation.line,
+					annotation.end_line,
+					annotation.message,
+				)
+			})
+			.collect();
+		assert_eq!(
+			annotations,
+			[
+				(
+					Cow::Borrowed(FILE),
+					5,
+					Some(9),
+					Cow::Borrowed("Lines 5-9 are not covered by tests")
+				),
+				(
+					Cow::Borrowed(FILE),
+					11,
+					None,
+					Cow::Borrowed("Line 11 is not covered by tests")
+				),
+			]
+		);
+
+		let diff = ChangedLines::parse(&format!("+++ b/{FILE}\n@@ -10,2 +10,3 @@\n"));
+		let report = report.locate(Some(&diff), None);
+		assert_eq!(
+			report
+				.clone()
+				.into_annotations(&options)
+				.iter()
+				.map(|annotation| annotation.line)
+				.collect::<Vec<_>>(),
+			[11]
+		);
+		assert_eq!(
+			summary::<_, CoverageSummaryWriter>(&[report], &options),
+			format!(
+				"> **TOTAL:** 50.00% line coverage (6/12 lines)
+
+|File|Covered lines|Lines|Coverage|
+|:--|--:|--:|--:|
+|`{FILE}`|6|12|50.00%|
+"
+			)
+		);
+	}
+
+	#[test]
+	fn fail_under() {
+		let report: CoverageReport = serde_json::from_str(REPORT).unwrap();
+		let preamble = |fail_under| {
+			summary::<_, CoverageSummaryWriter>(
+				&[report.clone().locate(None, Some(fail_under))],
+				&Options::default(),
+			)
+			.lines()
+			.nth(2)
+			.unwrap()
+			.to_owned()
+		};
+		assert_eq!(preamble(60.0), ":x: Below the threshold of 60%");
+		assert_eq!(
+			preamble(50.0),
+			":white_check_mark: Above the threshold of 50%"
+		);
+	}
+}
@@ -0,0 +1,223 @@
+//! Provides the parsers of the output of other tools, defined in the configuration
+
+use super::{relative_path, ReportedAnnotation};
+use crate::github::{Annotation, AnnotationKind};
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{borrow::Cow, collections::HashMap, path::Path};
+
+/// Fields of an annotation, as extracted from a line of output
+///
+/// With a regular expression, they are the names of the capture groups (e.g. `end_line`);
+/// with JSON lines, they are the JSON pointers to the values (e.g. `/location/line`).
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct CustomFields<T> {
+	/// File to annotate
+	pub file: Option<T>,
+	/// Start of the lines to annotate
+	pub line: Option<T>,
+	/// End of the lines to annotate
+	pub end_line: Option<T>,
+	/// Start of the columns to annotate
+	pub column: Option<T>,
+	/// End of the columns to annotate
+	pub end_column: Option<T>,
+	/// Severity of the issue, as named by the tool (e.g. `warn`)
+	pub severity: Option<T>,
+	/// Code of the issue (e.g. the name of a rule)
+	pub code: Option<T>,
+	/// Title of the annotation
+	pub title: Option<T>,
+	/// Message of the annotation
+	pub message: Option<T>,
+}
+impl<T> CustomFields<T> {
+	/// Extracts all the fields with `extract`
+	fn map<'a, U>(&'a self, mut extract: impl FnMut(&'a T) -> Option<U>) -> CustomFields<U> {
+		let mut field = |value: &'a Option<T>| value.as_ref().and_then(&mut extract);
+		CustomFields {
+			file: field(&self.file),
+			line: field(&self.line),
+			end_line: field(&self.end_line),
+			column: field(&self.column),
+			end_column: field(&self.end_column),
+			severity: field(&self.severity),
+			code: field(&self.code),
+			title: field(&self.title),
+			message: field(&self.message),
+		}
+	}
+}
+
+/// Parser of the output of another tool, as defined in the configuration
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "CustomParserDefinition")]
+pub struct CustomParser {
+	/// How the fields are extracted from each line
+	matcher: Matcher,
+	/// Kinds of annotation by severity of the tool, taking precedence over the usual names (e.g. `error`)
+	severity: HashMap<String, AnnotationKind>,
+	/// Kind of the annotations whose severity is absent or unknown
+	default_severity: AnnotationKind,
+}
+
+/// How a [`CustomParser`] extracts the fields from each line
+#[derive(Debug, Clone)]
+enum Matcher {
+	/// Regular expression whose named capture groups are the fields
+	Pattern(Regex),
+	/// JSON pointers to the fields of each line, read as a JSON value
+	Json(CustomFields<String>),
+}
+
+/// Definition of a [`CustomParser`] in the configuration
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct CustomParserDefinition {
+	/// Regular expression whose named capture groups are the fields (e.g. `(?<file>[^:]+):(?<line>\d+): (?<message>.*)`)
+	pattern: Option<String>,
+	/// JSON pointers to the fields of each line, read as a JSON value
+	json: Option<CustomFields<String>>,
+	/// Kinds of annotation by severity of the tool
+	#[serde(default)]
+	severity: HashMap<String, AnnotationKind>,
+	/// Kind of the annotations whose severity is absent or unknown
+	default_severity: Option<AnnotationKind>,
+}
+impl TryFrom<CustomParserDefinition> for CustomParser {
+	type Error = String;
+
+	fn try_from(definition: CustomParserDefinition) -> Result<Self, Self::Error> {
+		let matcher = match (definition.pattern, definition.json) {
+			(Some(pattern), None) => {
+				let pattern = Regex::new(&pattern).map_err(|err| err.to_string())?;
+				if !pattern.capture_names().any(|name| name == Some("message")) {
+					return Err("the pattern has no `message` capture group".to_owned());
+				}
+				Matcher::Pattern(pattern)
+			}
+			(None, Some(pointers)) => {
+				if pointers.message.is_none() {
+					return Err("the `message` pointer is missing".to_owned());
+				}
+				Matcher::Json(pointers)
+			}
+			_ => return Err("exactly one of `pattern` and `json` must be given".to_owned()),
+		};
+		Ok(Self {
+			matcher,
+			severity: definition.severity,
+			default_severity: definition
+				.default_severity
+				.unwrap_or(AnnotationKind::Warning),
+		})
+	}
+}
+impl CustomParser {
+	/// Parses a `line` of output into an annotation
+	///
+	/// Returns [`None`] if the line does not match.
+	pub fn parse(&self, line: &str) -> Option<ReportedAnnotation<'static>> {
+		let fields = match &self.matcher {
+			Matcher::Pattern(pattern) => {
+				let captures = pattern.captures(line)?;
+				let group = |name: &str| capture(&captures, name);
+				CustomFields {
+					file: group("file"),
+					line: group("line"),
+					end_line: group("end_line"),
+					column: group("column"),
+					end_column: group("end_column"),
+					severity: group("severity"),
+					code: group("code"),
+					title: group("title"),
+					message: group("message"),
+				}
+			}
+			Matcher::Json(pointers) => {
+				let value: Value = serde_json::from_str(line).ok()?;
+				pointers.map(|pointer| match value.pointer(pointer)? {
+					Value::String(string) => Some(string.clone()),
+					Value::Null => None,
+					other => Some(other.to_string()),
+				})
+			}
+		};
+		let number = |value: Option<String>| value?.trim().parse().ok();
+		let file = fields
+			.file
+			.filter(|file| !file.is_empty())
+			.map(|file| relative_path(Path::new(&file)))
+			.unwrap_or_default();
+		let line = number(fields.line).unwrap_or(if file.is_empty() { 0 } else { 1 });
+		Some(
+			Annotation {
+				kind: fields
+					.severity
+					.map_or(self.default_severity, |severity| self.kind(&severity)),
+				file: Cow::Owned(file),
+				line,
+				end_line: number(fields.end_line),
+				col: number(fields.column),
+				end_column: number(fields.end_column),
+				code: fields.code.map(Cow::Owned),
+				suggestions: Vec::new(),
+				title: fields.title.map(Cow::Owned),
+				message: Cow::Owned(fields.message?),
+			}
+			.into(),
+		)
+	}
+
+	/// Returns the kind of annotation of the `severity` of the tool
+	fn kind(&self, severity: &str) -> AnnotationKind {
+		if let Some(&kind) = self.severity.get(severity) {
+			return kind;
+		}
+		match severity.to_ascii_lowercase().as_str() {
+			"error" | "fatal" | "critical" => AnnotationKind::Error,
+			"warning" | "warn" => AnnotationKind::Warning,
+			"notice" | "note" | "info" | "help" => AnnotationKind::Notice,
+			_ => self.default_severity,
+		}
+	}
+}
+
+/// Returns the text captured by the group `name`, if it matched
+#[inline]
+fn capture(captures: &Captures<'_>, name: &str) -> Option<String> {
+	captures.name(name).map(|group| group.as_str().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::cargo::{HandleMessage, Options};
+
+	#[test]
+	fn parse_line() {
+		let parser: CustomParser = toml::from_str(
+			r#"
+pattern = '^(?<file>[^:]+):(?<line>\d+): (?<severity>\w+): (?<message>.*)$'
+severity = { fatal = "error" }
+"#,
+		)
+		.unwrap();
+		let annotations = parser
+			.parse("src/main.js:12: fatal: unreachable code")
+			.unwrap()
+			.into_annotations(&Options::default());
+		assert_eq!(annotations.len(), 1);
+		assert_eq!(annotations[0].kind, AnnotationKind::Error);
+		assert_eq!(
+			(annotations[0].file.as_ref(), annotations[0].line),
+			("src/main.js", 12)
+		);
+		assert_eq!(annotations[0].message, "unreachable code");
+		assert!(parser.parse("Done in 3s").is_none());
+
+		assert!(toml::from_str::<CustomParser>("pattern = '(?<file>.*)'").is_err());
+	}
+}
@@ -0,0 +1,431 @@
+//! Provides the structs to work with cargo-deny's output
+
+use super::{HandleMessage, Metadata, Options, SummaryWriter};
+use crate::github::{Annotation, AnnotationKind};
+use serde::{de::IgnoredAny, Deserialize};
+use std::{
+	borrow::Cow,
+	collections::BTreeMap,
+	fmt::{self, Display, Formatter, Write as FmtWrite},
+	io::{self, Write as IoWrite},
+};
+
+/// Message output by cargo-deny
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", content = "fields", rename_all = "lowercase")]
+pub enum DenyMessage {
+	/// Diagnostic of a check
+	Diagnostic(DenyDiagnostic),
+	/// Summary of the checks
+	Summary(IgnoredAny),
+	/// Log message
+	Log(IgnoredAny),
+}
+impl DenyMessage {
+	/// Locates the crate concerned by the diagnostic in the workspace
+	pub fn locate(self, metadata: &Metadata) -> LocatedDenyMessage {
+		let location = match &self {
+			Self::Diagnostic(diagnostic) => diagnostic
+				.graphs
+				.iter()
+				.find_map(|graph| metadata.locate_package(&graph.krate.name, &graph.krate.version)),
+			Self::Summary(_) | Self::Log(_) => None,
+		};
+		LocatedDenyMessage {
+			message: self,
+			location,
+		}
+	}
+}
+
+/// Diagnostic output by cargo-deny
+#[derive(Debug, Clone, Deserialize)]
+pub struct DenyDiagnostic {
+	/// Severity of the diagnostic
+	pub severity: DenySeverity,
+	/// Code of the diagnostic (e.g. `banned` or `rejected`)
+	#[serde(default)]
+	pub code: Option<String>,
+	/// Primary message
+	pub message: String,
+	/// Additional notes
+	#[serde(default)]
+	pub notes: Vec<String>,
+	/// Advisory concerned by the diagnostic
+	#[serde(default)]
+	pub advisory: Option<DenyAdvisory>,
+	/// Inclusion graphs of the crates concerned by the diagnostic
+	#[serde(default)]
+	pub graphs: Vec<CrateGraph>,
+}
+impl DenyDiagnostic {
+	/// Returns the check which has output the diagnostic
+	fn check(&self) -> DenyCheck {
+		match self.code.as_deref() {
+			_ if self.advisory.is_some() => DenyCheck::Advisories,
+			Some(
+				"vulnerability"
+				| "notice"
+				| "unmaintained"
+				| "unsound"
+				| "yanked"
+				| "index-failure"
+				| "index-cache-load-failure"
+				| "advisory-not-detected"
+				| "unknown-advisory",
+			) => DenyCheck::Advisories,
+			Some(
+				"banned"
+				| "allowed"
+				| "not-allowed"
+				| "duplicate"
+				| "skipped"
+				| "wildcard"
+				| "unmatched-skip"
+				| "unnecessary-skip"
+				| "build-script-not-allowed"
+				| "feature-banned"
+				| "unknown-feature"
+				| "default-feature-enabled",
+			) => DenyCheck::Bans,
+			Some(
+				"rejected"
+				| "accepted"
+				| "unlicensed"
+				| "skipped-private-workspace-crate"
+				| "license-not-encountered"
+				| "license-exception-not-encountered"
+				| "missing-clarification-file"
+				| "parse-error"
+				| "no-license-field"
+				| "gather-failure",
+			) => DenyCheck::Licenses,
+			Some(code) if code.contains("source") || code.contains("organization") => {
+				DenyCheck::Sources
+			}
+			_ => DenyCheck::Other,
+		}
+	}
+
+	/// Returns the name and version of the first crate concerned by the diagnostic
+	fn krate(&self) -> Option<String> {
+		self.graphs
+			.first()
+			.map(|graph| format!("`{}` {}", graph.krate.name, graph.krate.version))
+	}
+}
+
+/// Severity of a [`DenyDiagnostic`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DenySeverity {
+	/// Failed check
+	Error,
+	/// Possible problem
+	Warning,
+	/// Additional information
+	Note,
+	/// Suggestion
+	Help,
+}
+impl From<DenySeverity> for AnnotationKind {
+	#[inline]
+	fn from(severity: DenySeverity) -> Self {
+		match severity {
+			DenySeverity::Error => Self::Error,
+			DenySeverity::Warning => Self::Warning,
+			DenySeverity::Note | DenySeverity::Help => Self::Notice,
+		}
+	}
+}
+
+/// Advisory concerned by a [`DenyDiagnostic`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct DenyAdvisory {
+	/// Identifier of the advisory
+	pub id: String,
+}
+
+/// Inclusion graph of a crate
+#[derive(Debug, Clone, Deserialize)]
+pub struct CrateGraph {
+	/// The crate
+	#[serde(rename = "Krate")]
+	pub krate: DeniedCrate,
+}
+
+/// Crate concerned by a [`DenyDiagnostic`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeniedCrate {
+	/// Name of the crate
+	pub name: String,
+	/// Version of the crate
+	pub version: String,
+}
+
+/// Check performed by cargo-deny
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DenyCheck {
+	/// Security advisories
+	Advisories,
+	/// Banned crates
+	Bans,
+	/// License violations
+	Licenses,
+	/// Untrusted sources
+	Sources,
+	/// Any other check
+	Other,
+}
+impl Display for DenyCheck {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Advisories => write!(f, "Advisories"),
+			Self::Bans => write!(f, "Bans"),
+			Self::Licenses => write!(f, "Licenses"),
+			Self::Sources => write!(f, "Sources"),
+			Self::Other => write!(f, "Other"),
+		}
+	}
+}
+
+/// [`DenyMessage`] whose concerned crate has been located
+#[derive(Debug, Clone)]
+pub struct LocatedDenyMessage {
+	/// The message
+	message: DenyMessage,
+	/// Location of the declaration of the concerned crate
+	location: Option<(String, usize)>,
+}
+impl<'m> HandleMessage<'m> for LocatedDenyMessage {
+	type Summary = DenySummary;
+
+	fn into_annotations(self, _options: &Options) -> Vec<Annotation<'m>> {
+		let (DenyMessage::Diagnostic(diagnostic), Some((file, line))) =
+			(self.message, self.location)
+		else {
+			return Vec::new();
+		};
+		let title = match &diagnostic.advisory {
+			Some(advisory) => format!("{}: {}", diagnostic.check(), advisory.id),
+			None => diagnostic.check().to_string(),
+		};
+		let mut message = diagnostic.message;
+		for note in diagnostic.notes {
+			message.push_str("\n- ");
+			message.push_str(&note);
+		}
+		vec![Annotation {
+			kind: diagnostic.severity.into(),
+			file: Cow::Owned(file),
+			line,
+			end_line: None,
+			col: None,
+			end_column: None,
+			code: diagnostic.code.map(Cow::Owned),
+			suggestions: Vec::new(),
+			title: Some(Cow::Owned(title)),
+			message: Cow::Owned(message),
+		}]
+	}
+
+	fn summarize(&self, _options: &Options) -> Vec<Self::Summary> {
+		match &self.message {
+			DenyMessage::Diagnostic(diagnostic) => vec![DenySummary {
+				check: diagnostic.check(),
+				kind: diagnostic.severity.into(),
+				message: diagnostic.message.clone(),
+				krate: diagnostic.krate(),
+			}],
+			DenyMessage::Summary(_) | DenyMessage::Log(_) => Vec::new(),
+		}
+	}
+}
+
+/// Summary info of [`DenyDiagnostic`]
+#[derive(Debug, Clone)]
+pub struct DenySummary {
+	/// Check which has output the diagnostic
+	check: DenyCheck,
+	/// Kind of annotation
+	kind: AnnotationKind,
+	/// [`DenyDiagnostic.message`](DenyDiagnostic#structfield.message)
+	message: String,
+	/// Concerned crate
+	krate: Option<String>,
+}
+
+/// [`SummaryWriter`] for [`DenySummary`]
+#[derive(Debug, Default, Clone)]
+pub struct DenySummaryWriter {
+	/// Diagnostics grouped by check
+	checks: BTreeMap<DenyCheck, Vec<DenySummary>>,
+}
+impl SummaryWriter for DenySummaryWriter {
+	type Summary = DenySummary;
+
+	#[inline]
+	fn write_summary(
+		&mut self,
+		summary: Self::Summary,
+		_content: &mut dyn FmtWrite,
+	) -> fmt::Result {
+		self.checks.entry(summary.check).or_default().push(summary);
+		Ok(())
+	}
+
+	fn write_preamble(&self, file: &mut dyn IoWrite, _options: &Options) -> io::Result<()> {
+		let count = |kind| {
+			self.checks
+				.values()
+				.flatten()
+				.filter(|summary| summary.kind == kind)
+				.count()
+		};
+		writeln!(
+			file,
+			"> **TOTAL:** {} {}s, {} {}s, {} {}s\n",
+			count(AnnotationKind::Error),
+			AnnotationKind::Error,
+			count(AnnotationKind::Warning),
+			AnnotationKind::Warning,
+			count(AnnotationKind::Notice),
+			AnnotationKind::Notice,
+		)
+	}
+
+	fn write_postamble(self, file: &mut dyn IoWrite, _options: &Options) -> io::Result<()> {
+		for (check, summaries) in self.checks {
+			writeln!(file, "### {check}\n")?;
+			writeln!(file, "|Level|Message|Crate|")?;
+			writeln!(file, "|:--|:--|:--|")?;
+			for summary in summaries {
+				writeln!(
+					file,
+					"|{}|{}|{}|",
+					summary.kind,
+					summary.message,
+					summary.krate.unwrap_or_default()
+				)?;
+			}
+			writeln!(file)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::cargo::tests::{summary, Workspace};
+
+	/// Output of `cargo deny --format json check`
+	const OUTPUT: &[&str] = &[
+		r#"{"type":"log","fields":{"timestamp":"2024-06-01T12:00:00.000000000Z","level":"WARN","message":"unable to find a config path, falling back to default config"}}"#,
+		r#"{"type":"diagnostic","fields":{"graphs":[{"Krate":{"name":"openssl","version":"0.10.64"},"parents":[{"Krate":{"name":"lintfix","version":"0.1.0"}}]}],"labels":[{"column":5,"line":4,"message":"banned here","span":"openssl"}],"message":"crate 'openssl = 0.10.64' is explicitly banned","notes":[],"severity":"error","code":"banned"}}"#,
+		r#"{"type":"diagnostic","fields":{"graphs":[{"Krate":{"name":"syn","version":"1.0.109"},"parents":[{"Krate":{"name":"lintfix","version":"0.1.0"}}]},{"Krate":{"name":"syn","version":"2.0.66"},"parents":[{"Krate":{"name":"lintfix","version":"0.1.0"}}]}],"labels":[{"column":1,"line":37,"message":"lock entries","span":"syn 1.0.109 registry+https://github.com/rust-lang/crates.io-index\nsyn 2.0.66 registry+https://github.com/rust-lang/crates.io-index"}],"message":"found 2 duplicate entries for crate 'syn'","notes":[],"severity":"warning","code":"duplicate"}}"#,
+		r#"{"type":"diagnostic","fields":{"graphs":[{"Krate":{"name":"lintfix","version":"0.1.0"}}],"labels":[{"column":12,"line":5,"message":"rejected: license is not explicitly allowed","span":"GPL-3.0"}],"message":"failed to satisfy license requirements","notes":["GPL-3.0 - GNU General Public License v3.0 only:","  - No longer OSI approved","  - FSF Free/Libre"],"severity":"error","code":"rejected"}}"#,
+		r#"{"type":"diagnostic","fields":{"advisory":{"id":"RUSTSEC-2020-0071","package":"time","title":"Potential segfault in the time crate","description":"Unix-like operating systems may segfault due to dereferencing a dangling pointer in specific circumstances.","date":"2020-11-18","aliases":["CVE-2020-26235","GHSA-wcg3-cvx6-7396"],"related":[],"collection":"crates","categories":["code-execution","memory-corruption"],"keywords":["segfault"],"cvss":"CVSS:3.1/AV:L/AC:H/PR:N/UI:N/S:U/C:N/I:N/A:H","informational":null,"references":[],"source":null,"url":"https://github.com/time-rs/time/issues/293","withdrawn":null,"license":"CC0-1.0"},"graphs":[{"Krate":{"name":"time","version":"0.1.45"},"parents":[{"Krate":{"name":"lintfix","version":"0.1.0"}}]}],"labels":[{"column":1,"line":53,"message":"security vulnerability detected","span":"time 0.1.45 registry+https://github.com/rust-lang/crates.io-index"}],"message":"Potential segfault in the time crate","notes":["ID: RUSTSEC-2020-0071","Advisory: https://rustsec.org/advisories/RUSTSEC-2020-0071","Solution: Upgrade to >=0.2.23 (try `cargo update -p time`)"],"severity":"error","code":"vulnerability"}}"#,
+		r#"{"type":"summary","fields":{"advisories":{"errors":1,"warnings":0,"notes":0,"helps":0},"bans":{"errors":1,"warnings":1,"notes":0,"helps":0},"licenses":{"errors":1,"warnings":0,"notes":0,"helps":0},"sources":{"errors":0,"warnings":0,"notes":0,"helps":0}}}"#,
+	];
+
+	#[test]
+	fn diagnostics() {
+		let workspace = Workspace::new(
+			"deny",
+			&[
+				(
+					"Cargo.toml",
+					"[package]\nname = \"lintfix\"\nversion = \"0.1.0\"\nedition = \"2021\"\nlicense = \"GPL-3.0\"\n\n[dependencies]\nopenssl = \"0.10\"\ntime = \"0.1\"\n",
+				),
+				(
+					"Cargo.lock",
+					"version = 4\n\n[[package]]\nname = \"lintfix\"\nversion = \"0.1.0\"\ndependencies = [\n \"openssl\",\n \"time\",\n]\n\n[[package]]\nname = \"syn\"\nversion = \"1.0.109\"\n",
+				),
+			],
+		);
+		let messages: Vec<_> = OUTPUT
+			.iter()
+			.map(|line| {
+				serde_json::from_str::<DenyMessage>(line)
+					.unwrap()
+					.locate(&workspace.0)
+			})
+			.collect();
+		let options = Options::default();
+		assert_eq!(
+			summary::<_, DenySummaryWriter>(&messages, &options),
+			"> **TOTAL:** 3 :x: Errors, 1 :warning: Warnings, 0 :information_source: Notices
+
+### Advisories
+
+|Level|Message|Crate|
+|:--|:--|:--|
+|:x: Error|Potential segfault in the time crate|`time` 0.1.45|
+
+### Bans
+
+|Level|Message|Crate|
+|:--|:--|:--|
+|:x: Error|crate 'openssl = 0.10.64' is explicitly banned|`openssl` 0.10.64|
+|:warning: Warning|found 2 duplicate entries for crate 'syn'|`syn` 1.0.109|
+
+### Licenses
+
+|Level|Message|Crate|
+|:--|:--|:--|
+|:x: Error|failed to satisfy license requirements|`lintfix` 0.1.0|
+
+"
+		);
+
+		let manifest = workspace.annotated_path("Cargo.toml");
+		let lockfile = workspace.annotated_path("Cargo.lock");
+		let annotations: Vec<_> = messages
+			.into_iter()
+			.flat_map(|message| message.into_annotations(&options))
+			.map(|annotation| {
+				(
+					annotation.kind,
+					format!("{}:{}", annotation.file, annotation.line),
+					annotation.code.unwrap(),
+					annotation.title.unwrap(),
+					annotation.message,
+				)
+			})
+			.collect();
+		assert_eq!(
+			annotations,
+			[
+				(
+					AnnotationKind::Error,
+					format!("{manifest}:8"),
+					Cow::Borrowed("banned"),
+					Cow::Borrowed("Bans"),
+					Cow::Borrowed("crate 'openssl = 0.10.64' is explicitly banned"),
+				),
+				(
+					AnnotationKind::Warning,
+					format!("{lockfile}:12"),
+					Cow::Borrowed("duplicate"),
+					Cow::Borrowed("Bans"),
+					Cow::Borrowed("found 2 duplicate entries for crate 'syn'"),
+				),
+				(
+					AnnotationKind::Error,
+					format!("{lockfile}:4"),
+					Cow::Borrowed("rejected"),
+					Cow::Borrowed("Licenses"),
+					Cow::Borrowed("failed to satisfy license requirements\n- GPL-3.0 - GNU General Public License v3.0 only:\n-   - No longer OSI approved\n-   - FSF Free/Libre"),
+				),
+				(
+					AnnotationKind::Error,
+					format!("{manifest}:9"),
+					Cow::Borrowed("vulnerability"),
+					Cow::Borrowed("Advisories: RUSTSEC-2020-0071"),
+					Cow::Borrowed("Potential segfault in the time crate\n- ID: RUSTSEC-2020-0071\n- Advisory: https://rustsec.org/advisories/RUSTSEC-2020-0071\n- Solution: Upgrade to >=0.2.23 (try `cargo update -p time`)"),
+				),
+			]
+		);
+	}
+}
@@ -0,0 +1,198 @@
+//! Provides the structs to preview the fixes suggested by rustc
+
+use super::{
+	Applicability, CargoMessage, Diagnostic, DiagnosticSpan, DiagnosticSummary,
+	DiagnosticSummaryWriter, HandleMessage, Options, SummaryWriter,
+};
+use crate::github::Annotation;
+use std::{
+	fmt::{self, Write as FmtWrite},
+	io::{self, Write as IoWrite},
+};
+
+/// Machine-applicable fix suggested by rustc
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+	/// File to fix
+	file: String,
+	/// First line to fix (1-based)
+	line: usize,
+	/// Lines before the fix
+	original: Vec<String>,
+	/// Lines after the fix
+	fixed: Vec<String>,
+}
+impl Fix {
+	/// Builds the fix applying the suggestions of `spans`
+	///
+	/// The spans must be sorted, in the same file, and cover contiguous lines.
+	fn from_spans(spans: &[&DiagnosticSpan<'_>]) -> Option<Self> {
+		let first = spans.first()?;
+		let mut original: Vec<String> = Vec::new();
+		for span in spans {
+			for (i, line) in span.text.iter().enumerate() {
+				let index = span.line_start + i - first.line_start;
+				if index >= original.len() {
+					original.push(line.text.to_string());
+				}
+			}
+		}
+		// Offset (in characters) of a position in the original lines
+		let offset = |line: usize, col: usize| -> Option<usize> {
+			let index = line.checked_sub(first.line_start)?;
+			Some(
+				original
+					.get(..index)?
+					.iter()
+					.map(|line| line.chars().count() + 1)
+					.sum::<usize>() + col.checked_sub(1)?,
+			)
+		};
+		let mut fixed: Vec<char> = original.join("\n").chars().collect();
+		for span in spans.iter().rev() {
+			let start = offset(span.line_start, span.column_start)?;
+			let end = offset(span.line_end, span.column_end)?;
+			fixed.splice(
+				start..end.min(fixed.len()),
+				span.suggested_replacement.as_deref()?.chars(),
+			);
+		}
+		Some(Self {
+			file: first.file_name.to_owned(),
+			line: first.line_start,
+			original,
+			fixed: fixed
+				.into_iter()
+				.collect::<String>()
+				.lines()
+				.map(ToOwned::to_owned)
+				.collect(),
+		})
+	}
+
+	/// Extracts the fixes suggested by the children of a diagnostic, if they can be applied automatically
+	pub fn from_diagnostic(diagnostic: &Diagnostic<'_>) -> Vec<Self> {
+		let mut fixes = Vec::new();
+		for child in &diagnostic.children {
+			let mut spans: Vec<_> = child
+				.spans
+				.iter()
+				.filter(|span| {
+					span.suggestion_applicability == Some(Applicability::MachineApplicable)
+						&& span.suggested_replacement.is_some()
+				})
+				.collect();
+			spans.sort_by_key(|span| (span.file_name, span.line_start, span.column_start));
+			// Spans of a suggestion are applied together if their lines are contiguous
+			let mut hunk: Vec<&DiagnosticSpan<'_>> = Vec::new();
+			for span in spans {
+				if hunk.last().is_some_and(|last| {
+					last.file_name != span.file_name || last.line_end < span.line_start
+				}) {
+					fixes.extend(Self::from_spans(&hunk));
+					hunk.clear();
+				}
+				hunk.push(span);
+			}
+			fixes.extend(Self::from_spans(&hunk));
+		}
+		fixes
+	}
+
+	/// Returns the fix as a unified diff (without header)
+	pub fn diff(&self) -> String {
+		self.original
+			.iter()
+			.map(|line| format!("-{line}\n"))
+			.chain(self.fixed.iter().map(|line| format!("+{line}\n")))
+			.collect()
+	}
+}
+
+/// [`CargoMessage`] whose suggested fixes are previewed
+#[derive(Debug, Clone)]
+pub struct FixPreview<'m>(pub CargoMessage<'m>);
+impl<'m> FixPreview<'m> {
+	/// Returns the fixes suggested by the message
+	fn fixes(&self) -> Vec<Fix> {
+		match &self.0 {
+			CargoMessage::CompilerMessage { message, .. } => Fix::from_diagnostic(message),
+			CargoMessage::Other => Vec::new(),
+		}
+	}
+}
+impl<'m> HandleMessage<'m> for FixPreview<'m> {
+	type Summary = FixSummary;
+
+	#[inline]
+	fn into_annotations(self, options: &Options) -> Vec<Annotation<'m>> {
+		// The suggested fixes are already appended to the annotations of the diagnostic
+		self.0.into_annotations(options)
+	}
+
+	fn summarize(&self, options: &Options) -> Vec<Self::Summary> {
+		let fixes = self.fixes();
+		self.0
+			.summarize(options)
+			.into_iter()
+			.map(|diagnostic| FixSummary {
+				diagnostic,
+				fixes: fixes.clone(),
+			})
+			.collect()
+	}
+
+	#[inline]
+	fn rendered(&self) -> Option<&str> {
+		self.0.rendered()
+	}
+}
+
+/// Summary info of [`FixPreview`]
+#[derive(Debug, Clone)]
+pub struct FixSummary {
+	/// Summary of the diagnostic
+	diagnostic: DiagnosticSummary,
+	/// Fixes suggested for the diagnostic
+	fixes: Vec<Fix>,
+}
+
+/// [`SummaryWriter`] for [`FixSummary`]
+#[derive(Debug, Default, Clone)]
+pub struct FixSummaryWriter {
+	/// [`SummaryWriter`] for the diagnostics themselves
+	diagnostics: DiagnosticSummaryWriter,
+	/// Suggested fixes
+	fixes: Vec<Fix>,
+}
+impl SummaryWriter for FixSummaryWriter {
+	type Summary = FixSummary;
+
+	#[inline]
+	fn write_summary(&mut self, summary: Self::Summary, content: &mut dyn FmtWrite) -> fmt::Result {
+		for fix in summary.fixes {
+			if !self.fixes.contains(&fix) {
+				self.fixes.push(fix);
+			}
+		}
+		self.diagnostics.write_summary(summary.diagnostic, content)
+	}
+
+	#[inline]
+	fn write_preamble(&self, file: &mut dyn IoWrite, options: &Options) -> io::Result<()> {
+		self.diagnostics.write_preamble(file, options)
+	}
+
+	fn write_postamble(self, file: &mut dyn IoWrite, options: &Options) -> io::Result<()> {
+		self.diagnostics.write_postamble(file, options)?;
+		if self.fixes.is_empty() {
+			return Ok(());
+		}
+		writeln!(file, "\n### Suggested fixes\n")?;
+		for fix in self.fixes {
+			writeln!(file, "`{}:{}`\n", fix.file, fix.line)?;
+			writeln!(file, "```diff\n{}```\n", fix.diff())?;
+		}
+		Ok(())
+	}
+}
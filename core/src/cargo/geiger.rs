@@ -0,0 +1,340 @@
+//! Provides the structs to work with cargo-geiger's output
+
+use super::{HandleMessage, Metadata, Options, SummaryWriter};
+use crate::github::{Annotation, AnnotationKind};
+use serde::Deserialize;
+use std::{
+	borrow::Cow,
+	fmt::{self, Write as FmtWrite},
+	io::{self, Write as IoWrite},
+};
+
+/// Message output by `cargo geiger --output-format Json`
+#[derive(Debug, Clone, Deserialize)]
+pub struct GeigerReport {
+	/// Scanned packages
+	pub packages: Vec<GeigerPackage>,
+}
+impl GeigerReport {
+	/// Locates the workspace members whose unsafe usage has increased compared to the `baseline` report
+	pub fn locate(self, metadata: &Metadata, baseline: Option<&Self>) -> LocatedGeigerReport {
+		let packages = self
+			.packages
+			.into_iter()
+			.map(|package| {
+				let name = &package.package.id.name;
+				let previous = baseline.and_then(|baseline| {
+					baseline
+						.packages
+						.iter()
+						.find(|previous| previous.package.id.name == *name)
+						.map(|previous| previous.unsafety.used.exprs.unsafe_)
+				});
+				let is_member = metadata.package(name).is_some();
+				LocatedGeigerPackage {
+					location: is_member.then(|| metadata.locate_member(name)).flatten(),
+					is_member,
+					previous,
+					package,
+				}
+			})
+			.collect();
+		LocatedGeigerReport { packages }
+	}
+}
+
+/// Package scanned by cargo-geiger
+#[derive(Debug, Clone, Deserialize)]
+pub struct GeigerPackage {
+	/// Information about the package
+	pub package: GeigerPackageInfo,
+	/// Unsafe usage in the package
+	pub unsafety: Unsafety,
+}
+
+/// Information about a package scanned by cargo-geiger
+#[derive(Debug, Clone, Deserialize)]
+pub struct GeigerPackageInfo {
+	/// Identifier of the package
+	pub id: GeigerPackageId,
+}
+
+/// Identifier of a package scanned by cargo-geiger
+#[derive(Debug, Clone, Deserialize)]
+pub struct GeigerPackageId {
+	/// Name of the package
+	pub name: String,
+	/// Version of the package
+	pub version: String,
+}
+
+/// Unsafe usage in a package
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Unsafety {
+	/// Counters of the code used by the build
+	pub used: UnsafeCounters,
+	/// The package forbids unsafe code (`#![forbid(unsafe_code)]`)
+	pub forbids_unsafe: bool,
+}
+
+/// Counters of safe and unsafe items
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct UnsafeCounters {
+	/// Functions
+	pub functions: Count,
+	/// Expressions
+	pub exprs: Count,
+	/// Implementations
+	pub item_impls: Count,
+	/// Traits
+	pub item_traits: Count,
+	/// Methods
+	pub methods: Count,
+}
+
+/// Counter of safe and unsafe items
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Count {
+	/// Number of unsafe items
+	#[serde(alias = "unsafe")]
+	pub unsafe_: u64,
+}
+
+/// [`GeigerPackage`] whose manifest has been located
+#[derive(Debug, Clone)]
+struct LocatedGeigerPackage {
+	/// The package
+	package: GeigerPackage,
+	/// The package is a workspace member
+	is_member: bool,
+	/// Number of unsafe expressions in the baseline report
+	previous: Option<u64>,
+	/// Location of the `[package]` table of the workspace member
+	location: Option<(String, usize)>,
+}
+
+/// [`GeigerReport`] whose workspace members have been located
+#[derive(Debug, Clone)]
+pub struct LocatedGeigerReport {
+	/// Located packages
+	packages: Vec<LocatedGeigerPackage>,
+}
+impl<'m> HandleMessage<'m> for LocatedGeigerReport {
+	type Summary = GeigerSummary;
+
+	fn into_annotations(self, _options: &Options) -> Vec<Annotation<'m>> {
+		self.packages
+			.into_iter()
+			.filter_map(|located| {
+				let current = located.package.unsafety.used.exprs.unsafe_;
+				let previous = located.previous.filter(|previous| current > *previous)?;
+				let (file, line) = located.location?;
+				Some(Annotation {
+					kind: AnnotationKind::Warning,
+					file: Cow::Owned(file),
+					line,
+					end_line: None,
+					col: None,
+					end_column: None,
+					code: None,
+					suggestions: Vec::new(),
+					title: Some(Cow::Borrowed("Unsafe usage increased")),
+					message: Cow::Owned(format!(
+						"The unsafe expressions of `{}` have increased from {previous} to {current}",
+						located.package.package.id.name
+					)),
+				})
+			})
+			.collect()
+	}
+
+	fn summarize(&self, _options: &Options) -> Vec<Self::Summary> {
+		self.packages
+			.iter()
+			.map(|located| GeigerSummary {
+				krate: format!(
+					"`{}` {}",
+					located.package.package.id.name, located.package.package.id.version
+				),
+				is_member: located.is_member,
+				used: located.package.unsafety.used,
+				forbids_unsafe: located.package.unsafety.forbids_unsafe,
+				previous: located.previous,
+			})
+			.collect()
+	}
+}
+
+/// Summary info of [`GeigerPackage`]
+#[derive(Debug, Clone)]
+pub struct GeigerSummary {
+	/// Name and version of the crate
+	krate: String,
+	/// The crate is a workspace member
+	is_member: bool,
+	/// [`Unsafety.used`](Unsafety#structfield.used)
+	used: UnsafeCounters,
+	/// [`Unsafety.forbids_unsafe`](Unsafety#structfield.forbids_unsafe)
+	forbids_unsafe: bool,
+	/// Number of unsafe expressions in the baseline report
+	previous: Option<u64>,
+}
+
+/// [`SummaryWriter`] for [`GeigerSummary`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GeigerSummaryWriter {
+	/// Counter of crates
+	crates: usize,
+	/// Counter of unsafe expressions
+	exprs: u64,
+}
+impl SummaryWriter for GeigerSummaryWriter {
+	type Summary = GeigerSummary;
+
+	fn write_summary(&mut self, summary: Self::Summary, content: &mut dyn FmtWrite) -> fmt::Result {
+		self.crates += 1;
+		self.exprs += summary.used.exprs.unsafe_;
+		let exprs = match summary.previous {
+			Some(previous) if previous != summary.used.exprs.unsafe_ => {
+				format!("{previous} → {}", summary.used.exprs.unsafe_)
+			}
+			_ => summary.used.exprs.unsafe_.to_string(),
+		};
+		writeln!(
+			content,
+			"|{}{}|{}|{exprs}|{}|{}|{}|{}|",
+			summary.krate,
+			if summary.is_member { " (member)" } else { "" },
+			summary.used.functions.unsafe_,
+			summary.used.item_impls.unsafe_,
+			summary.used.item_traits.unsafe_,
+			summary.used.methods.unsafe_,
+			if summary.forbids_unsafe { ":lock:" } else { "" }
+		)
+	}
+
+	fn write_preamble(&self, file: &mut dyn IoWrite, _options: &Options) -> io::Result<()> {
+		writeln!(
+			file,
+			"> **TOTAL:** {} unsafe expressions in {} crates\n",
+			self.exprs, self.crates
+		)?;
+		writeln!(
+			file,
+			"|Crate|Functions|Expressions|Impls|Traits|Methods|Forbids unsafe|"
+		)?;
+		writeln!(file, "|:--|--:|--:|--:|--:|--:|:-:|")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::cargo::tests::{summary, Workspace};
+	use std::slice;
+
+	/// Returns a package of the report of `cargo geiger --output-format Json`
+	fn package(name: &str, version: &str, exprs: u64, forbids_unsafe: bool) -> serde_json::Value {
+		serde_json::json!({
+			"package": {
+				"id": {
+					"name": name,
+					"version": version,
+					"source": {"Registry": {"name": "crates.io", "url": "https://github.com/rust-lang/crates.io-index"}},
+				},
+				"dependencies": [],
+				"dev_dependencies": [],
+				"build_dependencies": [],
+			},
+			"unsafety": {
+				"used": {
+					"functions": {"safe": 4, "unsafe_": u64::from(exprs > 0)},
+					"exprs": {"safe": 120, "unsafe_": exprs},
+					"item_impls": {"safe": 2, "unsafe_": 0},
+					"item_traits": {"safe": 0, "unsafe_": 0},
+					"methods": {"safe": 6, "unsafe_": 2 * u64::from(exprs > 0)},
+				},
+				"unused": {
+					"functions": {"safe": 0, "unsafe_": 0},
+					"exprs": {"safe": 0, "unsafe_": 0},
+					"item_impls": {"safe": 0, "unsafe_": 0},
+					"item_traits": {"safe": 0, "unsafe_": 0},
+					"methods": {"safe": 0, "unsafe_": 0},
+				},
+				"forbids_unsafe": forbids_unsafe,
+			},
+		})
+	}
+
+	/// Returns a report of `cargo geiger --output-format Json`, with the unsafe expressions of the workspace member
+	fn report(exprs: u64) -> GeigerReport {
+		serde_json::from_value(serde_json::json!({
+			"packages": [
+				package("lintfix", "0.1.0", exprs, false),
+				package("libc", "0.2.155", 140, false),
+				package("either", "1.12.0", 0, true),
+			],
+			"packages_without_metrics": [],
+			"used_but_not_scanned_files": [],
+		}))
+		.unwrap()
+	}
+
+	#[test]
+	fn unsafe_usage() {
+		let workspace = Workspace::new(
+			"geiger",
+			&[(
+				"Cargo.toml",
+				"[package]\nname = \"lintfix\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+			)],
+		);
+		let baseline = report(1);
+		let options = Options::default();
+		let report = report(3).locate(&workspace.0, Some(&baseline));
+		assert_eq!(
+			summary::<_, GeigerSummaryWriter>(slice::from_ref(&report), &options),
+			"> **TOTAL:** 143 unsafe expressions in 3 crates
+
+|Crate|Functions|Expressions|Impls|Traits|Methods|Forbids unsafe|
+|:--|--:|--:|--:|--:|--:|:-:|
+|`lintfix` 0.1.0 (member)|1|1 → 3|0|0|2||
+|`libc` 0.2.155|1|140|0|0|2||
+|`either` 1.12.0|0|0|0|0|0|:lock:|
+"
+		);
+		assert_eq!(
+			report.into_annotations(&options),
+			[Annotation {
+				kind: AnnotationKind::Warning,
+				file: Cow::Owned(workspace.annotated_path("Cargo.toml")),
+				line: 1,
+				end_line: None,
+				col: None,
+				end_column: None,
+				code: None,
+				suggestions: Vec::new(),
+				title: Some(Cow::Borrowed("Unsafe usage increased")),
+				message: Cow::Borrowed(
+					"The unsafe expressions of `lintfix` have increased from 1 to 3"
+				),
+			}]
+		);
+
+		// Decreasing usage, or no baseline, is not annotated
+		assert!(report_annotations(&workspace, 1, Some(3)).is_empty());
+		assert!(report_annotations(&workspace, 3, None).is_empty());
+	}
+
+	/// Returns the annotations of a report with `exprs` unsafe expressions, compared to a baseline with `previous` ones
+	fn report_annotations(
+		workspace: &Workspace,
+		exprs: u64,
+		previous: Option<u64>,
+	) -> Vec<Annotation<'static>> {
+		let baseline = previous.map(report);
+		report(exprs)
+			.locate(&workspace.0, baseline.as_ref())
+			.into_annotations(&Options::default())
+	}
+}
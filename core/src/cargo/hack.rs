@@ -0,0 +1,331 @@
+//! Provides the structs to work with cargo-hack's feature combinations
+
+use super::{CargoMessage, DiagnosticSummary, HandleMessage, Options, SummaryWriter};
+use crate::{
+	github::{Annotation, AnnotationKind},
+	process::passthrough,
+};
+use serde::Deserialize;
+use std::{
+	collections::HashMap,
+	ffi::OsStr,
+	fmt::{self, Write as FmtWrite},
+	io::{self, Write as IoWrite},
+	process::{Command, Stdio},
+};
+
+/// Runs the commands listed by `cargo hack --print-command-list`
+///
+/// The messages output for each feature set are aggregated such that identical diagnostics are output once,
+/// along with all the feature sets which have triggered them.
+/// Each returned line is a [`FeatureSetMessage`].
+/// `on_run` is called with the arguments of Cargo before each command is run (e.g. to report the progress).
+/// The standard error of the commands is discarded if `quiet` is `true`.
+///
+/// # Errors
+/// This function returns an error if Cargo could not be invoked.
+pub fn run_feature_sets(
+	cargo: &OsStr,
+	command_list: &str,
+	quiet: bool,
+	mut on_run: impl FnMut(&[&str]),
+) -> io::Result<Vec<String>> {
+	let mut messages: Vec<FeatureSetLine> = Vec::new();
+	let mut diagnostics = HashMap::new();
+	for command in command_list.lines() {
+		let mut args: Vec<_> = command
+			.split_whitespace()
+			.skip(1)
+			.map(|arg| arg.trim_matches(['\'', '"']))
+			.collect();
+		if args.is_empty() {
+			continue;
+		}
+		let features = feature_set(&args);
+		args.insert(1, "--message-format=json");
+		on_run(&args);
+		let output = Command::new(cargo)
+			.args(&args)
+			.stdin(Stdio::null())
+			.stderr(passthrough(quiet))
+			.output()?;
+		for line in String::from_utf8_lossy(&output.stdout).lines() {
+			// Diagnostics are identified by their primary content,
+			// regardless of the target which output them and of their notes
+			let Some(diagnostic) = serde_json::from_str::<serde_json::Value>(line)
+				.ok()
+				.filter(|message| message["reason"] == "compiler-message")
+				.map(|message| {
+					let diagnostic = &message["message"];
+					serde_json::json!([
+						diagnostic["level"],
+						diagnostic["message"],
+						diagnostic["code"],
+						diagnostic["spans"],
+					])
+					.to_string()
+				})
+			else {
+				continue;
+			};
+			let i = *diagnostics.entry(diagnostic).or_insert_with(|| {
+				messages.push(FeatureSetLine {
+					features: Vec::new(),
+					message: line.to_owned(),
+				});
+				messages.len() - 1
+			});
+			if !messages[i].features.contains(&features) {
+				messages[i].features.push(features.clone());
+			}
+		}
+	}
+	messages
+		.into_iter()
+		.map(|message| {
+			Ok(format!(
+				"{{\"features\":{},\"message\":{}}}",
+				serde_json::to_string(&message.features)?,
+				message.message
+			))
+		})
+		.collect()
+}
+
+/// Returns the feature flags of the arguments of a Cargo command
+fn feature_set(args: &[&str]) -> String {
+	let mut features = Vec::new();
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		match *arg {
+			"--no-default-features" | "--all-features" => features.push((*arg).to_owned()),
+			"--features" | "-F" => {
+				if let Some(value) = args.next() {
+					features.push(format!("--features {value}"));
+				}
+			}
+			_ => {
+				if let Some(value) = arg.strip_prefix("--features=") {
+					features.push(format!("--features {value}"));
+				}
+			}
+		}
+	}
+	if features.is_empty() {
+		"default".to_owned()
+	} else {
+		features.join(" ")
+	}
+}
+
+/// Message output by Cargo for some feature sets, before serialization
+#[derive(Debug, Clone)]
+struct FeatureSetLine {
+	/// Feature sets which have output the message
+	features: Vec<String>,
+	/// The message
+	message: String,
+}
+
+/// Message output by Cargo for some feature sets
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeatureSetMessage<'m> {
+	/// Feature sets which have output the message
+	pub features: Vec<String>,
+	/// The message
+	#[serde(borrow)]
+	pub message: CargoMessage<'m>,
+}
+impl<'m> HandleMessage<'m> for FeatureSetMessage<'m> {
+	type Summary = FeatureSetSummary;
+
+	#[inline]
+	fn into_annotations(self, options: &Options) -> Vec<Annotation<'m>> {
+		self.message.into_annotations(options)
+	}
+
+	fn summarize(&self, options: &Options) -> Vec<Self::Summary> {
+		self.message
+			.summarize(options)
+			.into_iter()
+			.map(|diagnostic| FeatureSetSummary {
+				diagnostic,
+				features: self.features.clone(),
+			})
+			.collect()
+	}
+
+	#[inline]
+	fn rendered(&self) -> Option<&str> {
+		self.message.rendered()
+	}
+}
+
+/// Summary info of [`FeatureSetMessage`]
+#[derive(Debug, Clone)]
+pub struct FeatureSetSummary {
+	/// Summary of the diagnostic
+	diagnostic: DiagnosticSummary,
+	/// [`FeatureSetMessage.features`](FeatureSetMessage#structfield.features)
+	features: Vec<String>,
+}
+
+/// [`SummaryWriter`] for [`FeatureSetSummary`]
+#[derive(Debug, Default, Clone)]
+pub struct FeatureSetSummaryWriter {
+	/// Counter for each [`AnnotationKind`]
+	kind_count: HashMap<AnnotationKind, usize>,
+}
+impl SummaryWriter for FeatureSetSummaryWriter {
+	type Summary = FeatureSetSummary;
+
+	fn write_summary(&mut self, summary: Self::Summary, content: &mut dyn FmtWrite) -> fmt::Result {
+		let kind = summary.diagnostic.kind;
+		*self.kind_count.entry(kind).or_default() += 1;
+		let location = summary
+			.diagnostic
+			.location
+			.as_ref()
+			.map(|location| format!("`{}:{}`", location.0, location.1))
+			.unwrap_or_default();
+		let features = summary
+			.features
+			.iter()
+			.map(|features| format!("`{features}`"))
+			.collect::<Vec<_>>()
+			.join("<br>");
+		writeln!(
+			content,
+			"|{kind}|{}|{}|{location}|{features}|",
+			summary.diagnostic.formatted_message(),
+			summary.diagnostic.krate.as_deref().unwrap_or_default()
+		)
+	}
+
+	fn write_preamble(&self, file: &mut dyn IoWrite, _options: &Options) -> io::Result<()> {
+		let count = |kind| self.kind_count.get(&kind).copied().unwrap_or_default();
+		writeln!(
+			file,
+			"> **TOTAL:** {} {}s, {} {}s, {} {}s\n",
+			count(AnnotationKind::Error),
+			AnnotationKind::Error,
+			count(AnnotationKind::Warning),
+			AnnotationKind::Warning,
+			count(AnnotationKind::Notice),
+			AnnotationKind::Notice,
+		)?;
+		writeln!(file, "|Level|Message|Crate|Location|Feature sets|")?;
+		writeln!(file, "|:--|:--|:--|--:|:--|")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		cargo::tests::{summary, Workspace},
+		report::tests::{MISMATCHED_TYPES, PTR_ARG},
+	};
+	use std::{borrow::Cow, fs};
+
+	/// Returns a message of Cargo for the `target` of the workspace member, containing the rustc `diagnostic`
+	fn compiler_message(target: &str, diagnostic: &str) -> String {
+		format!(
+			r#"{{"reason":"compiler-message","package_id":"path+file:///lintfix#0.1.0","manifest_path":"/tmp/ws/lintfix/Cargo.toml","target":{{"kind":["{target}"],"crate_types":["{target}"],"name":"lintfix","src_path":"/tmp/ws/lintfix/src/lib.rs","edition":"2021","doc":true,"doctest":true,"test":true}},"message":{diagnostic}}}"#
+		)
+	}
+
+	#[test]
+	fn feature_sets() {
+		assert_eq!(feature_set(&["check", "--workspace"]), "default");
+		assert_eq!(
+			feature_set(&["check", "--no-default-features", "-F", "serde,std"]),
+			"--no-default-features --features serde,std"
+		);
+		assert_eq!(
+			feature_set(&["check", "--features=serde"]),
+			"--features serde"
+		);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn aggregated_diagnostics() {
+		use std::os::unix::fs::PermissionsExt;
+
+		let ptr_arg = compiler_message("lib", PTR_ARG);
+		let script = format!(
+			"#!/bin/sh\ncase \"$*\" in\n*--all-features*) cat <<'EOF'\n{}\n{{\"reason\":\"build-finished\",\"success\":true}}\nEOF\n;;\n*--features*) cat <<'EOF'\n{}\nEOF\n;;\n*) cat <<'EOF'\n{ptr_arg}\n{}\n{{\"reason\":\"build-finished\",\"success\":false}}\nEOF\n;;\nesac\n",
+			compiler_message("test", PTR_ARG),
+			ptr_arg,
+			compiler_message("lib", MISMATCHED_TYPES),
+		);
+		let workspace = Workspace::new("hack", &[("cargo", &script)]);
+		let cargo = workspace.path("cargo");
+		fs::set_permissions(&cargo, fs::Permissions::from_mode(0o755)).unwrap();
+
+		let mut runs = Vec::new();
+		let lines = run_feature_sets(
+			cargo.as_os_str(),
+			"cargo check --manifest-path Cargo.toml --no-default-features\ncargo check --manifest-path Cargo.toml --no-default-features --features serde\ncargo check --manifest-path Cargo.toml --all-features\n",
+			true,
+			|args| runs.push(args.join(" ")),
+		)
+		.unwrap();
+		assert_eq!(
+			runs,
+			[
+				"check --message-format=json --manifest-path Cargo.toml --no-default-features",
+				"check --message-format=json --manifest-path Cargo.toml --no-default-features --features serde",
+				"check --message-format=json --manifest-path Cargo.toml --all-features",
+			]
+		);
+		assert_eq!(
+			lines,
+			[
+				format!(
+					r#"{{"features":["--no-default-features","--no-default-features --features serde","--all-features"],"message":{ptr_arg}}}"#
+				),
+				format!(
+					r#"{{"features":["--no-default-features"],"message":{}}}"#,
+					compiler_message("lib", MISMATCHED_TYPES)
+				),
+			]
+		);
+
+		let options = Options {
+			workspace: Some(workspace.0.clone()),
+			..Options::default()
+		};
+		let messages: Vec<_> = lines
+			.iter()
+			.map(|line| serde_json::from_str::<FeatureSetMessage<'_>>(line).unwrap())
+			.collect();
+		let file = workspace.annotated_path("lintfix/src/lib.rs");
+		assert_eq!(
+			summary::<_, FeatureSetSummaryWriter>(&messages, &options),
+			format!(
+				"> **TOTAL:** 1 :x: Errors, 1 :warning: Warnings, 0 :information_source: Notices
+
+|Level|Message|Crate|Location|Feature sets|
+|:--|:--|:--|--:|:--|
+|:warning: Warning|writing `&Vec` instead of `&[_]` involves a new object where a slice will do ([`clippy::ptr_arg`](https://rust-lang.github.io/rust-clippy/master/index.html#ptr_arg))||`{file}:1`|`--no-default-features`<br>`--no-default-features --features serde`<br>`--all-features`|
+|:x: Error|mismatched types ([`E0308`](https://doc.rust-lang.org/error_codes/E0308.html))||`{file}:6`|`--no-default-features`|
+"
+			)
+		);
+		let annotations: Vec<_> = messages
+			.into_iter()
+			.flat_map(|message| message.into_annotations(&options))
+			.map(|annotation| (annotation.kind, annotation.file, annotation.line))
+			.collect();
+		assert_eq!(
+			annotations,
+			[
+				(AnnotationKind::Warning, Cow::Borrowed(file.as_str()), 1),
+				(AnnotationKind::Error, Cow::Borrowed(file.as_str()), 6),
+				(AnnotationKind::Notice, Cow::Borrowed(file.as_str()), 5),
+			]
+		);
+	}
+}
@@ -0,0 +1,163 @@
+//! Provides the structs to work with cargo-machete's output
+
+use super::{relative_path, DependencyKind, Metadata, UnusedDependencies, UnusedDependency};
+use std::path::{Path, PathBuf};
+
+/// Unused dependency reported by cargo-machete
+#[derive(Debug, Clone)]
+pub struct MacheteDependency {
+	/// Path to the manifest of the crate
+	manifest_path: PathBuf,
+	/// Name of the dependency
+	name: String,
+}
+impl MacheteDependency {
+	/// Locates the declaration of the unused dependency in the manifest of the crate
+	pub fn locate(self, metadata: &Metadata) -> UnusedDependencies {
+		let (kind, location) = [
+			DependencyKind::Normal,
+			DependencyKind::Development,
+			DependencyKind::Build,
+		]
+		.into_iter()
+		.find_map(|kind| {
+			metadata
+				.locate_dependency(&self.manifest_path, kind.section(), &self.name)
+				.map(|location| (kind, Some(location)))
+		})
+		.unwrap_or_default();
+		UnusedDependencies(vec![UnusedDependency {
+			manifest: relative_path(&self.manifest_path),
+			name: self.name,
+			kind,
+			location,
+		}])
+	}
+}
+
+/// Parser of the report output by cargo-machete
+///
+/// cargo-machete does not provide a machine-readable output, so its human-readable report is parsed.
+/// The unused dependencies of each crate are listed (indented) after a `<crate> -- <manifest>:` line.
+#[derive(Debug, Default, Clone)]
+pub struct MacheteReportParser {
+	/// Manifest of the crate whose dependencies are being listed
+	manifest_path: Option<PathBuf>,
+}
+impl MacheteReportParser {
+	/// Feeds a line of the report to the parser
+	///
+	/// Returns the unused dependency listed on the line, if any.
+	pub fn feed(&mut self, line: &str) -> Option<MacheteDependency> {
+		if let Some(manifest_path) = line
+			.split_once(" -- ")
+			.and_then(|(_, manifest_path)| manifest_path.strip_suffix(':'))
+		{
+			let manifest_path = Path::new(manifest_path);
+			self.manifest_path = Some(
+				manifest_path
+					.strip_prefix(".")
+					.unwrap_or(manifest_path)
+					.to_owned(),
+			);
+			None
+		} else if line.starts_with(char::is_whitespace) && !line.trim().is_empty() {
+			Some(MacheteDependency {
+				manifest_path: self.manifest_path.clone()?,
+				name: line.trim().to_owned(),
+			})
+		} else {
+			if !line.starts_with(char::is_whitespace) {
+				self.manifest_path = None;
+			}
+			None
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		cargo::{
+			tests::{summary, Workspace},
+			HandleMessage, Options, UnusedDependencySummaryWriter,
+		},
+		github::AnnotationKind,
+	};
+
+	#[test]
+	fn unused_dependencies() {
+		let workspace = Workspace::new(
+			"machete",
+			&[(
+				"Cargo.toml",
+				"[package]\nname = \"lintfix\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\nanyhow = \"1\"\n\n[dev-dependencies]\ntempfile = \"3\"\n",
+			)],
+		);
+		let report = format!(
+			"Analyzing dependencies of crates in this directory...\ncargo-machete found the following unused dependencies in this directory:\nlintfix -- {}:\n\tanyhow\n\ttempfile\n\nIf you believe cargo-machete has detected an unused dependency incorrectly,\nyou can add the dependency to the list of dependencies to ignore in the\n`[package.metadata.cargo-machete]` section of the appropriate Cargo.toml.\nFor example:\n\n[package.metadata.cargo-machete]\nignored = [\"prost\"]\n\nDone!\n",
+			workspace.path("Cargo.toml").display()
+		);
+		let mut parser = MacheteReportParser::default();
+		let dependencies: Vec<_> = report
+			.lines()
+			.filter_map(|line| parser.feed(line))
+			.map(|dependency| dependency.locate(&workspace.0))
+			.collect();
+
+		let options = Options::default();
+		let manifest = workspace.annotated_path("Cargo.toml");
+		assert_eq!(
+			summary::<_, UnusedDependencySummaryWriter>(&dependencies, &options),
+			format!(
+				"> **TOTAL:** 2 unused dependencies
+
+### `{manifest}`
+
+- `anyhow` (Normal)
+- `tempfile` (Development)
+
+"
+			)
+		);
+		let annotations: Vec<_> = dependencies
+			.into_iter()
+			.flat_map(|dependencies| dependencies.into_annotations(&options))
+			.map(|annotation| {
+				(
+					annotation.kind,
+					format!("{}:{}", annotation.file, annotation.line),
+					annotation.message.into_owned(),
+				)
+			})
+			.collect();
+		assert_eq!(
+			annotations,
+			[
+				(
+					AnnotationKind::Warning,
+					format!("{manifest}:7"),
+					"`anyhow` is declared as a normal dependency but is not used".to_owned()
+				),
+				(
+					AnnotationKind::Warning,
+					format!("{manifest}:10"),
+					"`tempfile` is declared as a development dependency but is not used".to_owned()
+				),
+			]
+		);
+	}
+
+	#[test]
+	fn relative_manifest() {
+		let mut parser = MacheteReportParser::default();
+		assert!(parser.feed("lintfix -- ./lintfix/Cargo.toml:").is_none());
+		let dependency = parser.feed("\tanyhow").unwrap();
+		assert_eq!(dependency.manifest_path, Path::new("lintfix/Cargo.toml"));
+		assert_eq!(dependency.name, "anyhow");
+		assert!(parser.feed("").is_none());
+		assert!(parser.feed("Done!").is_none());
+		assert!(parser.feed("\tprost").is_none());
+	}
+}
@@ -0,0 +1,131 @@
+//! Provides functions to locate items in Cargo manifests
+
+/// Declaration of a dependency or of a field in a manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Declaration {
+	/// Line of the declaration (1-based)
+	pub(crate) line: usize,
+	/// The declaration is inherited from the workspace (`workspace = true`)
+	pub(crate) inherited: bool,
+}
+
+/// Finds the declaration of the dependency `name` in the manifest `content`
+///
+/// Only the tables named `section` (e.g. `dependencies`) are searched,
+/// including their platform-specific variants (e.g. `target.'cfg(unix)'.dependencies`).
+pub(crate) fn find_dependency(content: &str, section: &str, name: &str) -> Option<Declaration> {
+	let table = format!("{section}.{name}");
+	let mut in_section = false;
+	let mut dependency_table: Option<Declaration> = None;
+	for (i, line) in content.lines().enumerate() {
+		let line = line.trim();
+		if let Some(header) = line.strip_prefix('[') {
+			if dependency_table.is_some() {
+				break;
+			}
+			let header = header.split(']').next().unwrap_or_default().trim();
+			let is_target_specific = header.starts_with("target.");
+			if header == table || (is_target_specific && header.ends_with(&format!(".{table}"))) {
+				dependency_table = Some(Declaration {
+					line: i + 1,
+					inherited: false,
+				});
+			}
+			in_section = header == section
+				|| (is_target_specific && header.ends_with(&format!(".{section}")));
+			continue;
+		}
+
+		let Some((key, value)) = line.split_once('=') else {
+			continue;
+		};
+		let key = key.trim().trim_matches('"');
+		if let Some(declaration) = dependency_table.as_mut() {
+			if key == "workspace" && value.trim() == "true" {
+				declaration.inherited = true;
+			}
+		} else if in_section {
+			if key == name {
+				return Some(Declaration {
+					line: i + 1,
+					inherited: value
+						.split([',', '{', '}'])
+						.filter_map(|pair| pair.split_once('='))
+						.any(|(key, value)| key.trim() == "workspace" && value.trim() == "true"),
+				});
+			}
+			if key.strip_suffix(".workspace") == Some(name) {
+				return Some(Declaration {
+					line: i + 1,
+					inherited: true,
+				});
+			}
+		}
+	}
+	dependency_table
+}
+
+/// Finds the declaration of the field `key` in the table `table` (e.g. `package`) of the manifest `content`
+pub(crate) fn find_field(content: &str, table: &str, key: &str) -> Option<Declaration> {
+	let mut in_table = false;
+	for (i, line) in content.lines().enumerate() {
+		let line = line.trim();
+		if let Some(header) = line.strip_prefix('[') {
+			in_table = header.split(']').next().unwrap_or_default().trim() == table;
+			continue;
+		}
+		if !in_table {
+			continue;
+		}
+		let Some((field, value)) = line.split_once('=') else {
+			continue;
+		};
+		let field = field.trim().trim_matches('"');
+		if field == key {
+			return Some(Declaration {
+				line: i + 1,
+				inherited: value
+					.split([',', '{', '}'])
+					.filter_map(|pair| pair.split_once('='))
+					.any(|(key, value)| key.trim() == "workspace" && value.trim() == "true"),
+			});
+		}
+		if field.strip_suffix(".workspace") == Some(key) {
+			return Some(Declaration {
+				line: i + 1,
+				inherited: true,
+			});
+		}
+	}
+	None
+}
+
+/// Finds the header of the table `table` (e.g. `package`) in the manifest `content`
+pub(crate) fn find_table(content: &str, table: &str) -> Option<usize> {
+	content
+		.lines()
+		.position(|line| {
+			line.trim()
+				.strip_prefix('[')
+				.and_then(|header| header.split(']').next())
+				.is_some_and(|header| header.trim() == table)
+		})
+		.map(|i| i + 1)
+}
+
+/// Finds the line declaring the package `name` at `version` in the lockfile `content`
+pub(crate) fn find_locked_package(content: &str, name: &str, version: &str) -> Option<usize> {
+	let name = format!("name = \"{name}\"");
+	let version = format!("version = \"{version}\"");
+	let mut lines = content.lines().enumerate().peekable();
+	while let Some((i, line)) = lines.next() {
+		if line.trim() == name
+			&& lines
+				.peek()
+				.is_some_and(|(_, next_line)| next_line.trim() == version)
+		{
+			return Some(i + 1);
+		}
+	}
+	None
+}
@@ -0,0 +1,44 @@
+//! Provides the structs to merge the annotations of several JSON reports
+
+use super::{DiagnosticLevel, DiagnosticSummary, HandleMessage, Options};
+use crate::github::{Annotation, AnnotationKind};
+use serde::Deserialize;
+
+/// Annotation read from a JSON report
+#[derive(Debug, Clone, Deserialize)]
+#[serde(transparent)]
+pub struct ReportedAnnotation<'m>(Annotation<'m>);
+impl<'m> From<Annotation<'m>> for ReportedAnnotation<'m> {
+	#[inline]
+	fn from(annotation: Annotation<'m>) -> Self {
+		Self(annotation)
+	}
+}
+impl<'m> HandleMessage<'m> for ReportedAnnotation<'m> {
+	type Summary = DiagnosticSummary;
+
+	#[inline]
+	fn into_annotations(self, _options: &Options) -> Vec<Annotation<'m>> {
+		vec![self.0]
+	}
+
+	fn summarize(&self, _options: &Options) -> Vec<Self::Summary> {
+		let annotation = &self.0;
+		vec![DiagnosticSummary::new(
+			match annotation.kind {
+				AnnotationKind::Error => DiagnosticLevel::Error,
+				AnnotationKind::Warning => DiagnosticLevel::Warning,
+				AnnotationKind::Notice => DiagnosticLevel::Note,
+			},
+			annotation
+				.title
+				.as_deref()
+				.or_else(|| annotation.message.lines().next())
+				.unwrap_or_default()
+				.to_owned(),
+			annotation
+				.is_located()
+				.then(|| (annotation.file.clone().into_owned(), annotation.line)),
+		)]
+	}
+}
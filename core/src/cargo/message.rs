@@ -0,0 +1,194 @@
+//! Provides the structs to work with Cargo's output
+
+use super::{Diagnostic, DiagnosticSummary, HandleMessage, Metadata, Options};
+use crate::{github::Annotation, path::repository_path};
+use serde::Deserialize;
+use std::{
+	borrow::Cow,
+	fmt::{self, Display, Formatter},
+	path::Path,
+};
+use tracing::debug;
+
+/// Returns `true` if the package `package_id` is a member of the `workspace`
+///
+/// All packages are members if the workspace is unknown.
+fn is_workspace_member(workspace: Option<&Metadata>, package_id: &str) -> bool {
+	workspace.is_none_or(|metadata| {
+		metadata
+			.workspace_members
+			.iter()
+			.any(|member| member == package_id)
+	})
+}
+
+/// Returns `true` if the package `package_id` is the only member of the `workspace`
+///
+/// The messages of such a package do not need to be tagged with it.
+fn is_single_member(workspace: Option<&Metadata>, package_id: &str) -> bool {
+	workspace.is_some_and(|metadata| metadata.workspace_members == [package_id])
+}
+
+/// Returns the root of the `workspace`, if `package_id` was compiled by Cargo in a known workspace
+///
+/// The paths output by the compiler are relative to it, instead of the current directory.
+fn workspace_root<'w>(
+	workspace: Option<&'w Metadata>,
+	package_id: Option<&str>,
+) -> Option<&'w Path> {
+	package_id
+		.and(workspace)
+		.map(|metadata| metadata.workspace_root.as_path())
+}
+
+/// Returns the name of the package `package_id`
+///
+/// Both the legacy (`name version (source)`) and the current (`source#name@version`) formats are supported.
+fn package_name(package_id: &str) -> &str {
+	if let Some((name, _)) = package_id.split_once(' ') {
+		return name;
+	}
+	let (source, fragment) = package_id.rsplit_once('#').unwrap_or((package_id, ""));
+	match fragment.split_once('@') {
+		Some((name, _)) => name,
+		// The name is omitted when it is the last segment of the source
+		None => source
+			.trim_end_matches('/')
+			.rsplit('/')
+			.next()
+			.unwrap_or(source),
+	}
+}
+
+/// Message output by Cargo
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+pub enum CargoMessage<'m> {
+	/// Message output by the compiler
+	CompilerMessage {
+		/// Identifier of the package being compiled (absent if the message was output by rustc directly)
+		#[serde(default, borrow)]
+		package_id: Option<Cow<'m, str>>,
+		/// Target being compiled (absent if the message was output by rustc directly)
+		#[serde(default, borrow)]
+		target: Option<CargoTarget<'m>>,
+		/// The message output by the compiler
+		#[serde(borrow)]
+		message: Diagnostic<'m>,
+	},
+	/// Any other message
+	#[serde(other)]
+	Other,
+}
+impl<'m> HandleMessage<'m> for CargoMessage<'m> {
+	type Summary = DiagnosticSummary;
+
+	fn into_annotations(self, options: &Options) -> Vec<Annotation<'m>> {
+		let Self::CompilerMessage {
+			package_id,
+			message,
+			..
+		} = self
+		else {
+			return Vec::new();
+		};
+		let workspace = options.workspace.as_ref();
+		if !package_id
+			.as_deref()
+			.is_none_or(|package_id| is_workspace_member(workspace, package_id))
+		{
+			debug!(
+				package_id = package_id.as_deref(),
+				diagnostic = message.message,
+				"diagnostic skipped, as its package is not a member of the workspace"
+			);
+			return Vec::new();
+		}
+		let base = workspace_root(workspace, package_id.as_deref());
+		// The target is left out so that the diagnostics shared by several targets are emitted once
+		let package = package_id
+			.as_deref()
+			.filter(|package_id| !is_single_member(workspace, package_id))
+			.map(package_name);
+		let mut annotations = message.into_annotations(options);
+		for annotation in &mut annotations {
+			if annotation.is_located() && !annotation.file.starts_with('<') {
+				annotation.file =
+					Cow::Owned(repository_path(Path::new(annotation.file.as_ref()), base));
+			}
+			if let Some(package) = package {
+				annotation.title = Some(Cow::Owned(match &annotation.title {
+					Some(title) => format!("{package}: {title}"),
+					None => package.to_owned(),
+				}));
+			}
+		}
+		annotations
+	}
+
+	fn summarize(&self, options: &Options) -> Vec<Self::Summary> {
+		let Self::CompilerMessage {
+			package_id,
+			target,
+			message,
+		} = self
+		else {
+			return Vec::new();
+		};
+		let workspace = options.workspace.as_ref();
+		let base = workspace_root(workspace, package_id.as_deref());
+		let package = package_id
+			.as_deref()
+			.filter(|package_id| !is_single_member(workspace, package_id))
+			.map(package_name);
+		let krate = package_id
+			.as_deref()
+			.filter(|package_id| !is_single_member(workspace, package_id))
+			.map(|package_id| match target {
+				Some(target) => format!("`{}` ({target})", package_name(package_id)),
+				None => format!("`{}`", package_name(package_id)),
+			});
+		let mut summaries = message.summarize(options);
+		for summary in &mut summaries {
+			if let Some((file, _)) = &mut summary.location {
+				if !file.starts_with('<') {
+					*file = repository_path(Path::new(file.as_str()), base);
+				}
+			}
+			summary.krate.clone_from(&krate);
+			summary.package = package.map(str::to_owned);
+		}
+		summaries
+	}
+
+	#[inline]
+	fn rendered(&self) -> Option<&str> {
+		match self {
+			Self::CompilerMessage { message, .. } => message.rendered(),
+			Self::Other => None,
+		}
+	}
+}
+
+/// Target of a package compiled by Cargo
+#[derive(Debug, Clone, Deserialize)]
+pub struct CargoTarget<'m> {
+	/// Name of the target
+	#[serde(borrow)]
+	pub name: Cow<'m, str>,
+	/// Kinds of the target (e.g. `lib` or `bin`)
+	#[serde(borrow)]
+	pub kind: Vec<Cow<'m, str>>,
+}
+impl Display for CargoTarget<'_> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self.kind.first().map(AsRef::as_ref) {
+			Some("lib" | "rlib" | "dylib" | "cdylib" | "staticlib" | "proc-macro") => {
+				write!(f, "lib")
+			}
+			Some("custom-build") => write!(f, "build script"),
+			Some(kind) => write!(f, "{kind} \"{}\"", self.name),
+			None => write!(f, "{}", self.name),
+		}
+	}
+}
@@ -0,0 +1,185 @@
+//! Provides the structs to work with `cargo metadata`'s output
+
+use super::{manifest, relative_path};
+use crate::process::passthrough;
+use serde::Deserialize;
+use std::{
+	ffi::OsStr,
+	fs, io,
+	path::{Path, PathBuf},
+	process::{Command, Stdio},
+};
+
+/// Workspace information output by `cargo metadata`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Metadata {
+	/// Members of the workspace
+	pub packages: Vec<Package>,
+	/// Identifiers of the members of the workspace
+	pub workspace_members: Vec<String>,
+	/// Root directory of the workspace
+	pub workspace_root: PathBuf,
+	/// Directory of the build artifacts
+	pub target_directory: PathBuf,
+}
+impl Metadata {
+	/// Invokes `cargo metadata` and parses its output
+	///
+	/// The workspace is the one of the current directory, or of the manifest at `manifest_path` if given.
+	/// The standard error of Cargo is discarded if `quiet` is `true`.
+	///
+	/// # Errors
+	/// This function returns an error if Cargo could not be invoked or if its output is invalid.
+	pub fn load(cargo: &OsStr, manifest_path: Option<&Path>, quiet: bool) -> io::Result<Self> {
+		let mut command = Command::new(cargo);
+		command.args(["metadata", "--no-deps", "--format-version=1"]);
+		if let Some(manifest_path) = manifest_path {
+			command.arg("--manifest-path").arg(manifest_path);
+		}
+		let output = command
+			.stdin(Stdio::null())
+			.stderr(passthrough(quiet))
+			.output()?;
+		serde_json::from_slice(&output.stdout).map_err(io::Error::from)
+	}
+
+	/// Returns the workspace member named `name`
+	#[inline]
+	pub fn package(&self, name: &str) -> Option<&Package> {
+		self.packages.iter().find(|package| package.name == name)
+	}
+
+	/// Returns the path to the manifest of the workspace
+	#[inline]
+	pub fn workspace_manifest(&self) -> PathBuf {
+		self.workspace_root.join("Cargo.toml")
+	}
+
+	/// Returns the highest minimum supported Rust version of the workspace members
+	pub fn rust_version(&self) -> Option<&str> {
+		/// Parses the components of a version
+		fn components(version: &str) -> Vec<u64> {
+			version
+				.split('.')
+				.map(|component| component.parse().unwrap_or_default())
+				.collect()
+		}
+
+		self.packages
+			.iter()
+			.filter_map(|package| package.rust_version.as_deref())
+			.max_by_key(|version| components(version))
+	}
+
+	/// Locates the `rust-version` fields declaring the minimum supported Rust version `rust_version`
+	///
+	/// Fields inherited from the workspace are located in the manifest of the workspace.
+	pub fn locate_rust_version(&self, rust_version: &str) -> Vec<(String, usize)> {
+		let mut locations = Vec::new();
+		for package in &self.packages {
+			if package.rust_version.as_deref() != Some(rust_version) {
+				continue;
+			}
+			let Ok(manifest) = fs::read_to_string(&package.manifest_path) else {
+				continue;
+			};
+			let Some(declaration) = manifest::find_field(&manifest, "package", "rust-version")
+			else {
+				continue;
+			};
+			let location = if declaration.inherited {
+				let workspace_manifest_path = self.workspace_manifest();
+				let Some(declaration) =
+					fs::read_to_string(&workspace_manifest_path)
+						.ok()
+						.and_then(|manifest| {
+							manifest::find_field(&manifest, "workspace.package", "rust-version")
+						})
+				else {
+					continue;
+				};
+				(relative_path(&workspace_manifest_path), declaration.line)
+			} else {
+				(relative_path(&package.manifest_path), declaration.line)
+			};
+			if !locations.contains(&location) {
+				locations.push(location);
+			}
+		}
+		locations
+	}
+
+	/// Locates the `[package]` table of the workspace member `name`
+	///
+	/// Returns the path to the manifest (relative to the current directory) and the line of the table.
+	pub fn locate_member(&self, name: &str) -> Option<(String, usize)> {
+		let manifest_path = &self.package(name)?.manifest_path;
+		let manifest = fs::read_to_string(manifest_path).ok()?;
+		manifest::find_table(&manifest, "package").map(|line| (relative_path(manifest_path), line))
+	}
+
+	/// Locates the declaration of the dependency `name` in the table `section` of the manifest at `manifest_path`
+	///
+	/// Dependencies inherited from the workspace are located in the manifest of the workspace.
+	/// Returns the path to the manifest (relative to the current directory) and the line of the declaration.
+	pub fn locate_dependency(
+		&self,
+		manifest_path: &Path,
+		section: &str,
+		name: &str,
+	) -> Option<(String, usize)> {
+		let manifest = fs::read_to_string(manifest_path).ok()?;
+		let declaration = manifest::find_dependency(&manifest, section, name)?;
+		if declaration.inherited {
+			let workspace_manifest_path = self.workspace_manifest();
+			let workspace_manifest = fs::read_to_string(&workspace_manifest_path).ok()?;
+			manifest::find_dependency(&workspace_manifest, "workspace.dependencies", name)
+				.map(|declaration| (relative_path(&workspace_manifest_path), declaration.line))
+		} else {
+			Some((relative_path(manifest_path), declaration.line))
+		}
+	}
+
+	/// Locates the declaration of the package `name` at `version`
+	///
+	/// The package is looked for in the dependencies of the workspace members,
+	/// then in the lockfile for transitive dependencies.
+	pub fn locate_package(&self, name: &str, version: &str) -> Option<(String, usize)> {
+		self.packages
+			.iter()
+			.find_map(|member| {
+				["dependencies", "dev-dependencies", "build-dependencies"]
+					.into_iter()
+					.find_map(|section| {
+						self.locate_dependency(&member.manifest_path, section, name)
+					})
+			})
+			.or_else(|| {
+				let lockfile_path = self.workspace_root.join("Cargo.lock");
+				let lockfile = fs::read_to_string(&lockfile_path).ok()?;
+				manifest::find_locked_package(&lockfile, name, version)
+					.map(|line| (relative_path(&lockfile_path), line))
+			})
+	}
+}
+
+/// Workspace member
+#[derive(Debug, Clone, Deserialize)]
+pub struct Package {
+	/// Name of the package
+	pub name: String,
+	/// Path to the manifest of the package
+	pub manifest_path: PathBuf,
+	/// Minimum supported Rust version of the package
+	pub rust_version: Option<String>,
+	/// Targets of the package
+	#[serde(default)]
+	pub targets: Vec<PackageTarget>,
+}
+
+/// Target of a [`Package`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct PackageTarget {
+	/// Kinds of the target (e.g. `lib`, `bin` or `test`)
+	pub kind: Vec<String>,
+}
@@ -0,0 +1,167 @@
+//! Provides the structs to work with Miri's output
+
+use super::{
+	relative_path, CargoMessage, DiagnosticLevel, DiagnosticSummary, HandleMessage, Options,
+};
+use crate::github::{Annotation, AnnotationKind};
+use std::{borrow::Cow, path::Path};
+
+/// Message output by `cargo miri`
+#[derive(Debug, Clone)]
+pub enum MiriMessage<'m> {
+	/// Message output by Cargo while building the crate
+	Cargo(CargoMessage<'m>),
+	/// Error reported by the interpreter
+	Report(MiriReport),
+}
+impl<'m> HandleMessage<'m> for MiriMessage<'m> {
+	type Summary = DiagnosticSummary;
+
+	#[inline]
+	fn into_annotations(self, options: &Options) -> Vec<Annotation<'m>> {
+		match self {
+			Self::Cargo(message) => message.into_annotations(options),
+			Self::Report(report) => vec![Annotation {
+				kind: AnnotationKind::Error,
+				file: Cow::Owned(report.file),
+				line: report.line,
+				end_line: None,
+				col: Some(report.col),
+				end_column: None,
+				code: None,
+				suggestions: Vec::new(),
+				title: Some(Cow::Owned(report.title)),
+				message: Cow::Owned(report.rendered),
+			}],
+		}
+	}
+
+	#[inline]
+	fn summarize(&self, options: &Options) -> Vec<Self::Summary> {
+		match self {
+			Self::Cargo(message) => message.summarize(options),
+			Self::Report(report) => vec![DiagnosticSummary::new(
+				DiagnosticLevel::Error,
+				report.message.clone(),
+				Some((report.file.clone(), report.line)),
+			)],
+		}
+	}
+
+	#[inline]
+	fn rendered(&self) -> Option<&str> {
+		match self {
+			Self::Cargo(message) => message.rendered(),
+			Self::Report(_) => None,
+		}
+	}
+}
+
+/// Error reported by the Miri interpreter on stderr
+#[derive(Debug, Clone)]
+pub struct MiriReport {
+	/// Kind of error (e.g. `Undefined Behavior`)
+	title: String,
+	/// Primary message
+	message: String,
+	/// Error as rendered by Miri
+	rendered: String,
+	/// File where the error occurred
+	file: String,
+	/// Line where the error occurred (1-based)
+	line: usize,
+	/// Column where the error occurred (1-based)
+	col: usize,
+}
+
+/// Parser of the errors reported by Miri on stderr
+///
+/// Miri's errors are rendered like rustc's human-readable diagnostics,
+/// so they span several lines and end with an empty line.
+#[derive(Debug, Default, Clone)]
+pub struct MiriReportParser {
+	/// Lines of the error being parsed
+	lines: Vec<String>,
+}
+impl MiriReportParser {
+	/// Feeds a line of stderr to the parser
+	///
+	/// Returns the report once all of its lines have been fed.
+	pub fn feed(&mut self, line: &str) -> Option<MiriReport> {
+		if self.lines.is_empty() {
+			if line
+				.split_once("error: ")
+				.is_some_and(|(_, header)| !header.starts_with("aborting due to"))
+			{
+				self.lines.push(line.to_owned());
+			}
+			None
+		} else if line.trim().is_empty() {
+			let lines = std::mem::take(&mut self.lines);
+			Self::parse(&lines)
+		} else {
+			self.lines.push(line.to_owned());
+			None
+		}
+	}
+
+	/// Parses the lines of an error
+	fn parse(lines: &[String]) -> Option<MiriReport> {
+		let (_, header) = lines.first()?.split_once("error: ")?;
+		let (file, line, col) = lines.iter().find_map(|line| {
+			let location = line
+				.trim_start()
+				.strip_prefix("--> ")
+				.or_else(|| line.split_once("` at ").map(|(_, location)| location))?;
+			let mut location = location.split(':');
+			let file = location.next()?;
+			if !Path::new(file).is_relative() || file.starts_with('<') {
+				return None;
+			}
+			Some((
+				relative_path(Path::new(file)),
+				location.next()?.parse().ok()?,
+				location.next()?.trim().parse().ok()?,
+			))
+		})?;
+		Some(MiriReport {
+			title: header
+				.split_once(": ")
+				.map_or(header, |(title, _)| title)
+				.to_owned(),
+			message: header.to_owned(),
+			rendered: lines.join("\n"),
+			file,
+			line,
+			col,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_report() {
+		let stderr = "test tests::oob ... error: Undefined Behavior: out-of-bounds pointer use
+ --> /rustc/library/core/src/ptr/mod.rs:10:5
+  |
+  = note: inside `tests::oob` at src/lib.rs:12:18: 12:28
+
+error: aborting due to 1 previous error
+
+";
+		let mut parser = MiriReportParser::default();
+		let reports: Vec<_> = stderr
+			.lines()
+			.filter_map(|line| parser.feed(line))
+			.collect();
+		assert_eq!(reports.len(), 1);
+		assert_eq!(reports[0].title, "Undefined Behavior");
+		assert_eq!(
+			(reports[0].file.as_str(), reports[0].line, reports[0].col),
+			("src/lib.rs", 12, 18)
+		);
+	}
+}
@@ -0,0 +1,82 @@
+//! Provides the structs to verify the minimum supported Rust version
+
+use super::{CargoMessage, DiagnosticLevel, DiagnosticSummary, HandleMessage, Options};
+use crate::github::{Annotation, AnnotationKind};
+use std::borrow::Cow;
+
+/// Message output while building with the minimum supported Rust version
+#[derive(Debug, Clone)]
+pub enum MsrvMessage<'m> {
+	/// Message output by Cargo
+	Cargo(CargoMessage<'m>),
+	/// The build has failed
+	Failure(MsrvFailure),
+}
+impl<'m> HandleMessage<'m> for MsrvMessage<'m> {
+	type Summary = DiagnosticSummary;
+
+	#[inline]
+	fn into_annotations(self, options: &Options) -> Vec<Annotation<'m>> {
+		match self {
+			Self::Cargo(message) => message.into_annotations(options),
+			Self::Failure(failure) => {
+				let message = failure.message();
+				failure
+					.locations
+					.into_iter()
+					.map(|(file, line)| Annotation {
+						kind: AnnotationKind::Error,
+						file: Cow::Owned(file),
+						line,
+						end_line: None,
+						col: None,
+						end_column: None,
+						code: None,
+						suggestions: Vec::new(),
+						title: Some(Cow::Borrowed("MSRV")),
+						message: Cow::Owned(message.clone()),
+					})
+					.collect()
+			}
+		}
+	}
+
+	#[inline]
+	fn summarize(&self, options: &Options) -> Vec<Self::Summary> {
+		match self {
+			Self::Cargo(message) => message.summarize(options),
+			Self::Failure(failure) => vec![DiagnosticSummary::new(
+				DiagnosticLevel::Error,
+				failure.message(),
+				failure.locations.first().cloned(),
+			)],
+		}
+	}
+
+	#[inline]
+	fn rendered(&self) -> Option<&str> {
+		match self {
+			Self::Cargo(message) => message.rendered(),
+			Self::Failure(_) => None,
+		}
+	}
+}
+
+/// Failure of the build with the minimum supported Rust version
+#[derive(Debug, Clone)]
+pub struct MsrvFailure {
+	/// Toolchain of the build (the minimum supported Rust version by default)
+	pub toolchain: String,
+	/// Locations of the `rust-version` fields declaring the minimum supported Rust version
+	pub locations: Vec<(String, usize)>,
+}
+impl MsrvFailure {
+	/// Returns the message of the failure
+	#[inline]
+	fn message(&self) -> String {
+		format!(
+			"The build fails with the `{}` toolchain, which should be supported according to `rust-version`",
+			self.toolchain
+		)
+	}
+}
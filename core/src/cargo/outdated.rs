@@ -0,0 +1,389 @@
+//! Provides the structs to work with cargo-outdated's output
+
+use super::{relative_path, HandleMessage, Metadata, Options, SummaryWriter};
+use crate::github::{Annotation, AnnotationKind};
+use serde::Deserialize;
+use std::{
+	borrow::Cow,
+	collections::BTreeMap,
+	fmt::{self, Write as FmtWrite},
+	io::{self, Write as IoWrite},
+};
+
+/// Message output by cargo-outdated
+#[derive(Debug, Clone, Deserialize)]
+pub struct OutdatedReport<'m> {
+	/// Name of the crate whose dependencies are reported
+	pub crate_name: &'m str,
+	/// Dependencies of the crate
+	#[serde(borrow)]
+	pub dependencies: Vec<OutdatedDependency<'m>>,
+}
+impl<'m> OutdatedReport<'m> {
+	/// Locates the declarations of the outdated dependencies in the manifests of the workspace
+	///
+	/// Dependencies that are already up to date are discarded.
+	/// Dependencies that lag behind a new major version will be annotated with `major_level`,
+	/// the others will be annotated as notices unless `major_only` is `true`.
+	pub fn locate(
+		self,
+		metadata: &Metadata,
+		major_level: AnnotationKind,
+		major_only: bool,
+	) -> LocatedOutdatedReport<'m> {
+		let manifest_path = metadata.package(self.crate_name).map_or_else(
+			|| metadata.workspace_manifest(),
+			|package| package.manifest_path.clone(),
+		);
+
+		let dependencies = self
+			.dependencies
+			.into_iter()
+			.filter(OutdatedDependency::is_outdated)
+			.map(|dependency| {
+				let location = if dependency.is_transitive() {
+					None
+				} else {
+					metadata.locate_dependency(
+						&manifest_path,
+						dependency.kind.section(),
+						dependency.name,
+					)
+				};
+				LocatedOutdatedDependency {
+					kind: if dependency.is_major_update() {
+						Some(major_level)
+					} else {
+						(!major_only).then_some(AnnotationKind::Notice)
+					},
+					dependency,
+					location,
+				}
+			})
+			.collect();
+
+		LocatedOutdatedReport {
+			manifest: relative_path(&manifest_path),
+			dependencies,
+		}
+	}
+}
+
+/// Dependency reported by cargo-outdated
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct OutdatedDependency<'m> {
+	/// Name of the dependency
+	///
+	/// Transitive dependencies are named after their path in the dependency tree (e.g. `clap->clap_builder`).
+	pub name: &'m str,
+	/// Version currently used by the project
+	pub project: &'m str,
+	/// Latest semver-compatible version
+	pub compat: &'m str,
+	/// Latest version
+	pub latest: &'m str,
+	/// Kind of dependency
+	#[serde(default)]
+	pub kind: DependencyKind,
+}
+impl<'m> OutdatedDependency<'m> {
+	/// Returns `true` if a newer version of the dependency exists
+	#[inline]
+	fn is_outdated(&self) -> bool {
+		[self.compat, self.latest]
+			.into_iter()
+			.any(|version| version != self.project && version != "---")
+	}
+
+	/// Returns `true` if the dependency is not a direct dependency of the crate
+	#[inline]
+	fn is_transitive(&self) -> bool {
+		self.name.contains("->")
+	}
+
+	/// Returns `true` if the latest version is a new major version
+	fn is_major_update(&self) -> bool {
+		/// Parses the version components that semver considers "major"
+		fn major_components(version: &str) -> Option<Vec<u64>> {
+			let components = version
+				.split(['.', '-', '+'])
+				.take(3)
+				.map(str::parse)
+				.collect::<Result<Vec<u64>, _>>()
+				.ok()?;
+			let major = components
+				.iter()
+				.position(|component| *component != 0)
+				.map_or(components.len(), |i| i + 1);
+			Some(components.into_iter().take(major).collect())
+		}
+
+		match (
+			major_components(self.project),
+			major_components(self.latest),
+		) {
+			(Some(project), Some(latest)) => project != latest,
+			_ => false,
+		}
+	}
+}
+
+/// Kind of a dependency
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub enum DependencyKind {
+	/// Regular dependency (`[dependencies]`)
+	#[default]
+	Normal,
+	/// Development dependency (`[dev-dependencies]`)
+	Development,
+	/// Build dependency (`[build-dependencies]`)
+	Build,
+}
+impl DependencyKind {
+	/// Returns the name of the manifest table where dependencies of this kind are declared
+	#[inline]
+	pub const fn section(&self) -> &'static str {
+		match self {
+			Self::Normal => "dependencies",
+			Self::Development => "dev-dependencies",
+			Self::Build => "build-dependencies",
+		}
+	}
+}
+impl fmt::Display for DependencyKind {
+	#[inline]
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Debug::fmt(self, f)
+	}
+}
+
+/// [`OutdatedReport`] whose dependencies have been located
+#[derive(Debug, Clone)]
+pub struct LocatedOutdatedReport<'m> {
+	/// Manifest of the crate whose dependencies are reported
+	manifest: String,
+	/// Outdated dependencies
+	dependencies: Vec<LocatedOutdatedDependency<'m>>,
+}
+impl<'m> HandleMessage<'m> for LocatedOutdatedReport<'m> {
+	type Summary = OutdatedSummary;
+
+	fn into_annotations(self, _options: &Options) -> Vec<Annotation<'m>> {
+		self.dependencies
+			.into_iter()
+			.filter_map(|located| {
+				let kind = located.kind?;
+				let (file, line) = located.location?;
+				let dependency = located.dependency;
+				Some(Annotation {
+					kind,
+					file: Cow::Owned(file),
+					line,
+					end_line: None,
+					col: None,
+					end_column: None,
+					code: None,
+					suggestions: Vec::new(),
+					title: Some(Cow::Borrowed("Outdated dependency")),
+					message: Cow::Owned(format!(
+						"`{}` {} is outdated (compatible: {}, latest: {})",
+						dependency.name, dependency.project, dependency.compat, dependency.latest
+					)),
+				})
+			})
+			.collect()
+	}
+
+	#[inline]
+	fn summarize(&self, _options: &Options) -> Vec<Self::Summary> {
+		self.dependencies
+			.iter()
+			.map(|located| OutdatedSummary {
+				manifest: self.manifest.clone(),
+				name: located.dependency.name.to_owned(),
+				current: located.dependency.project.to_owned(),
+				compatible: located.dependency.compat.to_owned(),
+				latest: located.dependency.latest.to_owned(),
+				kind: located.dependency.kind,
+			})
+			.collect()
+	}
+}
+
+/// [`OutdatedDependency`] with the location of its declaration
+#[derive(Debug, Clone)]
+struct LocatedOutdatedDependency<'m> {
+	/// Outdated dependency
+	dependency: OutdatedDependency<'m>,
+	/// Kind of annotation to emit (if any)
+	kind: Option<AnnotationKind>,
+	/// Location of the declaration of the dependency
+	location: Option<(String, usize)>,
+}
+
+/// Summary info of [`OutdatedDependency`]
+#[derive(Debug, Clone)]
+pub struct OutdatedSummary {
+	/// Manifest of the crate which depends on the dependency
+	manifest: String,
+	/// [`OutdatedDependency.name`](OutdatedDependency#structfield.name)
+	name: String,
+	/// [`OutdatedDependency.project`](OutdatedDependency#structfield.project)
+	current: String,
+	/// [`OutdatedDependency.compat`](OutdatedDependency#structfield.compat)
+	compatible: String,
+	/// [`OutdatedDependency.latest`](OutdatedDependency#structfield.latest)
+	latest: String,
+	/// [`OutdatedDependency.kind`](OutdatedDependency#structfield.kind)
+	kind: DependencyKind,
+}
+
+/// [`SummaryWriter`] for [`OutdatedSummary`]
+#[derive(Debug, Default, Clone)]
+pub struct OutdatedSummaryWriter {
+	/// Outdated dependencies grouped by manifest
+	manifests: BTreeMap<String, Vec<OutdatedSummary>>,
+}
+impl SummaryWriter for OutdatedSummaryWriter {
+	type Summary = OutdatedSummary;
+
+	#[inline]
+	fn write_summary(
+		&mut self,
+		summary: Self::Summary,
+		_content: &mut dyn FmtWrite,
+	) -> fmt::Result {
+		self.manifests
+			.entry(summary.manifest.clone())
+			.or_default()
+			.push(summary);
+		Ok(())
+	}
+
+	fn write_preamble(&self, file: &mut dyn IoWrite, _options: &Options) -> io::Result<()> {
+		writeln!(
+			file,
+			"> **TOTAL:** {} outdated dependencies\n",
+			self.manifests.values().map(Vec::len).sum::<usize>()
+		)
+	}
+
+	fn write_postamble(self, file: &mut dyn IoWrite, _options: &Options) -> io::Result<()> {
+		for (manifest, dependencies) in self.manifests {
+			writeln!(file, "### `{manifest}`\n")?;
+			writeln!(file, "|Name|Current|Compatible|Latest|Kind|")?;
+			writeln!(file, "|:--|:--|:--|:--|:--|")?;
+			for dependency in dependencies {
+				writeln!(
+					file,
+					"|`{}`|{}|{}|{}|{}|",
+					dependency.name,
+					dependency.current,
+					dependency.compatible,
+					dependency.latest,
+					dependency.kind
+				)?;
+			}
+			writeln!(file)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::cargo::tests::{summary, Workspace};
+
+	/// Report of cargo-outdated, with major, compatible, transitive and up-to-date dependencies
+	const REPORT: &str = r#"{"crate_name":"lintfix","dependencies":[{"name":"clap","project":"3.2.25","compat":"3.2.25","latest":"4.5.4","kind":"Normal","platform":null},{"name":"clap->clap_lex","project":"0.2.4","compat":"0.2.4","latest":"0.7.0","kind":"Normal","platform":null},{"name":"regex","project":"1.10.4","compat":"---","latest":"---","kind":"Normal","platform":null},{"name":"serde","project":"1.0.190","compat":"1.0.203","latest":"1.0.203","kind":"Normal","platform":null},{"name":"tempfile","project":"3.8.0","compat":"3.10.1","latest":"3.10.1","kind":"Development","platform":null}]}"#;
+
+	#[test]
+	fn outdated_dependencies() {
+		let workspace = Workspace::new(
+			"outdated",
+			&[(
+				"Cargo.toml",
+				"[package]\nname = \"lintfix\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\nclap = \"3\"\nregex = \"1\"\nserde = { workspace = true }\n\n[dev-dependencies]\ntempfile = \"3.8\"\n\n[workspace]\n\n[workspace.dependencies]\nserde = \"1.0.190\"\n",
+			)],
+		);
+		let manifest = workspace.annotated_path("Cargo.toml");
+		let annotations = |major_only| {
+			serde_json::from_str::<OutdatedReport<'_>>(REPORT)
+				.unwrap()
+				.locate(&workspace.0, AnnotationKind::Warning, major_only)
+				.into_annotations(&Options::default())
+				.into_iter()
+				.map(|annotation| {
+					(
+						annotation.kind,
+						annotation.line,
+						annotation.file.into_owned(),
+						annotation.message.into_owned(),
+					)
+				})
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(
+			annotations(false),
+			[
+				(
+					AnnotationKind::Warning,
+					7,
+					manifest.clone(),
+					"`clap` 3.2.25 is outdated (compatible: 3.2.25, latest: 4.5.4)".to_owned()
+				),
+				(
+					AnnotationKind::Notice,
+					17,
+					manifest.clone(),
+					"`serde` 1.0.190 is outdated (compatible: 1.0.203, latest: 1.0.203)".to_owned()
+				),
+				(
+					AnnotationKind::Notice,
+					12,
+					manifest.clone(),
+					"`tempfile` 3.8.0 is outdated (compatible: 3.10.1, latest: 3.10.1)".to_owned()
+				),
+			]
+		);
+		assert_eq!(annotations(true).len(), 1);
+
+		let report = serde_json::from_str::<OutdatedReport<'_>>(REPORT)
+			.unwrap()
+			.locate(&workspace.0, AnnotationKind::Warning, false);
+		assert_eq!(
+			summary::<_, OutdatedSummaryWriter>(&[report], &Options::default()),
+			format!(
+				"> **TOTAL:** 4 outdated dependencies
+
+### `{manifest}`
+
+|Name|Current|Compatible|Latest|Kind|
+|:--|:--|:--|:--|:--|
+|`clap`|3.2.25|3.2.25|4.5.4|Normal|
+|`clap->clap_lex`|0.2.4|0.2.4|0.7.0|Normal|
+|`serde`|1.0.190|1.0.203|1.0.203|Normal|
+|`tempfile`|3.8.0|3.10.1|3.10.1|Development|
+
+"
+			)
+		);
+	}
+
+	#[test]
+	fn major_update() {
+		let dependency = |project, latest| OutdatedDependency {
+			name: "rand",
+			project,
+			compat: project,
+			latest,
+			kind: DependencyKind::Normal,
+		};
+		assert!(dependency("0.7.3", "0.8.5").is_major_update());
+		assert!(dependency("0.0.1", "0.0.2").is_major_update());
+		assert!(dependency("1.2.3", "2.0.0-rc.1").is_major_update());
+		assert!(!dependency("1.2.3", "1.4.0").is_major_update());
+		assert!(!dependency("0.8.3", "0.8.5").is_major_update());
+	}
+}
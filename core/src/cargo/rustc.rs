@@ -0,0 +1,1099 @@
+//! Provides the structs to work with rustc's output
+
+use super::{strip_ansi, Fix, HandleMessage, Options, SummaryWriter};
+use crate::github::{source_link, Annotation, AnnotationKind, SortKey, SortOrder, Suggestion};
+use serde::Deserialize;
+use std::{
+	borrow::Cow,
+	cmp::Reverse,
+	collections::{BTreeMap, HashMap},
+	fmt::{self, Write as FmtWrite},
+	io::{self, Write as IoWrite},
+	path::Path,
+};
+use tracing::debug;
+
+/// Number of the most frequent codes drawn in the charts of the summary
+const CHART_CODES: usize = 10;
+
+/// Maximum size of the tables of diagnostics in the summary,
+/// leaving room for its other sections under [`MAX_SUMMARY_SIZE`](crate::github::MAX_SUMMARY_SIZE)
+const MAX_TABLES_SIZE: usize = 960 * 1024;
+/// Estimated size of the header and footer of the section of a file in the summary
+const FILE_SECTION_SIZE: usize = 256;
+
+/// Style of the messages of the annotations of rustc's diagnostics
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum MessageStyle {
+	/// Primary message only
+	Short,
+	/// Primary message, code and first help message
+	Condensed,
+	/// Diagnostic as rendered by rustc, with its notes, help messages and suggested fixes
+	#[default]
+	Rendered,
+}
+
+/// Message output by rustc
+#[derive(Debug, Clone, Deserialize)]
+pub struct Diagnostic<'m> {
+	/// Primary message
+	pub message: &'m str,
+	/// Unique code of the diagnostic
+	#[serde(borrow)]
+	pub code: Option<DiagnosticCode<'m>>,
+	/// Severity of the diagnostic
+	pub level: DiagnosticLevel,
+	/// Locations in the source code of this diagnostic
+	#[serde(borrow)]
+	pub spans: Vec<DiagnosticSpan<'m>>,
+	/// Diagnostic as rendered by rustc
+	#[serde(borrow)]
+	pub rendered: Option<Cow<'m, str>>,
+	/// Attached diagnostics (e.g. notes or suggestions)
+	#[serde(default, borrow)]
+	pub children: Vec<Diagnostic<'m>>,
+}
+impl Diagnostic<'_> {
+	/// Returns `true` if the diagnostic only wraps up the others
+	/// (e.g. `aborting due to 2 previous errors` or `For more information about this error, try ...`)
+	fn is_meta(&self) -> bool {
+		self.spans.is_empty()
+			&& self.code.is_none()
+			&& (self.message.starts_with("aborting due to")
+				|| self.message.ends_with(" emitted")
+				|| self.message.starts_with("For more information about")
+				|| self
+					.message
+					.starts_with("Some errors have detailed explanations"))
+	}
+
+	/// Returns `true` if the diagnostic is not to be handled
+	///
+	/// The wrap-up diagnostics are suppressed unless requested, and so are the diagnostics of ignored lints,
+	/// along with their secondary spans and children.
+	#[inline]
+	fn is_suppressed(&self, options: &Options) -> bool {
+		(self.is_meta() && !options.meta_diagnostics)
+			|| self
+				.code
+				.is_some_and(|code| options.lint_levels.is_ignored(code.code))
+	}
+}
+impl<'m> HandleMessage<'m> for Diagnostic<'m> {
+	type Summary = DiagnosticSummary;
+
+	fn into_annotations(self, options: &Options) -> Vec<Annotation<'m>> {
+		if self.is_suppressed(options) {
+			debug!(
+				diagnostic = self.message,
+				"diagnostic skipped, as it is a wrap-up diagnostic of rustc (see `--meta-diagnostics`) or its lint is ignored"
+			);
+			return Vec::new();
+		}
+		let title = match (&self.code, &self.rendered) {
+			(Some(code), _) => Some(Cow::Owned(format!("{} [{}]", self.message, code.code))),
+			(None, Some(_rendered)) => Some(Cow::Borrowed(self.message)),
+			(None, None) => None,
+		};
+		let fixes = Fix::from_diagnostic(&self);
+		// Colors are only meant for the logs
+		let rendered = self.rendered.map(|rendered| match strip_ansi(&rendered) {
+			Cow::Borrowed(_) => rendered,
+			Cow::Owned(stripped) => Cow::Owned(stripped),
+		});
+		let style = options.message_style;
+		// The children are only rendered in the long message format
+		let mut message = match rendered {
+			_ if style == MessageStyle::Short => Cow::Borrowed(self.message),
+			_ if style == MessageStyle::Condensed => {
+				let mut message = self.message.to_owned();
+				if let Some(code) = &self.code {
+					let _ = write!(message, " [{}]", code.code);
+				}
+				if let Some(help) = self
+					.children
+					.iter()
+					.find(|child| child.level == DiagnosticLevel::Help)
+				{
+					let _ = write!(message, "\nhelp: {}", help.message);
+				}
+				Cow::Owned(message)
+			}
+			Some(rendered) if rendered.trim_end().contains('\n') => rendered,
+			rendered => {
+				let mut message = rendered.map_or_else(|| self.message.to_owned(), Cow::into_owned);
+				for child in &self.children {
+					message.push_str(if message.ends_with('\n') { "" } else { "\n" });
+					message.push_str(child.level.name());
+					message.push_str(": ");
+					message.push_str(child.message);
+				}
+				Cow::Owned(message)
+			}
+		};
+		if !fixes.is_empty() && style == MessageStyle::Rendered {
+			let message = message.to_mut();
+			message.truncate(message.trim_end().len());
+			message.push_str("\n\nSuggested fix:\n");
+			for fix in &fixes {
+				message.push_str(&fix.diff());
+			}
+		}
+
+		let kind = self.level.into();
+		let code = self.code.map(|code| Cow::Borrowed(code.code));
+		let Some(primary_span) = self
+			.spans
+			.iter()
+			.find(|span| span.is_primary)
+			.map(DiagnosticSpan::user_span)
+		else {
+			// Diagnostics without location (e.g. linker errors) are not annotated in a file
+			return vec![Annotation {
+				kind,
+				file: Cow::Borrowed(""),
+				line: 0,
+				end_line: None,
+				col: None,
+				end_column: None,
+				code,
+				suggestions: Vec::new(),
+				title,
+				message,
+			}];
+		};
+
+		let mut notices: Vec<_> = self
+			.spans
+			.iter()
+			.filter(|span| !std::ptr::eq(span.user_span(), primary_span))
+			.map(|span| {
+				span.user_span().notice(
+					Cow::Borrowed(self.message),
+					span.label.clone().unwrap_or(Cow::Borrowed(self.message)),
+				)
+			})
+			.collect();
+		if options.child_notices {
+			notices.extend(self.children.iter().filter_map(|child| {
+				let span = child
+					.spans
+					.iter()
+					.find(|span| span.is_primary)
+					.or_else(|| child.spans.first())?;
+				Some(span.user_span().notice(
+					Cow::Owned(format!("{}: {}", child.level.name(), self.message)),
+					Cow::Borrowed(child.message),
+				))
+			}));
+		}
+		let mut annotations = vec![Annotation {
+			kind,
+			file: Cow::Borrowed(primary_span.file_name),
+			line: primary_span.line_start,
+			end_line: Some(primary_span.line_end),
+			col: Some(primary_span.column_start),
+			end_column: Some(primary_span.column_end),
+			code,
+			suggestions: self
+				.children
+				.iter()
+				.flat_map(|child| &child.spans)
+				.filter(|span| {
+					span.file_name == primary_span.file_name
+						&& span.suggestion_applicability == Some(Applicability::MachineApplicable)
+				})
+				.filter_map(|span| {
+					Some(Suggestion {
+						line: span.line_start,
+						col: span.column_start,
+						end_line: span.line_end,
+						end_column: span.column_end,
+						replacement: span.suggested_replacement.clone()?,
+					})
+				})
+				.collect(),
+			title,
+			message,
+		}];
+		annotations.extend(notices);
+		annotations
+	}
+
+	#[inline]
+	fn summarize(&self, options: &Options) -> Vec<Self::Summary> {
+		if self.is_suppressed(options) {
+			return Vec::new();
+		}
+		let mut summary = DiagnosticSummary::from(self);
+		summary.kind = options.lint_levels.kind(summary.code(), summary.kind);
+		if options.summary_snippets {
+			summary.rendered = self
+				.rendered
+				.as_deref()
+				.map(|rendered| strip_ansi(rendered).into_owned());
+		}
+		vec![summary]
+	}
+
+	#[inline]
+	fn rendered(&self) -> Option<&str> {
+		self.rendered.as_deref()
+	}
+}
+
+/// Unique code of a [`Diagnostic`]
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct DiagnosticCode<'m> {
+	/// The code itself (e.g. `E0308` or `clippy::needless_return`)
+	pub code: &'m str,
+}
+
+/// Severity of a [`Diagnostic`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiagnosticLevel {
+	/// A fatal error that prevents compilation
+	Error,
+	/// A possible error of concern
+	Warning,
+	/// Additional information or context about the diagnostic
+	Note,
+	/// A suggestion on how to resolve the diagnostic
+	Help,
+	/// A note attached to the message for further information
+	FailureNote,
+	/// Indicates a bug within the compiler
+	#[serde(rename = "error: internal compiler error")]
+	InternalCompilerError,
+}
+impl DiagnosticLevel {
+	/// Returns the name of the level, as rendered by rustc
+	#[inline]
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::Error => "error",
+			Self::Warning => "warning",
+			Self::Note => "note",
+			Self::Help => "help",
+			Self::FailureNote => "failure-note",
+			Self::InternalCompilerError => "error: internal compiler error",
+		}
+	}
+}
+
+/// The location of a diagnostic in the source code
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiagnosticSpan<'m> {
+	/// The file where the span is located
+	///
+	/// This path may not exist or may point to the source of an external crate.
+	pub file_name: &'m str,
+	/// The first line number of the span (1-based, inclusive)
+	pub line_start: usize,
+	/// The last line number of the span (1-based, inclusive)
+	pub line_end: usize,
+	/// The first column number of the span (1-based, inclusive)
+	pub column_start: usize,
+	/// The last column number of the span (1-based, exclusive)
+	pub column_end: usize,
+	/// This span is the "primary" span
+	pub is_primary: bool,
+	/// Source lines of the span
+	#[serde(default, borrow)]
+	pub text: Vec<DiagnosticSpanLine<'m>>,
+	/// Label of the span
+	#[serde(borrow)]
+	pub label: Option<Cow<'m, str>>,
+	/// Suggested replacement of the span
+	#[serde(borrow)]
+	pub suggested_replacement: Option<Cow<'m, str>>,
+	/// Confidence in the suggested replacement
+	pub suggestion_applicability: Option<Applicability>,
+	/// Macro expansion which produced the span, if any
+	#[serde(default, borrow)]
+	pub expansion: Option<Box<DiagnosticSpanMacroExpansion<'m>>>,
+}
+impl<'m> DiagnosticSpan<'m> {
+	/// Checks if the span is located in a source file of the user
+	///
+	/// Spans in macro expansions (e.g. `<macro expansion>`) or in the sources of external crates
+	/// (whose paths are absolute) are not.
+	fn is_in_user_code(&self) -> bool {
+		!self.file_name.starts_with('<') && Path::new(self.file_name).is_relative()
+	}
+
+	/// Returns the span where the user wrote the code producing this span
+	///
+	/// If the span is not in the code of the user, the chain of macro expansions is walked up to the invocation in the code of the user.
+	/// The span itself is returned if there is no such invocation.
+	pub fn user_span(&self) -> &Self {
+		let mut span = self;
+		while !span.is_in_user_code() {
+			match &span.expansion {
+				Some(expansion) => span = &expansion.span,
+				None => return self,
+			}
+		}
+		span
+	}
+
+	/// Creates a notice annotating the span
+	const fn notice(&self, title: Cow<'m, str>, message: Cow<'m, str>) -> Annotation<'m> {
+		Annotation {
+			kind: AnnotationKind::Notice,
+			file: Cow::Borrowed(self.file_name),
+			line: self.line_start,
+			end_line: Some(self.line_end),
+			col: Some(self.column_start),
+			end_column: Some(self.column_end),
+			code: None,
+			suggestions: Vec::new(),
+			title: Some(title),
+			message,
+		}
+	}
+}
+
+/// Macro expansion which produced a [`DiagnosticSpan`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiagnosticSpanMacroExpansion<'m> {
+	/// Span of the macro invocation
+	#[serde(borrow)]
+	pub span: DiagnosticSpan<'m>,
+}
+
+/// Source line of a [`DiagnosticSpan`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiagnosticSpanLine<'m> {
+	/// The line itself
+	#[serde(borrow)]
+	pub text: Cow<'m, str>,
+}
+
+/// Confidence in a suggested replacement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Applicability {
+	/// The suggestion is definitely what the user intended and can be applied automatically
+	MachineApplicable,
+	/// Any other applicability
+	#[serde(other)]
+	Other,
+}
+
+/// Summary info of [`Diagnostic`]
+#[derive(Debug, Clone)]
+pub struct DiagnosticSummary {
+	/// [`Diagnostic.level`](Diagnostic#structfield.level)
+	pub level: DiagnosticLevel,
+	/// Kind of the annotation of the diagnostic, once the [lint levels](crate::config::LintLevels) are applied
+	pub kind: AnnotationKind,
+	/// [`Diagnostic.message`](Diagnostic#structfield.message)
+	pub message: String,
+	/// [`Diagnostic.code`](Diagnostic#structfield.code)
+	code: Option<String>,
+	/// Location of the diagnostic (primary [span](crate::cargo::DiagnosticSpan))
+	pub location: Option<(String, usize)>,
+	/// Whether a machine-applicable fix is suggested for the diagnostic
+	fixable: bool,
+	/// Package and target which have output the diagnostic (e.g. ``` `foo` (lib) ```)
+	pub krate: Option<String>,
+	/// Name of the package which has output the diagnostic, if it is not the only member of the workspace
+	pub package: Option<String>,
+	/// Diagnostic as rendered by rustc, if it is to be written in the summary
+	rendered: Option<String>,
+}
+impl<'c> From<&'c Diagnostic<'c>> for DiagnosticSummary {
+	#[inline]
+	fn from(message: &'c Diagnostic<'c>) -> Self {
+		Self {
+			level: message.level,
+			kind: message.level.into(),
+			message: message.message.to_owned(),
+			code: message.code.map(|code| code.code.to_owned()),
+			location: message.spans.iter().find_map(|span| {
+				span.is_primary.then(|| {
+					let span = span.user_span();
+					(span.file_name.to_owned(), span.line_start)
+				})
+			}),
+			fixable: !Fix::from_diagnostic(message).is_empty(),
+			krate: None,
+			package: None,
+			rendered: None,
+		}
+	}
+}
+impl DiagnosticSummary {
+	/// Creates the summary of a diagnostic which was not output as JSON
+	#[inline]
+	pub fn new(level: DiagnosticLevel, message: String, location: Option<(String, usize)>) -> Self {
+		Self {
+			level,
+			kind: level.into(),
+			message,
+			code: None,
+			location,
+			fixable: false,
+			krate: None,
+			package: None,
+			rendered: None,
+		}
+	}
+
+	/// Returns the code of the diagnostic
+	#[inline]
+	pub fn code(&self) -> Option<&str> {
+		self.code.as_deref()
+	}
+
+	/// Checks if a machine-applicable fix is suggested for the diagnostic
+	#[inline]
+	pub const fn is_fixable(&self) -> bool {
+		self.fixable
+	}
+
+	/// Returns the message of the diagnostic, as displayed in the summary
+	///
+	/// The message is flagged if the diagnostic can be fixed automatically,
+	/// and followed by its code, linked to its documentation if possible.
+	/// Its pipes are escaped, as it is written in a table.
+	pub fn formatted_message(&self) -> String {
+		let escaped = self.message.replace('|', "\\|");
+		let mut message = if self.fixable {
+			format!(":wrench: {escaped}")
+		} else {
+			escaped
+		};
+		if let Some(code) = &self.code {
+			let _ = match code_url(code) {
+				Some(url) => write!(message, " ([`{code}`]({url}))"),
+				None => write!(message, " (`{code}`)"),
+			};
+		}
+		message
+	}
+}
+
+/// Returns the URL of the documentation of the diagnostic `code`, if known
+///
+/// Clippy lints link to the Clippy lint index, rustdoc lints to the rustdoc book
+/// and error codes to the rustc error index.
+pub fn code_url(code: &str) -> Option<String> {
+	if let Some(lint) = code.strip_prefix("clippy::") {
+		Some(format!(
+			"https://rust-lang.github.io/rust-clippy/master/index.html#{lint}"
+		))
+	} else if let Some(lint) = code.strip_prefix("rustdoc::") {
+		Some(format!(
+			"https://doc.rust-lang.org/rustdoc/lints.html#{lint}"
+		))
+	} else if code.len() == 5
+		&& code.starts_with('E')
+		&& code[1..].bytes().all(|byte| byte.is_ascii_digit())
+	{
+		Some(format!("https://doc.rust-lang.org/error_codes/{code}.html"))
+	} else {
+		None
+	}
+}
+
+/// Row of a table of [`DiagnosticSummaryWriter`]
+#[derive(Debug, Clone)]
+struct DiagnosticRow {
+	/// Kind of annotation of the diagnostic
+	kind: AnnotationKind,
+	/// Code of the diagnostic
+	code: Option<String>,
+	/// Line of the diagnostic (0 if it is not located in a file)
+	line: usize,
+	/// Markdown row of the table
+	row: String,
+	/// Diagnostic as rendered by rustc, if it is written in the summary
+	snippet: Option<String>,
+}
+
+impl DiagnosticRow {
+	/// Returns the properties by which the row can be sorted, given the `path` of its file
+	fn sort_key<'a>(&'a self, path: &'a str) -> SortKey<'a> {
+		SortKey {
+			kind: self.kind,
+			code: self.code.as_deref(),
+			file: path,
+			line: self.line,
+		}
+	}
+}
+
+/// Rows of diagnostics, grouped by file (if they are located in a file)
+type FileRows = BTreeMap<Option<String>, Vec<DiagnosticRow>>;
+
+/// [`SummaryWriter`] for [`DiagnosticSummary`]
+///
+/// The diagnostics are grouped by package, then by file in collapsible sections.
+#[derive(Debug, Default, Clone)]
+pub struct DiagnosticSummaryWriter {
+	/// Counter for each [`AnnotationKind`]
+	kind_count: HashMap<AnnotationKind, usize>,
+	/// Counter of the diagnostics which can be fixed automatically
+	fixable_count: usize,
+	/// Counter for each diagnostic code
+	code_count: HashMap<String, usize>,
+	/// Kinds and table rows of the diagnostics, grouped by package and by file (if they are known)
+	packages: BTreeMap<Option<String>, FileRows>,
+}
+impl DiagnosticSummaryWriter {
+	/// Checks if no diagnostic has been written
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.packages.is_empty()
+	}
+
+	/// Returns the diagnostic codes with their counts, from the most frequent
+	fn sorted_codes(&self) -> Vec<(&str, usize)> {
+		let mut codes: Vec<_> = self
+			.code_count
+			.iter()
+			.map(|(code, count)| (code.as_str(), *count))
+			.collect();
+		codes.sort_unstable_by(|(code_a, count_a), (code_b, count_b)| {
+			count_b.cmp(count_a).then_with(|| code_a.cmp(code_b))
+		});
+		codes
+	}
+
+	/// Selects the rows of the tables which fit in [`MAX_TABLES_SIZE`], in the order of the tables
+	///
+	/// The errors are kept first, then the warnings, then the notices.
+	fn kept_rows(&self) -> Vec<bool> {
+		let rows: Vec<_> = self
+			.packages
+			.values()
+			.flat_map(BTreeMap::values)
+			.flatten()
+			.collect();
+		let files = self.packages.values().map(BTreeMap::len).sum::<usize>();
+		let mut budget = MAX_TABLES_SIZE.saturating_sub(files * FILE_SECTION_SIZE);
+		let mut kept = vec![false; rows.len()];
+		for kind in [
+			AnnotationKind::Error,
+			AnnotationKind::Warning,
+			AnnotationKind::Notice,
+		] {
+			for (index, row) in rows.iter().enumerate().filter(|(_, row)| row.kind == kind) {
+				let size =
+					row.row.len() + row.snippet.as_ref().map_or(0, |snippet| snippet.len() + 2) + 1;
+				if size <= budget {
+					budget -= size;
+					kept[index] = true;
+				}
+			}
+		}
+		kept
+	}
+
+	/// Writes Mermaid charts of the counts of diagnostics by kind and of the most frequent codes
+	fn write_charts(&self, file: &mut dyn IoWrite) -> io::Result<()> {
+		writeln!(file, "```mermaid\npie title Diagnostics by level")?;
+		for (kind, label) in [
+			(AnnotationKind::Error, "Errors"),
+			(AnnotationKind::Warning, "Warnings"),
+			(AnnotationKind::Notice, "Notices"),
+		] {
+			if let Some(count) = self.kind_count.get(&kind).filter(|count| **count > 0) {
+				writeln!(file, "\t\"{label}\" : {count}")?;
+			}
+		}
+		writeln!(file, "```\n")?;
+		let codes: Vec<_> = self.sorted_codes().into_iter().take(CHART_CODES).collect();
+		if !codes.is_empty() {
+			writeln!(file, "```mermaid\nxychart-beta horizontal")?;
+			writeln!(file, "\ttitle \"Most frequent codes\"")?;
+			writeln!(
+				file,
+				"\tx-axis [{}]",
+				codes
+					.iter()
+					.map(|(code, _)| format!("\"{code}\""))
+					.collect::<Vec<_>>()
+					.join(", ")
+			)?;
+			writeln!(file, "\ty-axis \"Occurrences\"")?;
+			writeln!(
+				file,
+				"\tbar [{}]",
+				codes
+					.iter()
+					.map(|(_, count)| count.to_string())
+					.collect::<Vec<_>>()
+					.join(", ")
+			)?;
+			writeln!(file, "```\n")?;
+		}
+		Ok(())
+	}
+}
+
+/// Escapes the special characters of HTML in `s`, which is written inside of tags of the summary
+fn escape_html(s: &str) -> String {
+	s.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+}
+
+/// Formats the nonzero counts of `kinds` (e.g. `1 :x: Errors, 2 :warning: Warnings`)
+fn kind_counts<'k>(kinds: impl IntoIterator<Item = &'k AnnotationKind>) -> String {
+	let kinds: Vec<_> = kinds.into_iter().collect();
+	[
+		AnnotationKind::Error,
+		AnnotationKind::Warning,
+		AnnotationKind::Notice,
+	]
+	.into_iter()
+	.filter_map(|kind| {
+		let count = kinds.iter().filter(|row_kind| ***row_kind == kind).count();
+		(count > 0).then(|| format!("{count} {kind}s"))
+	})
+	.collect::<Vec<_>>()
+	.join(", ")
+}
+impl SummaryWriter for DiagnosticSummaryWriter {
+	type Summary = DiagnosticSummary;
+
+	fn write_summary(
+		&mut self,
+		summary: Self::Summary,
+		_content: &mut dyn FmtWrite,
+	) -> fmt::Result {
+		let kind = summary.kind;
+		*self.kind_count.entry(kind).or_default() += 1;
+		if summary.is_fixable() {
+			self.fixable_count += 1;
+		}
+		if let Some(code) = summary.code() {
+			*self.code_count.entry(code.to_owned()).or_default() += 1;
+		}
+		let snippet = summary.rendered.as_ref().map(|rendered| {
+			let line = summary
+				.location
+				.as_ref()
+				.map_or_else(String::new, |(_, line)| format!("Line {line}: "));
+			format!(
+				"<details><summary>{line}{}</summary>\n\n```text\n{}\n```\n\n</details>",
+				escape_html(&summary.message),
+				rendered.trim_end()
+			)
+		});
+		let (file, line) =
+			summary
+				.location
+				.as_ref()
+				.map_or((None, String::new()), |(file, line)| {
+					let line = match source_link(file, *line) {
+						Some(link) => format!("[`{line}`]({link})"),
+						None => format!("`{line}`"),
+					};
+					(Some(file.clone()), line)
+				});
+		let row = format!(
+			"|{kind}|{}|{}|{line}|",
+			summary.formatted_message(),
+			summary.krate.as_deref().unwrap_or_default()
+		);
+		self.packages
+			.entry(summary.package)
+			.or_default()
+			.entry(file)
+			.or_default()
+			.push(DiagnosticRow {
+				kind,
+				code: summary.code,
+				line: summary.location.map_or(0, |(_, line)| line),
+				row,
+				snippet,
+			});
+		Ok(())
+	}
+
+	fn write_preamble(&self, file: &mut dyn IoWrite, options: &Options) -> io::Result<()> {
+		writeln!(
+			file,
+			"> **TOTAL:** {} {}s, {} {}s, {} {}s\n",
+			self.kind_count
+				.get(&AnnotationKind::Error)
+				.copied()
+				.unwrap_or_default(),
+			AnnotationKind::Error,
+			self.kind_count
+				.get(&AnnotationKind::Warning)
+				.copied()
+				.unwrap_or_default(),
+			AnnotationKind::Warning,
+			self.kind_count
+				.get(&AnnotationKind::Notice)
+				.copied()
+				.unwrap_or_default(),
+			AnnotationKind::Notice,
+		)?;
+		if self.fixable_count > 0 {
+			writeln!(
+				file,
+				"> :wrench: {} can be fixed automatically\n",
+				self.fixable_count
+			)?;
+		}
+		if options.summary_charts && !self.is_empty() {
+			self.write_charts(file)?;
+		}
+		Ok(())
+	}
+
+	fn write_postamble(self, file: &mut dyn IoWrite, options: &Options) -> io::Result<()> {
+		if !self.code_count.is_empty() {
+			let total: usize = self.code_count.values().sum();
+			writeln!(file, "|Code|Count|Share|")?;
+			writeln!(file, "|:--|--:|--:|")?;
+			for (code, count) in self.sorted_codes() {
+				let code = match code_url(code) {
+					Some(url) => format!("[`{code}`]({url})"),
+					None => format!("`{code}`"),
+				};
+				writeln!(file, "|{code}|{count}|{}%|", count * 100 / total)?;
+			}
+			writeln!(file)?;
+		}
+		let mut kept = self.kept_rows().into_iter();
+		let sort_order = options.sort_order;
+		let code_count = &self.code_count;
+		let count = |code: Option<&str>| {
+			code.and_then(|code| code_count.get(code))
+				.copied()
+				.unwrap_or_default()
+		};
+		let split = self.packages.len() > 1;
+		let mut omitted = 0;
+		for (package, files) in self.packages {
+			let subtotal = kind_counts(files.values().flatten().map(|row| &row.kind));
+			let mut files: Vec<_> = files
+				.into_iter()
+				.map(|(path, rows)| {
+					let counts = kind_counts(rows.iter().map(|row| &row.kind));
+					let total = rows.len();
+					let mut rows: Vec<_> = rows
+						.into_iter()
+						.filter(|_| kept.next().unwrap_or_default())
+						.collect();
+					omitted += total - rows.len();
+					if let Some(order) = sort_order {
+						let path = path.as_deref().unwrap_or_default();
+						rows.sort_by(|a, b| {
+							order.compare(&a.sort_key(path), &b.sort_key(path), count)
+						});
+					}
+					(path, counts, rows)
+				})
+				.filter(|(_, _, rows)| !rows.is_empty())
+				.collect();
+			if sort_order == Some(SortOrder::Severity) {
+				// The files with the most severe diagnostics come first
+				files.sort_by_key(|(_, _, rows)| Reverse(rows.iter().map(|row| row.kind).max()));
+			}
+			if files.is_empty() {
+				continue;
+			}
+			if package.is_some() || split {
+				writeln!(
+					file,
+					"### {}\n",
+					package.map_or_else(
+						|| "Other diagnostics".to_owned(),
+						|package| format!("`{package}`")
+					)
+				)?;
+				writeln!(file, "> **SUBTOTAL:** {subtotal}\n")?;
+			}
+			for (path, counts, rows) in files {
+				writeln!(
+					file,
+					"<details><summary><code>{}</code>: {counts}</summary>\n",
+					escape_html(path.as_deref().unwrap_or("(no file)")),
+				)?;
+				writeln!(file, "|Level|Message|Crate|Line|")?;
+				writeln!(file, "|:--|:--|:--|--:|")?;
+				for row in &rows {
+					writeln!(file, "{}", row.row)?;
+				}
+				writeln!(file)?;
+				for row in rows {
+					if let Some(snippet) = row.snippet {
+						writeln!(file, "{snippet}\n")?;
+					}
+				}
+				writeln!(file, "</details>\n")?;
+			}
+		}
+		if omitted > 0 {
+			writeln!(
+				file,
+				"> :scissors: {omitted} more issues omitted to fit the size limit of the summary\n"
+			)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::config::LintLevels;
+
+	/// Warning of Clippy's `let_and_return`, with a secondary span and a child with spans
+	const LET_AND_RETURN: &str = r#"{"rendered":"warning: returning the result of a `let` binding from a block\n --> src/lib.rs:3:5\n  |\n2 |     let x = 42;\n  |     ----------- unnecessary `let` binding\n3 |     x\n  |     ^\n  |\n  = help: for further information visit https://rust-lang.github.io/rust-clippy/rust-1.95.0/index.html#let_and_return\n  = note: `#[warn(clippy::let_and_return)]` on by default\nhelp: return the expression directly\n  |\n2 ~     \n3 ~     42\n  |\n\n","$message_type":"diagnostic","children":[{"children":[],"code":null,"level":"help","message":"for further information visit https://rust-lang.github.io/rust-clippy/rust-1.95.0/index.html#let_and_return","rendered":null,"spans":[]},{"children":[],"code":null,"level":"note","message":"`#[warn(clippy::let_and_return)]` on by default","rendered":null,"spans":[]},{"children":[],"code":null,"level":"help","message":"return the expression directly","rendered":null,"spans":[{"byte_end":40,"byte_start":29,"column_end":16,"column_start":5,"expansion":null,"file_name":"src/lib.rs","is_primary":true,"label":null,"line_end":2,"line_start":2,"suggested_replacement":"","suggestion_applicability":"MachineApplicable","text":[{"highlight_end":16,"highlight_start":5,"text":"    let x = 42;"}]},{"byte_end":46,"byte_start":45,"column_end":6,"column_start":5,"expansion":null,"file_name":"src/lib.rs","is_primary":true,"label":null,"line_end":3,"line_start":3,"suggested_replacement":"42","suggestion_applicability":"MachineApplicable","text":[{"highlight_end":6,"highlight_start":5,"text":"    x"}]}]}],"level":"warning","message":"returning the result of a `let` binding from a block","spans":[{"byte_end":40,"byte_start":29,"column_end":16,"column_start":5,"expansion":null,"file_name":"src/lib.rs","is_primary":false,"label":"unnecessary `let` binding","line_end":2,"line_start":2,"suggested_replacement":null,"suggestion_applicability":null,"text":[{"highlight_end":16,"highlight_start":5,"text":"    let x = 42;"}]},{"byte_end":46,"byte_start":45,"column_end":6,"column_start":5,"expansion":null,"file_name":"src/lib.rs","is_primary":true,"label":null,"line_end":3,"line_start":3,"suggested_replacement":null,"suggestion_applicability":null,"text":[{"highlight_end":6,"highlight_start":5,"text":"    x"}]}],"code":{"code":"clippy::let_and_return","explanation":null}}"#;
+
+	/// Warning of Clippy's `bind_instead_of_map`, whose message contains pipes
+	const BIND_INSTEAD_OF_MAP: &str = r#"{"rendered":"warning: using `Option.and_then(|x| Some(y))`, which is more succinctly expressed as `map(|x| y)`\n --> src/lib.rs:2:5\n  |\n2 |     x.and_then(|x| Some(x * 2))\n  |     ^^^^^^^^^^^^^^^^^^^^^^^^^^^ help: try: `x.map(|x| x * 2)`\n  |\n  = help: for further information visit https://rust-lang.github.io/rust-clippy/rust-1.95.0/index.html#bind_instead_of_map\n  = note: `#[warn(clippy::bind_instead_of_map)]` on by default\n\n","$message_type":"diagnostic","children":[{"children":[],"code":null,"level":"help","message":"for further information visit https://rust-lang.github.io/rust-clippy/rust-1.95.0/index.html#bind_instead_of_map","rendered":null,"spans":[]},{"children":[],"code":null,"level":"note","message":"`#[warn(clippy::bind_instead_of_map)]` on by default","rendered":null,"spans":[]},{"children":[],"code":null,"level":"help","message":"try","rendered":null,"spans":[{"byte_end":78,"byte_start":51,"column_end":32,"column_start":5,"expansion":null,"file_name":"src/lib.rs","is_primary":true,"label":null,"line_end":2,"line_start":2,"suggested_replacement":"x.map(|x| x * 2)","suggestion_applicability":"MachineApplicable","text":[{"highlight_end":32,"highlight_start":5,"text":"    x.and_then(|x| Some(x * 2))"}]}]}],"level":"warning","message":"using `Option.and_then(|x| Some(y))`, which is more succinctly expressed as `map(|x| y)`","spans":[{"byte_end":78,"byte_start":51,"column_end":32,"column_start":5,"expansion":null,"file_name":"src/lib.rs","is_primary":true,"label":null,"line_end":2,"line_start":2,"suggested_replacement":null,"suggestion_applicability":null,"text":[{"highlight_end":32,"highlight_start":5,"text":"    x.and_then(|x| Some(x * 2))"}]}],"code":{"code":"clippy::bind_instead_of_map","explanation":null}}"#;
+
+	/// Error raised in the expansion of `assert_eq!`, whose primary span is in the sources of the standard library
+	const MACRO_EXPANSION: &str = r#"{"$message_type":"diagnostic","message":"mismatched types","code":{"code":"E0308","explanation":null},"level":"error","spans":[{"file_name":"/rustc/59807616e1fa2540724bfbac14d7976d7e4a3860/library/core/src/macros/mod.rs","byte_start":1422,"byte_end":1432,"line_start":44,"line_end":44,"column_start":26,"column_end":36,"is_primary":true,"text":[],"label":"expected `u32`, found `u64`","suggested_replacement":null,"suggestion_applicability":null,"expansion":{"span":{"file_name":"<::core::macros::assert_eq macros>","byte_start":0,"byte_end":48,"line_start":1,"line_end":1,"column_start":1,"column_end":49,"is_primary":false,"text":[],"label":null,"suggested_replacement":null,"suggestion_applicability":null,"expansion":{"span":{"file_name":"src/lib.rs","byte_start":38,"byte_end":61,"line_start":3,"line_end":3,"column_start":5,"column_end":28,"is_primary":false,"text":[{"text":"    assert_eq!(x, 42_u64);","highlight_start":5,"highlight_end":28}],"label":null,"suggested_replacement":null,"suggestion_applicability":null,"expansion":null},"macro_decl_name":"assert_eq!","def_site_span":null}},"macro_decl_name":"$crate::assert_eq!","def_site_span":null}}],"children":[],"rendered":null}"#;
+
+	/// Wrap-up error of rustc
+	const ABORTING: &str = r#"{"$message_type":"diagnostic","message":"aborting due to 1 previous error","code":null,"level":"error","spans":[],"children":[],"rendered":"error: aborting due to 1 previous error\n\n"}"#;
+
+	/// Wrap-up warning of rustc
+	const WARNINGS_EMITTED: &str = r#"{"$message_type":"diagnostic","message":"2 warnings emitted","code":null,"level":"warning","spans":[],"children":[],"rendered":"warning: 2 warnings emitted\n\n"}"#;
+
+	#[test]
+	fn remapped_summary() {
+		let options = Options {
+			lint_levels: LintLevels {
+				severity: HashMap::from([(AnnotationKind::Warning, AnnotationKind::Notice)]),
+				lints: HashMap::from([(
+					"clippy::bind_instead_of_map".to_owned(),
+					AnnotationKind::Error,
+				)]),
+				..LintLevels::default()
+			},
+			..Options::default()
+		};
+		let mut writer = DiagnosticSummaryWriter::default();
+		for fixture in [BIND_INSTEAD_OF_MAP, LET_AND_RETURN] {
+			let diagnostic: Diagnostic = serde_json::from_str(fixture).unwrap();
+			for summary in diagnostic.summarize(&options) {
+				writer.write_summary(summary, &mut String::new()).unwrap();
+			}
+		}
+		let mut summary = Vec::new();
+		writer.write_preamble(&mut summary, &options).unwrap();
+		writer.write_postamble(&mut summary, &options).unwrap();
+		// The lines are linked to the sources when run in a GitHub workflow
+		let line = |line| {
+			source_link("src/lib.rs", line)
+				.map_or_else(|| format!("`{line}`"), |link| format!("[`{line}`]({link})"))
+		};
+		assert_eq!(
+			String::from_utf8(summary).unwrap(),
+			format!(
+				r"> **TOTAL:** 1 :x: Errors, 0 :warning: Warnings, 1 :information_source: Notices
+
+> :wrench: 2 can be fixed automatically
+
+|Code|Count|Share|
+|:--|--:|--:|
+|[`clippy::bind_instead_of_map`](https://rust-lang.github.io/rust-clippy/master/index.html#bind_instead_of_map)|1|50%|
+|[`clippy::let_and_return`](https://rust-lang.github.io/rust-clippy/master/index.html#let_and_return)|1|50%|
+
+<details><summary><code>src/lib.rs</code>: 1 :x: Errors, 1 :information_source: Notices</summary>
+
+|Level|Message|Crate|Line|
+|:--|:--|:--|--:|
+|:x: Error|:wrench: using `Option.and_then(\|x\| Some(y))`, which is more succinctly expressed as `map(\|x\| y)` ([`clippy::bind_instead_of_map`](https://rust-lang.github.io/rust-clippy/master/index.html#bind_instead_of_map))||{}|
+|:information_source: Notice|:wrench: returning the result of a `let` binding from a block ([`clippy::let_and_return`](https://rust-lang.github.io/rust-clippy/master/index.html#let_and_return))||{}|
+
+</details>
+
+",
+				line(2),
+				line(3)
+			)
+		);
+	}
+
+	#[test]
+	fn notices() {
+		let diagnostic: Diagnostic = serde_json::from_str(LET_AND_RETURN).unwrap();
+		let notices = |options: &Options| -> Vec<_> {
+			diagnostic
+				.clone()
+				.into_annotations(options)
+				.into_iter()
+				.filter(|annotation| annotation.kind == AnnotationKind::Notice)
+				.map(|annotation| {
+					(
+						annotation.line,
+						annotation.col,
+						annotation.end_column,
+						annotation.title.unwrap_or_default(),
+						annotation.message,
+					)
+				})
+				.collect()
+		};
+		// The secondary span is annotated with its label
+		let secondary = (
+			2,
+			Some(5),
+			Some(16),
+			Cow::Borrowed("returning the result of a `let` binding from a block"),
+			Cow::Borrowed("unnecessary `let` binding"),
+		);
+		assert_eq!(
+			notices(&Options::default()),
+			std::slice::from_ref(&secondary)
+		);
+
+		// The children with spans are annotated at their primary span
+		let options = Options {
+			child_notices: true,
+			..Options::default()
+		};
+		assert_eq!(
+			notices(&options),
+			[
+				secondary,
+				(
+					2,
+					Some(5),
+					Some(16),
+					Cow::Borrowed("help: returning the result of a `let` binding from a block"),
+					Cow::Borrowed("return the expression directly"),
+				),
+			]
+		);
+	}
+
+	#[test]
+	fn ignored_lint() {
+		let diagnostic: Diagnostic = serde_json::from_str(LET_AND_RETURN).unwrap();
+		let options = Options {
+			child_notices: true,
+			..Options::default()
+		};
+		let annotations: Vec<_> = diagnostic
+			.clone()
+			.into_annotations(&options)
+			.into_iter()
+			.map(|annotation| (annotation.kind, annotation.line, annotation.code))
+			.collect();
+		assert_eq!(
+			annotations,
+			[
+				(
+					AnnotationKind::Warning,
+					3,
+					Some(Cow::Borrowed("clippy::let_and_return"))
+				),
+				(AnnotationKind::Notice, 2, None),
+				(AnnotationKind::Notice, 2, None),
+			]
+		);
+
+		// The secondary span and the child are not annotated either
+		let options = Options {
+			lint_levels: LintLevels {
+				ignored: vec!["clippy::let_and_return".to_owned()],
+				..LintLevels::default()
+			},
+			..options
+		};
+		assert!(diagnostic.summarize(&options).is_empty());
+		assert!(diagnostic.into_annotations(&options).is_empty());
+	}
+
+	#[test]
+	fn escaped_summary() {
+		let fixture = BIND_INSTEAD_OF_MAP
+			.replace(
+				r#""message":"using `Option.and_then(|x| Some(y))`, which is more succinctly expressed as `map(|x| y)`""#,
+				r#""message":"expected `Vec<T>`, found `&[T]`""#,
+			)
+			.replace("src/lib.rs", "src/a&b.rs");
+		let diagnostic: Diagnostic = serde_json::from_str(&fixture).unwrap();
+		let summary = crate::cargo::tests::summary::<_, DiagnosticSummaryWriter>(
+			&[diagnostic],
+			&Options {
+				summary_snippets: true,
+				..Options::default()
+			},
+		);
+		assert!(summary.contains("<details><summary><code>src/a&amp;b.rs</code>: "));
+		assert!(summary.contains(
+			"<details><summary>Line 2: expected `Vec&lt;T&gt;`, found `&amp;[T]`</summary>"
+		));
+	}
+
+	#[test]
+	fn macro_expansion() {
+		let diagnostic: Diagnostic = serde_json::from_str(MACRO_EXPANSION).unwrap();
+		let primary_span = &diagnostic.spans[0];
+		let user_span = primary_span.user_span();
+		assert_eq!(
+			(
+				user_span.file_name,
+				user_span.line_start,
+				user_span.column_start
+			),
+			("src/lib.rs", 3, 5)
+		);
+
+		// The error is annotated on the invocation of the macro in the code of the user
+		let annotations: Vec<_> = diagnostic
+			.into_annotations(&Options::default())
+			.into_iter()
+			.map(|annotation| {
+				(
+					annotation.kind,
+					annotation.file,
+					annotation.line,
+					annotation.col,
+					annotation.end_column,
+				)
+			})
+			.collect();
+		assert_eq!(
+			annotations,
+			[(
+				AnnotationKind::Error,
+				Cow::Borrowed("src/lib.rs"),
+				3,
+				Some(5),
+				Some(28)
+			)]
+		);
+	}
+
+	#[test]
+	fn meta_diagnostics() {
+		for fixture in [ABORTING, WARNINGS_EMITTED] {
+			let diagnostic: Diagnostic = serde_json::from_str(fixture).unwrap();
+			assert!(diagnostic.is_meta());
+			// The wrap-up diagnostics are only annotated if requested
+			assert!(diagnostic
+				.clone()
+				.into_annotations(&Options::default())
+				.is_empty());
+			let options = Options {
+				meta_diagnostics: true,
+				..Options::default()
+			};
+			let annotations = diagnostic.into_annotations(&options);
+			assert_eq!(annotations.len(), 1);
+			assert!(!annotations[0].is_located());
+		}
+
+		let diagnostic: Diagnostic = serde_json::from_str(LET_AND_RETURN).unwrap();
+		assert!(!diagnostic.is_meta());
+	}
+}
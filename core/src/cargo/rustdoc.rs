@@ -0,0 +1,91 @@
+//! Provides the structs to work with rustdoc's output
+
+use super::{DiagnosticSummary, DiagnosticSummaryWriter, Options, SummaryWriter};
+use std::{
+	collections::BTreeMap,
+	fmt::{self, Display, Formatter, Write as FmtWrite},
+	io::{self, Write as IoWrite},
+};
+
+/// Category of documentation issue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum DocCheck {
+	/// Intra-doc link that cannot be resolved
+	BrokenIntraDocLinks,
+	/// URL that is not a hyperlink
+	BareUrls,
+	/// Code block that cannot be parsed or has invalid attributes
+	InvalidCodeblocks,
+	/// Invalid HTML tag
+	InvalidHtmlTags,
+	/// Any other issue
+	Other,
+}
+impl From<Option<&str>> for DocCheck {
+	#[inline]
+	fn from(code: Option<&str>) -> Self {
+		match code {
+			Some("rustdoc::broken_intra_doc_links" | "rustdoc::private_intra_doc_links") => {
+				Self::BrokenIntraDocLinks
+			}
+			Some("rustdoc::bare_urls") => Self::BareUrls,
+			Some("rustdoc::invalid_rust_codeblocks" | "rustdoc::invalid_codeblock_attributes") => {
+				Self::InvalidCodeblocks
+			}
+			Some("rustdoc::invalid_html_tags") => Self::InvalidHtmlTags,
+			_ => Self::Other,
+		}
+	}
+}
+impl Display for DocCheck {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::BrokenIntraDocLinks => write!(f, "Broken intra-doc links"),
+			Self::BareUrls => write!(f, "Bare URLs"),
+			Self::InvalidCodeblocks => write!(f, "Invalid code blocks"),
+			Self::InvalidHtmlTags => write!(f, "Invalid HTML tags"),
+			Self::Other => write!(f, "Other"),
+		}
+	}
+}
+
+/// [`SummaryWriter`] for the [`DiagnosticSummary`] of rustdoc
+#[derive(Debug, Default, Clone)]
+pub struct RustdocSummaryWriter {
+	/// [`SummaryWriter`] for the diagnostics themselves
+	diagnostics: DiagnosticSummaryWriter,
+	/// Counter for each [`DocCheck`]
+	check_count: BTreeMap<DocCheck, usize>,
+}
+impl SummaryWriter for RustdocSummaryWriter {
+	type Summary = DiagnosticSummary;
+
+	#[inline]
+	fn write_summary(&mut self, summary: Self::Summary, content: &mut dyn FmtWrite) -> fmt::Result {
+		*self
+			.check_count
+			.entry(DocCheck::from(summary.code()))
+			.or_default() += 1;
+		self.diagnostics.write_summary(summary, content)
+	}
+
+	fn write_preamble(&self, file: &mut dyn IoWrite, options: &Options) -> io::Result<()> {
+		writeln!(file, "### Documentation health\n")?;
+		if self.check_count.is_empty() {
+			writeln!(file, ":white_check_mark: No documentation issue\n")?;
+		} else {
+			writeln!(file, "|Check|Count|")?;
+			writeln!(file, "|:--|--:|")?;
+			for (check, count) in &self.check_count {
+				writeln!(file, "|{check}|{count}|")?;
+			}
+			writeln!(file)?;
+		}
+		self.diagnostics.write_preamble(file, options)
+	}
+
+	#[inline]
+	fn write_postamble(self, file: &mut dyn IoWrite, options: &Options) -> io::Result<()> {
+		self.diagnostics.write_postamble(file, options)
+	}
+}
@@ -0,0 +1,475 @@
+//! Provides the structs to work with rustfmt's output
+
+use super::{relative_path, strip_ansi, HandleMessage, Options, SummaryWriter};
+use crate::github::{source_link, Annotation, AnnotationKind, Suggestion};
+use serde::Deserialize;
+use std::{
+	borrow::Cow,
+	env,
+	ffi::{OsStr, OsString},
+	fmt::{self, Display, Formatter, Write as FmtWrite},
+	fs,
+	io::{self, Write as IoWrite},
+	path::{Path, PathBuf},
+	process::{Command, Stdio},
+};
+use tracing::debug;
+
+/// Names of the toolchain files of rustup, by precedence
+const TOOLCHAIN_FILES: [&str; 2] = ["rust-toolchain", "rust-toolchain.toml"];
+
+/// Where the rustfmt with which `cargo fmt` is run is found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RustfmtSource {
+	/// Executable of rustfmt, installed outside of rustup
+	Executable(PathBuf),
+	/// Toolchain of rustup (e.g. `nightly-2024-06-01`)
+	Toolchain(String),
+	/// rustfmt found by the given executable of Cargo, as rustup is unavailable
+	Cargo(OsString),
+}
+impl RustfmtSource {
+	/// Returns the command printing the version of this rustfmt
+	fn version(&self) -> Command {
+		let mut command = match self {
+			Self::Executable(executable) => Command::new(executable),
+			Self::Toolchain(toolchain) => {
+				let mut command = Command::new("rustup");
+				command.args(["run", toolchain, "rustfmt"]);
+				command
+			}
+			Self::Cargo(cargo) => {
+				let mut command = Command::new(cargo);
+				command.arg("fmt");
+				command
+			}
+		};
+		command.arg("--version");
+		command
+	}
+}
+impl Display for RustfmtSource {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Executable(executable) => {
+				write!(f, "the rustfmt executable `{}`", executable.display())
+			}
+			Self::Toolchain(toolchain) => write!(f, "the rustfmt of the `{toolchain}` toolchain"),
+			Self::Cargo(cargo) => write!(f, "the rustfmt of `{}`", cargo.to_string_lossy()),
+		}
+	}
+}
+
+/// rustfmt with which `cargo fmt` is run, along with its version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rustfmt {
+	/// Where rustfmt is found
+	pub source: RustfmtSource,
+	/// Version of rustfmt (e.g. `rustfmt 1.8.0-nightly (...)`), or [`None`] if it could not be invoked
+	pub version: Option<String>,
+}
+impl Rustfmt {
+	/// Selects the rustfmt executable at `executable`, or else the `toolchain`, and invokes it for its version
+	///
+	/// If neither is given, the *nightly* toolchain pinned by the toolchain file of the current directory
+	/// (or of its ancestors) is selected, or else `nightly`.
+	/// If rustup is unavailable (e.g. in minimal container images), the rustfmt found by `cargo` is selected instead.
+	#[must_use]
+	pub fn select(executable: Option<PathBuf>, toolchain: Option<String>, cargo: &OsStr) -> Self {
+		let source = match (executable, toolchain) {
+			(Some(executable), _) => RustfmtSource::Executable(executable),
+			(None, Some(toolchain)) => RustfmtSource::Toolchain(toolchain),
+			(None, None) if version(Command::new("rustup").arg("--version")).is_none() => {
+				RustfmtSource::Cargo(cargo.to_owned())
+			}
+			(None, None) => RustfmtSource::Toolchain(
+				env::current_dir()
+					.ok()
+					.and_then(|dir| pinned_nightly(&dir))
+					.unwrap_or_else(|| "nightly".to_owned()),
+			),
+		};
+		let version = version(&mut source.version());
+		Self { source, version }
+	}
+
+	/// Returns whether this rustfmt could be invoked
+	#[inline]
+	#[must_use]
+	pub const fn is_available(&self) -> bool {
+		self.version.is_some()
+	}
+
+	/// Returns whether this rustfmt is *nightly*, and may thus output JSON messages
+	///
+	/// Otherwise, the mismatches are read from the diff output by `cargo fmt --check`.
+	#[inline]
+	#[must_use]
+	pub fn is_nightly(&self) -> bool {
+		self.version
+			.as_deref()
+			.is_some_and(|version| version.contains("-nightly"))
+	}
+
+	/// Returns the command running `cargo fmt` with this rustfmt
+	///
+	/// `cargo` is the executable of Cargo run along with an [executable](RustfmtSource::Executable).
+	/// The command outputs JSON messages if this rustfmt [is *nightly*](Self::is_nightly),
+	/// or else a diff to be read with [`FormatDiffParser`].
+	#[must_use]
+	pub fn cargo_fmt(&self, cargo: &OsStr) -> Command {
+		let mut command = match &self.source {
+			RustfmtSource::Executable(executable) => {
+				// cargo-fmt runs the rustfmt found in this variable
+				let mut command = Command::new(cargo);
+				command.env("RUSTFMT", executable);
+				command
+			}
+			RustfmtSource::Toolchain(toolchain) => {
+				let mut command = Command::new("rustup");
+				command.args(["run", toolchain, "cargo"]);
+				command
+			}
+			RustfmtSource::Cargo(cargo) => Command::new(cargo),
+		};
+		command.arg("fmt").arg(if self.is_nightly() {
+			"--message-format=json"
+		} else {
+			"--check"
+		});
+		command
+	}
+}
+
+/// Returns the first line output by `command`, if it succeeded
+pub(super) fn version(command: &mut Command) -> Option<String> {
+	let output = command
+		.stdin(Stdio::null())
+		.stderr(Stdio::null())
+		.output()
+		.ok()?;
+	output.status.success().then(|| {
+		String::from_utf8_lossy(&output.stdout)
+			.lines()
+			.next()
+			.unwrap_or_default()
+			.to_owned()
+	})
+}
+
+/// Returns the *nightly* channel pinned by the toolchain file of rustup in `dir` or in its ancestors
+///
+/// Returns [`None`] if there is no toolchain file, or if it does not pin a *nightly* channel.
+fn pinned_nightly(dir: &Path) -> Option<String> {
+	/// Contents of `rust-toolchain.toml`
+	#[derive(Debug, Deserialize)]
+	struct ToolchainFile {
+		/// `[toolchain]` table
+		toolchain: ToolchainTable,
+	}
+	/// `[toolchain]` table of `rust-toolchain.toml`
+	#[derive(Debug, Deserialize)]
+	struct ToolchainTable {
+		/// Channel of the toolchain (e.g. `nightly-2024-06-01`)
+		channel: Option<String>,
+	}
+
+	// As rustup does, only the closest toolchain file is read
+	let content = dir
+		.ancestors()
+		.flat_map(|dir| TOOLCHAIN_FILES.map(|name| dir.join(name)))
+		.find_map(|path| fs::read_to_string(path).ok())?;
+	let channel = match toml::from_str::<ToolchainFile>(&content) {
+		Ok(file) => file.toolchain.channel?,
+		// The legacy `rust-toolchain` file may only contain the channel
+		Err(_) => content.trim().to_owned(),
+	};
+	channel.starts_with("nightly").then_some(channel)
+}
+
+/// Message output by rustfmt
+#[derive(Debug, Clone, Deserialize)]
+pub struct FormatMismatches<'m> {
+	/// The file where the mismatches are located
+	#[serde(borrow)]
+	pub name: Cow<'m, str>,
+	/// Reported errors and their locations
+	#[serde(borrow)]
+	pub mismatches: Vec<FormatMismatch<'m>>,
+}
+impl<'m> HandleMessage<'m> for Vec<FormatMismatches<'m>> {
+	type Summary = FormatMismatchesSummary;
+
+	#[inline]
+	fn into_annotations(self, _options: &Options) -> Vec<Annotation<'m>> {
+		self.into_iter()
+			.flat_map(|mismatches| {
+				let file = relative_path(Path::new(mismatches.name.as_ref()));
+				mismatches
+					.mismatches
+					.into_iter()
+					.map(move |mismatch| Annotation {
+						kind: AnnotationKind::Warning,
+						file: Cow::Owned(file.clone()),
+						line: mismatch.original_begin_line,
+						end_line: Some(mismatch.original_end_line),
+						col: None,
+						end_column: None,
+						code: None,
+						suggestions: vec![Suggestion {
+							line: mismatch.original_begin_line,
+							col: 1,
+							end_line: mismatch.original_end_line + 1,
+							end_column: 1,
+							replacement: if mismatch.expected.is_empty()
+								|| mismatch.expected.ends_with('\n')
+							{
+								mismatch.expected.clone()
+							} else {
+								Cow::Owned(format!("{}\n", mismatch.expected))
+							},
+						}],
+						title: Some(Cow::Borrowed("Format mismatch")),
+						message: mismatch.expected,
+					})
+			})
+			.collect()
+	}
+
+	#[inline]
+	fn summarize(&self, _options: &Options) -> Vec<Self::Summary> {
+		self.iter().map(From::from).collect()
+	}
+}
+
+#[allow(dead_code)]
+/// Reported errors contained in a single file
+#[derive(Debug, Clone, Deserialize)]
+pub struct FormatMismatch<'m> {
+	/// The first line of the span in the current file (1-based, inclusive)
+	pub original_begin_line: usize,
+	/// The last line of the span in the current file (1-based, inclusive)
+	pub original_end_line: usize,
+	/// The first line of the span if the file was correct (1-based, inclusive)
+	pub expected_begin_line: usize,
+	/// The last line of the span if the file was correct (1-based, inclusive)
+	pub expected_end_line: usize,
+	/// The current code
+	pub original: Cow<'m, str>,
+	/// The corrected code
+	pub expected: Cow<'m, str>,
+}
+impl FormatMismatch<'_> {
+	/// Returns the mismatch as a unified diff (without header)
+	fn diff(&self) -> String {
+		self.original
+			.lines()
+			.map(|line| format!("-{line}\n"))
+			.chain(self.expected.lines().map(|line| format!("+{line}\n")))
+			.collect()
+	}
+}
+
+/// Parser of the diff output by `cargo fmt --check`, which does not require a *nightly* toolchain
+///
+/// The diff is made of chunks introduced by `Diff in <FILE>:<LINE>:`,
+/// whose lines are prefixed with ` ` (unchanged), `-` (current code) or `+` (corrected code).
+#[derive(Debug, Default)]
+pub struct FormatDiffParser {
+	/// Mismatches of the file being parsed
+	mismatches: Option<FormatMismatches<'static>>,
+	/// Mismatch being parsed
+	mismatch: Option<FormatMismatch<'static>>,
+	/// Next line of the chunk in the current file
+	original_line: usize,
+	/// Next line of the chunk if the file was correct
+	expected_line: usize,
+	/// Lines added by the previous chunks of the file, minus the removed ones
+	offset: isize,
+}
+impl FormatDiffParser {
+	/// Feeds a line of the diff to the parser
+	///
+	/// Returns the mismatches of a file once all of them have been parsed,
+	/// i.e. when the next file starts or when an empty line is fed at the end of the diff.
+	pub fn feed(&mut self, line: &str) -> Option<FormatMismatches<'static>> {
+		// rustfmt colors the diff even if its output is not a terminal
+		let line = strip_ansi(line);
+		let line = line.as_ref();
+		if let Some((file, line)) = line
+			.strip_prefix("Diff in ")
+			.and_then(|header| header.strip_suffix(':'))
+			.and_then(|header| {
+				// Older versions of rustfmt output `Diff in <FILE> at line <LINE>:`
+				header
+					.rsplit_once(" at line ")
+					.or_else(|| header.rsplit_once(':'))
+			}) {
+			let line = line.parse().ok()?;
+			self.end_mismatch();
+			let finished = match &self.mismatches {
+				Some(mismatches) if mismatches.name == file => None,
+				_ => {
+					self.offset = 0;
+					self.mismatches.replace(FormatMismatches {
+						name: Cow::Owned(file.to_owned()),
+						mismatches: Vec::new(),
+					})
+				}
+			};
+			self.original_line = line;
+			self.expected_line = line.saturating_add_signed(self.offset);
+			return finished.filter(|mismatches| !mismatches.mismatches.is_empty());
+		}
+		let Some(mismatches) = &self.mismatches else {
+			return None;
+		};
+		let (original, expected) = (self.original_line, self.expected_line);
+		if let Some(code) = line.strip_prefix('-') {
+			let mismatch = self.mismatch(original, expected);
+			mismatch.original.to_mut().push_str(code);
+			mismatch.original.to_mut().push('\n');
+			self.original_line += 1;
+			self.offset -= 1;
+		} else if let Some(code) = line.strip_prefix('+') {
+			let mismatch = self.mismatch(original, expected);
+			mismatch.expected.to_mut().push_str(code);
+			mismatch.expected.to_mut().push('\n');
+			self.expected_line += 1;
+			self.offset += 1;
+		} else if line.starts_with(' ') {
+			self.end_mismatch();
+			self.original_line += 1;
+			self.expected_line += 1;
+		} else if line.is_empty() {
+			self.end_mismatch();
+			return self
+				.mismatches
+				.take()
+				.filter(|mismatches| !mismatches.mismatches.is_empty());
+		} else {
+			debug!(file = %mismatches.name, line, "line skipped, as it is not part of the diff");
+		}
+		None
+	}
+
+	/// Returns the mismatch being parsed, starting one at the given lines if there is none
+	fn mismatch(&mut self, original: usize, expected: usize) -> &mut FormatMismatch<'static> {
+		self.mismatch.get_or_insert_with(|| FormatMismatch {
+			original_begin_line: original,
+			original_end_line: original,
+			expected_begin_line: expected,
+			expected_end_line: expected,
+			original: Cow::Owned(String::new()),
+			expected: Cow::Owned(String::new()),
+		})
+	}
+
+	/// Adds the mismatch being parsed to the mismatches of the file
+	fn end_mismatch(&mut self) {
+		if let (Some(mut mismatch), Some(mismatches)) = (self.mismatch.take(), &mut self.mismatches)
+		{
+			mismatch.original_end_line = self.original_line.saturating_sub(1);
+			mismatch.expected_end_line = self.expected_line.saturating_sub(1);
+			mismatches.mismatches.push(mismatch);
+		}
+	}
+}
+
+/// Summary info for [`FormatMismatches`]
+#[derive(Debug, Clone)]
+pub struct FormatMismatchesSummary {
+	/// [`FormatMismatches.name`](FormatMismatches#structfield.name)
+	file: String,
+	/// Collection of [`FormatMismatch.original_begin_line`](FormatMismatch#structfield.original_begin_line)
+	/// and [diffs](FormatMismatch::diff)
+	mismatches: Vec<(usize, String)>,
+}
+impl<'c> From<&'c FormatMismatches<'c>> for FormatMismatchesSummary {
+	#[inline]
+	fn from(message: &'c FormatMismatches<'c>) -> Self {
+		Self {
+			file: relative_path(Path::new(message.name.as_ref())),
+			mismatches: message
+				.mismatches
+				.iter()
+				.map(|mismatch| (mismatch.original_begin_line, mismatch.diff()))
+				.collect(),
+		}
+	}
+}
+
+/// [`SummaryWriter`] for [`FormatMismatchesSummary`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FormatMismatchSummaryWriter {
+	/// Counter of mismatches
+	count: usize,
+}
+impl SummaryWriter for FormatMismatchSummaryWriter {
+	type Summary = FormatMismatchesSummary;
+
+	fn write_summary(&mut self, summary: Self::Summary, content: &mut dyn FmtWrite) -> fmt::Result {
+		self.count += summary.mismatches.len();
+		writeln!(
+			content,
+			"<details><summary><code>{}</code>: {} mismatches</summary>\n",
+			summary.file,
+			summary.mismatches.len()
+		)?;
+		for (line, diff) in summary.mismatches {
+			match source_link(&summary.file, line) {
+				Some(link) => writeln!(content, "[L{line}]({link}):\n")?,
+				None => writeln!(content, "L{line}:\n")?,
+			}
+			writeln!(content, "```diff\n{diff}```\n")?;
+		}
+		writeln!(content, "</details>\n")
+	}
+
+	fn write_preamble(&self, file: &mut dyn IoWrite, _options: &Options) -> io::Result<()> {
+		writeln!(file, "> **TOTAL:** {} mismatches\n", self.count)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_diff() {
+		let diff = "Diff in /repo/src/main.rs:1:
+-fn main(){
++fn main() {
+     let x = 1;
+ }
+ 
+Diff in /repo/src/main.rs:7:
+ 
+-
+-
+-fn b(){}
++fn b() {}
+ 
+Diff in /repo/src/lib.rs:1:
+-pub fn f( ) {}
++pub fn f() {}
+";
+		let mut parser = FormatDiffParser::default();
+		let messages: Vec<_> = diff
+			.lines()
+			.chain([""])
+			.filter_map(|line| parser.feed(line))
+			.collect();
+		assert_eq!(messages.len(), 2);
+		assert_eq!(messages[0].name, "/repo/src/main.rs");
+		let lines: Vec<_> = messages[0]
+			.mismatches
+			.iter()
+			.map(|mismatch| (mismatch.original_begin_line, mismatch.original_end_line))
+			.collect();
+		assert_eq!(lines, [(1, 1), (8, 10)]);
+		assert_eq!(messages[0].mismatches[1].original, "\n\nfn b(){}\n");
+		assert_eq!(messages[0].mismatches[1].expected, "fn b() {}\n");
+		assert_eq!(messages[1].name, "/repo/src/lib.rs");
+	}
+}
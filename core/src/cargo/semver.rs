@@ -0,0 +1,238 @@
+//! Provides the structs to work with cargo-semver-checks' output
+
+use super::{relative_path, HandleMessage, Options, SummaryWriter};
+use crate::github::{Annotation, AnnotationKind};
+use std::{
+	borrow::Cow,
+	fmt::{self, Write as FmtWrite},
+	io::{self, Write as IoWrite},
+	path::Path,
+};
+
+/// Message output by `cargo semver-checks`
+#[derive(Debug, Clone)]
+pub enum SemverMessage {
+	/// Item breaking a semver lint
+	Violation(SemverViolation),
+	/// Version bump required by the changes (e.g. `new major version`)
+	Verdict(String),
+}
+impl<'m> HandleMessage<'m> for SemverMessage {
+	type Summary = SemverSummary;
+
+	fn into_annotations(self, _options: &Options) -> Vec<Annotation<'m>> {
+		let Self::Violation(violation) = self else {
+			return Vec::new();
+		};
+		let Some((file, line)) = violation.location else {
+			return Vec::new();
+		};
+		vec![Annotation {
+			kind: violation.kind,
+			file: Cow::Owned(file),
+			line,
+			end_line: None,
+			col: None,
+			end_column: None,
+			code: None,
+			suggestions: Vec::new(),
+			title: Some(Cow::Owned(format!("Semver: {}", violation.lint))),
+			message: Cow::Owned(format!("{}\n\n{}", violation.item, violation.description)),
+		}]
+	}
+
+	fn summarize(&self, _options: &Options) -> Vec<Self::Summary> {
+		vec![match self {
+			Self::Violation(violation) => SemverSummary::Violation {
+				kind: violation.kind,
+				lint: violation.lint.clone(),
+				item: violation.item.clone(),
+				location: violation
+					.location
+					.as_ref()
+					.map(|(file, line)| format!("`{file}:{line}`")),
+			},
+			Self::Verdict(verdict) => SemverSummary::Verdict(verdict.clone()),
+		}]
+	}
+}
+
+/// Item breaking a semver lint
+#[derive(Debug, Clone)]
+pub struct SemverViolation {
+	/// Kind of annotation
+	kind: AnnotationKind,
+	/// Name of the lint (e.g. `function_missing`)
+	lint: String,
+	/// Description of the lint
+	description: String,
+	/// Description of the item (e.g. `function my_crate::foo, previously in file src/lib.rs:1`)
+	item: String,
+	/// Location of the item
+	location: Option<(String, usize)>,
+}
+
+/// Parser of the report output by `cargo semver-checks`
+///
+/// cargo-semver-checks does not provide a machine-readable output, so its human-readable report is parsed.
+/// Each failed lint starts with a `--- failure <lint>: <description> ---` header
+/// and lists the offending items after a `Failed in:` line, until an empty line.
+#[derive(Debug, Default, Clone)]
+pub struct SemverReportParser {
+	/// Lint being parsed, with its kind of annotation and description
+	lint: Option<(AnnotationKind, String, String)>,
+	/// Whether the offending items are being parsed
+	items: bool,
+}
+impl SemverReportParser {
+	/// Feeds a line of the report to the parser
+	///
+	/// Returns a message for each offending item and for the final verdict.
+	pub fn feed(&mut self, line: &str) -> Option<SemverMessage> {
+		let trimmed = line.trim();
+		if let Some(header) = trimmed
+			.strip_prefix("--- ")
+			.and_then(|header| header.strip_suffix(" ---"))
+		{
+			let (kind, header) = if let Some(header) = header.strip_prefix("failure ") {
+				(AnnotationKind::Error, header)
+			} else {
+				(AnnotationKind::Warning, header.strip_prefix("warning ")?)
+			};
+			let (lint, description) = header.split_once(": ").unwrap_or((header, ""));
+			self.lint = Some((kind, lint.to_owned(), description.to_owned()));
+			self.items = false;
+			None
+		} else if trimmed == "Failed in:" {
+			self.items = self.lint.is_some();
+			None
+		} else if trimmed.is_empty() {
+			self.items = false;
+			None
+		} else if self.items {
+			let (kind, lint, description) = self.lint.clone()?;
+			Some(SemverMessage::Violation(SemverViolation {
+				kind,
+				lint,
+				description,
+				item: trimmed.to_owned(),
+				location: Self::locate(trimmed),
+			}))
+		} else {
+			let verdict = trimmed.strip_prefix("Summary semver requires ")?;
+			Some(SemverMessage::Verdict(
+				verdict
+					.split_once(':')
+					.map_or(verdict, |(verdict, _)| verdict)
+					.to_owned(),
+			))
+		}
+	}
+
+	/// Extracts the location ending the description of an item
+	fn locate(item: &str) -> Option<(String, usize)> {
+		let (file, line) = item.rsplit(' ').next()?.rsplit_once(':')?;
+		let file = relative_path(Path::new(file));
+		(!file.starts_with('/')).then_some((file, line.parse().ok()?))
+	}
+}
+
+/// Summary info of [`SemverMessage`]
+#[derive(Debug, Clone)]
+pub enum SemverSummary {
+	/// [`SemverMessage::Violation`]
+	Violation {
+		/// Kind of annotation
+		kind: AnnotationKind,
+		/// [`SemverViolation.lint`](SemverViolation#structfield.lint)
+		lint: String,
+		/// [`SemverViolation.item`](SemverViolation#structfield.item)
+		item: String,
+		/// Location of the item
+		location: Option<String>,
+	},
+	/// [`SemverMessage::Verdict`]
+	Verdict(String),
+}
+
+/// [`SummaryWriter`] for [`SemverSummary`]
+#[derive(Debug, Default, Clone)]
+pub struct SemverSummaryWriter {
+	/// Counter of violations
+	violations: usize,
+	/// Required version bump
+	verdict: Option<String>,
+}
+impl SummaryWriter for SemverSummaryWriter {
+	type Summary = SemverSummary;
+
+	fn write_summary(&mut self, summary: Self::Summary, content: &mut dyn FmtWrite) -> fmt::Result {
+		match summary {
+			SemverSummary::Violation {
+				kind,
+				lint,
+				item,
+				location,
+			} => {
+				self.violations += 1;
+				writeln!(
+					content,
+					"|{kind}|`{lint}`|{item}|{}|",
+					location.unwrap_or_default()
+				)
+			}
+			SemverSummary::Verdict(verdict) => {
+				self.verdict = Some(verdict);
+				Ok(())
+			}
+		}
+	}
+
+	fn write_preamble(&self, file: &mut dyn IoWrite, _options: &Options) -> io::Result<()> {
+		writeln!(file, "> **TOTAL:** {} semver violations\n", self.violations)?;
+		match &self.verdict {
+			Some(verdict) => writeln!(file, "**Required version bump:** {verdict}\n")?,
+			None => writeln!(file, ":white_check_mark: No semver update required\n")?,
+		}
+		if self.violations > 0 {
+			writeln!(file, "|Level|Lint|Item|Location|")?;
+			writeln!(file, "|:--|:--|:--|:--|")?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_report() {
+		let report = "    Checking my-crate v0.1.0 -> v0.2.0 (minor change)
+
+--- failure function_missing: pub fn removed or renamed ---
+
+Description:
+A publicly-visible function cannot be imported by its prior path.
+
+Failed in:
+  function my_crate::foo, previously in file src/lib.rs:1
+
+     Summary semver requires new major version: 1 major and 0 minor checks failed
+";
+		let mut parser = SemverReportParser::default();
+		let messages: Vec<_> = report
+			.lines()
+			.filter_map(|line| parser.feed(line))
+			.collect();
+		assert_eq!(messages.len(), 2);
+		let SemverMessage::Violation(violation) = &messages[0] else {
+			panic!("expected a violation");
+		};
+		assert_eq!(violation.lint, "function_missing");
+		assert_eq!(violation.location, Some(("src/lib.rs".to_owned(), 1)));
+		assert!(
+			matches!(&messages[1], SemverMessage::Verdict(verdict) if verdict == "new major version")
+		);
+	}
+}
@@ -0,0 +1,353 @@
+//! Provides the structs to work with the output of `cargo test` and `cargo nextest`
+
+use super::{
+	relative_path, rustfmt::version, CargoMessage, DiagnosticSummary, DiagnosticSummaryWriter,
+	HandleMessage, Options, SummaryWriter,
+};
+use crate::github::{Annotation, AnnotationKind};
+use serde::Deserialize;
+use std::{
+	borrow::Cow,
+	ffi::OsStr,
+	fmt::{self, Write as FmtWrite},
+	io::{self, Write as IoWrite},
+	path::Path,
+	process::Command,
+};
+
+/// Checks if `cargo` belongs to a *nightly* toolchain, whose libtest outputs JSON without `RUSTC_BOOTSTRAP`
+///
+/// Cargo is invoked for its version (e.g. `cargo 1.83.0-nightly (...)`).
+pub fn is_nightly_cargo(cargo: &OsStr) -> bool {
+	version(Command::new(cargo).arg("--version"))
+		.is_some_and(|version| version.contains("-nightly") || version.contains("-dev"))
+}
+
+/// Message output by `cargo test` or `cargo nextest`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum TestMessage<'m> {
+	/// Message output by Cargo while building the tests
+	#[serde(borrow)]
+	Cargo(CargoMessage<'m>),
+	/// Message output by the test harness
+	#[serde(borrow)]
+	Libtest(LibtestMessage<'m>),
+}
+impl<'m> HandleMessage<'m> for TestMessage<'m> {
+	type Summary = TestSummary;
+
+	#[inline]
+	fn into_annotations(self, options: &Options) -> Vec<Annotation<'m>> {
+		match self {
+			Self::Cargo(message) => message.into_annotations(options),
+			Self::Libtest(message) => message.into_annotations(options),
+		}
+	}
+
+	#[inline]
+	fn summarize(&self, options: &Options) -> Vec<Self::Summary> {
+		match self {
+			Self::Cargo(message) => message
+				.summarize(options)
+				.into_iter()
+				.map(TestSummary::Diagnostic)
+				.collect(),
+			Self::Libtest(message) => message.summarize(options),
+		}
+	}
+
+	#[inline]
+	fn rendered(&self) -> Option<&str> {
+		match self {
+			Self::Cargo(message) => message.rendered(),
+			Self::Libtest(_) => None,
+		}
+	}
+}
+
+/// Message output by libtest with `--format=json` (or by nextest with `--message-format=libtest-json`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum LibtestMessage<'m> {
+	/// Event concerning a whole test suite
+	Suite(SuiteEvent),
+	/// Event concerning a single test
+	#[serde(borrow)]
+	Test(TestEvent<'m>),
+	/// Any other message (e.g. benchmarks)
+	#[serde(other)]
+	Other,
+}
+impl<'m> HandleMessage<'m> for LibtestMessage<'m> {
+	type Summary = TestSummary;
+
+	fn into_annotations(self, _options: &Options) -> Vec<Annotation<'m>> {
+		let Self::Test(test) = self else {
+			return Vec::new();
+		};
+		if test.event != TestOutcome::Failed {
+			return Vec::new();
+		}
+		let stdout = test.stdout.unwrap_or_default();
+		// Tests failing without a panic in the workspace (e.g. returning `Err`, panicking in a dependency or aborting)
+		// are not annotated in a file
+		let (file, line, col) = panic_location(&stdout)
+			.map_or((String::new(), 0, None), |(file, line, col)| {
+				(relative_path(Path::new(file)), line, Some(col))
+			});
+		vec![Annotation {
+			kind: AnnotationKind::Error,
+			file: Cow::Owned(file),
+			line,
+			end_line: None,
+			col,
+			end_column: None,
+			code: None,
+			suggestions: Vec::new(),
+			title: Some(Cow::Owned(format!("Test failed: {}", test.name))),
+			message: if stdout.trim().is_empty() {
+				Cow::Borrowed("Test failed")
+			} else {
+				stdout
+			},
+		}]
+	}
+
+	fn summarize(&self, _options: &Options) -> Vec<Self::Summary> {
+		match self {
+			Self::Suite(suite) if suite.event != TestOutcome::Started => {
+				vec![TestSummary::Suite(*suite)]
+			}
+			Self::Test(test) if test.event == TestOutcome::Failed => {
+				vec![TestSummary::Failure {
+					name: test.name.clone().into_owned(),
+					location: test
+						.stdout
+						.as_deref()
+						.and_then(panic_location)
+						.map(|(file, line, _col)| (relative_path(Path::new(file)), line)),
+				}]
+			}
+			_ => Vec::new(),
+		}
+	}
+}
+
+/// Outcome reported in a libtest event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestOutcome {
+	/// The test (suite) has started
+	Started,
+	/// The test (suite) has passed
+	Ok,
+	/// The test (suite) has failed
+	Failed,
+	/// The test has been ignored
+	Ignored,
+	/// The test is taking too long
+	Timeout,
+}
+
+/// Event concerning a whole test suite
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct SuiteEvent {
+	/// Outcome of the suite
+	pub event: TestOutcome,
+	/// Number of passed tests
+	#[serde(default)]
+	pub passed: usize,
+	/// Number of failed tests
+	#[serde(default)]
+	pub failed: usize,
+	/// Number of ignored tests
+	#[serde(default)]
+	pub ignored: usize,
+	/// Number of tests excluded by filters
+	#[serde(default)]
+	pub filtered_out: usize,
+}
+
+/// Event concerning a single test
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestEvent<'m> {
+	/// Outcome of the test
+	pub event: TestOutcome,
+	/// Full path of the test
+	#[serde(borrow)]
+	pub name: Cow<'m, str>,
+	/// Captured output of the test
+	#[serde(borrow, default)]
+	pub stdout: Option<Cow<'m, str>>,
+}
+
+/// Finds the location of the first panic in the workspace in the captured output of a test
+///
+/// Both the current (`panicked at src/lib.rs:1:1:`) and the legacy (`panicked at 'message', src/lib.rs:1:1`) formats are supported.
+fn panic_location(stdout: &str) -> Option<(&str, usize, usize)> {
+	stdout
+		.lines()
+		.filter_map(|line| {
+			let (_, location) = line.split_once("panicked at ")?;
+			let location = location.rsplit(", ").next()?.trim_end_matches(':');
+			let (location, col) = location.rsplit_once(':')?;
+			let (file, line) = location.rsplit_once(':')?;
+			Some((file, line.parse().ok()?, col.parse().ok()?))
+		})
+		.find(|(file, _line, _col)| Path::new(file).is_relative() && !file.starts_with('<'))
+}
+
+/// Summary info of [`TestMessage`]
+#[derive(Debug, Clone)]
+pub enum TestSummary {
+	/// Summary of a compiler diagnostic
+	Diagnostic(DiagnosticSummary),
+	/// Summary of a failed test
+	Failure {
+		/// [`TestEvent.name`](TestEvent#structfield.name)
+		name: String,
+		/// Location where the test panicked
+		location: Option<(String, usize)>,
+	},
+	/// Summary of a test suite
+	Suite(SuiteEvent),
+}
+
+/// [`SummaryWriter`] for [`TestSummary`]
+#[derive(Debug, Default, Clone)]
+pub struct TestSummaryWriter {
+	/// Counter of passed tests
+	passed: usize,
+	/// Counter of failed tests
+	failed: usize,
+	/// Counter of skipped tests (ignored or filtered out)
+	skipped: usize,
+	/// Whether failed tests have been written
+	has_failures: bool,
+	/// [`SummaryWriter`] for compiler diagnostics
+	diagnostics: DiagnosticSummaryWriter,
+}
+impl SummaryWriter for TestSummaryWriter {
+	type Summary = TestSummary;
+
+	fn write_summary(&mut self, summary: Self::Summary, content: &mut dyn FmtWrite) -> fmt::Result {
+		match summary {
+			TestSummary::Diagnostic(summary) => self.diagnostics.write_summary(summary, content),
+			TestSummary::Failure { name, location } => {
+				let location = location
+					.map(|location| format!("`{}:{}`", location.0, location.1))
+					.unwrap_or_default();
+				self.has_failures = true;
+				writeln!(content, "|`{name}`|{location}|")
+			}
+			TestSummary::Suite(suite) => {
+				self.passed += suite.passed;
+				self.failed += suite.failed;
+				self.skipped += suite.ignored + suite.filtered_out;
+				Ok(())
+			}
+		}
+	}
+
+	fn write_preamble(&self, file: &mut dyn IoWrite, _options: &Options) -> io::Result<()> {
+		writeln!(file, "|:white_check_mark: Passed|:x: Failed|:zzz: Skipped|")?;
+		writeln!(file, "|--:|--:|--:|")?;
+		writeln!(file, "|{}|{}|{}|\n", self.passed, self.failed, self.skipped)?;
+		if self.has_failures {
+			writeln!(file, "|Failed test|Location|")?;
+			writeln!(file, "|:--|--:|")?;
+		}
+		Ok(())
+	}
+
+	fn write_postamble(self, file: &mut dyn IoWrite, options: &Options) -> io::Result<()> {
+		if !self.diagnostics.is_empty() {
+			writeln!(file)?;
+			self.diagnostics.write_preamble(file, options)?;
+			self.diagnostics.write_postamble(file, options)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Parses the libtest JSON `lines` into annotations, and writes their summary
+	fn annotate(lines: &[&str]) -> (Vec<Annotation<'static>>, String) {
+		let options = Options::default();
+		let mut writer = TestSummaryWriter::default();
+		let mut content = String::new();
+		let mut annotations = Vec::new();
+		for line in lines {
+			let message: TestMessage<'_> = serde_json::from_str(line).unwrap();
+			for summary in message.summarize(&options) {
+				writer.write_summary(summary, &mut content).unwrap();
+			}
+			annotations.extend(
+				message
+					.into_annotations(&options)
+					.iter()
+					.map(Annotation::to_owned),
+			);
+		}
+		let mut summary = Vec::new();
+		writer.write_preamble(&mut summary, &options).unwrap();
+		summary.extend_from_slice(content.as_bytes());
+		writer.write_postamble(&mut summary, &options).unwrap();
+		(annotations, String::from_utf8(summary).unwrap())
+	}
+
+	#[test]
+	fn failed_tests() {
+		let (annotations, summary) = annotate(&[
+			r#"{ "type": "suite", "event": "started", "test_count": 3 }"#,
+			r#"{ "type": "test", "event": "started", "name": "tests::panics" }"#,
+			r#"{ "type": "test", "name": "tests::panics", "event": "failed", "stdout": "\nthread 'tests::panics' panicked at src/lib.rs:10:9:\nassertion `left == right` failed\n  left: 1\n right: 2\nnote: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n" }"#,
+			r#"{ "type": "test", "name": "tests::returns_err", "event": "failed", "stdout": "Error: \"boom\"\n" }"#,
+			r#"{ "type": "test", "name": "tests::passes", "event": "ok" }"#,
+			r#"{ "type": "suite", "event": "failed", "passed": 1, "failed": 2, "ignored": 0, "measured": 0, "filtered_out": 0, "exec_time": 0.001 }"#,
+		]);
+		assert_eq!(annotations.len(), 2);
+		assert_eq!(
+			annotations[0].to_string(),
+			"::error file=src/lib.rs,line=10,col=9,title=Test failed%3A tests%3A%3Apanics::thread 'tests::panics' panicked at src/lib.rs:10:9:%0Aassertion `left == right` failed%0A  left: 1%0A right: 2%0Anote: run with `RUST_BACKTRACE=1` environment variable to display a backtrace"
+		);
+		assert_eq!(
+			annotations[1].to_string(),
+			"::error title=Test failed%3A tests%3A%3Areturns_err::Error: \"boom\""
+		);
+		assert_eq!(
+			summary,
+			"|:white_check_mark: Passed|:x: Failed|:zzz: Skipped|
+|--:|--:|--:|
+|1|2|0|
+
+|Failed test|Location|
+|:--|--:|
+|`tests::panics`|`src/lib.rs:10`|
+|`tests::returns_err`||
+"
+		);
+	}
+
+	#[test]
+	fn passed_tests() {
+		let (annotations, summary) = annotate(&[
+			r#"{ "type": "test", "name": "tests::passes", "event": "ok" }"#,
+			r#"{ "type": "test", "name": "tests::ignored", "event": "ignored" }"#,
+			r#"{ "type": "suite", "event": "ok", "passed": 1, "failed": 0, "ignored": 1, "measured": 0, "filtered_out": 2, "exec_time": 0.001 }"#,
+		]);
+		assert!(annotations.is_empty());
+		assert_eq!(
+			summary,
+			"|:white_check_mark: Passed|:x: Failed|:zzz: Skipped|
+|--:|--:|--:|
+|1|0|3|
+
+"
+		);
+	}
+}
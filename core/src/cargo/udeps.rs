@@ -0,0 +1,235 @@
+//! Provides the structs to work with cargo-udeps's output
+
+use super::{relative_path, DependencyKind, HandleMessage, Metadata, Options, SummaryWriter};
+use crate::github::{Annotation, AnnotationKind};
+use serde::Deserialize;
+use std::{
+	borrow::Cow,
+	collections::{BTreeMap, HashMap},
+	fmt::{self, Write as FmtWrite},
+	io::{self, Write as IoWrite},
+	path::PathBuf,
+};
+
+/// Message output by cargo-udeps
+#[derive(Debug, Clone, Deserialize)]
+pub struct UdepsReport {
+	/// Unused dependencies of each workspace member
+	pub unused_deps: HashMap<String, UnusedDeps>,
+}
+impl UdepsReport {
+	/// Locates the declarations of the unused dependencies in the manifests of the workspace
+	pub fn locate(self, metadata: &Metadata) -> UnusedDependencies {
+		UnusedDependencies(
+			self.unused_deps
+				.into_values()
+				.flat_map(|unused| {
+					[
+						(DependencyKind::Normal, unused.normal),
+						(DependencyKind::Development, unused.development),
+						(DependencyKind::Build, unused.build),
+					]
+					.into_iter()
+					.flat_map(move |(kind, names)| {
+						let manifest_path = unused.manifest_path.clone();
+						names
+							.into_iter()
+							.map(move |name| (manifest_path.clone(), kind, name))
+					})
+				})
+				.map(|(manifest_path, kind, name)| UnusedDependency {
+					location: metadata.locate_dependency(&manifest_path, kind.section(), &name),
+					manifest: relative_path(&manifest_path),
+					name,
+					kind,
+				})
+				.collect(),
+		)
+	}
+}
+
+/// Unused dependencies of a workspace member
+#[derive(Debug, Clone, Deserialize)]
+pub struct UnusedDeps {
+	/// Path to the manifest of the workspace member
+	pub manifest_path: PathBuf,
+	/// Unused regular dependencies
+	#[serde(default)]
+	pub normal: Vec<String>,
+	/// Unused development dependencies
+	#[serde(default)]
+	pub development: Vec<String>,
+	/// Unused build dependencies
+	#[serde(default)]
+	pub build: Vec<String>,
+}
+
+/// Unused dependency of a workspace member
+#[derive(Debug, Clone)]
+pub struct UnusedDependency {
+	/// Manifest of the workspace member
+	pub manifest: String,
+	/// Name of the dependency
+	pub name: String,
+	/// Kind of dependency
+	pub kind: DependencyKind,
+	/// Location of the declaration of the dependency
+	pub location: Option<(String, usize)>,
+}
+
+/// List of [`UnusedDependency`]
+#[derive(Debug, Clone)]
+pub struct UnusedDependencies(pub Vec<UnusedDependency>);
+impl<'m> HandleMessage<'m> for UnusedDependencies {
+	type Summary = UnusedDependency;
+
+	fn into_annotations(self, _options: &Options) -> Vec<Annotation<'m>> {
+		self.0
+			.into_iter()
+			.filter_map(|dependency| {
+				let (file, line) = dependency.location?;
+				Some(Annotation {
+					kind: AnnotationKind::Warning,
+					file: Cow::Owned(file),
+					line,
+					end_line: None,
+					col: None,
+					end_column: None,
+					code: None,
+					suggestions: Vec::new(),
+					title: Some(Cow::Borrowed("Unused dependency")),
+					message: Cow::Owned(format!(
+						"`{}` is declared as a {} dependency but is not used",
+						dependency.name,
+						dependency.kind.to_string().to_lowercase()
+					)),
+				})
+			})
+			.collect()
+	}
+
+	#[inline]
+	fn summarize(&self, _options: &Options) -> Vec<Self::Summary> {
+		self.0.clone()
+	}
+}
+
+/// [`SummaryWriter`] for [`UnusedDependency`]
+#[derive(Debug, Default, Clone)]
+pub struct UnusedDependencySummaryWriter {
+	/// Unused dependencies grouped by manifest
+	manifests: BTreeMap<String, Vec<UnusedDependency>>,
+}
+impl SummaryWriter for UnusedDependencySummaryWriter {
+	type Summary = UnusedDependency;
+
+	#[inline]
+	fn write_summary(
+		&mut self,
+		summary: Self::Summary,
+		_content: &mut dyn FmtWrite,
+	) -> fmt::Result {
+		self.manifests
+			.entry(summary.manifest.clone())
+			.or_default()
+			.push(summary);
+		Ok(())
+	}
+
+	fn write_preamble(&self, file: &mut dyn IoWrite, _options: &Options) -> io::Result<()> {
+		writeln!(
+			file,
+			"> **TOTAL:** {} unused dependencies\n",
+			self.manifests.values().map(Vec::len).sum::<usize>()
+		)
+	}
+
+	fn write_postamble(self, file: &mut dyn IoWrite, _options: &Options) -> io::Result<()> {
+		for (manifest, dependencies) in self.manifests {
+			writeln!(file, "### `{manifest}`\n")?;
+			for dependency in dependencies {
+				writeln!(file, "- `{}` ({})", dependency.name, dependency.kind)?;
+			}
+			writeln!(file)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::cargo::tests::{summary, Workspace};
+	use std::slice;
+
+	#[test]
+	fn unused_dependencies() {
+		let workspace = Workspace::new(
+			"udeps",
+			&[(
+				"Cargo.toml",
+				"[package]\nname = \"lintfix\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\nanyhow = \"1\"\nlog = { workspace = true }\nregex = \"1\"\n\n[dev-dependencies]\ntempfile = \"3\"\n\n[workspace.dependencies]\nlog = \"0.4\"\n",
+			)],
+		);
+		let manifest = workspace.annotated_path("Cargo.toml");
+		let report = serde_json::json!({
+			"success": false,
+			"unused_deps": {
+				"lintfix 0.1.0 (path+file:///lintfix)": {
+					"manifest_path": workspace.path("Cargo.toml"),
+					"normal": ["anyhow", "log"],
+					"development": ["tempfile"],
+					"build": [],
+				},
+			},
+			"note": "Note: They might be false-positive.\n      For example, `cargo-udeps` cannot detect usage of crates that are only used in doc-tests.\n      To ignore some dependencies, write `package.metadata.cargo-udeps.ignore` in Cargo.toml.\n",
+		})
+		.to_string();
+		let dependencies = serde_json::from_str::<UdepsReport>(&report)
+			.unwrap()
+			.locate(&workspace.0);
+
+		let options = Options::default();
+		assert_eq!(
+			summary::<_, UnusedDependencySummaryWriter>(slice::from_ref(&dependencies), &options),
+			format!(
+				"> **TOTAL:** 3 unused dependencies
+
+### `{manifest}`
+
+- `anyhow` (Normal)
+- `log` (Normal)
+- `tempfile` (Development)
+
+"
+			)
+		);
+		let annotations: Vec<_> = dependencies
+			.into_annotations(&options)
+			.into_iter()
+			.map(|annotation| (annotation.line, annotation.file, annotation.message))
+			.collect();
+		assert_eq!(
+			annotations,
+			[
+				(
+					7,
+					Cow::Borrowed(manifest.as_str()),
+					Cow::Borrowed("`anyhow` is declared as a normal dependency but is not used")
+				),
+				(
+					15,
+					Cow::Borrowed(manifest.as_str()),
+					Cow::Borrowed("`log` is declared as a normal dependency but is not used")
+				),
+				(
+					12,
+					Cow::Borrowed(manifest.as_str()),
+					Cow::Borrowed(
+						"`tempfile` is declared as a development dependency but is not used"
+					)
+				),
+			]
+		);
+	}
+}
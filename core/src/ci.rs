@@ -0,0 +1,200 @@
+//! Provides the backends annotating the jobs of the supported CI platforms
+
+use crate::github::{Annotation, AnnotationKind};
+use std::{
+	collections::BTreeSet,
+	env,
+	ffi::OsString,
+	fs::File,
+	io::{self, IsTerminal, Write},
+	path::{Path, PathBuf},
+	process::{Command, Stdio},
+};
+
+mod azure;
+mod buildkite;
+mod gitlab;
+mod teamcity;
+mod terminal;
+
+pub use azure::*;
+pub use buildkite::*;
+pub use gitlab::*;
+pub use teamcity::*;
+pub use terminal::*;
+
+/// Environment variable containing the path to the special summary file of GitHub Actions
+const GITHUB_SUMMARY_PATH_VAR: &str = "GITHUB_STEP_SUMMARY";
+/// Path to the summary file of GitLab CI, to be declared as an artifact
+const GITLAB_SUMMARY_PATH: &str = "ghannotate-summary.md";
+/// Environment variable containing the path to the temporary directory of Azure Pipelines
+const AZURE_TEMP_DIR_VAR: &str = "AGENT_TEMPDIRECTORY";
+/// Name of the summary file of Azure Pipelines
+const AZURE_SUMMARY_FILE: &str = "ghannotate-summary.md";
+/// Path to the summary file of `TeamCity`, published as an artifact of the build
+const TEAMCITY_SUMMARY_PATH: &str = "ghannotate-summary.md";
+/// Path to the summary file of Buildkite, posted as an annotation of the build
+const BUILDKITE_SUMMARY_PATH: &str = "ghannotate-summary.md";
+/// Path to the summary file used in debug contexts
+const DEBUG_SUMMARY_PATH: &str = "SUMMARY.md";
+
+/// CI platform running the job
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum CiPlatform {
+	/// GitHub Actions
+	Github,
+	/// GitLab CI/CD
+	Gitlab,
+	/// Azure Pipelines
+	Azure,
+	/// `TeamCity`
+	Teamcity,
+	/// Buildkite
+	Buildkite,
+	/// Local terminal, outside of any CI platform
+	Terminal,
+}
+impl CiPlatform {
+	/// Detects the CI platform from the environment
+	///
+	/// A local terminal is assumed if no platform is detected.
+	pub fn detect() -> Self {
+		if env::var_os("GITLAB_CI").is_some() {
+			Self::Gitlab
+		} else if env::var_os("TF_BUILD").is_some() {
+			Self::Azure
+		} else if env::var_os("TEAMCITY_VERSION").is_some() {
+			Self::Teamcity
+		} else if env::var_os("BUILDKITE").is_some() {
+			Self::Buildkite
+		} else if env::var_os("GITHUB_ACTIONS").is_some() {
+			Self::Github
+		} else {
+			Self::Terminal
+		}
+	}
+
+	/// Writes `annotation` in the log of the job
+	///
+	/// # Errors
+	/// This function returns an error if the annotation could not be written.
+	pub fn write_annotation(
+		self,
+		annotation: &Annotation<'_>,
+		writer: &mut dyn Write,
+	) -> io::Result<()> {
+		match self {
+			Self::Github => writeln!(writer, "{annotation}"),
+			Self::Gitlab => writeln!(writer, "{}", GitlabAnnotation(annotation)),
+			Self::Azure => writeln!(writer, "{}", AzureAnnotation(annotation)),
+			Self::Teamcity => writeln!(writer, "{}", TeamcityAnnotation(annotation)),
+			Self::Buildkite => writeln!(writer, "{}", BuildkiteAnnotation(annotation)),
+			Self::Terminal => Ok(()),
+		}
+	}
+
+	/// Returns the path to the file to write the summary to
+	pub fn summary_path(self) -> Option<PathBuf> {
+		match self {
+			Self::Github => env::var_os(GITHUB_SUMMARY_PATH_VAR)
+				.or(cfg!(debug_assertions).then(|| OsString::from(DEBUG_SUMMARY_PATH)))
+				.map(PathBuf::from),
+			Self::Gitlab => Some(PathBuf::from(GITLAB_SUMMARY_PATH)),
+			Self::Azure => Some(
+				env::var_os(AZURE_TEMP_DIR_VAR)
+					.map(PathBuf::from)
+					.unwrap_or_default()
+					.join(AZURE_SUMMARY_FILE),
+			),
+			Self::Teamcity => Some(PathBuf::from(TEAMCITY_SUMMARY_PATH)),
+			Self::Buildkite => Some(PathBuf::from(BUILDKITE_SUMMARY_PATH)),
+			Self::Terminal => None,
+		}
+	}
+
+	/// Publishes the summary written to `path`
+	///
+	/// `outcome` is the most severe kind of annotation raised, if any.
+	///
+	/// # Errors
+	/// This function returns an error if the summary could not be published.
+	pub fn publish_summary(
+		self,
+		path: &Path,
+		outcome: Option<AnnotationKind>,
+		writer: &mut dyn Write,
+	) -> io::Result<()> {
+		match self {
+			Self::Github | Self::Gitlab | Self::Terminal => Ok(()),
+			Self::Azure => writeln!(
+				writer,
+				"##vso[task.uploadsummary]{}",
+				path.canonicalize()?.display()
+			),
+			Self::Teamcity => writeln!(writer, "##teamcity[publishArtifacts '{}']", path.display()),
+			Self::Buildkite => {
+				let context = env::var("BUILDKITE_JOB_ID").map_or_else(
+					|_err| "ghannotate".to_owned(),
+					|job| format!("ghannotate-{job}"),
+				);
+				let status = Command::new("buildkite-agent")
+					.args(["annotate", "--style", buildkite_style(outcome), "--context"])
+					.arg(context)
+					.stdin(File::open(path)?)
+					.stdout(Stdio::null())
+					.status()?;
+				if status.success() {
+					Ok(())
+				} else {
+					Err(io::Error::other(format!(
+						"buildkite-agent exited with {status}"
+					)))
+				}
+			}
+		}
+	}
+
+	/// Writes the report of all the `annotations` at the end of the job
+	///
+	/// # Errors
+	/// This function returns an error if the report could not be written.
+	pub fn write_report(
+		self,
+		annotations: &BTreeSet<Annotation<'_>>,
+		writer: &mut dyn Write,
+	) -> io::Result<()> {
+		match self {
+			Self::Terminal => {
+				let color = io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+				writeln!(writer, "{}", TerminalReport { annotations, color })
+			}
+			Self::Github | Self::Gitlab | Self::Azure | Self::Teamcity | Self::Buildkite => Ok(()),
+		}
+	}
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+	use crate::report::tests::annotate;
+
+	/// Warning E0602 of rustc, which is not located in a file
+	const UNKNOWN_LINT: &str = r#"{"rendered":"warning[E0602]: unknown lint: `clippy::nonexistent_lint`\n  |\n  = note: requested on the command line with `-W clippy::nonexistent_lint`\n  = note: `#[warn(unknown_lints)]` on by default\n\n","$message_type":"diagnostic","children":[{"children":[],"code":null,"level":"note","message":"requested on the command line with `-W clippy::nonexistent_lint`","rendered":null,"spans":[]},{"children":[],"code":null,"level":"note","message":"`#[warn(unknown_lints)]` on by default","rendered":null,"spans":[]}],"level":"warning","message":"unknown lint: `clippy::nonexistent_lint`","spans":[],"code":{"code":"E0602","explanation":null}}"#;
+
+	/// Returns the annotations of the reports, after a warning which is not located in a file
+	pub(crate) fn annotations() -> Vec<Annotation<'static>> {
+		let mut annotations = annotate(UNKNOWN_LINT);
+		annotations.extend(crate::report::tests::annotations());
+		annotations
+	}
+
+	/// Returns the log of the job annotated by `platform`
+	pub(crate) fn log(platform: CiPlatform) -> String {
+		let mut log = Vec::new();
+		for annotation in annotations() {
+			platform.write_annotation(&annotation, &mut log).unwrap();
+		}
+		String::from_utf8(log).unwrap()
+	}
+}
@@ -0,0 +1,92 @@
+//! Provides the structs to annotate Azure Pipelines jobs
+
+use crate::github::{Annotation, AnnotationKind};
+use std::fmt::{self, Display, Formatter};
+
+/// [`Annotation`] displayed as a logging command of Azure Pipelines
+///
+/// Azure Pipelines only supports errors and warnings, so notices are displayed as single-line sections of the log.
+#[derive(Debug, Clone, Copy)]
+pub struct AzureAnnotation<'a, 's>(pub &'a Annotation<'s>);
+impl<'a, 's> Display for AzureAnnotation<'a, 's> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		/// Escapes the special characters of a property value
+		fn escape_property(value: &str) -> String {
+			escape_message(value)
+				.replace(';', "%3B")
+				.replace(']', "%5D")
+		}
+		/// Escapes the special characters of a message
+		fn escape_message(message: &str) -> String {
+			message
+				.replace('%', "%AZP25")
+				.replace('\r', "%0D")
+				.replace('\n', "%0A")
+		}
+
+		let annotation = self.0;
+		let message = match &annotation.title {
+			Some(title) => format!("{title}\n{}", annotation.message.trim()),
+			None => annotation.message.trim().to_owned(),
+		};
+		let kind = match annotation.kind {
+			AnnotationKind::Error => "error",
+			AnnotationKind::Warning => "warning",
+			AnnotationKind::Notice => {
+				write!(f, "##[section]")?;
+				if let Some(location) = annotation.location() {
+					write!(f, "{location}: ")?;
+				}
+				// The escapes are only decoded in logging commands, so the section is written on a single line as is
+				if let Some(title) = &annotation.title {
+					write!(f, "{title}: ")?;
+				}
+				return write!(
+					f,
+					"{}",
+					annotation
+						.message
+						.trim()
+						.lines()
+						.collect::<Vec<_>>()
+						.join(" ")
+				);
+			}
+		};
+
+		write!(f, "##vso[task.logissue type={kind}")?;
+		if annotation.is_located() {
+			write!(
+				f,
+				";sourcepath={};linenumber={}",
+				escape_property(&annotation.file),
+				annotation.line
+			)?;
+			if let Some(col) = annotation.col {
+				write!(f, ";columnnumber={col}")?;
+			}
+		}
+		if let Some(code) = &annotation.code {
+			write!(f, ";code={}", escape_property(code))?;
+		}
+		write!(f, ";]{}", escape_message(&message))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::ci::{tests::log, CiPlatform};
+
+	#[test]
+	fn logging_commands() {
+		assert_eq!(
+			log(CiPlatform::Azure),
+			concat!(
+				"##vso[task.logissue type=warning;code=E0602;]unknown lint: `clippy::nonexistent_lint` [E0602]%0Aunknown lint: `clippy::nonexistent_lint`\n",
+				"##vso[task.logissue type=warning;sourcepath=lintfix/src/lib.rs;linenumber=1;columnnumber=15;code=clippy::ptr_arg;]writing `&Vec` instead of `&[_]` involves a new object where a slice will do [clippy::ptr_arg]%0Awriting `&Vec` instead of `&[_]` involves a new object where a slice will do\n",
+				"##[section]lintfix/src/lib.rs:5:20: mismatched types: expected `u32` because of return type\n",
+				"##vso[task.logissue type=error;sourcepath=lintfix/src/lib.rs;linenumber=6;columnnumber=5;code=E0308;]mismatched types [E0308]%0Amismatched types\n",
+			)
+		);
+	}
+}
@@ -0,0 +1,66 @@
+//! Provides the structs to annotate Buildkite builds
+
+use crate::github::{Annotation, AnnotationKind};
+use std::fmt::{self, Display, Formatter};
+
+/// [`Annotation`] displayed as a collapsed group of the log of a Buildkite job
+///
+/// The header of the group locates the annotated issue, and its content is the message of the annotation.
+#[derive(Debug, Clone, Copy)]
+pub struct BuildkiteAnnotation<'a, 's>(pub &'a Annotation<'s>);
+impl<'a, 's> Display for BuildkiteAnnotation<'a, 's> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let annotation = self.0;
+		let emoji = match annotation.kind {
+			AnnotationKind::Error => ":x:",
+			AnnotationKind::Warning => ":warning:",
+			AnnotationKind::Notice => ":information_source:",
+		};
+
+		write!(f, "--- {emoji} ")?;
+		if let Some(location) = annotation.location() {
+			write!(f, "{location}: ")?;
+		}
+		write!(f, "{}", annotation.kind.name())?;
+		if let Some(title) = &annotation.title {
+			write!(f, ": {title}")?;
+		}
+		writeln!(f)?;
+		write!(f, "{}", annotation.message.trim_end())
+	}
+}
+
+/// Returns the style of the Buildkite annotation of the summary
+///
+/// `outcome` is the most severe kind of annotation raised, if any.
+#[inline]
+pub const fn buildkite_style(outcome: Option<AnnotationKind>) -> &'static str {
+	match outcome {
+		Some(AnnotationKind::Error) => "error",
+		Some(AnnotationKind::Warning) => "warning",
+		Some(AnnotationKind::Notice) => "info",
+		None => "success",
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::ci::{tests::log, CiPlatform};
+
+	#[test]
+	fn collapsed_groups() {
+		assert_eq!(
+			log(CiPlatform::Buildkite),
+			concat!(
+				"--- :warning: warning: unknown lint: `clippy::nonexistent_lint` [E0602]\n",
+				"unknown lint: `clippy::nonexistent_lint`\n",
+				"--- :warning: lintfix/src/lib.rs:1:15: warning: writing `&Vec` instead of `&[_]` involves a new object where a slice will do [clippy::ptr_arg]\n",
+				"writing `&Vec` instead of `&[_]` involves a new object where a slice will do\n",
+				"--- :information_source: lintfix/src/lib.rs:5:20: notice: mismatched types\n",
+				"expected `u32` because of return type\n",
+				"--- :x: lintfix/src/lib.rs:6:5: error: mismatched types [E0308]\n",
+				"mismatched types\n",
+			)
+		);
+	}
+}
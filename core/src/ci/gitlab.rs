@@ -0,0 +1,87 @@
+//! Provides the structs to annotate GitLab CI/CD jobs
+
+use crate::github::{Annotation, AnnotationKind};
+use std::{
+	fmt::{self, Display, Formatter},
+	hash::{DefaultHasher, Hash, Hasher},
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+/// [`Annotation`] displayed as a collapsed section of the log of a GitLab job
+///
+/// The header of the section locates the annotated issue, and its content is the message of the annotation.
+#[derive(Debug, Clone, Copy)]
+pub struct GitlabAnnotation<'a, 's>(pub &'a Annotation<'s>);
+impl<'a, 's> Display for GitlabAnnotation<'a, 's> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let annotation = self.0;
+		let now = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.unwrap_or_default();
+		let timestamp = now.as_secs();
+		// The sections are named after the annotation and the time it is written at, to give them unique names
+		let mut hasher = DefaultHasher::new();
+		now.hash(&mut hasher);
+		annotation.file.hash(&mut hasher);
+		annotation.line.hash(&mut hasher);
+		annotation.message.hash(&mut hasher);
+		let section = format!("ghannotate_{:016x}", hasher.finish());
+		let color = match annotation.kind {
+			AnnotationKind::Error => "31",
+			AnnotationKind::Warning => "33",
+			AnnotationKind::Notice => "36",
+		};
+
+		write!(
+			f,
+			"\x1b[0Ksection_start:{timestamp}:{section}[collapsed=true]\r\x1b[0K"
+		)?;
+		if let Some(location) = annotation.location() {
+			write!(f, "{location}: ")?;
+		}
+		write!(f, "\x1b[1;{color}m{}\x1b[0m", annotation.kind.name())?;
+		if let Some(title) = &annotation.title {
+			write!(f, ": {title}")?;
+		}
+		writeln!(f)?;
+		writeln!(f, "{}", annotation.message.trim_end())?;
+		write!(f, "\x1b[0Ksection_end:{timestamp}:{section}\r\x1b[0K")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::ci::{tests::log, CiPlatform};
+	use regex::Regex;
+	use std::collections::BTreeSet;
+
+	#[test]
+	fn collapsed_sections() {
+		let log = log(CiPlatform::Gitlab);
+		// The sections are named after the time they are written at
+		let section =
+			Regex::new(r"(section_start|section_end):\d+:(ghannotate_[0-9a-f]{16})").unwrap();
+		let names: BTreeSet<_> = section
+			.captures_iter(&log)
+			.map(|captures| captures[2].to_owned())
+			.collect();
+		assert_eq!(names.len(), 4);
+		assert_eq!(
+			section.replace_all(&log, "$1"),
+			concat!(
+				"\x1b[0Ksection_start[collapsed=true]\r\x1b[0K\x1b[1;33mwarning\x1b[0m: unknown lint: `clippy::nonexistent_lint` [E0602]\n",
+				"unknown lint: `clippy::nonexistent_lint`\n",
+				"\x1b[0Ksection_end\r\x1b[0K\n",
+				"\x1b[0Ksection_start[collapsed=true]\r\x1b[0Klintfix/src/lib.rs:1:15: \x1b[1;33mwarning\x1b[0m: writing `&Vec` instead of `&[_]` involves a new object where a slice will do [clippy::ptr_arg]\n",
+				"writing `&Vec` instead of `&[_]` involves a new object where a slice will do\n",
+				"\x1b[0Ksection_end\r\x1b[0K\n",
+				"\x1b[0Ksection_start[collapsed=true]\r\x1b[0Klintfix/src/lib.rs:5:20: \x1b[1;36mnotice\x1b[0m: mismatched types\n",
+				"expected `u32` because of return type\n",
+				"\x1b[0Ksection_end\r\x1b[0K\n",
+				"\x1b[0Ksection_start[collapsed=true]\r\x1b[0Klintfix/src/lib.rs:6:5: \x1b[1;31merror\x1b[0m: mismatched types [E0308]\n",
+				"mismatched types\n",
+				"\x1b[0Ksection_end\r\x1b[0K\n",
+			)
+		);
+	}
+}
@@ -0,0 +1,114 @@
+//! Provides the structs to annotate `TeamCity` builds
+
+use crate::github::{Annotation, AnnotationKind};
+use std::fmt::{self, Display, Formatter};
+
+/// Inspection type of the annotations without code
+const DEFAULT_INSPECTION_TYPE: &str = "ghannotate";
+
+/// [`Annotation`] displayed as service messages of `TeamCity`
+///
+/// Every annotation is reported as an inspection, preceded by the declaration of its type.
+/// Errors are also reported as build problems.
+#[derive(Debug, Clone, Copy)]
+pub struct TeamcityAnnotation<'a, 's>(pub &'a Annotation<'s>);
+impl<'a, 's> Display for TeamcityAnnotation<'a, 's> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		/// Escapes the special characters of an attribute value
+		fn escape(value: &str) -> String {
+			let mut escaped = String::with_capacity(value.len());
+			for c in value.chars() {
+				match c {
+					'|' => escaped.push_str("||"),
+					'\'' => escaped.push_str("|'"),
+					'\n' => escaped.push_str("|n"),
+					'\r' => escaped.push_str("|r"),
+					'[' => escaped.push_str("|["),
+					']' => escaped.push_str("|]"),
+					c => escaped.push(c),
+				}
+			}
+			escaped
+		}
+
+		let annotation = self.0;
+		let type_id = escape(
+			annotation
+				.code
+				.as_deref()
+				.unwrap_or(DEFAULT_INSPECTION_TYPE),
+		);
+		let category = type_id.split_once("::").map_or("rustc", |(tool, _)| tool);
+		let title = annotation
+			.title
+			.as_deref()
+			.unwrap_or_else(|| annotation.message.lines().next().unwrap_or_default());
+		let severity = match annotation.kind {
+			AnnotationKind::Error => "ERROR",
+			AnnotationKind::Warning => "WARNING",
+			AnnotationKind::Notice => "INFO",
+		};
+
+		let location = annotation.location();
+		match &location {
+			Some(_) => {
+				writeln!(
+					f,
+					"##teamcity[inspectionType id='{type_id}' name='{type_id}' category='{category}' description='{type_id}']"
+				)?;
+				write!(
+					f,
+					"##teamcity[inspection typeId='{type_id}' message='{}' file='{}' line='{}' SEVERITY='{severity}']",
+					escape(annotation.message.trim()),
+					escape(&annotation.file),
+					annotation.line
+				)?;
+			}
+			// Inspections must be located in a file
+			None => write!(
+				f,
+				"##teamcity[message text='{}' status='{}']",
+				escape(annotation.message.trim()),
+				if annotation.kind == AnnotationKind::Error {
+					"ERROR"
+				} else {
+					"WARNING"
+				}
+			)?,
+		}
+		if annotation.kind == AnnotationKind::Error {
+			let description = match &location {
+				Some(location) => format!("{location}: {title}"),
+				None => title.to_owned(),
+			};
+			write!(
+				f,
+				"\n##teamcity[buildProblem description='{}']",
+				escape(&description)
+			)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::ci::{tests::log, CiPlatform};
+
+	#[test]
+	fn service_messages() {
+		assert_eq!(
+			log(CiPlatform::Teamcity),
+			concat!(
+				"##teamcity[message text='unknown lint: `clippy::nonexistent_lint`' status='WARNING']\n",
+				"##teamcity[inspectionType id='clippy::ptr_arg' name='clippy::ptr_arg' category='clippy' description='clippy::ptr_arg']\n",
+				"##teamcity[inspection typeId='clippy::ptr_arg' message='writing `&Vec` instead of `&|[_|]` involves a new object where a slice will do' file='lintfix/src/lib.rs' line='1' SEVERITY='WARNING']\n",
+				"##teamcity[inspectionType id='ghannotate' name='ghannotate' category='rustc' description='ghannotate']\n",
+				"##teamcity[inspection typeId='ghannotate' message='expected `u32` because of return type' file='lintfix/src/lib.rs' line='5' SEVERITY='INFO']\n",
+				"##teamcity[inspectionType id='E0308' name='E0308' category='rustc' description='E0308']\n",
+				"##teamcity[inspection typeId='E0308' message='mismatched types' file='lintfix/src/lib.rs' line='6' SEVERITY='ERROR']\n",
+				"##teamcity[buildProblem description='lintfix/src/lib.rs:6:5: mismatched types |[E0308|]']\n",
+			)
+		);
+	}
+}
@@ -0,0 +1,137 @@
+//! Provides the structs to report annotations in a terminal
+
+use crate::github::{Annotation, AnnotationKind};
+use std::{
+	collections::BTreeSet,
+	fmt::{self, Display, Formatter},
+};
+
+/// Human-readable report of annotations, grouped by file
+#[derive(Debug, Clone, Copy)]
+pub struct TerminalReport<'a, 's> {
+	/// Annotations to report, sorted by location
+	pub annotations: &'a BTreeSet<Annotation<'s>>,
+	/// Whether to color the report with ANSI escape codes
+	pub color: bool,
+}
+impl<'a, 's> Display for TerminalReport<'a, 's> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let paint = |code: &str, text: &str| {
+			if self.color {
+				format!("\x1b[{code}m{text}\x1b[0m")
+			} else {
+				text.to_owned()
+			}
+		};
+
+		let mut file = None;
+		for annotation in self.annotations {
+			if file != Some(&annotation.file) {
+				if file.is_some() {
+					writeln!(f)?;
+				}
+				let header = if annotation.is_located() {
+					&annotation.file
+				} else {
+					"(no file)"
+				};
+				writeln!(f, "{}", paint("1;4", header))?;
+				file = Some(&annotation.file);
+			}
+			let (icon, color) = match annotation.kind {
+				AnnotationKind::Error => ("\u{2716}", "1;31"),
+				AnnotationKind::Warning => ("\u{26a0}", "1;33"),
+				AnnotationKind::Notice => ("\u{2139}", "1;36"),
+			};
+			let location = match annotation.col {
+				_ if !annotation.is_located() => String::new(),
+				Some(col) => format!("{}:{col}", annotation.line),
+				None => annotation.line.to_string(),
+			};
+			let title = annotation
+				.title
+				.as_deref()
+				.unwrap_or_else(|| annotation.message.lines().next().unwrap_or_default());
+			write!(
+				f,
+				"  {} {} {} {title}",
+				paint(color, icon),
+				paint("2", &format!("{location:<7}")),
+				paint(color, &format!("{:<7}", annotation.kind.name())),
+			)?;
+			if let Some(code) = annotation
+				.code
+				.as_ref()
+				.filter(|code| !title.contains(code.as_ref()))
+			{
+				write!(f, " {}", paint("2", &format!("[{code}]")))?;
+			}
+			writeln!(f)?;
+		}
+
+		let count = |kind| {
+			self.annotations
+				.iter()
+				.filter(|annotation| annotation.kind == kind)
+				.count()
+		};
+		if file.is_some() {
+			writeln!(f)?;
+		}
+		write!(
+			f,
+			"{} {}, {} {}, {} {}",
+			count(AnnotationKind::Error),
+			paint("1;31", "errors"),
+			count(AnnotationKind::Warning),
+			paint("1;33", "warnings"),
+			count(AnnotationKind::Notice),
+			paint("1;36", "notices"),
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ci::tests::annotations;
+
+	#[test]
+	fn terminal_report() {
+		let annotations: BTreeSet<_> = annotations().into_iter().collect();
+		assert_eq!(
+			TerminalReport {
+				annotations: &annotations,
+				color: false,
+			}
+			.to_string(),
+			"(no file)
+  ⚠         warning unknown lint: `clippy::nonexistent_lint` [E0602]
+
+lintfix/src/lib.rs
+  ⚠ 1:15    warning writing `&Vec` instead of `&[_]` involves a new object where a slice will do [clippy::ptr_arg]
+  ℹ 5:20    notice  mismatched types
+  ✖ 6:5     error   mismatched types [E0308]
+
+1 errors, 2 warnings, 1 notices"
+		);
+		assert_eq!(
+			TerminalReport {
+				annotations: &annotations,
+				color: true,
+			}
+			.to_string(),
+			concat!(
+				"\x1b[1;4m(no file)\x1b[0m\n",
+				"  \x1b[1;33m⚠\x1b[0m \x1b[2m       \x1b[0m \x1b[1;33mwarning\x1b[0m unknown lint: `clippy::nonexistent_lint` [E0602]\n",
+				"\n",
+				"\x1b[1;4mlintfix/src/lib.rs\x1b[0m\n",
+				"  \x1b[1;33m⚠\x1b[0m \x1b[2m1:15   \x1b[0m \x1b[1;33mwarning\x1b[0m writing `&Vec` instead of `&[_]` involves a new object where a slice will do [clippy::ptr_arg]\n",
+				"  \x1b[1;36mℹ\x1b[0m \x1b[2m5:20   \x1b[0m \x1b[1;36mnotice \x1b[0m mismatched types\n",
+				"  \x1b[1;31m✖\x1b[0m \x1b[2m6:5    \x1b[0m \x1b[1;31merror  \x1b[0m mismatched types [E0308]\n",
+				"\n",
+				"1 \x1b[1;31merrors\x1b[0m, 2 \x1b[1;33mwarnings\x1b[0m, 1 \x1b[1;36mnotices\x1b[0m",
+			)
+		);
+	}
+}
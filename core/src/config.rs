@@ -0,0 +1,418 @@
+//! Provides the configuration of the program
+
+use crate::{
+	cargo::{CustomParser, MessageStyle},
+	github::{Annotation, AnnotationKind, SortOrder},
+	path,
+	process::Timeout,
+};
+use serde::Deserialize;
+use std::{
+	borrow::Cow,
+	collections::HashMap,
+	fs, io,
+	path::{Path, PathBuf},
+	str::FromStr,
+};
+use tracing::debug;
+
+/// Name of the configuration file
+const CONFIG_FILE: &str = "ghannotate.toml";
+/// Name of the file listing the glob patterns of the paths whose annotations are not emitted
+const IGNORE_FILE: &str = ".ghannotateignore";
+
+/// Configuration read from `ghannotate.toml` or from the metadata of `Cargo.toml`
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+	/// If warnings were to be raised, they would not cause the job to fail
+	pub allow_warnings: bool,
+	/// Maximum number of warnings which do not cause the job to fail
+	pub max_warnings: Option<usize>,
+	/// Rustup toolchain with which Cargo is run (e.g. `beta`)
+	pub toolchain: Option<String>,
+	/// Rustup toolchain with which `cargo fmt` is run (e.g. `nightly-2024-06-01`)
+	pub fmt_toolchain: Option<String>,
+	/// Path to the rustfmt executable with which `cargo fmt` is run, instead of a rustup toolchain
+	pub rustfmt: Option<PathBuf>,
+	/// Run `cargo test` with `RUSTC_BOOTSTRAP=1` on non-*nightly* toolchains, to read the JSON output of libtest
+	pub rustc_bootstrap: bool,
+	/// Number of times Cargo is run again if it failed because of the network
+	pub retries: Option<u32>,
+	/// Time limit after which Cargo is killed
+	pub timeout: Option<Timeout>,
+	/// Stop Cargo as soon as an error is annotated
+	pub fail_fast: bool,
+	/// Never fail because of the annotations
+	pub no_fail: bool,
+	/// Fail with the exit code of Cargo if it failed
+	pub propagate_exit_code: bool,
+	/// Also print the annotated diagnostics as rendered by the tools
+	pub tee: bool,
+	/// Also annotate the wrap-up diagnostics of rustc
+	pub meta_diagnostics: bool,
+	/// Also write the diagnostics as rendered by rustc in the summary
+	pub summary_snippets: bool,
+	/// Also draw charts of the diagnostics in the summary
+	pub summary_charts: bool,
+	/// Path to a template to render the summary with
+	pub summary_template: Option<PathBuf>,
+	/// Minimum kind of the emitted annotations
+	pub annotation_level: Option<AnnotationKind>,
+	/// Style of the messages of the annotations of rustc's diagnostics
+	pub message_style: Option<MessageStyle>,
+	/// Order of the emitted annotations and of the rows of the summary
+	pub sort: Option<SortOrder>,
+	/// Additional arguments to be passed down to Cargo, by subcommand
+	pub args: HashMap<String, Vec<String>>,
+	/// Kinds of annotation to emit instead of others
+	pub severity: HashMap<AnnotationKind, AnnotationKind>,
+	/// Kinds of annotation to emit for lints (or any other code), taking precedence over [`Config.severity`](Config#structfield.severity)
+	pub lints: HashMap<String, AnnotationKind>,
+	/// Lints (or any other codes) whose annotations are not emitted
+	pub ignore_lints: Vec<String>,
+	/// Glob patterns of the paths whose annotations are not emitted
+	pub exclude: Vec<String>,
+	/// Prefixes of the annotated paths to rewrite
+	pub path_map: Vec<PathMap>,
+	/// Parsers of the output of other tools, by name
+	pub parsers: HashMap<String, CustomParser>,
+}
+impl Config {
+	/// Loads the configuration
+	///
+	/// If `path` is [`None`], the configuration is read from `ghannotate.toml`,
+	/// or from the `[workspace.metadata.ghannotate]` or `[package.metadata.ghannotate]` table of `Cargo.toml`.
+	/// If none of them exist, the default configuration is returned.
+	///
+	/// # Errors
+	/// This function returns an error if the configuration could not be read or is invalid.
+	pub fn load(path: Option<&Path>) -> io::Result<Self> {
+		/// Manifest of the package or workspace in the current directory
+		#[derive(Debug, Default, Deserialize)]
+		#[serde(default)]
+		struct Manifest {
+			/// `[workspace]` table
+			workspace: Option<MetadataTable>,
+			/// `[package]` table
+			package: Option<MetadataTable>,
+		}
+		/// Table containing a `metadata` table
+		#[derive(Debug, Default, Deserialize)]
+		#[serde(default)]
+		struct MetadataTable {
+			/// `metadata` table
+			metadata: Option<ToolMetadata>,
+		}
+		/// `metadata` table of a manifest
+		#[derive(Debug, Default, Deserialize)]
+		#[serde(default)]
+		struct ToolMetadata {
+			/// Configuration of this program
+			ghannotate: Option<Config>,
+		}
+
+		if let Some(path) = path {
+			return toml::from_str(&fs::read_to_string(path)?).map_err(io::Error::other);
+		}
+		match fs::read_to_string(CONFIG_FILE) {
+			Ok(content) => return toml::from_str(&content).map_err(io::Error::other),
+			Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+			Err(_) => {}
+		}
+		match fs::read_to_string("Cargo.toml") {
+			Ok(content) => {
+				let manifest: Manifest = toml::from_str(&content).map_err(io::Error::other)?;
+				Ok([manifest.workspace, manifest.package]
+					.into_iter()
+					.flatten()
+					.find_map(|table| table.metadata?.ghannotate)
+					.unwrap_or_default())
+			}
+			Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+			Err(err) => Err(err),
+		}
+	}
+
+	/// Reads the glob patterns of `.ghannotateignore`, if it exists
+	///
+	/// Empty lines and lines starting with `#` are skipped.
+	///
+	/// # Errors
+	/// This function returns an error if the file exists but could not be read.
+	pub fn load_ignore_file() -> io::Result<Vec<String>> {
+		match fs::read_to_string(IGNORE_FILE) {
+			Ok(content) => Ok(content
+				.lines()
+				.map(str::trim)
+				.filter(|line| !(line.is_empty() || line.starts_with('#')))
+				.map(ToOwned::to_owned)
+				.collect()),
+			Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+			Err(err) => Err(err),
+		}
+	}
+
+	/// Returns the additional arguments to be passed down to the Cargo `subcommand`
+	#[inline]
+	pub fn args(&self, subcommand: &str) -> &[String] {
+		self.args.get(subcommand).map_or(&[], Vec::as_slice)
+	}
+
+	/// Returns the kinds of annotation to emit and the ignored lints of the configuration
+	#[inline]
+	pub fn lint_levels(&self) -> LintLevels {
+		LintLevels {
+			severity: self.severity.clone(),
+			lints: self.lints.clone(),
+			ignored: self.ignore_lints.clone(),
+		}
+	}
+
+	/// Applies the rewritings and exclusions of paths of the configuration to `annotation`
+	///
+	/// Returns [`None`] if the annotation is excluded.
+	/// The [lint levels](LintLevels) are applied separately, as the parsers also need them.
+	pub fn apply<'a>(&self, mut annotation: Annotation<'a>) -> Option<Annotation<'a>> {
+		if let Some(file) = self
+			.path_map
+			.iter()
+			.find_map(|path_map| path_map.rewrite(&annotation.file))
+		{
+			annotation.file = Cow::Owned(file);
+		}
+		// The last matching pattern wins, so that negated patterns can re-include paths
+		let excluded =
+			self.exclude
+				.iter()
+				.fold(false, |excluded, pattern| match pattern.strip_prefix('!') {
+					Some(pattern) if path::matches_glob(pattern, &annotation.file) => false,
+					None if path::matches_glob(pattern, &annotation.file) => true,
+					_ => excluded,
+				});
+		if excluded {
+			debug!(file = %annotation.file, "annotation skipped, as its file is excluded");
+			return None;
+		}
+		Some(annotation)
+	}
+}
+
+/// Kinds of annotation to emit instead of others, and lints whose annotations are not emitted
+#[derive(Debug, Default, Clone)]
+pub struct LintLevels {
+	/// [`Config.severity`](Config#structfield.severity)
+	pub severity: HashMap<AnnotationKind, AnnotationKind>,
+	/// [`Config.lints`](Config#structfield.lints)
+	pub lints: HashMap<String, AnnotationKind>,
+	/// [`Config.ignore_lints`](Config#structfield.ignore_lints)
+	pub ignored: Vec<String>,
+}
+impl LintLevels {
+	/// Checks if the annotations of the lint (or any other code) `code` are not emitted
+	#[inline]
+	pub fn is_ignored(&self, code: &str) -> bool {
+		self.ignored.iter().any(|lint| lint == code)
+	}
+
+	/// Returns the kind of annotation to emit instead of `kind`, for a diagnostic of code `code`
+	pub fn kind(&self, code: Option<&str>, kind: AnnotationKind) -> AnnotationKind {
+		code.and_then(|code| self.lints.get(code))
+			.or_else(|| self.severity.get(&kind))
+			.copied()
+			.unwrap_or(kind)
+	}
+
+	/// Applies the lint levels to `annotation`
+	///
+	/// Returns [`None`] if the lint of the annotation is ignored.
+	pub fn apply<'a>(&self, mut annotation: Annotation<'a>) -> Option<Annotation<'a>> {
+		if let Some(code) = annotation
+			.code
+			.as_deref()
+			.filter(|code| self.is_ignored(code))
+		{
+			debug!(file = %annotation.file, code, "annotation skipped, as its lint is ignored");
+			return None;
+		}
+		annotation.kind = self.kind(annotation.code.as_deref(), annotation.kind);
+		Some(annotation)
+	}
+}
+
+/// Rewriting of a prefix of the annotated paths, as passed to the `--path-map` option
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct PathMap {
+	/// Prefix to rewrite
+	from: PathBuf,
+	/// Replacement of the prefix
+	to: PathBuf,
+}
+impl FromStr for PathMap {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (from, to) = s
+			.split_once('=')
+			.ok_or_else(|| format!("invalid path map `{s}`, expected FROM=TO"))?;
+		Ok(Self {
+			from: PathBuf::from(from),
+			to: PathBuf::from(to),
+		})
+	}
+}
+impl TryFrom<String> for PathMap {
+	type Error = String;
+
+	#[inline]
+	fn try_from(s: String) -> Result<Self, Self::Error> {
+		s.parse()
+	}
+}
+impl PathMap {
+	/// Rewrites the prefix of `file`
+	///
+	/// Returns [`None`] if `file` does not start with the prefix.
+	fn rewrite(&self, file: &str) -> Option<String> {
+		path::rewrite_prefix(file, &self.from, &self.to)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::report::tests::annotations;
+
+	/// Returns the TOML code blocks of the README
+	fn readme_examples() -> Vec<&'static str> {
+		include_str!("../../README.md")
+			.split("```toml\n")
+			.skip(1)
+			.filter_map(|block| block.split_once("```").map(|(toml, _)| toml))
+			.collect()
+	}
+
+	#[test]
+	fn readme() {
+		let examples = readme_examples();
+		let config: Config = toml::from_str(examples[0]).unwrap();
+		assert!(config.allow_warnings);
+		assert_eq!(config.max_warnings, Some(25));
+		assert_eq!(config.toolchain.as_deref(), Some("beta"));
+		assert_eq!(config.timeout.unwrap().to_string(), "30m");
+		assert_eq!(config.annotation_level, Some(AnnotationKind::Warning));
+		assert_eq!(
+			config.args("clippy"),
+			["--all-targets", "--", "-W", "clippy::pedantic"]
+		);
+		assert!(config.args("test").is_empty());
+		assert_eq!(
+			config.exclude,
+			["src/generated", "vendor/", "tests/fixtures/**"]
+		);
+		assert_eq!(
+			(&config.path_map[0].from, &config.path_map[0].to),
+			(&PathBuf::from("/build/src"), &PathBuf::from("."))
+		);
+		assert_eq!(
+			config.severity,
+			HashMap::from([(AnnotationKind::Notice, AnnotationKind::Warning)])
+		);
+		assert_eq!(
+			config.lints,
+			HashMap::from([
+				("clippy::todo".to_owned(), AnnotationKind::Error),
+				("dead_code".to_owned(), AnnotationKind::Notice),
+			])
+		);
+
+		let config: Config = toml::from_str(examples[1]).unwrap();
+		let mut parsers: Vec<_> = config.parsers.keys().collect();
+		parsers.sort_unstable();
+		assert_eq!(parsers, ["mylint", "ruff"]);
+	}
+
+	#[test]
+	fn unknown_field() {
+		let err = toml::from_str::<Config>("allow-warning = true").unwrap_err();
+		assert!(
+			err.message().starts_with("unknown field `allow-warning`"),
+			"{err}"
+		);
+		assert!(toml::from_str::<Config>(r#"path-map = ["/build/src"]"#).is_err());
+	}
+
+	#[test]
+	fn apply() {
+		let files = |config: &str| {
+			let config: Config = toml::from_str(config).unwrap();
+			annotations()
+				.into_iter()
+				.filter_map(|annotation| config.apply(annotation))
+				.map(|annotation| format!("{}:{}", annotation.file, annotation.line))
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(
+			files(r#"path-map = ["lintfix=crates/lintfix"]"#),
+			[
+				"crates/lintfix/src/lib.rs:1",
+				"crates/lintfix/src/lib.rs:5",
+				"crates/lintfix/src/lib.rs:6"
+			]
+		);
+		assert!(files(r#"exclude = ["src/"]"#).is_empty());
+		assert!(files(r#"exclude = ["lintfix/**/*.rs"]"#).is_empty());
+		assert_eq!(
+			files(r#"exclude = ["src/", "!lib.rs"]"#),
+			[
+				"lintfix/src/lib.rs:1",
+				"lintfix/src/lib.rs:5",
+				"lintfix/src/lib.rs:6"
+			]
+		);
+		assert!(files(r#"exclude = ["!lib.rs", "src/"]"#).is_empty());
+		assert!(files(
+			r#"
+			path-map = ["lintfix=crates/lintfix"]
+			exclude = ["crates/"]
+			"#
+		)
+		.is_empty());
+	}
+
+	#[test]
+	fn lint_levels() {
+		let config: Config = toml::from_str(
+			r#"
+			ignore-lints = ["E0308"]
+			[severity]
+			notice = "warning"
+			warning = "error"
+			[lints]
+			"clippy::ptr_arg" = "notice"
+			"#,
+		)
+		.unwrap();
+		let lint_levels = config.lint_levels();
+		assert!(lint_levels.is_ignored("E0308"));
+		assert!(!lint_levels.is_ignored("clippy::ptr_arg"));
+		let annotations: Vec<_> = annotations()
+			.into_iter()
+			.filter_map(|annotation| lint_levels.apply(annotation))
+			.map(|annotation| (annotation.line, annotation.kind))
+			.collect();
+		assert_eq!(
+			annotations,
+			[(1, AnnotationKind::Notice), (5, AnnotationKind::Warning)]
+		);
+		assert_eq!(
+			lint_levels.kind(Some("unused_variables"), AnnotationKind::Warning),
+			AnnotationKind::Error
+		);
+		assert_eq!(
+			lint_levels.kind(None, AnnotationKind::Error),
+			AnnotationKind::Error
+		);
+	}
+}
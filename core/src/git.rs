@@ -0,0 +1,98 @@
+//! Provides structures and functions to query Git
+
+use crate::{path::repository_root, process::passthrough};
+use std::{
+	collections::HashMap,
+	ffi::OsStr,
+	io,
+	ops::RangeInclusive,
+	process::{Command, Stdio},
+};
+
+/// Lines changed since a revision, grouped by file
+#[derive(Debug, Default, Clone)]
+pub struct ChangedLines(HashMap<String, Vec<RangeInclusive<usize>>>);
+impl ChangedLines {
+	/// Invokes `git diff` and parses its output
+	///
+	/// The changes are computed between the merge base of `base` and `HEAD`, and `HEAD`,
+	/// with the paths relative to the root of the repository.
+	/// The standard error of Git is discarded if `quiet` is `true`.
+	///
+	/// # Errors
+	/// This function returns an error if Git could not be invoked or if it failed.
+	pub fn load(base: &OsStr, quiet: bool) -> io::Result<Self> {
+		let mut range = base.to_owned();
+		range.push("...HEAD");
+		let output = Command::new("git")
+			.args([
+				"diff",
+				"--unified=0",
+				"--no-color",
+				"--no-ext-diff",
+				"--relative",
+			])
+			.arg(range)
+			.current_dir(repository_root())
+			.stdin(Stdio::null())
+			.stderr(passthrough(quiet))
+			.output()?;
+		if !output.status.success() {
+			return Err(io::Error::other(format!(
+				"git diff failed with {}",
+				output.status
+			)));
+		}
+		Ok(Self::parse(&String::from_utf8_lossy(&output.stdout)))
+	}
+
+	/// Parses the output of `git diff`
+	///
+	/// With `--unified=0`, only the changed lines are kept; otherwise the context lines of the hunks are kept too.
+	pub fn parse(diff: &str) -> Self {
+		let mut changed_lines = Self::default();
+		let mut file = None;
+		for line in diff.lines() {
+			if let Some(path) = line.strip_prefix("+++ ") {
+				file = path.strip_prefix("b/").map(ToOwned::to_owned);
+			} else if let (Some(file), Some(hunk)) = (&file, line.strip_prefix("@@ ")) {
+				let Some(new) = hunk.split(' ').find_map(|range| range.strip_prefix('+')) else {
+					continue;
+				};
+				let (start, count) = new.split_once(',').unwrap_or((new, "1"));
+				let (Ok(start), Ok(count)) = (start.parse::<usize>(), count.parse::<usize>())
+				else {
+					continue;
+				};
+				if count > 0 {
+					changed_lines
+						.0
+						.entry(file.clone())
+						.or_default()
+						.push(start..=start + count - 1);
+				}
+			}
+		}
+		changed_lines
+	}
+
+	/// Checks if any of the `lines` of `file` has changed
+	#[inline]
+	pub fn contains(&self, file: &str, lines: &RangeInclusive<usize>) -> bool {
+		self.0.get(file).is_some_and(|ranges| {
+			ranges
+				.iter()
+				.any(|range| range.start() <= lines.end() && lines.start() <= range.end())
+		})
+	}
+
+	/// Checks if all the `lines` of `file` are in the same hunk
+	#[inline]
+	pub fn covers(&self, file: &str, lines: &RangeInclusive<usize>) -> bool {
+		self.0.get(file).is_some_and(|ranges| {
+			ranges
+				.iter()
+				.any(|range| range.start() <= lines.start() && lines.end() <= range.end())
+		})
+	}
+}
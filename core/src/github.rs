@@ -0,0 +1,711 @@
+//! Provides structures and functions to annotate GitHub Actions
+
+use crate::{cargo::DiagnosticLevel, ci::CiPlatform, path::repository_root};
+use serde::{Deserialize, Serialize};
+use std::{
+	borrow::Cow,
+	cmp::Ordering,
+	collections::{BTreeSet, HashMap},
+	env,
+	fmt::{self, Display, Formatter},
+	fs::{self, OpenOptions},
+	io::{self, Write as _},
+	path::Path,
+};
+
+mod api;
+mod checks;
+mod comment;
+mod debug;
+mod review;
+mod status;
+
+pub use api::*;
+pub use checks::*;
+pub use comment::*;
+pub use debug::*;
+pub use review::*;
+pub use status::*;
+
+/// Maximum length of the message of a workflow command, beyond which GitHub drops the annotation
+const MAX_MESSAGE_LEN: usize = 4000;
+/// Maximum size of the job summary, beyond which GitHub does not show it
+pub const MAX_SUMMARY_SIZE: usize = 1024 * 1024;
+/// Maximum number of annotations of each kind shown by GitHub for a step
+const MAX_ANNOTATIONS_PER_STEP: usize = 10;
+/// Environment variable containing the path to the file of the outputs of the step
+const OUTPUT_PATH_VAR: &str = "GITHUB_OUTPUT";
+
+/// An annotation command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Annotation<'s> {
+	/// Kind of annotation
+	pub kind: AnnotationKind,
+	/// File to annotate (empty if the annotated issue is not located in a file)
+	pub file: Cow<'s, str>,
+	/// Start of the lines to annotate (1-based, inclusive)
+	pub line: usize,
+	/// End of the lines to annotate (1-based)
+	pub end_line: Option<usize>,
+	/// Start of the columns to annotate (1-based, inclusive)
+	pub col: Option<usize>,
+	/// End of the lines to annotate (1-based)
+	pub end_column: Option<usize>,
+	/// Unique code of the annotated issue (e.g. the name of a lint)
+	pub code: Option<Cow<'s, str>>,
+	/// Replacements suggested to fix the annotated issue
+	pub suggestions: Vec<Suggestion<'s>>,
+	/// Annotation title
+	pub title: Option<Cow<'s, str>>,
+	/// Annotation message
+	pub message: Cow<'s, str>,
+}
+impl<'s> Annotation<'s> {
+	/// Checks if the annotated issue is located in a file
+	///
+	/// Some issues (e.g. linker errors) are not located in any file, and are annotated with an empty file.
+	#[inline]
+	pub fn is_located(&self) -> bool {
+		!self.file.is_empty()
+	}
+
+	/// Returns the location of the annotated issue (e.g. `src/main.rs:12:5`), if it is located in a file
+	pub fn location(&self) -> Option<String> {
+		self.is_located().then(|| match self.col {
+			Some(col) => format!("{}:{}:{col}", self.file, self.line),
+			None => format!("{}:{}", self.file, self.line),
+		})
+	}
+
+	/// Returns a fingerprint of the annotated issue, which is stable across the shifts of its lines
+	///
+	/// The fingerprint is computed from the code, the file, the title (or the first line of the message) without its numbers,
+	/// and the content of the annotated lines without their indentation.
+	pub fn fingerprint(&self) -> String {
+		let title = self
+			.title
+			.as_deref()
+			.or_else(|| self.message.lines().next())
+			.unwrap_or_default();
+		let mut normalized_title = String::with_capacity(title.len());
+		for word in title.split_whitespace() {
+			if !normalized_title.is_empty() {
+				normalized_title.push(' ');
+			}
+			normalized_title.extend(word.chars().filter(|c| !c.is_ascii_digit()));
+		}
+		let content = if self.is_located() {
+			fs::read_to_string(repository_root().join(self.file.as_ref()))
+				.map(|content| {
+					let end_line = self.end_line.unwrap_or(self.line).max(self.line);
+					content
+						.lines()
+						.skip(self.line.saturating_sub(1))
+						.take(end_line + 1 - self.line)
+						.map(str::trim)
+						.collect::<Vec<_>>()
+						.join("\n")
+				})
+				.unwrap_or_default()
+		} else {
+			String::new()
+		};
+		fingerprint(
+			&[
+				self.code.as_deref().unwrap_or_default(),
+				&self.file,
+				&normalized_title,
+				&content,
+			],
+			0,
+		)
+	}
+
+	/// Checks if the message is too long to be annotated in full by a workflow command
+	#[inline]
+	pub fn is_truncated(&self) -> bool {
+		self.message.trim().len() > MAX_MESSAGE_LEN
+	}
+
+	/// Returns the message, truncated to fit in a workflow command
+	///
+	/// Only the first lines of long messages are kept, followed by a pointer to the job summary.
+	fn truncated_message(&self) -> Cow<'_, str> {
+		/// Notice appended to truncated messages
+		const NOTICE: &str =
+			"\n[...]\nThis message is truncated, see the job summary for the full message.";
+
+		let message = self.message.trim();
+		if !self.is_truncated() {
+			return Cow::Borrowed(message);
+		}
+		let max_len = MAX_MESSAGE_LEN - NOTICE.len();
+		let mut len = message
+			.match_indices('\n')
+			.map(|(index, _)| index)
+			.take_while(|&index| index <= max_len)
+			.last()
+			.unwrap_or(max_len);
+		while !message.is_char_boundary(len) {
+			len -= 1;
+		}
+		Cow::Owned(format!("{}{NOTICE}", &message[..len]))
+	}
+
+	/// Clones `self` such that all strings are owned
+	#[inline]
+	pub fn to_owned(&self) -> Annotation<'static> {
+		Annotation {
+			kind: self.kind,
+			file: Cow::Owned(self.file.clone().into_owned()),
+			line: self.line,
+			end_line: self.end_line,
+			col: self.col,
+			end_column: self.end_column,
+			code: self.code.clone().map(|code| Cow::Owned(code.into_owned())),
+			suggestions: self
+				.suggestions
+				.iter()
+				.map(|suggestion| Suggestion {
+					replacement: Cow::Owned(suggestion.replacement.clone().into_owned()),
+					..*suggestion
+				})
+				.collect(),
+			title: self
+				.title
+				.clone()
+				.map(|title| Cow::Owned(title.into_owned())),
+			message: Cow::Owned(self.message.clone().into_owned()),
+		}
+	}
+}
+impl<'s> PartialOrd for Annotation<'s> {
+	#[inline]
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+impl<'s> Ord for Annotation<'s> {
+	#[inline]
+	fn cmp(&self, other: &Self) -> Ordering {
+		Path::new(self.file.as_ref())
+			.cmp(Path::new(other.file.as_ref()))
+			.then_with(|| self.line.cmp(&other.line))
+			.then_with(|| self.col.cmp(&other.col))
+			.then_with(|| self.kind.cmp(&other.kind).reverse())
+			// The other fields break the ties, so that the ordering is consistent with equality
+			.then_with(|| self.file.cmp(&other.file))
+			.then_with(|| self.end_line.cmp(&other.end_line))
+			.then_with(|| self.end_column.cmp(&other.end_column))
+			.then_with(|| self.code.cmp(&other.code))
+			.then_with(|| self.title.cmp(&other.title))
+			.then_with(|| self.message.cmp(&other.message))
+			.then_with(|| self.suggestions.cmp(&other.suggestions))
+	}
+}
+impl<'s> Display for Annotation<'s> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "::")?;
+		self.kind.serialize(&mut *f)?;
+		let mut separator = ' ';
+		if self.is_located() {
+			write!(
+				f,
+				" file={},line={}",
+				escape_property(&self.file),
+				self.line
+			)?;
+			if let Some(end_line) = self.end_line {
+				write!(f, ",endLine={end_line}")?;
+			}
+			if let Some(col) = self.col {
+				write!(f, ",col={col}")?;
+				if let Some(end_column) = self.end_column {
+					write!(f, ",endColumn={end_column}")?;
+				}
+			}
+			separator = ',';
+		}
+		if let Some(title) = &self.title {
+			write!(f, "{separator}title={}", escape_property(title))?;
+		}
+		write!(f, "::{}", escape_data(&self.truncated_message()))
+	}
+}
+
+/// Computes a fingerprint of the `occurrence`-th issue described by `parts`
+///
+/// The fingerprint is the 64-bit FNV-1a hash of the parts, so it is stable across runs and platforms.
+pub fn fingerprint(parts: &[&str], occurrence: usize) -> String {
+	/// FNV offset basis
+	const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+	/// FNV prime
+	const PRIME: u64 = 0x0100_0000_01b3;
+
+	let hash = parts
+		.iter()
+		.flat_map(|part| part.bytes().chain([0]))
+		.chain(occurrence.to_le_bytes())
+		.fold(OFFSET_BASIS, |hash, byte| {
+			(hash ^ u64::from(byte)).wrapping_mul(PRIME)
+		});
+	format!("{hash:016x}")
+}
+
+/// Escapes the message of a workflow command
+fn escape_data(data: &str) -> String {
+	data.replace('%', "%25")
+		.replace('\r', "%0D")
+		.replace('\n', "%0A")
+}
+
+/// Escapes a property of a workflow command
+fn escape_property(property: &str) -> String {
+	escape_data(property)
+		.replace(':', "%3A")
+		.replace(',', "%2C")
+}
+
+/// Writes the full messages of the `annotations` which are truncated in workflow commands, as a collapsible section of the job summary
+///
+/// # Errors
+/// This function returns an error if the summary could not be written.
+pub fn write_truncated_messages(
+	annotations: &BTreeSet<Annotation<'_>>,
+	summary: &mut dyn io::Write,
+) -> io::Result<()> {
+	let truncated: Vec<_> = annotations
+		.iter()
+		.filter(|annotation| annotation.is_truncated())
+		.collect();
+	if truncated.is_empty() {
+		return Ok(());
+	}
+	writeln!(
+		summary,
+		"\n<details><summary>Full messages of the {} truncated annotations</summary>\n",
+		truncated.len()
+	)?;
+	for annotation in truncated {
+		let location = annotation
+			.location()
+			.map_or_else(String::new, |location| format!("`{location}`: "));
+		writeln!(
+			summary,
+			"{location}**{}**\n\n```text\n{}\n```\n",
+			annotation.title.as_deref().unwrap_or_default(),
+			annotation.message.trim_end()
+		)?;
+	}
+	writeln!(summary, "</details>")
+}
+
+/// Writes the counts of `annotations` as outputs of the step
+///
+/// The outputs are `errors`, `warnings`, `notices` and `fixable` (the annotations with suggested fixes).
+/// Nothing is written outside of GitHub Actions.
+///
+/// # Errors
+/// This function returns an error if the outputs could not be written.
+pub fn write_outputs<'a>(
+	annotations: impl IntoIterator<Item = &'a Annotation<'a>>,
+) -> io::Result<()> {
+	let Some(path) = env::var_os(OUTPUT_PATH_VAR) else {
+		return Ok(());
+	};
+	let (mut errors, mut warnings, mut notices, mut fixable) = (0, 0, 0, 0);
+	for annotation in annotations {
+		*match annotation.kind {
+			AnnotationKind::Error => &mut errors,
+			AnnotationKind::Warning => &mut warnings,
+			AnnotationKind::Notice => &mut notices,
+		} += 1;
+		if !annotation.suggestions.is_empty() {
+			fixable += 1;
+		}
+	}
+	let mut file = OpenOptions::new().append(true).create(true).open(path)?;
+	write!(
+		file,
+		"errors={errors}\nwarnings={warnings}\nnotices={notices}\nfixable={fixable}\n"
+	)
+}
+
+/// Truncates the `summary` to [`MAX_SUMMARY_SIZE`], at the end of a line
+///
+/// A notice is appended if the summary has been truncated.
+pub fn truncate_summary(summary: &mut Vec<u8>) {
+	/// Notice appended to the truncated summaries
+	const NOTICE: &[u8] = b"\n> :scissors: The summary was truncated to the size limit of GitHub\n";
+
+	if summary.len() <= MAX_SUMMARY_SIZE {
+		return;
+	}
+	let end = summary[..MAX_SUMMARY_SIZE - NOTICE.len()]
+		.iter()
+		.rposition(|byte| *byte == b'\n')
+		.map_or(0, |index| index + 1);
+	summary.truncate(end);
+	summary.extend_from_slice(NOTICE);
+}
+
+/// Writes a preview of the `annotations` and of the `summary`, as a dry run would have written them
+///
+/// The annotations beyond the limits of GitHub are pointed out,
+/// and the summary is truncated as it would be.
+///
+/// # Errors
+/// This function returns an error if the preview could not be written.
+pub fn write_preview(
+	annotations: &BTreeSet<Annotation<'_>>,
+	summary: &[u8],
+	writer: &mut dyn io::Write,
+) -> io::Result<()> {
+	writeln!(writer, "Annotations ({}):", annotations.len())?;
+	CiPlatform::Terminal.write_report(annotations, writer)?;
+	for kind in [
+		AnnotationKind::Error,
+		AnnotationKind::Warning,
+		AnnotationKind::Notice,
+	] {
+		let count = annotations
+			.iter()
+			.filter(|annotation| annotation.kind == kind)
+			.count();
+		if count > MAX_ANNOTATIONS_PER_STEP {
+			writeln!(
+				writer,
+				"GitHub would only show {MAX_ANNOTATIONS_PER_STEP} of the {count} {}s in the step (the others are still listed in the summary)",
+				kind.name()
+			)?;
+		}
+	}
+	let truncated = annotations
+		.iter()
+		.filter(|annotation| annotation.is_truncated())
+		.count();
+	if truncated > 0 {
+		writeln!(
+			writer,
+			"{truncated} annotations would have their message truncated to {MAX_MESSAGE_LEN} bytes (the full messages are written in the summary)"
+		)?;
+	}
+	if summary.len() > MAX_SUMMARY_SIZE {
+		writeln!(
+			writer,
+			"The summary would be truncated from {} bytes to {MAX_SUMMARY_SIZE} bytes",
+			summary.len()
+		)?;
+	}
+	let mut summary = summary.to_vec();
+	truncate_summary(&mut summary);
+	writeln!(writer, "\nSummary:\n")?;
+	writer.write_all(&summary)
+}
+
+/// Writes the `annotations` of pre-existing issues as a collapsible section of the job summary
+///
+/// Pre-existing issues are outside of the changes of the pull request, or known by the baseline.
+///
+/// # Errors
+/// This function returns an error if the summary could not be written.
+pub fn write_preexisting_annotations(
+	annotations: &BTreeSet<Annotation<'_>>,
+	summary: &mut dyn io::Write,
+) -> io::Result<()> {
+	if annotations.is_empty() {
+		return Ok(());
+	}
+	writeln!(
+		summary,
+		"\n<details><summary>{} pre-existing issues</summary>\n",
+		annotations.len()
+	)?;
+	writeln!(summary, "|Level|Message|Location|")?;
+	writeln!(summary, "|:--|:--|--:|")?;
+	for annotation in annotations {
+		let location = annotation.location().unwrap_or_default();
+		let location = match annotation
+			.is_located()
+			.then(|| source_link(&annotation.file, annotation.line))
+			.flatten()
+		{
+			Some(link) => format!("[`{location}`]({link})"),
+			None => format!("`{location}`"),
+		};
+		writeln!(
+			summary,
+			"|{}|{}|{location}|",
+			annotation.kind,
+			annotation
+				.title
+				.as_deref()
+				.or_else(|| annotation.message.lines().next())
+				.unwrap_or_default()
+				.replace('|', "\\|"),
+		)?;
+	}
+	writeln!(summary, "\n</details>")
+}
+
+/// Replacement of a region of the annotated file
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Suggestion<'s> {
+	/// First line of the region (1-based, inclusive)
+	pub line: usize,
+	/// First column of the region (1-based, inclusive)
+	pub col: usize,
+	/// Last line of the region (1-based, inclusive)
+	pub end_line: usize,
+	/// End of the columns of the region (1-based, exclusive)
+	pub end_column: usize,
+	/// Text replacing the region
+	pub replacement: Cow<'s, str>,
+}
+
+#[allow(missing_docs, clippy::missing_docs_in_private_items)]
+/// Kind of annotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationKind {
+	Notice,
+	Warning,
+	Error,
+}
+impl From<DiagnosticLevel> for AnnotationKind {
+	#[inline]
+	fn from(level: DiagnosticLevel) -> Self {
+		match level {
+			DiagnosticLevel::Error | DiagnosticLevel::InternalCompilerError => Self::Error,
+			DiagnosticLevel::Warning => Self::Warning,
+			DiagnosticLevel::Note | DiagnosticLevel::Help | DiagnosticLevel::FailureNote => {
+				Self::Notice
+			}
+		}
+	}
+}
+impl AnnotationKind {
+	/// Returns the name of the annotation kind, as used in workflow commands
+	#[inline]
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::Notice => "notice",
+			Self::Warning => "warning",
+			Self::Error => "error",
+		}
+	}
+
+	/// Returns the emoji associated with the annotation kind
+	#[inline]
+	pub const fn emoji(&self) -> &'static str {
+		match self {
+			Self::Notice => ":information_source:",
+			Self::Warning => ":warning:",
+			Self::Error => ":x:",
+		}
+	}
+}
+impl Display for AnnotationKind {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{} {self:?}", self.emoji())
+	}
+}
+
+/// Order of the emitted annotations and of the rows of the summary
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+	/// By file and line
+	Path,
+	/// Errors first, then warnings, then notices
+	Severity,
+	/// By lint (or any other code)
+	Lint,
+	/// Most frequent lints (or any other codes) first
+	Count,
+}
+impl SortOrder {
+	/// Compares the annotated issues `a` and `b`
+	///
+	/// `count` returns the number of occurrences of a code.
+	pub fn compare(
+		self,
+		a: &SortKey<'_>,
+		b: &SortKey<'_>,
+		count: impl Fn(Option<&str>) -> usize,
+	) -> Ordering {
+		let by_path = || {
+			Path::new(a.file)
+				.cmp(Path::new(b.file))
+				.then_with(|| a.line.cmp(&b.line))
+		};
+		let by_lint = || {
+			// The issues without code come last
+			a.code
+				.is_none()
+				.cmp(&b.code.is_none())
+				.then_with(|| a.code.cmp(&b.code))
+		};
+		match self {
+			Self::Path => by_path(),
+			Self::Severity => b.kind.cmp(&a.kind).then_with(by_path),
+			Self::Lint => by_lint().then_with(by_path),
+			Self::Count => count(b.code)
+				.cmp(&count(a.code))
+				.then_with(by_lint)
+				.then_with(by_path),
+		}
+	}
+}
+
+/// Properties of an annotated issue by which it can be sorted
+#[derive(Debug, Clone, Copy)]
+pub struct SortKey<'a> {
+	/// Kind of annotation
+	pub kind: AnnotationKind,
+	/// Code of the issue
+	pub code: Option<&'a str>,
+	/// Annotated file
+	pub file: &'a str,
+	/// Annotated line
+	pub line: usize,
+}
+impl<'a> From<&'a Annotation<'a>> for SortKey<'a> {
+	#[inline]
+	fn from(annotation: &'a Annotation<'a>) -> Self {
+		Self {
+			kind: annotation.kind,
+			code: annotation.code.as_deref(),
+			file: &annotation.file,
+			line: annotation.line,
+		}
+	}
+}
+
+/// Sorts the `annotations` in the given `order`
+pub fn sort_annotations(annotations: &mut [Annotation<'_>], order: SortOrder) {
+	let mut counts = HashMap::<String, usize>::new();
+	let mut without_code = 0;
+	for annotation in annotations.iter() {
+		match &annotation.code {
+			Some(code) => *counts.entry(code.clone().into_owned()).or_default() += 1,
+			None => without_code += 1,
+		}
+	}
+	annotations.sort_by(|a, b| {
+		order.compare(&SortKey::from(a), &SortKey::from(b), |code| {
+			code.map_or(without_code, |code| {
+				counts.get(code).copied().unwrap_or_default()
+			})
+		})
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn escape_command() {
+		let annotation = Annotation {
+			kind: AnnotationKind::Warning,
+			file: Cow::Borrowed("src/a,b.rs"),
+			line: 1,
+			end_line: None,
+			col: None,
+			end_column: None,
+			code: None,
+			suggestions: Vec::new(),
+			title: Some(Cow::Borrowed("expected `::`, found `%`\r\n")),
+			message: Cow::Borrowed("100% done:\nnext::line"),
+		};
+		assert_eq!(
+			annotation.to_string(),
+			"::warning file=src/a%2Cb.rs,line=1,title=expected `%3A%3A`%2C found `%25`%0D%0A::100%25 done:%0Anext::line"
+		);
+	}
+
+	#[test]
+	fn order_consistent_with_equality() {
+		let annotation = Annotation {
+			kind: AnnotationKind::Error,
+			file: Cow::Borrowed("src/lib.rs"),
+			line: 3,
+			end_line: Some(3),
+			col: Some(5),
+			end_column: Some(9),
+			code: Some(Cow::Borrowed("E0308")),
+			suggestions: Vec::new(),
+			title: Some(Cow::Borrowed("mismatched types [E0308]")),
+			message: Cow::Owned(format!("{}\nexpected `u8`", "a".repeat(MAX_MESSAGE_LEN))),
+		};
+		let other = Annotation {
+			message: Cow::Owned(format!("{}\nexpected `u16`", "a".repeat(MAX_MESSAGE_LEN))),
+			..annotation.clone()
+		};
+		// Both messages are truncated to the same command
+		assert_eq!(annotation.to_string(), other.to_string());
+		assert_ne!(annotation.cmp(&other), Ordering::Equal);
+		let retitled = Annotation {
+			title: Some(Cow::Borrowed("mismatched types")),
+			..annotation.clone()
+		};
+		assert_ne!(annotation.cmp(&retitled), Ordering::Equal);
+		assert_eq!(annotation.cmp(&annotation.to_owned()), Ordering::Equal);
+		let set = BTreeSet::from([annotation.clone(), other, retitled, annotation]);
+		assert_eq!(set.len(), 3);
+	}
+
+	#[test]
+	fn truncate_message_at_char_boundary() {
+		// Either of the messages has a 2-byte character at the cut point
+		for prefix in ["", "a"] {
+			let annotation = Annotation {
+				kind: AnnotationKind::Error,
+				file: Cow::Borrowed(""),
+				line: 0,
+				end_line: None,
+				col: None,
+				end_column: None,
+				code: None,
+				suggestions: Vec::new(),
+				title: None,
+				message: Cow::Owned(format!("{prefix}{}", "é".repeat(MAX_MESSAGE_LEN))),
+			};
+			assert!(annotation.is_truncated());
+			let message = annotation.truncated_message();
+			assert!(message.len() <= MAX_MESSAGE_LEN);
+			let (kept, notice) = message.split_once("\n[...]\n").unwrap();
+			assert!(kept.trim_start_matches(prefix).chars().all(|c| c == 'é'));
+			assert!(kept.len() > MAX_MESSAGE_LEN - 100);
+			assert!(notice.starts_with("This message is truncated"));
+		}
+	}
+
+	#[test]
+	fn truncate_summary_at_char_boundary() {
+		let line = format!("{}\n", "é".repeat(99));
+		for prefix in ["", "a"] {
+			let mut summary =
+				format!("{prefix}{}", line.repeat(MAX_SUMMARY_SIZE / line.len() + 1)).into_bytes();
+			truncate_summary(&mut summary);
+			assert!(summary.len() <= MAX_SUMMARY_SIZE);
+			let summary = String::from_utf8(summary).unwrap();
+			let (kept, notice) = summary.rsplit_once("\n\n").unwrap();
+			assert!(kept
+				.trim_start_matches(prefix)
+				.lines()
+				.all(|kept| kept == line.trim_end()));
+			assert!(notice.starts_with("> :scissors:"));
+		}
+
+		// A summary without lines is dropped rather than cut in a character
+		let mut summary = "é".repeat(MAX_SUMMARY_SIZE).into_bytes();
+		truncate_summary(&mut summary);
+		assert!(String::from_utf8(summary)
+			.unwrap()
+			.starts_with("\n> :scissors:"));
+	}
+}
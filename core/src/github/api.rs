@@ -0,0 +1,464 @@
+//! Provides a client of the GitHub REST API
+
+use crate::TOOL_NAME;
+use rustls_pki_types::{pem::PemObject, CertificateDer};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+	env, fs, io,
+	path::Path,
+	sync::{Arc, OnceLock},
+};
+
+/// URL of the GitHub REST API of github.com
+const DEFAULT_API_URL: &str = "https://api.github.com";
+/// URL of github.com
+const DEFAULT_SERVER_URL: &str = "https://github.com";
+/// Environment variable containing the URL of the GitHub REST API
+const API_URL_VAR: &str = "GITHUB_API_URL";
+/// Environment variable containing the URL of the GitHub server
+const SERVER_URL_VAR: &str = "GITHUB_SERVER_URL";
+/// Version of the GitHub REST API
+const API_VERSION: &str = "2022-11-28";
+/// Environment variable containing the token to authenticate to the API
+const TOKEN_VAR: &str = "GITHUB_TOKEN";
+/// Environment variable containing the owner and name of the repository
+const REPOSITORY_VAR: &str = "GITHUB_REPOSITORY";
+
+/// Client of the GitHub REST API, authenticated for a repository
+#[derive(Debug, Clone)]
+pub struct GithubClient {
+	/// HTTP agent sending the requests
+	agent: ureq::Agent,
+	/// URL of the REST API (e.g. `https://github.example.com/api/v3` for GitHub Enterprise Server)
+	api_url: String,
+	/// Token to authenticate to the API
+	token: String,
+	/// Owner and name of the repository (e.g. `Dragonink/cargo-ghannotate`)
+	repository: String,
+}
+impl GithubClient {
+	/// Creates a client from the environment of the job
+	///
+	/// The certificates of `ca_bundle` are trusted in addition to the usual root certificates.
+	///
+	/// # Errors
+	/// This function returns an error if the token or the repository are not set, or if `ca_bundle` is invalid.
+	pub fn from_env(ca_bundle: Option<&Path>) -> io::Result<Self> {
+		let var = |name| {
+			env::var(name)
+				.ok()
+				.filter(|value| !value.is_empty())
+				.ok_or_else(|| io::Error::other(format!("{name} is not set")))
+		};
+		let mut agent = ureq::AgentBuilder::new()
+			.user_agent(&format!("{TOOL_NAME}/{}", env!("CARGO_PKG_VERSION")));
+		if let Some(ca_bundle) = ca_bundle {
+			agent = agent.tls_config(Arc::new(tls_config(ca_bundle)?));
+		}
+		let api_url = match (env::var(API_URL_VAR), env::var(SERVER_URL_VAR)) {
+			(Ok(api_url), _) if !api_url.is_empty() => api_url,
+			(_, Ok(server_url)) if !server_url.is_empty() && server_url != DEFAULT_SERVER_URL => {
+				format!("{}/api/v3", server_url.trim_end_matches('/'))
+			}
+			_ => DEFAULT_API_URL.to_owned(),
+		};
+		Ok(Self {
+			agent: agent.build(),
+			api_url: api_url.trim_end_matches('/').to_owned(),
+			token: var(TOKEN_VAR)?,
+			repository: var(REPOSITORY_VAR)?,
+		})
+	}
+
+	/// Returns the URL of the endpoint `path` of the repository
+	///
+	/// Absolute URLs (e.g. from the `Link` header) are returned as is.
+	fn url(&self, path: &str) -> String {
+		if path.starts_with("https://") || path.starts_with("http://") {
+			path.to_owned()
+		} else {
+			format!("{}/repos/{}{path}", self.api_url, self.repository)
+		}
+	}
+
+	/// Sends a request to the endpoint `path` of the repository
+	///
+	/// # Errors
+	/// This function returns an error if the request could not be sent or if the API responded with an error.
+	fn send(
+		&self,
+		method: &str,
+		path: &str,
+		body: Option<serde_json::Value>,
+	) -> io::Result<ureq::Response> {
+		let request = self
+			.agent
+			.request(method, &self.url(path))
+			.set("Accept", "application/vnd.github+json")
+			.set("Authorization", &format!("Bearer {}", self.token))
+			.set("X-GitHub-Api-Version", API_VERSION);
+		let response = match body {
+			Some(body) => request.send_json(body),
+			None => request.call(),
+		};
+		response.map_err(|err| match err {
+			ureq::Error::Status(status, response) => io::Error::other(format!(
+				"{method} {path} failed with {status}: {}",
+				response.into_string().unwrap_or_default()
+			)),
+			ureq::Error::Transport(err) => io::Error::other(err),
+		})
+	}
+
+	/// Sends `GET` requests to the endpoint `path` of the repository, following the pagination
+	///
+	/// Returns all the pages of the response.
+	///
+	/// # Errors
+	/// This function returns an error if a request failed or if a response is invalid.
+	pub fn get_pages<T: DeserializeOwned>(&self, path: &str) -> io::Result<Vec<T>> {
+		let mut pages = Vec::new();
+		let mut next = Some(path.to_owned());
+		while let Some(path) = next {
+			let response = self.send("GET", &path, None)?;
+			next = response.header("Link").and_then(next_page);
+			pages.push(response.into_json()?);
+		}
+		Ok(pages)
+	}
+
+	/// Returns the OAuth scopes of the token, after checking that it gives access to the repository
+	///
+	/// Returns [`None`] if the token has permissions instead of scopes (e.g. `GITHUB_TOKEN` or fine-grained tokens).
+	///
+	/// # Errors
+	/// This function returns an error if the repository could not be reached with the token.
+	pub fn scopes(&self) -> io::Result<Option<Vec<String>>> {
+		let response = self.send("GET", "", None)?;
+		Ok(response.header("X-OAuth-Scopes").map(|scopes| {
+			scopes
+				.split(',')
+				.map(str::trim)
+				.filter(|scope| !scope.is_empty())
+				.map(ToOwned::to_owned)
+				.collect()
+		}))
+	}
+
+	/// Sends a `POST` request with `body` to the endpoint `path` of the repository
+	///
+	/// # Errors
+	/// This function returns an error if the request failed or if the response is invalid.
+	pub fn post<T: DeserializeOwned>(&self, path: &str, body: &impl Serialize) -> io::Result<T> {
+		self.send("POST", path, Some(serde_json::to_value(body)?))?
+			.into_json()
+	}
+
+	/// Sends a `PATCH` request with `body` to the endpoint `path` of the repository
+	///
+	/// # Errors
+	/// This function returns an error if the request failed or if the response is invalid.
+	pub fn patch<T: DeserializeOwned>(&self, path: &str, body: &impl Serialize) -> io::Result<T> {
+		self.send("PATCH", path, Some(serde_json::to_value(body)?))?
+			.into_json()
+	}
+}
+
+/// Returns the TLS configuration trusting the certificates of `ca_bundle`
+///
+/// # Errors
+/// This function returns an error if `ca_bundle` could not be read or contains invalid certificates.
+fn tls_config(ca_bundle: &Path) -> io::Result<rustls::ClientConfig> {
+	let mut roots = rustls::RootCertStore {
+		roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+	};
+	for certificate in CertificateDer::pem_file_iter(ca_bundle).map_err(io::Error::other)? {
+		roots
+			.add(certificate.map_err(io::Error::other)?)
+			.map_err(io::Error::other)?;
+	}
+	Ok(rustls::ClientConfig::builder_with_provider(Arc::new(
+		rustls::crypto::ring::default_provider(),
+	))
+	.with_safe_default_protocol_versions()
+	.map_err(io::Error::other)?
+	.with_root_certificates(roots)
+	.with_no_client_auth())
+}
+
+/// Extracts the URL of the next page from the `Link` header of a response
+fn next_page(link: &str) -> Option<String> {
+	link.split(',').find_map(|link| {
+		let (url, params) = link.split_once(';')?;
+		params
+			.split(';')
+			.any(|param| param.trim() == r#"rel="next""#)
+			.then(|| {
+				url.trim()
+					.trim_start_matches('<')
+					.trim_end_matches('>')
+					.to_owned()
+			})
+	})
+}
+
+/// Payload of the event which triggered the workflow
+#[derive(Debug, Deserialize)]
+struct Event {
+	/// Pull request of the event
+	pull_request: Option<PullRequest>,
+}
+
+/// Pull request of an [`Event`]
+#[derive(Debug, Deserialize)]
+struct PullRequest {
+	/// Number of the pull request
+	number: u64,
+	/// Head of the pull request
+	head: PullRequestHead,
+}
+
+/// Head of a [`PullRequest`]
+#[derive(Debug, Deserialize)]
+struct PullRequestHead {
+	/// SHA of the head commit
+	sha: String,
+}
+
+/// Returns the pull request of the event which triggered the workflow, if any
+fn pull_request() -> Option<PullRequest> {
+	let event = fs::read(env::var_os("GITHUB_EVENT_PATH")?).ok()?;
+	serde_json::from_slice::<Event>(&event).ok()?.pull_request
+}
+
+/// Returns the number of the pull request which triggered the workflow, if any
+#[inline]
+pub fn pull_request_number() -> Option<u64> {
+	pull_request().map(|pull_request| pull_request.number)
+}
+
+/// Returns the SHA of the commit being checked
+///
+/// For pull requests, this is the head of the pull request rather than the merge commit.
+///
+/// # Errors
+/// This function returns an error if the commit could not be determined.
+pub fn head_sha() -> io::Result<String> {
+	match pull_request() {
+		Some(pull_request) => Ok(pull_request.head.sha),
+		None => env::var("GITHUB_SHA").map_err(io::Error::other),
+	}
+}
+
+/// Returns the remote revision of the base branch of the pull request which triggered the workflow
+///
+/// # Errors
+/// This function returns an error if the workflow was not triggered by a pull request.
+pub fn base_revision() -> io::Result<String> {
+	env::var("GITHUB_BASE_REF")
+		.ok()
+		.filter(|base| !base.is_empty())
+		.map(|base| format!("origin/{base}"))
+		.ok_or_else(|| io::Error::other("the workflow was not triggered by a pull request"))
+}
+
+/// Returns the URL of the files of the checked commit (e.g. `https://github.com/Dragonink/cargo-ghannotate/blob/<SHA>`)
+///
+/// Returns [`None`] if the repository or the commit could not be determined.
+fn blob_url() -> Option<&'static str> {
+	/// Cached URL
+	static BLOB_URL: OnceLock<Option<String>> = OnceLock::new();
+
+	BLOB_URL
+		.get_or_init(|| {
+			let server_url = env::var(SERVER_URL_VAR)
+				.ok()
+				.filter(|url| !url.is_empty())
+				.unwrap_or_else(|| DEFAULT_SERVER_URL.to_owned());
+			let repository = env::var(REPOSITORY_VAR)
+				.ok()
+				.filter(|repo| !repo.is_empty())?;
+			let sha = head_sha().ok().filter(|sha| !sha.is_empty())?;
+			Some(format!(
+				"{}/{repository}/blob/{sha}",
+				server_url.trim_end_matches('/')
+			))
+		})
+		.as_deref()
+}
+
+/// Returns a link to the `line` of `file` in the checked commit
+///
+/// Returns [`None`] if the repository or the commit could not be determined.
+pub fn source_link(file: &str, line: usize) -> Option<String> {
+	Some(format!(
+		"{}/{}#L{line}",
+		blob_url()?,
+		file.replace('\\', "/").replace(' ', "%20")
+	))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+	use std::{
+		io::{BufRead, BufReader, Read, Write},
+		net::TcpListener,
+		thread::{self, JoinHandle},
+	};
+
+	/// Request received by a [served](serve) API
+	#[derive(Debug, PartialEq, Eq)]
+	pub(crate) struct Request {
+		/// Method of the request
+		pub(crate) method: String,
+		/// Path and query of the request
+		pub(crate) path: String,
+		/// JSON body of the request
+		pub(crate) body: Option<serde_json::Value>,
+	}
+
+	/// Serves the `responses` (status, headers and body) to the successive requests of the returned client
+	///
+	/// `{api}` is replaced by the URL of the API in the headers.
+	/// The handle returns the received requests once all the responses have been served.
+	pub(crate) fn serve(
+		responses: Vec<(u16, &'static str, &'static str)>,
+	) -> (GithubClient, JoinHandle<Vec<Request>>) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let api_url = format!("http://{}", listener.local_addr().unwrap());
+		let client = GithubClient {
+			agent: ureq::Agent::new(),
+			api_url: api_url.clone(),
+			token: "ghp_token".to_owned(),
+			repository: "Dragonink/cargo-ghannotate".to_owned(),
+		};
+		let handle = thread::spawn(move || {
+			responses
+				.into_iter()
+				.map(|(status, headers, body)| {
+					let (stream, _) = listener.accept().unwrap();
+					let mut reader = BufReader::new(stream);
+					let mut line = String::new();
+					reader.read_line(&mut line).unwrap();
+					let mut request_line = line.split_whitespace();
+					let method = request_line.next().unwrap().to_owned();
+					let path = request_line.next().unwrap().to_owned();
+					let mut length = 0;
+					loop {
+						line.clear();
+						reader.read_line(&mut line).unwrap();
+						let Some((name, value)) = line.split_once(':') else {
+							break;
+						};
+						if name.eq_ignore_ascii_case("Content-Length") {
+							length = value.trim().parse().unwrap();
+						} else if name.eq_ignore_ascii_case("Authorization") {
+							assert_eq!(value.trim(), "Bearer ghp_token");
+						}
+					}
+					let mut request_body = vec![0; length];
+					reader.read_exact(&mut request_body).unwrap();
+					write!(
+						reader.get_mut(),
+						"HTTP/1.1 {status} Status\r\n{}Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+						headers.replace("{api}", &api_url),
+						body.len(),
+					)
+					.unwrap();
+					Request {
+						method,
+						path,
+						body: (length > 0).then(|| serde_json::from_slice(&request_body).unwrap()),
+					}
+				})
+				.collect()
+		});
+		(client, handle)
+	}
+
+	#[test]
+	fn pagination() {
+		let (client, handle) = serve(vec![
+			(
+				200,
+				"Link: <{api}/repositories/1/pulls/7/files?per_page=2&page=2>; rel=\"next\", <{api}/repositories/1/pulls/7/files?per_page=2&page=2>; rel=\"last\"\r\n",
+				r#"[{"filename":"src/main.rs"},{"filename":"src/lib.rs"}]"#,
+			),
+			(
+				200,
+				"Link: <{api}/repositories/1/pulls/7/files?per_page=2&page=1>; rel=\"prev\", <{api}/repositories/1/pulls/7/files?per_page=2&page=1>; rel=\"first\"\r\n",
+				r#"[{"filename":"README.md"}]"#,
+			),
+		]);
+		let pages: Vec<serde_json::Value> = client.get_pages("/pulls/7/files?per_page=2").unwrap();
+		assert_eq!(
+			pages,
+			[
+				serde_json::json!([{"filename": "src/main.rs"}, {"filename": "src/lib.rs"}]),
+				serde_json::json!([{"filename": "README.md"}]),
+			]
+		);
+		assert_eq!(
+			handle.join().unwrap(),
+			[
+				Request {
+					method: "GET".to_owned(),
+					path: "/repos/Dragonink/cargo-ghannotate/pulls/7/files?per_page=2".to_owned(),
+					body: None,
+				},
+				Request {
+					method: "GET".to_owned(),
+					path: "/repositories/1/pulls/7/files?per_page=2&page=2".to_owned(),
+					body: None,
+				},
+			]
+		);
+	}
+
+	#[test]
+	fn scopes() {
+		let (client, handle) = serve(vec![
+			(200, "X-OAuth-Scopes: repo, workflow\r\n", "{}"),
+			(200, "", "{}"),
+		]);
+		assert_eq!(
+			client.scopes().unwrap(),
+			Some(vec!["repo".to_owned(), "workflow".to_owned()])
+		);
+		assert_eq!(client.scopes().unwrap(), None);
+		assert!(handle
+			.join()
+			.unwrap()
+			.iter()
+			.all(|request| request.method == "GET"
+				&& request.path == "/repos/Dragonink/cargo-ghannotate"));
+	}
+
+	#[test]
+	fn error() {
+		let (client, handle) = serve(vec![(
+			422,
+			"",
+			r#"{"message":"Validation Failed","documentation_url":"https://docs.github.com/rest/checks/runs#update-a-check-run","status":"422"}"#,
+		)]);
+		let err = client
+			.patch::<serde_json::Value>(
+				"/check-runs/4",
+				&serde_json::json!({"status": "completed"}),
+			)
+			.unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			r#"PATCH /check-runs/4 failed with 422: {"message":"Validation Failed","documentation_url":"https://docs.github.com/rest/checks/runs#update-a-check-run","status":"422"}"#
+		);
+		assert_eq!(
+			handle.join().unwrap(),
+			[Request {
+				method: "PATCH".to_owned(),
+				path: "/repos/Dragonink/cargo-ghannotate/check-runs/4".to_owned(),
+				body: Some(serde_json::json!({"status": "completed"})),
+			}]
+		);
+	}
+}
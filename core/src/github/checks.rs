@@ -0,0 +1,393 @@
+//! Provides the structs to report annotations in a check run of the GitHub Checks API
+
+use super::{head_sha, Annotation, AnnotationKind, GithubClient};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, io};
+
+/// Maximum number of annotations per request to the Checks API
+const MAX_ANNOTATIONS_PER_REQUEST: usize = 50;
+/// Maximum length of the texts of the output of a check run
+const MAX_TEXT_LENGTH: usize = 65_535;
+
+/// Check run of the GitHub Checks API
+#[derive(Debug, Clone)]
+pub struct CheckRun {
+	/// Client of the API
+	client: GithubClient,
+	/// Identifier of the check run
+	id: u64,
+}
+impl CheckRun {
+	/// Starts the check run named `name` on the commit being checked
+	///
+	/// If a check run with the same name already exists on the commit, it is updated instead.
+	///
+	/// # Errors
+	/// This function returns an error if the check run could not be created.
+	pub fn start(client: GithubClient, name: &str) -> io::Result<Self> {
+		let head_sha = head_sha()?;
+		let existing = client
+			.get_pages::<CheckRunList>(&format!(
+				"/commits/{head_sha}/check-runs?check_name={}&filter=latest&per_page=100",
+				encode_query(name)
+			))?
+			.into_iter()
+			.flat_map(|page| page.check_runs)
+			.next();
+		let body = CheckRunRequest {
+			name: Some(name),
+			head_sha: Some(&head_sha),
+			status: "in_progress",
+			conclusion: None,
+			output: None,
+		};
+		let check_run: CheckRunResponse = match existing {
+			Some(check_run) => client.patch(&format!("/check-runs/{}", check_run.id), &body)?,
+			None => client.post("/check-runs", &body)?,
+		};
+		Ok(Self {
+			client,
+			id: check_run.id,
+		})
+	}
+
+	/// Completes the check run with the `annotations` and the `summary`
+	///
+	/// The annotations are attached in batches, as the API limits their number per request.
+	/// The check run fails if an annotation is at least as severe as `threshold`.
+	///
+	/// # Errors
+	/// This function returns an error if the check run could not be updated.
+	pub fn complete(
+		self,
+		annotations: &BTreeSet<Annotation<'_>>,
+		summary: &str,
+		threshold: AnnotationKind,
+	) -> io::Result<()> {
+		let count = |kind| {
+			annotations
+				.iter()
+				.filter(|annotation| annotation.kind == kind)
+				.count()
+		};
+		let title = format!(
+			"{} errors, {} warnings, {} notices",
+			count(AnnotationKind::Error),
+			count(AnnotationKind::Warning),
+			count(AnnotationKind::Notice),
+		);
+		let conclusion = match annotations.iter().map(|annotation| annotation.kind).max() {
+			Some(kind) if kind >= threshold => "failure",
+			Some(_) => "neutral",
+			None => "success",
+		};
+		// Annotations of the Checks API must be located in a file, the others are only in the summary
+		let annotations: Vec<_> = annotations
+			.iter()
+			.filter(|annotation| annotation.is_located())
+			.map(CheckAnnotation::from)
+			.collect();
+		let mut batches = annotations.chunks(MAX_ANNOTATIONS_PER_REQUEST).peekable();
+		let path = format!("/check-runs/{}", self.id);
+		loop {
+			let batch = batches.next().unwrap_or_default();
+			let last = batches.peek().is_none();
+			self.client.patch::<CheckRunResponse>(
+				&path,
+				&CheckRunRequest {
+					name: None,
+					head_sha: None,
+					status: if last { "completed" } else { "in_progress" },
+					conclusion: last.then_some(conclusion),
+					output: Some(CheckRunOutput {
+						title: &title,
+						summary: truncate(summary),
+						annotations: batch,
+					}),
+				},
+			)?;
+			if last {
+				return Ok(());
+			}
+		}
+	}
+}
+
+/// Percent-encodes `value` to be used in a query string
+fn encode_query(value: &str) -> String {
+	value
+		.bytes()
+		.map(|byte| match byte {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+				char::from(byte).to_string()
+			}
+			byte => format!("%{byte:02X}"),
+		})
+		.collect()
+}
+
+/// Truncates `text` to the maximum length of the texts of the output of a check run
+fn truncate(text: &str) -> &str {
+	if text.len() <= MAX_TEXT_LENGTH {
+		return text;
+	}
+	let mut end = MAX_TEXT_LENGTH;
+	while !text.is_char_boundary(end) {
+		end -= 1;
+	}
+	&text[..end]
+}
+
+/// Body of the requests creating or updating a check run
+#[derive(Debug, Serialize)]
+struct CheckRunRequest<'a> {
+	/// Name of the check run
+	#[serde(skip_serializing_if = "Option::is_none")]
+	name: Option<&'a str>,
+	/// SHA of the checked commit
+	#[serde(skip_serializing_if = "Option::is_none")]
+	head_sha: Option<&'a str>,
+	/// Status of the check run
+	status: &'a str,
+	/// Conclusion of the completed check run
+	#[serde(skip_serializing_if = "Option::is_none")]
+	conclusion: Option<&'a str>,
+	/// Output of the check run
+	#[serde(skip_serializing_if = "Option::is_none")]
+	output: Option<CheckRunOutput<'a>>,
+}
+
+/// Output of a check run
+#[derive(Debug, Serialize)]
+struct CheckRunOutput<'a> {
+	/// Title of the output
+	title: &'a str,
+	/// Summary of the output (Markdown)
+	summary: &'a str,
+	/// Annotations attached by the request
+	annotations: &'a [CheckAnnotation<'a>],
+}
+
+/// Annotation of a check run
+#[derive(Debug, Serialize)]
+struct CheckAnnotation<'a> {
+	/// Path to the annotated file
+	path: &'a str,
+	/// First annotated line
+	start_line: usize,
+	/// Last annotated line
+	end_line: usize,
+	/// First annotated column (only if a single line is annotated)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	start_column: Option<usize>,
+	/// Last annotated column (only if a single line is annotated)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	end_column: Option<usize>,
+	/// Level of the annotation
+	annotation_level: &'static str,
+	/// Message of the annotation
+	message: &'a str,
+	/// Title of the annotation
+	#[serde(skip_serializing_if = "Option::is_none")]
+	title: Option<&'a str>,
+}
+impl<'a, 's> From<&'a Annotation<'s>> for CheckAnnotation<'a> {
+	fn from(annotation: &'a Annotation<'s>) -> Self {
+		let end_line = annotation.end_line.unwrap_or(annotation.line);
+		let single_line = end_line == annotation.line;
+		Self {
+			path: &annotation.file,
+			start_line: annotation.line,
+			end_line,
+			start_column: annotation.col.filter(|_| single_line),
+			end_column: annotation.end_column.filter(|_| single_line),
+			annotation_level: match annotation.kind {
+				AnnotationKind::Error => "failure",
+				AnnotationKind::Warning => "warning",
+				AnnotationKind::Notice => "notice",
+			},
+			message: truncate(annotation.message.trim()),
+			title: annotation.title.as_deref(),
+		}
+	}
+}
+
+/// Response of the requests creating or updating a check run
+#[derive(Debug, Deserialize)]
+struct CheckRunResponse {
+	/// Identifier of the check run
+	id: u64,
+}
+
+/// Page of the list of the check runs of a commit
+#[derive(Debug, Deserialize)]
+struct CheckRunList {
+	/// Check runs of the page
+	check_runs: Vec<CheckRunResponse>,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		ci::tests::annotations,
+		github::api::tests::{serve, Request},
+	};
+	use serde_json::json;
+	use std::borrow::Cow;
+
+	/// Returns the `PATCH` request completing the check run with the `output`
+	fn completion(conclusion: &str, output: serde_json::Value) -> Request {
+		Request {
+			method: "PATCH".to_owned(),
+			path: "/repos/Dragonink/cargo-ghannotate/check-runs/4".to_owned(),
+			body: Some(json!({
+				"status": "completed",
+				"conclusion": conclusion,
+				"output": output,
+			})),
+		}
+	}
+
+	#[test]
+	fn complete() {
+		let (client, handle) = serve(vec![(200, "", r#"{"id":4}"#)]);
+		CheckRun { client, id: 4 }
+			.complete(
+				&annotations().into_iter().collect(),
+				"# Summary",
+				AnnotationKind::Error,
+			)
+			.unwrap();
+		assert_eq!(
+			handle.join().unwrap(),
+			[completion(
+				"failure",
+				json!({
+					"title": "1 errors, 2 warnings, 1 notices",
+					"summary": "# Summary",
+					"annotations": [
+						{
+							"path": "lintfix/src/lib.rs",
+							"start_line": 1,
+							"end_line": 1,
+							"start_column": 15,
+							"end_column": 24,
+							"annotation_level": "warning",
+							"message": "writing `&Vec` instead of `&[_]` involves a new object where a slice will do",
+							"title": "writing `&Vec` instead of `&[_]` involves a new object where a slice will do [clippy::ptr_arg]",
+						},
+						{
+							"path": "lintfix/src/lib.rs",
+							"start_line": 5,
+							"end_line": 5,
+							"start_column": 20,
+							"end_column": 23,
+							"annotation_level": "notice",
+							"message": "expected `u32` because of return type",
+							"title": "mismatched types",
+						},
+						{
+							"path": "lintfix/src/lib.rs",
+							"start_line": 6,
+							"end_line": 6,
+							"start_column": 5,
+							"end_column": 9,
+							"annotation_level": "failure",
+							"message": "mismatched types",
+							"title": "mismatched types [E0308]",
+						},
+					],
+				})
+			)]
+		);
+	}
+
+	#[test]
+	fn success() {
+		let (client, handle) = serve(vec![(200, "", r#"{"id":4}"#)]);
+		CheckRun { client, id: 4 }
+			.complete(&BTreeSet::new(), "# Summary", AnnotationKind::Warning)
+			.unwrap();
+		assert_eq!(
+			handle.join().unwrap(),
+			[completion(
+				"success",
+				json!({
+					"title": "0 errors, 0 warnings, 0 notices",
+					"summary": "# Summary",
+					"annotations": [],
+				})
+			)]
+		);
+	}
+
+	#[test]
+	fn batches() {
+		let (client, handle) = serve(vec![(200, "", r#"{"id":4}"#), (200, "", r#"{"id":4}"#)]);
+		let annotations: BTreeSet<_> = (1..=MAX_ANNOTATIONS_PER_REQUEST + 1)
+			.map(|line| Annotation {
+				kind: AnnotationKind::Warning,
+				file: Cow::Borrowed("src/lib.rs"),
+				line,
+				end_line: Some(line + 1),
+				col: Some(1),
+				end_column: Some(2),
+				code: None,
+				suggestions: Vec::new(),
+				title: None,
+				message: Cow::Borrowed("unused variable: `x`"),
+			})
+			.collect();
+		CheckRun { client, id: 4 }
+			.complete(&annotations, "# Summary", AnnotationKind::Error)
+			.unwrap();
+		let annotation = |line: usize| {
+			json!({
+				"path": "src/lib.rs",
+				"start_line": line,
+				"end_line": line + 1,
+				"annotation_level": "warning",
+				"message": "unused variable: `x`",
+			})
+		};
+		let title = "0 errors, 51 warnings, 0 notices";
+		assert_eq!(
+			handle.join().unwrap(),
+			[
+				Request {
+					method: "PATCH".to_owned(),
+					path: "/repos/Dragonink/cargo-ghannotate/check-runs/4".to_owned(),
+					body: Some(json!({
+						"status": "in_progress",
+						"output": {
+							"title": title,
+							"summary": "# Summary",
+							"annotations": (1..=MAX_ANNOTATIONS_PER_REQUEST).map(annotation).collect::<Vec<_>>(),
+						},
+					})),
+				},
+				completion(
+					"neutral",
+					json!({
+						"title": title,
+						"summary": "# Summary",
+						"annotations": [annotation(MAX_ANNOTATIONS_PER_REQUEST + 1)],
+					})
+				),
+			]
+		);
+	}
+
+	#[test]
+	fn encoding() {
+		assert_eq!(
+			encode_query("cargo clippy (1.80)"),
+			"cargo%20clippy%20%281.80%29"
+		);
+		assert_eq!(
+			truncate(&"é".repeat(MAX_TEXT_LENGTH)).len(),
+			MAX_TEXT_LENGTH - 1
+		);
+	}
+}
@@ -0,0 +1,77 @@
+//! Provides the structs to post the summary as a comment of a pull request
+
+use super::{pull_request_number, GithubClient};
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// Maximum length of the body of a comment
+const MAX_BODY_LENGTH: usize = 65_536;
+
+/// Comment of a pull request, updated in place by subsequent runs
+#[derive(Debug, Clone)]
+pub struct StickyComment {
+	/// Hidden marker identifying the comment
+	marker: String,
+	/// Body of the comment
+	body: String,
+}
+impl StickyComment {
+	/// Creates a comment identified by `name`, containing the `summary`
+	pub fn new(name: &str, summary: &str) -> Self {
+		let marker = format!("<!-- cargo-ghannotate: {name} -->");
+		let mut body = format!("{marker}\n## `{name}`\n\n{summary}");
+		if body.len() > MAX_BODY_LENGTH {
+			let mut end = MAX_BODY_LENGTH;
+			while !body.is_char_boundary(end) {
+				end -= 1;
+			}
+			body.truncate(end);
+		}
+		Self { marker, body }
+	}
+
+	/// Posts the comment to the pull request which triggered the workflow
+	///
+	/// If a comment with the same marker already exists, it is updated instead.
+	///
+	/// # Errors
+	/// This function returns an error if the comment could not be posted.
+	pub fn post(&self, client: &GithubClient) -> io::Result<()> {
+		let number = pull_request_number()
+			.ok_or_else(|| io::Error::other("the workflow was not triggered by a pull request"))?;
+		let existing = client
+			.get_pages::<Vec<IssueComment>>(&format!("/issues/{number}/comments?per_page=100"))?
+			.into_iter()
+			.flatten()
+			.find(|comment| {
+				comment
+					.body
+					.as_deref()
+					.is_some_and(|body| body.starts_with(&self.marker))
+			});
+		let body = CommentRequest { body: &self.body };
+		match existing {
+			Some(comment) => {
+				client.patch::<IssueComment>(&format!("/issues/comments/{}", comment.id), &body)
+			}
+			None => client.post::<IssueComment>(&format!("/issues/{number}/comments"), &body),
+		}
+		.map(|_| ())
+	}
+}
+
+/// Body of the requests creating or updating a comment
+#[derive(Debug, Serialize)]
+struct CommentRequest<'a> {
+	/// Body of the comment
+	body: &'a str,
+}
+
+/// Comment of an issue or a pull request
+#[derive(Debug, Deserialize)]
+struct IssueComment {
+	/// Identifier of the comment
+	id: u64,
+	/// Body of the comment
+	body: Option<String>,
+}
@@ -0,0 +1,61 @@
+//! Provides the structs to log as debug messages of GitHub Actions
+
+use super::escape_data;
+use std::{
+	env,
+	fmt::{self, Write as _},
+	io::{self, Write as _},
+};
+use tracing::{
+	field::{Field, Visit},
+	Event,
+};
+
+/// Environment variable set to `1` when the debug logging of the runner is enabled
+const RUNNER_DEBUG_VAR: &str = "RUNNER_DEBUG";
+
+/// Returns `true` if the debug logging of the runner is enabled
+#[inline]
+pub fn is_runner_debug() -> bool {
+	env::var_os(RUNNER_DEBUG_VAR).is_some_and(|debug| debug == "1")
+}
+
+/// Writes `event` as a `::debug::` workflow command on stdout
+///
+/// The debug messages are only shown in the logs of the job when the debug logging of the runner is enabled.
+pub fn write_debug(event: &Event<'_>) {
+	let mut visitor = DebugVisitor::default();
+	event.record(&mut visitor);
+	let _ = writeln!(
+		io::stdout(),
+		"::debug::{}{}",
+		escape_data(&visitor.message),
+		escape_data(&visitor.fields)
+	);
+}
+
+/// Formats the message and the fields of an event
+#[derive(Debug, Default)]
+struct DebugVisitor {
+	/// Message of the event
+	message: String,
+	/// Other fields of the event, formatted as ` name=value`
+	fields: String,
+}
+impl Visit for DebugVisitor {
+	fn record_str(&mut self, field: &Field, value: &str) {
+		if field.name() == "message" {
+			self.message.push_str(value);
+		} else {
+			let _ = write!(self.fields, " {}={value}", field.name());
+		}
+	}
+
+	fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+		if field.name() == "message" {
+			let _ = write!(self.message, "{value:?}");
+		} else {
+			let _ = write!(self.fields, " {}={value:?}", field.name());
+		}
+	}
+}
@@ -0,0 +1,290 @@
+//! Provides the structs to post annotations as review comments of a pull request
+
+use super::{head_sha, pull_request_number, Annotation, GithubClient};
+use crate::{git::ChangedLines, path::repository_root};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fmt::Write, fs, io, ops::RangeInclusive};
+
+/// Review of a pull request, commenting the annotated lines of its diff
+#[derive(Debug, Serialize)]
+pub struct PullRequestReview {
+	/// SHA of the reviewed commit
+	commit_id: String,
+	/// Action performed by the review
+	event: &'static str,
+	/// Body of the review
+	body: String,
+	/// Comments of the review
+	comments: Vec<ReviewComment>,
+}
+impl PullRequestReview {
+	/// Creates a review commenting the `annotations` located in the diff of the pull request
+	///
+	/// Machine-applicable suggestions are converted to suggestion blocks.
+	/// Returns the number of the pull request and the review, or [`None`] if no annotation is located in the diff.
+	///
+	/// # Errors
+	/// This function returns an error if the pull request or its diff could not be fetched.
+	pub fn new(
+		client: &GithubClient,
+		annotations: &BTreeSet<Annotation<'_>>,
+	) -> io::Result<Option<(u64, Self)>> {
+		let number = pull_request_number()
+			.ok_or_else(|| io::Error::other("the workflow was not triggered by a pull request"))?;
+		let mut diff = String::new();
+		for file in client
+			.get_pages::<Vec<PullRequestFile>>(&format!("/pulls/{number}/files?per_page=100"))?
+			.into_iter()
+			.flatten()
+		{
+			if let Some(patch) = file.patch {
+				let _ = writeln!(diff, "+++ b/{}\n{patch}", file.filename);
+			}
+		}
+		let diff = ChangedLines::parse(&diff);
+
+		let comments: Vec<_> = annotations
+			.iter()
+			.filter_map(|annotation| ReviewComment::new(annotation, &diff))
+			.collect();
+		if comments.is_empty() {
+			return Ok(None);
+		}
+		Ok(Some((
+			number,
+			Self {
+				commit_id: head_sha()?,
+				event: "COMMENT",
+				body: format!(
+					"{} issues were found in the changes of this pull request.",
+					comments.len()
+				),
+				comments,
+			},
+		)))
+	}
+
+	/// Posts the review to the pull request `number`
+	///
+	/// # Errors
+	/// This function returns an error if the review could not be posted.
+	pub fn post(&self, client: &GithubClient, number: u64) -> io::Result<()> {
+		client
+			.post::<serde_json::Value>(&format!("/pulls/{number}/reviews"), self)
+			.map(|_| ())
+	}
+}
+
+/// Comment of a [`PullRequestReview`]
+#[derive(Debug, Serialize)]
+struct ReviewComment {
+	/// Path to the commented file
+	path: String,
+	/// First commented line (only if several lines are commented)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	start_line: Option<usize>,
+	/// Last commented line
+	line: usize,
+	/// Side of the diff of the commented lines
+	side: &'static str,
+	/// Body of the comment
+	body: String,
+}
+impl ReviewComment {
+	/// Creates a comment from `annotation`, if it is located in the `diff`
+	fn new(annotation: &Annotation<'_>, diff: &ChangedLines) -> Option<Self> {
+		let annotated_lines = annotation.line..=annotation.end_line.unwrap_or(annotation.line);
+		let suggestion =
+			suggested_lines(annotation).filter(|(lines, _)| diff.covers(&annotation.file, lines));
+		let lines = match &suggestion {
+			Some((lines, _)) => lines.clone(),
+			None if diff.covers(&annotation.file, &annotated_lines) => annotated_lines,
+			None => return None,
+		};
+
+		let mut body = format!(
+			"{} **{}**",
+			annotation.kind.emoji(),
+			annotation.title.as_deref().unwrap_or_else(|| annotation
+				.message
+				.lines()
+				.next()
+				.unwrap_or_default())
+		);
+		if annotation.title.is_some() {
+			let _ = write!(
+				body,
+				"\n\n<details><summary>Details</summary>\n\n```text\n{}\n```\n\n</details>",
+				annotation.message.trim_end()
+			);
+		}
+		if let Some((_, replacement)) = suggestion {
+			let _ = write!(body, "\n\n```suggestion\n{replacement}```");
+		}
+		Some(Self {
+			path: annotation.file.clone().into_owned(),
+			start_line: (lines.start() != lines.end()).then_some(*lines.start()),
+			line: *lines.end(),
+			side: "RIGHT",
+			body,
+		})
+	}
+}
+
+/// Applies the suggestions of `annotation` to the annotated file
+///
+/// Returns the replaced lines and their replacement.
+fn suggested_lines(annotation: &Annotation<'_>) -> Option<(RangeInclusive<usize>, String)> {
+	let start = annotation
+		.suggestions
+		.iter()
+		.map(|suggestion| suggestion.line)
+		.min()?;
+	let end = annotation
+		.suggestions
+		.iter()
+		.map(|suggestion| {
+			if suggestion.end_column == 1 && suggestion.end_line > suggestion.line {
+				suggestion.end_line - 1
+			} else {
+				suggestion.end_line
+			}
+		})
+		.max()?;
+	let file = fs::read_to_string(repository_root().join(annotation.file.as_ref())).ok()?;
+	let lines: Vec<_> = file
+		.split_inclusive('\n')
+		.skip(start - 1)
+		.take(end - start + 1)
+		.collect();
+	if lines.len() != end - start + 1 {
+		return None;
+	}
+	let offset = |line: usize, col: usize| {
+		let preceding: usize = lines
+			.get(..line - start)?
+			.iter()
+			.map(|line| line.len())
+			.sum();
+		let line = lines.get(line - start).copied().unwrap_or_default();
+		let col = line
+			.char_indices()
+			.nth(col - 1)
+			.map_or(line.len(), |(offset, _)| offset);
+		Some(preceding + col)
+	};
+
+	let mut replacements = annotation
+		.suggestions
+		.iter()
+		.map(|suggestion| {
+			Some((
+				offset(suggestion.line, suggestion.col)?,
+				offset(suggestion.end_line, suggestion.end_column)?,
+				suggestion.replacement.as_ref(),
+			))
+		})
+		.collect::<Option<Vec<_>>>()?;
+	replacements.sort_unstable_by_key(|(start, _, _)| *start);
+	if replacements.windows(2).any(|pair| pair[0].1 > pair[1].0) {
+		return None;
+	}
+	let mut text = lines.concat();
+	for (start, end, replacement) in replacements.into_iter().rev() {
+		text.replace_range(start..end, replacement);
+	}
+	if !text.ends_with('\n') {
+		text.push('\n');
+	}
+	Some((start..=end, text))
+}
+
+/// File changed by a pull request
+#[derive(Debug, Deserialize)]
+struct PullRequestFile {
+	/// Path to the file
+	filename: String,
+	/// Diff of the file (absent for binary or too large files)
+	patch: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::report::tests::{annotate, annotations};
+	use std::{borrow::Cow, env, process};
+
+	/// Warning of Clippy's `let_and_return`, with a machine-applicable suggestion over two lines
+	const LET_AND_RETURN: &str = r#"{"rendered":"warning: returning the result of a `let` binding from a block\n --> lintfix/src/lib.rs:3:5\n  |\n2 |     let x = 42;\n  |     ----------- unnecessary `let` binding\n3 |     x\n  |     ^\n  |\n  = help: for further information visit https://rust-lang.github.io/rust-clippy/rust-1.95.0/index.html#let_and_return\n  = note: `#[warn(clippy::let_and_return)]` on by default\nhelp: return the expression directly\n  |\n2 ~     \n3 ~     42\n  |\n\n","$message_type":"diagnostic","children":[{"children":[],"code":null,"level":"help","message":"for further information visit https://rust-lang.github.io/rust-clippy/rust-1.95.0/index.html#let_and_return","rendered":null,"spans":[]},{"children":[],"code":null,"level":"note","message":"`#[warn(clippy::let_and_return)]` on by default","rendered":null,"spans":[]},{"children":[],"code":null,"level":"help","message":"return the expression directly","rendered":null,"spans":[{"byte_end":40,"byte_start":29,"column_end":16,"column_start":5,"expansion":null,"file_name":"lintfix/src/lib.rs","is_primary":true,"label":null,"line_end":2,"line_start":2,"suggested_replacement":"","suggestion_applicability":"MachineApplicable","text":[{"highlight_end":16,"highlight_start":5,"text":"    let x = 42;"}]},{"byte_end":46,"byte_start":45,"column_end":6,"column_start":5,"expansion":null,"file_name":"lintfix/src/lib.rs","is_primary":true,"label":null,"line_end":3,"line_start":3,"suggested_replacement":"42","suggestion_applicability":"MachineApplicable","text":[{"highlight_end":6,"highlight_start":5,"text":"    x"}]}]}],"level":"warning","message":"returning the result of a `let` binding from a block","spans":[{"byte_end":40,"byte_start":29,"column_end":16,"column_start":5,"expansion":null,"file_name":"lintfix/src/lib.rs","is_primary":false,"label":"unnecessary `let` binding","line_end":2,"line_start":2,"suggested_replacement":null,"suggestion_applicability":null,"text":[{"highlight_end":16,"highlight_start":5,"text":"    let x = 42;"}]},{"byte_end":46,"byte_start":45,"column_end":6,"column_start":5,"expansion":null,"file_name":"lintfix/src/lib.rs","is_primary":true,"label":null,"line_end":3,"line_start":3,"suggested_replacement":null,"suggestion_applicability":null,"text":[{"highlight_end":6,"highlight_start":5,"text":"    x"}]}],"code":{"code":"clippy::let_and_return","explanation":null}}"#;
+
+	#[test]
+	fn comments() {
+		let diff = ChangedLines::parse(
+			"+++ b/lintfix/src/lib.rs\n@@ -5,2 +5,2 @@ pub fn len(v: &Vec<u32>) -> usize {\n",
+		);
+		let comments: Vec<_> = annotations()
+			.iter()
+			.filter_map(|annotation| ReviewComment::new(annotation, &diff))
+			.collect();
+		assert_eq!(
+			serde_json::to_value(comments).unwrap(),
+			serde_json::json!([
+				{
+					"path": "lintfix/src/lib.rs",
+					"line": 5,
+					"side": "RIGHT",
+					"body": ":information_source: **mismatched types**\n\n<details><summary>Details</summary>\n\n```text\nexpected `u32` because of return type\n```\n\n</details>",
+				},
+				{
+					"path": "lintfix/src/lib.rs",
+					"line": 6,
+					"side": "RIGHT",
+					"body": ":x: **mismatched types [E0308]**\n\n<details><summary>Details</summary>\n\n```text\nmismatched types\n```\n\n</details>",
+				},
+			])
+		);
+	}
+
+	#[test]
+	fn suggestion() {
+		let path = env::temp_dir().join(format!("ghannotate-review-{}.rs", process::id()));
+		fs::write(
+			&path,
+			"pub fn answer() -> u32 {\n    let x = 42;\n    x\n}\n",
+		)
+		.unwrap();
+		let file = path.to_string_lossy().into_owned();
+		let mut annotation = annotate(LET_AND_RETURN).remove(0);
+		annotation.file = Cow::Owned(file.clone());
+		let comment = |hunk: &str| {
+			let diff = ChangedLines::parse(&format!("+++ b/{file}\n@@ {hunk} @@\n"));
+			serde_json::to_value(ReviewComment::new(&annotation, &diff)).unwrap()
+		};
+		let details = "<details><summary>Details</summary>\n\n```text\nreturning the result of a `let` binding from a block\n```\n\n</details>";
+		let suggested = comment("-0,0 +1,4");
+		let unsuggested = comment("-3 +3");
+		let uncommented = comment("-1 +1");
+		fs::remove_file(path).unwrap();
+
+		assert_eq!(
+			suggested,
+			serde_json::json!({
+				"path": file,
+				"start_line": 2,
+				"line": 3,
+				"side": "RIGHT",
+				"body": format!(":warning: **returning the result of a `let` binding from a block [clippy::let_and_return]**\n\n{details}\n\n```suggestion\n    \n    42\n```"),
+			})
+		);
+		assert_eq!(
+			unsuggested,
+			serde_json::json!({
+				"path": file,
+				"line": 3,
+				"side": "RIGHT",
+				"body": format!(":warning: **returning the result of a `let` binding from a block [clippy::let_and_return]**\n\n{details}"),
+			})
+		);
+		assert_eq!(uncommented, serde_json::Value::Null);
+	}
+}
@@ -0,0 +1,71 @@
+//! Provides the structs to report the annotations in a commit status
+
+use super::{head_sha, Annotation, AnnotationKind, GithubClient};
+use serde::Serialize;
+use std::{collections::BTreeSet, env, io};
+
+/// Commit status summarizing the annotations of a command
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitStatus {
+	/// State of the status
+	state: &'static str,
+	/// Context distinguishing the status from the others (e.g. `ghannotate/clippy`)
+	context: String,
+	/// Description of the status
+	description: String,
+	/// URL of the workflow run
+	#[serde(skip_serializing_if = "Option::is_none")]
+	target_url: Option<String>,
+}
+impl CommitStatus {
+	/// Creates the status of the subcommand `name` from its `annotations`
+	///
+	/// The status fails if the most severe kind of annotation emitted (`max_annotation`) is at least as severe as `threshold`.
+	pub fn new(
+		name: &str,
+		annotations: &BTreeSet<Annotation<'_>>,
+		max_annotation: AnnotationKind,
+		threshold: AnnotationKind,
+	) -> Self {
+		let count = |kind| {
+			annotations
+				.iter()
+				.filter(|annotation| annotation.kind == kind)
+				.count()
+		};
+		Self {
+			state: if max_annotation >= threshold {
+				"failure"
+			} else {
+				"success"
+			},
+			context: format!("ghannotate/{name}"),
+			description: format!(
+				"{} errors, {} warnings, {} notices",
+				count(AnnotationKind::Error),
+				count(AnnotationKind::Warning),
+				count(AnnotationKind::Notice),
+			),
+			target_url: match (
+				env::var("GITHUB_SERVER_URL"),
+				env::var("GITHUB_REPOSITORY"),
+				env::var("GITHUB_RUN_ID"),
+			) {
+				(Ok(server), Ok(repository), Ok(run)) => {
+					Some(format!("{server}/{repository}/actions/runs/{run}"))
+				}
+				_ => None,
+			},
+		}
+	}
+
+	/// Sets the status on the commit being checked
+	///
+	/// # Errors
+	/// This function returns an error if the status could not be set.
+	pub fn post(&self, client: &GithubClient) -> io::Result<()> {
+		client
+			.post::<serde_json::Value>(&format!("/statuses/{}", head_sha()?), self)
+			.map(|_| ())
+	}
+}
@@ -0,0 +1,326 @@
+//! Provides the conversion of paths to the form expected in annotations
+//!
+//! Annotated paths are relative to the root of the repository and use forward slashes on all platforms.
+
+use std::{
+	env,
+	path::{Component, Path, PathBuf, Prefix},
+};
+
+/// Returns the root of the repository
+///
+/// The root of the repository is `GITHUB_WORKSPACE` if it is set, or the current directory otherwise.
+pub fn repository_root() -> PathBuf {
+	env::var_os("GITHUB_WORKSPACE")
+		.filter(|root| !root.is_empty())
+		.map(PathBuf::from)
+		.or_else(|| env::current_dir().ok())
+		.unwrap_or_default()
+}
+
+/// Returns `path` relative to the root of the repository
+///
+/// Relative paths are resolved against `base` (the current directory if [`None`]),
+/// and paths outside of the repository are kept absolute.
+pub fn repository_path(path: &Path, base: Option<&Path>) -> String {
+	let Ok(current_dir) = env::current_dir() else {
+		return render(&components(path));
+	};
+	let base = base.map_or_else(|| current_dir.clone(), |base| current_dir.join(base));
+	let path = components(&base.join(path));
+	let root = components(&current_dir.join(repository_root()));
+	match strip_prefix(&path, &root) {
+		Some(relative) => render(relative),
+		None => render(&path),
+	}
+}
+
+/// Rewrites the prefix `from` of `path` into `to`
+///
+/// Returns [`None`] if `path` does not start with `from`.
+pub fn rewrite_prefix(path: &str, from: &Path, to: &Path) -> Option<String> {
+	let path = components(Path::new(path));
+	let mut rewritten = components(to);
+	rewritten.extend_from_slice(strip_prefix(&path, &components(from))?);
+	Some(render(&rewritten))
+}
+
+/// Returns whether the annotated `path` matches the glob `pattern`, like in `.gitignore` files
+///
+/// `*` matches any characters but `/`, `?` matches any character but `/`, and `**` matches any directories.
+/// Patterns without a `/` (but a trailing one) match in any directory,
+/// and the paths inside a matching directory match too.
+pub fn matches_glob(pattern: &str, path: &str) -> bool {
+	let pattern = pattern.strip_suffix('/').unwrap_or(pattern);
+	let anchored = pattern.contains('/');
+	let pattern: Vec<_> = pattern.trim_start_matches('/').split('/').collect();
+	let path: Vec<_> = path
+		.split('/')
+		.filter(|component| !(component.is_empty() || *component == "."))
+		.collect();
+	if anchored {
+		matches_components(&pattern, &path)
+	} else {
+		(0..path.len()).any(|start| matches_components(&pattern, &path[start..]))
+	}
+}
+
+/// Returns whether the first components of `path` match those of `pattern`
+fn matches_components(pattern: &[&str], path: &[&str]) -> bool {
+	match pattern.split_first() {
+		None => true,
+		Some((&"**", pattern)) => {
+			(0..=path.len()).any(|start| matches_components(pattern, &path[start..]))
+		}
+		Some((component, pattern)) => path.split_first().is_some_and(|(name, path)| {
+			matches_name(component, name) && matches_components(pattern, path)
+		}),
+	}
+}
+
+/// Returns whether the file `name` matches the glob `pattern`
+fn matches_name(pattern: &str, name: &str) -> bool {
+	let mut pattern_chars = pattern.chars();
+	match pattern_chars.next() {
+		None => name.is_empty(),
+		Some('*') => name
+			.char_indices()
+			.map(|(index, _)| index)
+			.chain([name.len()])
+			.any(|index| matches_name(pattern_chars.as_str(), &name[index..])),
+		Some(c) => {
+			let mut name_chars = name.chars();
+			name_chars.next().is_some_and(|n| c == '?' || c == n)
+				&& matches_name(pattern_chars.as_str(), name_chars.as_str())
+		}
+	}
+}
+
+/// Splits `path` into normalized components
+///
+/// The `.` and `..` components are resolved without following symbolic links,
+/// and the Windows prefixes are normalized (e.g. `\\?\c:` into `C:`).
+/// The root directory is represented by `/`.
+fn components(path: &Path) -> Vec<String> {
+	let mut components: Vec<String> = Vec::new();
+	for component in path.components() {
+		match component {
+			Component::Prefix(prefix) => components.push(match prefix.kind() {
+				Prefix::Disk(disk) | Prefix::VerbatimDisk(disk) => {
+					format!("{}:", char::from(disk.to_ascii_uppercase()))
+				}
+				Prefix::UNC(server, share) | Prefix::VerbatimUNC(server, share) => {
+					format!("//{}/{}", server.to_string_lossy(), share.to_string_lossy())
+				}
+				_ => prefix.as_os_str().to_string_lossy().replace('\\', "/"),
+			}),
+			Component::RootDir => components.push(String::from("/")),
+			Component::CurDir => {}
+			Component::ParentDir => match components.last().map(String::as_str) {
+				Some(last)
+					if last != ".."
+						&& last != "/" && !last.ends_with(':')
+						&& !last.starts_with("//") =>
+				{
+					components.pop();
+				}
+				_ => components.push(String::from("..")),
+			},
+			Component::Normal(name) => components.push(name.to_string_lossy().into_owned()),
+		}
+	}
+	components
+}
+
+/// Returns the components of `path` following those of `prefix`
+///
+/// Components are compared case-insensitively on Windows, like its file systems do.
+fn strip_prefix<'p>(path: &'p [String], prefix: &[String]) -> Option<&'p [String]> {
+	if path.len() < prefix.len()
+		|| !path.iter().zip(prefix).all(|(component, prefix)| {
+			if cfg!(windows) {
+				component.eq_ignore_ascii_case(prefix)
+			} else {
+				component == prefix
+			}
+		}) {
+		return None;
+	}
+	Some(&path[prefix.len()..])
+}
+
+/// Joins `components` with forward slashes
+fn render(components: &[String]) -> String {
+	let mut path = String::new();
+	for component in components {
+		if !(path.is_empty() || path.ends_with('/') || component == "/") {
+			path.push('/');
+		}
+		if !(component == "/" && path.ends_with('/')) {
+			path.push_str(component);
+		}
+	}
+	path
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rewrite() {
+		assert_eq!(
+			rewrite_prefix(
+				"/build/src/./src/../src/lib.rs",
+				Path::new("/build/src"),
+				Path::new(".")
+			),
+			Some(String::from("src/lib.rs"))
+		);
+		assert_eq!(
+			rewrite_prefix(
+				"/build/src/lib.rs",
+				Path::new("/build/src"),
+				Path::new("/repo")
+			),
+			Some(String::from("/repo/lib.rs"))
+		);
+		assert_eq!(
+			rewrite_prefix(
+				"/build/srcs/lib.rs",
+				Path::new("/build/src"),
+				Path::new(".")
+			),
+			None
+		);
+	}
+
+	#[test]
+	fn glob() {
+		assert!(matches_glob("src/generated", "src/generated/mod.rs"));
+		assert!(matches_glob("/src/*.rs", "src/lib.rs"));
+		assert!(!matches_glob("/src/*.rs", "src/bin/main.rs"));
+		assert!(matches_glob("vendor/", "crates/vendor/lib.rs"));
+		assert!(matches_glob("tests/fixtures/**", "tests/fixtures/a/b.rs"));
+		assert!(matches_glob("**/build?.rs", "crates/a/build1.rs"));
+		assert!(!matches_glob("src/gen", "src/generated/mod.rs"));
+	}
+
+	/// Returns `components` as owned strings
+	fn owned(components: &[&str]) -> Vec<String> {
+		components
+			.iter()
+			.map(|&component| component.to_owned())
+			.collect()
+	}
+
+	#[test]
+	fn windows_components() {
+		// The components of Windows paths are rendered with forward slashes on all platforms
+		assert_eq!(
+			render(&owned(&["C:", "/", "repo", "src", "lib.rs"])),
+			"C:/repo/src/lib.rs"
+		);
+		assert_eq!(
+			render(&owned(&["//server/share", "/", "repo", "lib.rs"])),
+			"//server/share/repo/lib.rs"
+		);
+		assert_eq!(
+			strip_prefix(
+				&owned(&["C:", "/", "repo", "src", "lib.rs"]),
+				&owned(&["C:", "/", "repo"])
+			),
+			Some(owned(&["src", "lib.rs"]).as_slice())
+		);
+		assert_eq!(
+			strip_prefix(
+				&owned(&["D:", "/", "repo", "src", "lib.rs"]),
+				&owned(&["C:", "/", "repo"])
+			),
+			None
+		);
+	}
+
+	#[test]
+	#[cfg(windows)]
+	fn drive_letters() {
+		assert_eq!(
+			components(Path::new(r"c:\repo\src\..\src\lib.rs")),
+			owned(&["C:", "/", "repo", "src", "lib.rs"])
+		);
+		// The parent of the root directory is not resolved past the drive
+		assert_eq!(
+			render(&components(Path::new(r"C:\..\lib.rs"))),
+			"C:/../lib.rs"
+		);
+		// Drive letters and file names are compared case-insensitively
+		assert_eq!(
+			rewrite_prefix(r"c:\Repo\src\lib.rs", Path::new(r"C:\repo"), Path::new(".")),
+			Some(String::from("src/lib.rs"))
+		);
+	}
+
+	#[test]
+	#[cfg(windows)]
+	fn verbatim_prefixes() {
+		assert_eq!(
+			components(Path::new(r"\\?\c:\repo\src\lib.rs")),
+			components(Path::new(r"C:\repo\src\lib.rs"))
+		);
+		assert_eq!(
+			rewrite_prefix(
+				r"\\?\C:\repo\src\lib.rs",
+				Path::new(r"C:\repo"),
+				Path::new(".")
+			),
+			Some(String::from("src/lib.rs"))
+		);
+		assert_eq!(
+			render(&components(Path::new(r"\\?\UNC\server\share\lib.rs"))),
+			"//server/share/lib.rs"
+		);
+	}
+
+	#[test]
+	#[cfg(windows)]
+	fn unc_paths() {
+		assert_eq!(
+			components(Path::new(r"\\server\share\repo\lib.rs")),
+			owned(&["//server/share", "/", "repo", "lib.rs"])
+		);
+		// The parent of the share is not resolved past it
+		assert_eq!(
+			render(&components(Path::new(r"\\server\share\..\lib.rs"))),
+			"//server/share/../lib.rs"
+		);
+		assert_eq!(
+			rewrite_prefix(
+				r"\\server\share\repo\src\lib.rs",
+				Path::new(r"\\server\share\repo"),
+				Path::new(".")
+			),
+			Some(String::from("src/lib.rs"))
+		);
+	}
+
+	#[test]
+	#[cfg(windows)]
+	fn backslash_separators() {
+		assert_eq!(
+			render(&components(Path::new(r"src\bin\main.rs"))),
+			"src/bin/main.rs"
+		);
+		assert_eq!(
+			repository_path(Path::new(r"src\lib.rs"), None),
+			repository_path(Path::new("src/lib.rs"), None)
+		);
+		assert_eq!(
+			rewrite_prefix(
+				r"C:\build/src\lib.rs",
+				Path::new("C:/build"),
+				Path::new(".")
+			),
+			Some(String::from("src/lib.rs"))
+		);
+	}
+}
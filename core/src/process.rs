@@ -0,0 +1,365 @@
+//! Provides structures to read the output of commands while they are running
+
+use serde::Deserialize;
+use std::{
+	fmt::{self, Display, Formatter},
+	io::{self, BufRead, BufReader, Cursor, Read, Write},
+	process::{Child, Command, ExitStatus, Stdio},
+	str::FromStr,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc, Mutex, MutexGuard, PoisonError,
+	},
+	thread::{self, JoinHandle},
+	time::Duration,
+};
+
+/// Returns the configuration of a standard stream of a command which is passed through to this program
+///
+/// The stream is discarded if `quiet` is `true`.
+#[inline]
+pub fn passthrough(quiet: bool) -> Stdio {
+	if quiet {
+		Stdio::null()
+	} else {
+		Stdio::inherit()
+	}
+}
+
+/// Interval between the checks of whether a command has exited
+const WAIT_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Running command, shared with the thread killing it once its time limit is exceeded
+pub type SharedChild = Arc<Mutex<Child>>;
+
+/// Output of a command, read while the command is running
+pub struct CommandOutput {
+	/// The running command (absent if the output does not come from a command)
+	pub child: Option<SharedChild>,
+	/// Standard output of the command (empty if it is not piped)
+	pub stdout: Box<dyn BufRead>,
+	/// Standard error of the command (empty if it is not piped)
+	pub stderr: CollectedStderr,
+	/// Whether the command has been killed because it exceeded its time limit
+	timed_out: Arc<AtomicBool>,
+}
+impl fmt::Debug for CommandOutput {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("CommandOutput")
+			.field("child", &self.child)
+			.field("stderr", &self.stderr)
+			.field("timed_out", &self.timed_out)
+			.finish_non_exhaustive()
+	}
+}
+impl CommandOutput {
+	/// Spawns `command` with its standard input closed
+	///
+	/// The standard output is piped if `pipe_stdout` is `true`, and the standard error if `pipe_stderr` is `true`;
+	/// they are passed through otherwise, unless `quiet` is `true` (see [`passthrough`]).
+	/// If `echo_stderr` is `true`, the piped standard error is also written to the standard error of this program while it is read.
+	///
+	/// On Unix, the command is spawned in its own process group so that the processes it spawns are killed with it.
+	///
+	/// # Errors
+	/// This function returns an error if the command could not be spawned.
+	pub fn spawn(
+		command: &mut Command,
+		pipe_stdout: bool,
+		pipe_stderr: bool,
+		echo_stderr: bool,
+		quiet: bool,
+	) -> io::Result<Self> {
+		/// Returns the configuration of a standard stream
+		fn stdio(piped: bool, quiet: bool) -> Stdio {
+			if piped {
+				Stdio::piped()
+			} else {
+				passthrough(quiet)
+			}
+		}
+
+		#[cfg(unix)]
+		std::os::unix::process::CommandExt::process_group(command, 0);
+		let mut child = command
+			.stdin(Stdio::null())
+			.stdout(stdio(pipe_stdout, quiet))
+			.stderr(stdio(pipe_stderr, quiet))
+			.spawn()?;
+		let stdout: Box<dyn BufRead> = match child.stdout.take() {
+			Some(stdout) => Box::new(BufReader::new(stdout)),
+			None => Box::new(io::empty()),
+		};
+		// The standard error is read in the background so that the command is not blocked when its pipe is full
+		let stderr = CollectedStderr::new(child.stderr.take().map(|mut stderr| {
+			thread::spawn(move || {
+				let mut buf = Vec::new();
+				if !echo_stderr {
+					return stderr.read_to_end(&mut buf).map(|_| buf);
+				}
+				let mut chunk = [0; 4096];
+				loop {
+					match stderr.read(&mut chunk) {
+						Ok(0) => return Ok(buf),
+						Ok(len) => {
+							io::stderr().write_all(&chunk[..len])?;
+							buf.extend_from_slice(&chunk[..len]);
+						}
+						Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+						Err(err) => return Err(err),
+					}
+				}
+			})
+		}));
+		Ok(Self {
+			child: Some(Arc::new(Mutex::new(child))),
+			stdout,
+			stderr,
+			timed_out: Arc::default(),
+		})
+	}
+
+	/// Creates an output which does not come from a command
+	///
+	/// Some tools report on stderr, so `input` is provided as both outputs.
+	pub fn from_input(input: Vec<u8>) -> Self {
+		let stdout = Box::new(Cursor::new(input.clone()));
+		Self {
+			child: None,
+			stdout,
+			stderr: CollectedStderr::new(Some(thread::spawn(move || Ok(input)))),
+			timed_out: Arc::default(),
+		}
+	}
+
+	/// Creates an empty output
+	#[inline]
+	pub fn empty() -> Self {
+		Self {
+			child: None,
+			stdout: Box::new(io::empty()),
+			stderr: CollectedStderr::default(),
+			timed_out: Arc::default(),
+		}
+	}
+
+	/// Kills the command if it is still running after `timeout`
+	pub fn kill_after(&self, timeout: Timeout) {
+		let Some(child) = self.child.clone() else {
+			return;
+		};
+		let timed_out = Arc::clone(&self.timed_out);
+		thread::spawn(move || {
+			thread::sleep(timeout.0);
+			let mut child = lock(&child);
+			// A command which has been waited for is not killed
+			if matches!(child.try_wait(), Ok(None)) && kill_group(&mut child).is_ok() {
+				timed_out.store(true, Ordering::Relaxed);
+			}
+		});
+	}
+
+	/// Returns `true` if the command has been killed because it exceeded its time limit
+	#[inline]
+	pub fn timed_out(&self) -> bool {
+		self.timed_out.load(Ordering::Relaxed)
+	}
+
+	/// Waits for the command to exit and returns its exit status
+	///
+	/// # Errors
+	/// This function returns an error if the command could not be waited for.
+	#[inline]
+	pub fn wait(&self) -> io::Result<ExitStatus> {
+		wait(self.child.as_ref())
+	}
+}
+
+/// Locks `child`, even if a thread panicked while holding it
+fn lock(child: &SharedChild) -> MutexGuard<'_, Child> {
+	child.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Waits for `child` to exit and returns its exit status
+///
+/// If there is no command, the exit status is successful.
+/// The command is not locked while it is running, so that it may be killed meanwhile.
+///
+/// # Errors
+/// This function returns an error if the command could not be waited for.
+pub fn wait(child: Option<&SharedChild>) -> io::Result<ExitStatus> {
+	let Some(child) = child else {
+		return Ok(ExitStatus::default());
+	};
+	loop {
+		if let Some(status) = lock(child).try_wait()? {
+			return Ok(status);
+		}
+		thread::sleep(WAIT_INTERVAL);
+	}
+}
+
+/// Kills `child` if it is still running
+///
+/// # Errors
+/// This function returns an error if the command could not be killed.
+pub fn kill(child: Option<&SharedChild>) -> io::Result<()> {
+	child.map_or(Ok(()), |child| kill_group(&mut lock(child)))
+}
+
+/// Kills `child` along with the processes it spawned
+///
+/// The processes spawned by the command would otherwise keep its output open after it is killed.
+fn kill_group(child: &mut Child) -> io::Result<()> {
+	#[cfg(unix)]
+	if let Ok(pid) = i32::try_from(child.id()) {
+		use nix::{
+			sys::signal::{killpg, Signal},
+			unistd::Pid,
+		};
+
+		// The command leads its own process group (see `CommandOutput::spawn`)
+		if killpg(Pid::from_raw(pid), Signal::SIGKILL).is_ok() {
+			return Ok(());
+		}
+	}
+	child.kill()
+}
+
+/// Time limit of a command, as passed to the `--timeout` option (e.g. `90s`, `30m` or `1h`)
+///
+/// A number without unit is a number of seconds.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Timeout(Duration);
+impl FromStr for Timeout {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (value, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+		let value: u64 = value.parse().map_err(|_| {
+			format!("invalid timeout `{s}`, expected a number of seconds, minutes or hours (e.g. `30m`)")
+		})?;
+		let seconds = match unit {
+			"" | "s" => value,
+			"m" => value.saturating_mul(60),
+			"h" => value.saturating_mul(60 * 60),
+			_ => {
+				return Err(format!(
+					"invalid unit of timeout `{unit}`, expected `s`, `m` or `h`"
+				))
+			}
+		};
+		Ok(Self(Duration::from_secs(seconds)))
+	}
+}
+impl TryFrom<String> for Timeout {
+	type Error = String;
+
+	#[inline]
+	fn try_from(s: String) -> Result<Self, Self::Error> {
+		s.parse()
+	}
+}
+impl Display for Timeout {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let seconds = self.0.as_secs();
+		if seconds >= 60 * 60 && seconds.is_multiple_of(60 * 60) {
+			write!(f, "{}h", seconds / (60 * 60))
+		} else if seconds >= 60 && seconds.is_multiple_of(60) {
+			write!(f, "{}m", seconds / 60)
+		} else {
+			write!(f, "{seconds}s")
+		}
+	}
+}
+
+/// Standard error of a command, collected in the background
+#[derive(Debug, Default)]
+pub struct CollectedStderr {
+	/// Thread collecting the standard error (absent once it has been waited for)
+	thread: Option<JoinHandle<io::Result<Vec<u8>>>>,
+	/// Standard error collected by the thread
+	collected: Vec<u8>,
+}
+impl CollectedStderr {
+	/// Creates a standard error collected by `thread`
+	#[inline]
+	const fn new(thread: Option<JoinHandle<io::Result<Vec<u8>>>>) -> Self {
+		Self {
+			thread,
+			collected: Vec::new(),
+		}
+	}
+
+	/// Returns the lines of the standard error
+	///
+	/// The standard error is only waited for once the first line is requested,
+	/// and it is kept so that its lines may be requested again.
+	/// If `echo` is `true`, it is also written to the standard error of this program.
+	pub fn lines(&mut self, echo: bool) -> impl Iterator<Item = io::Result<String>> + '_ {
+		std::iter::once_with(move || {
+			if let Some(thread) = self.thread.take() {
+				self.collected = thread
+					.join()
+					.unwrap_or_else(|_| Err(io::Error::other("stderr could not be read")))?;
+			}
+			if echo {
+				io::stderr().write_all(&self.collected)?;
+			}
+			Ok(Cursor::new(self.collected.as_slice()).lines())
+		})
+		.flat_map(|lines: io::Result<_>| {
+			let (lines, err) = match lines {
+				Ok(lines) => (Some(lines), None),
+				Err(err) => (None, Some(Err(err))),
+			};
+			lines.into_iter().flatten().chain(err)
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Instant;
+
+	#[test]
+	#[cfg(unix)]
+	fn kill_grandchildren() {
+		let mut command = Command::new("sh");
+		command.args(["-c", "sleep 30 & wait"]);
+		let mut output = CommandOutput::spawn(&mut command, true, true, false, true).unwrap();
+		let start = Instant::now();
+		output.kill_after(Timeout(Duration::from_millis(200)));
+		// The grandchild inherited the standard output, so reading it only ends once the grandchild is killed too
+		let mut stdout = Vec::new();
+		output.stdout.read_to_end(&mut stdout).unwrap();
+		assert!(start.elapsed() < Duration::from_secs(10));
+		assert!(output.timed_out());
+		assert!(!output.wait().unwrap().success());
+	}
+
+	#[test]
+	fn stderr_lines() {
+		let mut output = CommandOutput::from_input(b"first\nsecond\n".to_vec());
+		let lines = || vec!["first".to_owned(), "second".to_owned()];
+		assert_eq!(
+			output
+				.stderr
+				.lines(false)
+				.collect::<io::Result<Vec<_>>>()
+				.unwrap(),
+			lines()
+		);
+		// The lines are kept once they have been read
+		assert_eq!(
+			output
+				.stderr
+				.lines(false)
+				.collect::<io::Result<Vec<_>>>()
+				.unwrap(),
+			lines()
+		);
+	}
+}
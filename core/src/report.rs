@@ -0,0 +1,180 @@
+//! Provides structures to write reports of the annotations in other formats
+
+use crate::github::{fingerprint, Annotation};
+use std::{
+	collections::BTreeSet,
+	fs::File,
+	io::{self, BufWriter, Write},
+	path::PathBuf,
+	str::FromStr,
+	time::Duration,
+};
+
+mod baseline;
+mod checkstyle;
+mod gitlab;
+mod html;
+mod json;
+mod junit;
+mod ratchet;
+mod rdjson;
+mod sarif;
+mod template;
+
+pub use baseline::*;
+pub use checkstyle::*;
+pub use gitlab::*;
+pub use html::*;
+pub use json::*;
+pub use junit::*;
+pub use ratchet::*;
+pub use rdjson::*;
+pub use sarif::*;
+pub use template::*;
+
+/// Format of a report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum ReportFormat {
+	/// [SARIF](https://sarifweb.azurewebsites.net) 2.1.0 log, for GitHub code scanning
+	Sarif,
+	/// `JUnit` XML report, for test report dashboards
+	Junit,
+	/// Checkstyle XML report, for Jenkins and code review bots
+	Checkstyle,
+	/// GitLab Code Quality report, for merge request widgets
+	Gitlab,
+	/// Reviewdog Diagnostic Format, for reviewdog
+	Rdjson,
+	/// JSON document with information about the run, for other tools
+	Json,
+	/// Standalone HTML page, for build artifacts
+	Html,
+}
+impl FromStr for ReportFormat {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_ascii_lowercase().as_str() {
+			"sarif" => Ok(Self::Sarif),
+			"junit" => Ok(Self::Junit),
+			"checkstyle" => Ok(Self::Checkstyle),
+			"gitlab" => Ok(Self::Gitlab),
+			"rdjson" => Ok(Self::Rdjson),
+			"json" => Ok(Self::Json),
+			"html" => Ok(Self::Html),
+			_ => Err(format!(
+				"invalid report format `{s}`, expected `sarif`, `junit`, `checkstyle`, `gitlab`, `rdjson`, `json` or `html`"
+			)),
+		}
+	}
+}
+
+/// Information about the run, written in some reports
+#[derive(Debug, Clone)]
+pub struct RunInfo {
+	/// Annotated command (e.g. `cargo clippy`)
+	pub command: String,
+	/// Duration of the run
+	pub duration: Duration,
+}
+
+/// Report to write, as passed to the `--emit` option
+#[derive(Debug, Clone)]
+pub struct Emit {
+	/// Format of the report
+	pub format: ReportFormat,
+	/// Path to the file to write the report to
+	pub path: PathBuf,
+}
+impl FromStr for Emit {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (format, path) = s
+			.split_once('=')
+			.ok_or_else(|| format!("invalid report `{s}`, expected FORMAT=PATH"))?;
+		Ok(Self {
+			format: format.parse()?,
+			path: PathBuf::from(path),
+		})
+	}
+}
+impl Emit {
+	/// Writes the report of `annotations` raised during the `run`
+	///
+	/// # Errors
+	/// This function returns an error if the report could not be written.
+	pub fn write(&self, annotations: &BTreeSet<Annotation<'_>>, run: &RunInfo) -> io::Result<()> {
+		let mut file = BufWriter::new(File::create(&self.path)?);
+		match self.format {
+			ReportFormat::Sarif => write_sarif(annotations, &mut file)?,
+			ReportFormat::Junit => write_junit(annotations, &mut file)?,
+			ReportFormat::Checkstyle => write_checkstyle(annotations, &mut file)?,
+			ReportFormat::Gitlab => write_gitlab(annotations, &mut file)?,
+			ReportFormat::Rdjson => write_rdjson(annotations, &mut file)?,
+			ReportFormat::Json => write_json(annotations, run, &mut file)?,
+			ReportFormat::Html => write_html(annotations, &mut file)?,
+		}
+		file.flush()
+	}
+}
+
+/// Returns the title of `annotation`, or the first line of its message if it has none
+fn title<'a>(annotation: &'a Annotation<'_>) -> &'a str {
+	annotation
+		.title
+		.as_deref()
+		.or_else(|| annotation.message.lines().next())
+		.unwrap_or_default()
+}
+
+/// Escapes the special characters of XML in `s`
+///
+/// The characters which are not allowed in XML are removed.
+fn escape_xml(s: &str) -> String {
+	let mut escaped = String::with_capacity(s.len());
+	for c in s.chars() {
+		match c {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&apos;"),
+			// Control characters are not allowed in XML 1.0
+			c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+			c => escaped.push(c),
+		}
+	}
+	escaped
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+	use crate::cargo::{Diagnostic, HandleMessage, MessageStyle, Options};
+
+	/// Error E0308 of rustc, with a secondary span
+	pub(crate) const MISMATCHED_TYPES: &str = r#"{"rendered":"error[E0308]: mismatched types\n --> lintfix/src/lib.rs:6:5\n  |\n5 | pub fn answer() -> u32 {\n  |                    --- expected `u32` because of return type\n6 |     \"42\"\n  |     ^^^^ expected `u32`, found `&str`\n\n","$message_type":"diagnostic","children":[],"level":"error","message":"mismatched types","spans":[{"byte_end":84,"byte_start":80,"column_end":9,"column_start":5,"expansion":null,"file_name":"lintfix/src/lib.rs","is_primary":true,"label":"expected `u32`, found `&str`","line_end":6,"line_start":6,"suggested_replacement":null,"suggestion_applicability":null,"text":[{"highlight_end":9,"highlight_start":5,"text":"    \"42\""}]},{"byte_end":73,"byte_start":70,"column_end":23,"column_start":20,"expansion":null,"file_name":"lintfix/src/lib.rs","is_primary":false,"label":"expected `u32` because of return type","line_end":5,"line_start":5,"suggested_replacement":null,"suggestion_applicability":null,"text":[{"highlight_end":23,"highlight_start":20,"text":"pub fn answer() -> u32 {"}]}],"code":{"code":"E0308","explanation":null}}"#;
+	/// Warning of Clippy's `ptr_arg`, in the same file
+	pub(crate) const PTR_ARG: &str = r#"{"rendered":"warning: writing `&Vec` instead of `&[_]` involves a new object where a slice will do\n --> lintfix/src/lib.rs:1:15\n  |\n1 | pub fn len(v: &Vec<u32>) -> usize {\n  |               ^^^^^^^^^\n  |\n  = help: for further information visit https://rust-lang.github.io/rust-clippy/rust-1.95.0/index.html#ptr_arg\n  = note: `#[warn(clippy::ptr_arg)]` on by default\nhelp: change this to\n  |\n1 - pub fn len(v: &Vec<u32>) -> usize {\n1 + pub fn len(v: &[u32]) -> usize {\n  |\n\n","$message_type":"diagnostic","children":[{"children":[],"code":null,"level":"help","message":"for further information visit https://rust-lang.github.io/rust-clippy/rust-1.95.0/index.html#ptr_arg","rendered":null,"spans":[]},{"children":[],"code":null,"level":"note","message":"`#[warn(clippy::ptr_arg)]` on by default","rendered":null,"spans":[]},{"children":[],"code":null,"level":"help","message":"change this to","rendered":null,"spans":[{"byte_end":23,"byte_start":14,"column_end":24,"column_start":15,"expansion":null,"file_name":"lintfix/src/lib.rs","is_primary":true,"label":null,"line_end":1,"line_start":1,"suggested_replacement":"&[u32]","suggestion_applicability":"Unspecified","text":[{"highlight_end":24,"highlight_start":15,"text":"pub fn len(v: &Vec<u32>) -> usize {"}]}]}],"level":"warning","message":"writing `&Vec` instead of `&[_]` involves a new object where a slice will do","spans":[{"byte_end":23,"byte_start":14,"column_end":24,"column_start":15,"expansion":null,"file_name":"lintfix/src/lib.rs","is_primary":true,"label":null,"line_end":1,"line_start":1,"suggested_replacement":null,"suggestion_applicability":null,"text":[{"highlight_end":24,"highlight_start":15,"text":"pub fn len(v: &Vec<u32>) -> usize {"}]}],"code":{"code":"clippy::ptr_arg","explanation":null}}"#;
+
+	/// Returns the annotations of the rustc `diagnostic`, with short messages
+	pub(crate) fn annotate(diagnostic: &'static str) -> Vec<Annotation<'static>> {
+		let options = Options {
+			message_style: MessageStyle::Short,
+			..Options::default()
+		};
+		serde_json::from_str::<Diagnostic>(diagnostic)
+			.unwrap()
+			.into_annotations(&options)
+	}
+
+	/// Returns the annotations of an error, of its secondary span and of a warning of the same file
+	pub(crate) fn annotations() -> BTreeSet<Annotation<'static>> {
+		[MISMATCHED_TYPES, PTR_ARG]
+			.into_iter()
+			.flat_map(annotate)
+			.collect()
+	}
+}
@@ -0,0 +1,151 @@
+//! Provides the structs to record known issues in a baseline, whose annotations are not emitted again
+
+use super::title;
+use crate::github::Annotation;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	fs::{self, File},
+	io::{self, BufWriter, Write},
+	path::Path,
+};
+
+/// Snapshot of the issues, as written to a baseline file
+#[derive(Debug, Serialize, Deserialize)]
+struct BaselineFile<I> {
+	/// Recorded issues
+	issues: Vec<I>,
+}
+
+/// Issue written to a baseline file
+///
+/// The location and title are only written for the readers of the file.
+#[derive(Debug, Serialize)]
+struct WrittenIssue<'a> {
+	/// Fingerprint of the issue
+	fingerprint: String,
+	/// Location of the issue (absent if it is not located in a file)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	location: Option<String>,
+	/// Title of the issue
+	title: &'a str,
+}
+
+/// Issue read from a baseline file
+#[derive(Debug, Deserialize)]
+struct ReadIssue {
+	/// Fingerprint of the issue
+	fingerprint: String,
+}
+
+/// Known issues, whose annotations are not emitted again
+#[derive(Debug, Default, Clone)]
+pub struct Baseline {
+	/// Number of known occurrences, by fingerprint
+	issues: HashMap<String, usize>,
+}
+impl Baseline {
+	/// Reads the baseline file at `path`
+	///
+	/// # Errors
+	/// This function returns an error if the file could not be read or is invalid.
+	pub fn load(path: &Path) -> io::Result<Self> {
+		let file: BaselineFile<ReadIssue> = serde_json::from_slice(&fs::read(path)?)?;
+		let mut issues = HashMap::new();
+		for issue in file.issues {
+			*issues.entry(issue.fingerprint).or_default() += 1;
+		}
+		Ok(Self { issues })
+	}
+
+	/// Removes an occurrence of the issue of `annotation` from the baseline
+	///
+	/// Identical issues are told apart by their number of occurrences.
+	/// Returns `true` if the issue is known.
+	pub fn remove(&mut self, annotation: &Annotation<'_>) -> bool {
+		match self.issues.get_mut(&annotation.fingerprint()) {
+			Some(count) if *count > 0 => {
+				*count -= 1;
+				true
+			}
+			_ => false,
+		}
+	}
+}
+
+/// Writes `annotations` to the baseline file at `path`
+///
+/// # Errors
+/// This function returns an error if the baseline could not be written.
+pub fn write_baseline<'a>(
+	path: &Path,
+	annotations: impl IntoIterator<Item = &'a Annotation<'a>>,
+) -> io::Result<()> {
+	let mut annotations: Vec<_> = annotations.into_iter().collect();
+	annotations.sort_unstable();
+	let issues = annotations
+		.into_iter()
+		.map(|annotation| WrittenIssue {
+			fingerprint: annotation.fingerprint(),
+			location: annotation.location(),
+			title: title(annotation),
+		})
+		.collect();
+	let mut file = BufWriter::new(File::create(path)?);
+	serde_json::to_writer_pretty(&mut file, &BaselineFile { issues })?;
+	writeln!(file)?;
+	file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::report::tests::annotations;
+	use std::env;
+
+	#[test]
+	fn baseline() {
+		let annotations = annotations();
+		let path = env::temp_dir().join(format!("ghannotate-baseline-{}.json", std::process::id()));
+		write_baseline(&path, &annotations).unwrap();
+		let written = fs::read_to_string(&path).unwrap();
+		let baseline = Baseline::load(&path);
+		fs::remove_file(&path).unwrap();
+		assert_eq!(
+			written,
+			r#"{
+  "issues": [
+    {
+      "fingerprint": "985994fb683ce722",
+      "location": "lintfix/src/lib.rs:1:15",
+      "title": "writing `&Vec` instead of `&[_]` involves a new object where a slice will do [clippy::ptr_arg]"
+    },
+    {
+      "fingerprint": "e1d0cd0ee5bbbc8b",
+      "location": "lintfix/src/lib.rs:5:20",
+      "title": "mismatched types"
+    },
+    {
+      "fingerprint": "16a3f26475fabb58",
+      "location": "lintfix/src/lib.rs:6:5",
+      "title": "mismatched types [E0308]"
+    }
+  ]
+}
+"#
+		);
+
+		let mut baseline = baseline.unwrap();
+		let mut annotations = annotations.into_iter();
+		// The issues are still known once their lines are shifted
+		let mut warning = annotations.next().unwrap();
+		warning.line += 2;
+		warning.end_line = Some(warning.line);
+		assert!(baseline.remove(&warning));
+		// Each occurrence is only known once
+		assert!(!baseline.remove(&warning));
+		for annotation in annotations {
+			assert!(baseline.remove(&annotation));
+		}
+	}
+}
@@ -0,0 +1,85 @@
+//! Provides the functions to write Checkstyle XML reports
+
+use super::{escape_xml, title};
+use crate::github::{Annotation, AnnotationKind};
+use std::{
+	collections::BTreeSet,
+	io::{self, Write},
+};
+
+/// Writes `annotations` as a Checkstyle XML report
+///
+/// # Errors
+/// This function returns an error if the report could not be written.
+pub fn write_checkstyle(
+	annotations: &BTreeSet<Annotation<'_>>,
+	writer: &mut dyn Write,
+) -> io::Result<()> {
+	writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+	writeln!(writer, r#"<checkstyle version="4.3">"#)?;
+	let mut file = None;
+	// Checkstyle errors must be located in a file
+	for annotation in annotations
+		.iter()
+		.filter(|annotation| annotation.is_located())
+	{
+		// Annotations are sorted by file
+		if file != Some(annotation.file.as_ref()) {
+			if file.is_some() {
+				writeln!(writer, "  </file>")?;
+			}
+			writeln!(
+				writer,
+				r#"  <file name="{}">"#,
+				escape_xml(&annotation.file)
+			)?;
+			file = Some(annotation.file.as_ref());
+		}
+		write!(writer, r#"    <error line="{}""#, annotation.line)?;
+		if let Some(col) = annotation.col {
+			write!(writer, r#" column="{col}""#)?;
+		}
+		write!(
+			writer,
+			r#" severity="{}" message="{}""#,
+			match annotation.kind {
+				AnnotationKind::Error => "error",
+				AnnotationKind::Warning => "warning",
+				AnnotationKind::Notice => "info",
+			},
+			escape_xml(title(annotation))
+		)?;
+		if let Some(code) = &annotation.code {
+			write!(writer, r#" source="{}""#, escape_xml(code))?;
+		}
+		writeln!(writer, "/>")?;
+	}
+	if file.is_some() {
+		writeln!(writer, "  </file>")?;
+	}
+	writeln!(writer, "</checkstyle>")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::report::tests::annotations;
+
+	#[test]
+	fn checkstyle_report() {
+		let mut report = Vec::new();
+		write_checkstyle(&annotations(), &mut report).unwrap();
+		assert_eq!(
+			String::from_utf8(report).unwrap(),
+			r#"<?xml version="1.0" encoding="UTF-8"?>
+<checkstyle version="4.3">
+  <file name="lintfix/src/lib.rs">
+    <error line="1" column="15" severity="warning" message="writing `&amp;Vec` instead of `&amp;[_]` involves a new object where a slice will do [clippy::ptr_arg]" source="clippy::ptr_arg"/>
+    <error line="5" column="20" severity="info" message="mismatched types"/>
+    <error line="6" column="5" severity="error" message="mismatched types [E0308]" source="E0308"/>
+  </file>
+</checkstyle>
+"#
+		);
+	}
+}
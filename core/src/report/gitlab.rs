@@ -0,0 +1,131 @@
+//! Provides the structs to write GitLab Code Quality reports
+
+use super::{fingerprint, title};
+use crate::github::{Annotation, AnnotationKind};
+use serde::Serialize;
+use std::{
+	collections::{BTreeSet, HashMap},
+	io::{self, Write},
+};
+
+/// Issue of a GitLab Code Quality report
+#[derive(Debug, Serialize)]
+struct CodeQualityIssue<'a> {
+	/// Description of the issue
+	description: &'a str,
+	/// Name of the check which has found the issue
+	check_name: &'a str,
+	/// Unique identifier of the issue
+	fingerprint: String,
+	/// Severity of the issue
+	severity: &'static str,
+	/// Location of the issue
+	location: CodeQualityLocation<'a>,
+}
+
+/// Location of a [`CodeQualityIssue`]
+#[derive(Debug, Serialize)]
+struct CodeQualityLocation<'a> {
+	/// Path to the file, relative to the root of the repository
+	path: &'a str,
+	/// Lines of the issue
+	lines: CodeQualityLines,
+}
+
+/// Lines of a [`CodeQualityIssue`]
+#[derive(Debug, Clone, Copy, Serialize)]
+struct CodeQualityLines {
+	/// First line (1-based, inclusive)
+	begin: usize,
+	/// Last line (1-based, inclusive)
+	end: usize,
+}
+
+/// Writes `annotations` as a GitLab Code Quality report
+///
+/// # Errors
+/// This function returns an error if the report could not be written.
+pub fn write_gitlab(
+	annotations: &BTreeSet<Annotation<'_>>,
+	writer: &mut dyn Write,
+) -> io::Result<()> {
+	let mut occurrences = HashMap::new();
+	// Code Quality issues must be located in a file
+	let issues: Vec<_> = annotations
+		.iter()
+		.filter(|annotation| annotation.is_located())
+		.map(|annotation| {
+			let title = title(annotation);
+			let check_name = annotation.code.as_deref().unwrap_or(title);
+			// Identical issues are told apart by their order, which does not depend on their lines
+			let annotation_fingerprint = annotation.fingerprint();
+			let occurrence = occurrences
+				.entry(annotation_fingerprint.clone())
+				.or_insert(0);
+			*occurrence += 1;
+			CodeQualityIssue {
+				description: title,
+				check_name,
+				fingerprint: fingerprint(&[&annotation_fingerprint], *occurrence),
+				severity: match annotation.kind {
+					AnnotationKind::Error => "major",
+					AnnotationKind::Warning => "minor",
+					AnnotationKind::Notice => "info",
+				},
+				location: CodeQualityLocation {
+					path: &annotation.file,
+					lines: CodeQualityLines {
+						begin: annotation.line,
+						end: annotation.end_line.unwrap_or(annotation.line),
+					},
+				},
+			}
+		})
+		.collect();
+	serde_json::to_writer_pretty(writer, &issues).map_err(io::Error::from)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::report::tests::annotations;
+	use serde_json::json;
+
+	#[test]
+	fn code_quality_report() {
+		let mut report = Vec::new();
+		write_gitlab(&annotations(), &mut report).unwrap();
+		let location = |line| {
+			json!({
+				"path": "lintfix/src/lib.rs",
+				"lines": { "begin": line, "end": line },
+			})
+		};
+		assert_eq!(
+			serde_json::from_slice::<serde_json::Value>(&report).unwrap(),
+			json!([
+				{
+					"description": "writing `&Vec` instead of `&[_]` involves a new object where a slice will do [clippy::ptr_arg]",
+					"check_name": "clippy::ptr_arg",
+					"fingerprint": "866cbfa3c8b23380",
+					"severity": "minor",
+					"location": location(1),
+				},
+				{
+					"description": "mismatched types",
+					"check_name": "mismatched types",
+					"fingerprint": "ea93cadcf450e031",
+					"severity": "info",
+					"location": location(5),
+				},
+				{
+					"description": "mismatched types [E0308]",
+					"check_name": "E0308",
+					"fingerprint": "ba64a1b7aaa09c75",
+					"severity": "major",
+					"location": location(6),
+				},
+			])
+		);
+	}
+}
@@ -0,0 +1,224 @@
+//! Provides the functions to write standalone HTML reports
+
+use super::{escape_xml, title};
+use crate::{
+	github::{Annotation, AnnotationKind},
+	path::repository_root,
+	TOOL_NAME,
+};
+use std::{
+	collections::{BTreeSet, HashMap},
+	fs,
+	io::{self, Write},
+};
+
+/// Style sheet of the report
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2em;color:#1f2328}
+table{border-collapse:collapse;width:100%}
+th,td{border-bottom:1px solid #d0d7de;padding:.4em;text-align:left;vertical-align:top}
+tr.error td:first-child{color:#cf222e}tr.warning td:first-child{color:#9a6700}tr.notice td:first-child{color:#0969da}
+pre{background:#f6f8fa;padding:.5em;overflow-x:auto}
+.highlighted{background:#fff8c5}
+.line-number{color:#6e7781;user-select:none}
+#filters{display:flex;gap:1em;margin-bottom:1em}";
+
+/// Script filtering the annotations by kind and text
+const SCRIPT: &str = r#"const filter = document.getElementById("filter");
+const kinds = document.querySelectorAll("input[data-kind]");
+function update() {
+	const text = filter.value.toLowerCase();
+	const shown = new Set([...kinds].filter((kind) => kind.checked).map((kind) => kind.dataset.kind));
+	for (const row of document.querySelectorAll("tbody tr")) {
+		row.hidden = !(shown.has(row.dataset.kind) && row.textContent.toLowerCase().includes(text));
+	}
+}
+filter.addEventListener("input", update);
+kinds.forEach((kind) => kind.addEventListener("change", update));"#;
+
+/// Number of lines shown around the annotated lines in the code snippets
+const CONTEXT_LINES: usize = 2;
+
+/// Writes `annotations` as a standalone HTML report
+///
+/// The annotations can be filtered by kind and text, and are shown with a snippet of the annotated code.
+///
+/// # Errors
+/// This function returns an error if the report could not be written.
+pub fn write_html(
+	annotations: &BTreeSet<Annotation<'_>>,
+	writer: &mut dyn Write,
+) -> io::Result<()> {
+	let mut files = HashMap::new();
+
+	writeln!(writer, "<!DOCTYPE html>")?;
+	writeln!(writer, r#"<html lang="en">"#)?;
+	writeln!(
+		writer,
+		r#"<head><meta charset="utf-8"><title>{} report</title><style>{STYLE}</style></head>"#,
+		TOOL_NAME
+	)?;
+	writeln!(writer, "<body>")?;
+	writeln!(writer, "<h1>{} report</h1>", TOOL_NAME)?;
+	writeln!(writer, r#"<div id="filters">"#)?;
+	for kind in [
+		AnnotationKind::Error,
+		AnnotationKind::Warning,
+		AnnotationKind::Notice,
+	] {
+		writeln!(
+			writer,
+			r#"<label><input type="checkbox" data-kind="{}" checked> {}s ({})</label>"#,
+			kind.name(),
+			kind.name(),
+			annotations
+				.iter()
+				.filter(|annotation| annotation.kind == kind)
+				.count()
+		)?;
+	}
+	writeln!(
+		writer,
+		r#"<input type="search" id="filter" placeholder="Filter">"#
+	)?;
+	writeln!(writer, "</div>")?;
+	writeln!(writer, "<table>")?;
+	writeln!(
+		writer,
+		"<thead><tr><th>Level</th><th>Issue</th><th>Code</th><th>Location</th></tr></thead>"
+	)?;
+	writeln!(writer, "<tbody>")?;
+	for annotation in annotations {
+		let kind = annotation.kind.name();
+		writeln!(writer, r#"<tr class="{kind}" data-kind="{kind}">"#)?;
+		writeln!(writer, "<td>{kind}</td>")?;
+		writeln!(
+			writer,
+			"<td><details><summary>{}</summary><pre>{}</pre>",
+			escape_xml(title(annotation)),
+			escape_xml(annotation.message.trim_end())
+		)?;
+		if annotation.is_located() {
+			let content = files
+				.entry(annotation.file.as_ref())
+				.or_insert_with(|| {
+					fs::read_to_string(repository_root().join(annotation.file.as_ref())).ok()
+				})
+				.as_deref();
+			if let Some(content) = content {
+				write_snippet(annotation, content, writer)?;
+			}
+		}
+		writeln!(writer, "</details></td>")?;
+		writeln!(
+			writer,
+			"<td>{}</td>",
+			escape_xml(annotation.code.as_deref().unwrap_or_default())
+		)?;
+		writeln!(
+			writer,
+			"<td>{}</td>",
+			escape_xml(&annotation.location().unwrap_or_default())
+		)?;
+		writeln!(writer, "</tr>")?;
+	}
+	writeln!(writer, "</tbody>")?;
+	writeln!(writer, "</table>")?;
+	writeln!(writer, "<script>{SCRIPT}</script>")?;
+	writeln!(writer, "</body>")?;
+	writeln!(writer, "</html>")
+}
+
+/// Writes a snippet of the `content` of the file annotated by `annotation`, with the annotated lines highlighted
+fn write_snippet(
+	annotation: &Annotation<'_>,
+	content: &str,
+	writer: &mut dyn Write,
+) -> io::Result<()> {
+	let end_line = annotation
+		.end_line
+		.unwrap_or(annotation.line)
+		.max(annotation.line);
+	let first_line = annotation.line.saturating_sub(CONTEXT_LINES).max(1);
+	let lines: Vec<_> = content
+		.lines()
+		.enumerate()
+		.skip(first_line - 1)
+		.take(end_line + CONTEXT_LINES + 1 - first_line)
+		.collect();
+	if lines.is_empty() {
+		return Ok(());
+	}
+	write!(writer, "<pre>")?;
+	for (index, line) in lines {
+		let number = index + 1;
+		let line = format!(
+			r#"<span class="line-number">{number:>5} </span>{}"#,
+			escape_xml(line)
+		);
+		if (annotation.line..=end_line).contains(&number) {
+			write!(writer, "<span class=\"highlighted\">{line}\n</span>")?;
+		} else {
+			writeln!(writer, "{line}")?;
+		}
+	}
+	write!(writer, "</pre>")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::report::tests::annotations;
+
+	#[test]
+	fn html_report() {
+		let mut report = Vec::new();
+		write_html(&annotations(), &mut report).unwrap();
+		// The annotated file is not in this repository, so there is no snippet
+		assert_eq!(
+			String::from_utf8(report).unwrap(),
+			format!(
+				r#"<!DOCTYPE html>
+<html lang="en">
+<head><meta charset="utf-8"><title>cargo-ghannotate report</title><style>{STYLE}</style></head>
+<body>
+<h1>cargo-ghannotate report</h1>
+<div id="filters">
+<label><input type="checkbox" data-kind="error" checked> errors (1)</label>
+<label><input type="checkbox" data-kind="warning" checked> warnings (1)</label>
+<label><input type="checkbox" data-kind="notice" checked> notices (1)</label>
+<input type="search" id="filter" placeholder="Filter">
+</div>
+<table>
+<thead><tr><th>Level</th><th>Issue</th><th>Code</th><th>Location</th></tr></thead>
+<tbody>
+<tr class="warning" data-kind="warning">
+<td>warning</td>
+<td><details><summary>writing `&amp;Vec` instead of `&amp;[_]` involves a new object where a slice will do [clippy::ptr_arg]</summary><pre>writing `&amp;Vec` instead of `&amp;[_]` involves a new object where a slice will do</pre>
+</details></td>
+<td>clippy::ptr_arg</td>
+<td>lintfix/src/lib.rs:1:15</td>
+</tr>
+<tr class="notice" data-kind="notice">
+<td>notice</td>
+<td><details><summary>mismatched types</summary><pre>expected `u32` because of return type</pre>
+</details></td>
+<td></td>
+<td>lintfix/src/lib.rs:5:20</td>
+</tr>
+<tr class="error" data-kind="error">
+<td>error</td>
+<td><details><summary>mismatched types [E0308]</summary><pre>mismatched types</pre>
+</details></td>
+<td>E0308</td>
+<td>lintfix/src/lib.rs:6:5</td>
+</tr>
+</tbody>
+</table>
+<script>{SCRIPT}</script>
+</body>
+</html>
+"#
+			)
+		);
+	}
+}
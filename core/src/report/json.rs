@@ -0,0 +1,202 @@
+//! Provides the structs to write JSON reports
+
+use super::RunInfo;
+use crate::github::{Annotation, AnnotationKind};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeSet,
+	env, fs,
+	io::{self, Write},
+	path::Path,
+	process::{Command, Stdio},
+};
+
+/// JSON report of a run
+#[derive(Debug, Serialize)]
+struct JsonReport<'a> {
+	/// Version of this program
+	version: &'static str,
+	/// Annotated command (e.g. `cargo clippy`)
+	command: &'a str,
+	/// Version of the Rust toolchain (e.g. `rustc 1.80.0 (051478957 2024-07-21)`)
+	toolchain: Option<String>,
+	/// Duration of the run, in seconds
+	duration: f64,
+	/// Counts of annotations, by kind
+	counts: Counts,
+	/// The annotations
+	annotations: Vec<JsonAnnotation<'a>>,
+}
+
+/// Counts of annotations of a [`JsonReport`]
+#[derive(Debug, Default, Serialize)]
+pub(super) struct Counts {
+	/// Number of errors
+	errors: usize,
+	/// Number of warnings
+	warnings: usize,
+	/// Number of notices
+	notices: usize,
+}
+impl Counts {
+	/// Counts the `annotations` by kind
+	pub(super) fn count<'a>(annotations: impl IntoIterator<Item = &'a Annotation<'a>>) -> Self {
+		let mut counts = Self::default();
+		for annotation in annotations {
+			*match annotation.kind {
+				AnnotationKind::Error => &mut counts.errors,
+				AnnotationKind::Warning => &mut counts.warnings,
+				AnnotationKind::Notice => &mut counts.notices,
+			} += 1;
+		}
+		counts
+	}
+}
+
+/// Annotation of a [`JsonReport`]
+#[derive(Debug, Serialize)]
+pub(super) struct JsonAnnotation<'a> {
+	/// The annotation
+	#[serde(flatten)]
+	annotation: &'a Annotation<'a>,
+	/// Fingerprint of the annotation
+	fingerprint: String,
+}
+impl<'a> From<&'a Annotation<'a>> for JsonAnnotation<'a> {
+	#[inline]
+	fn from(annotation: &'a Annotation<'a>) -> Self {
+		Self {
+			annotation,
+			fingerprint: annotation.fingerprint(),
+		}
+	}
+}
+
+/// JSON report of a run, as read back
+#[derive(Debug, Deserialize)]
+struct ReadReport {
+	/// The annotations
+	annotations: Vec<Annotation<'static>>,
+}
+
+/// Returns the version of the Rust toolchain, as output by `rustc --version`
+fn toolchain() -> Option<String> {
+	let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+	let output = Command::new(rustc)
+		.arg("--version")
+		.stdin(Stdio::null())
+		.stderr(Stdio::null())
+		.output()
+		.ok()?;
+	output
+		.status
+		.success()
+		.then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+/// Writes `annotations` and information about the `run` as a JSON report
+///
+/// # Errors
+/// This function returns an error if the report could not be written.
+pub fn write_json(
+	annotations: &BTreeSet<Annotation<'_>>,
+	run: &RunInfo,
+	writer: &mut dyn Write,
+) -> io::Result<()> {
+	let report = JsonReport {
+		version: env!("CARGO_PKG_VERSION"),
+		command: &run.command,
+		toolchain: toolchain(),
+		duration: run.duration.as_secs_f64(),
+		counts: Counts::count(annotations),
+		annotations: annotations.iter().map(From::from).collect(),
+	};
+	serde_json::to_writer_pretty(&mut *writer, &report)?;
+	writeln!(writer)
+}
+
+/// Reads the annotations of the JSON report at `path`
+///
+/// # Errors
+/// This function returns an error if the report could not be read or is invalid.
+pub fn read_json(path: &Path) -> io::Result<Vec<Annotation<'static>>> {
+	let report: ReadReport = serde_json::from_slice(&fs::read(path)?)?;
+	Ok(report.annotations)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::report::tests::annotations;
+	use serde_json::json;
+	use std::time::Duration;
+
+	#[test]
+	fn json_report() {
+		let annotations = annotations();
+		let run = RunInfo {
+			command: "cargo clippy".to_owned(),
+			duration: Duration::from_millis(1500),
+		};
+		let mut report = Vec::new();
+		write_json(&annotations, &run, &mut report).unwrap();
+		assert_eq!(
+			serde_json::from_slice::<serde_json::Value>(&report).unwrap(),
+			json!({
+				"version": env!("CARGO_PKG_VERSION"),
+				"command": "cargo clippy",
+				"toolchain": toolchain(),
+				"duration": 1.5,
+				"counts": { "errors": 1, "warnings": 1, "notices": 1 },
+				"annotations": [
+					{
+						"kind": "warning",
+						"file": "lintfix/src/lib.rs",
+						"line": 1,
+						"end_line": 1,
+						"col": 15,
+						"end_column": 24,
+						"code": "clippy::ptr_arg",
+						"suggestions": [],
+						"title": "writing `&Vec` instead of `&[_]` involves a new object where a slice will do [clippy::ptr_arg]",
+						"message": "writing `&Vec` instead of `&[_]` involves a new object where a slice will do",
+						"fingerprint": "985994fb683ce722",
+					},
+					{
+						"kind": "notice",
+						"file": "lintfix/src/lib.rs",
+						"line": 5,
+						"end_line": 5,
+						"col": 20,
+						"end_column": 23,
+						"code": null,
+						"suggestions": [],
+						"title": "mismatched types",
+						"message": "expected `u32` because of return type",
+						"fingerprint": "e1d0cd0ee5bbbc8b",
+					},
+					{
+						"kind": "error",
+						"file": "lintfix/src/lib.rs",
+						"line": 6,
+						"end_line": 6,
+						"col": 5,
+						"end_column": 9,
+						"code": "E0308",
+						"suggestions": [],
+						"title": "mismatched types [E0308]",
+						"message": "mismatched types",
+						"fingerprint": "16a3f26475fabb58",
+					},
+				],
+			})
+		);
+
+		// The annotations are read back by `merge`
+		let path = env::temp_dir().join(format!("ghannotate-report-{}.json", std::process::id()));
+		fs::write(&path, &report).unwrap();
+		let read = read_json(&path);
+		fs::remove_file(&path).unwrap();
+		assert_eq!(read.unwrap(), annotations.into_iter().collect::<Vec<_>>());
+	}
+}
@@ -0,0 +1,110 @@
+//! Provides the functions to write `JUnit` XML reports
+
+use super::{escape_xml, title};
+use crate::{
+	github::{Annotation, AnnotationKind},
+	TOOL_NAME,
+};
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	io::{self, Write},
+};
+
+/// Writes `annotations` as a `JUnit` XML report
+///
+/// There is a test suite for each annotated file and a test case for each annotation.
+/// Errors and warnings are reported as failures.
+///
+/// # Errors
+/// This function returns an error if the report could not be written.
+pub fn write_junit(
+	annotations: &BTreeSet<Annotation<'_>>,
+	writer: &mut dyn Write,
+) -> io::Result<()> {
+	/// Returns `true` if `annotation` is reported as a failure
+	fn is_failure(annotation: &Annotation<'_>) -> bool {
+		annotation.kind >= AnnotationKind::Warning
+	}
+
+	let mut files: BTreeMap<&str, Vec<&Annotation<'_>>> = BTreeMap::new();
+	for annotation in annotations {
+		files.entry(&annotation.file).or_default().push(annotation);
+	}
+
+	writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+	writeln!(
+		writer,
+		r#"<testsuites name="{}" tests="{}" failures="{}">"#,
+		TOOL_NAME,
+		annotations.len(),
+		annotations
+			.iter()
+			.filter(|annotation| is_failure(annotation))
+			.count()
+	)?;
+	for (file, annotations) in files {
+		let file = escape_xml(file);
+		writeln!(
+			writer,
+			r#"  <testsuite name="{file}" tests="{}" failures="{}" errors="0">"#,
+			annotations.len(),
+			annotations
+				.iter()
+				.filter(|annotation| is_failure(annotation))
+				.count()
+		)?;
+		for annotation in annotations {
+			let title = title(annotation);
+			let name = escape_xml(annotation.code.as_deref().unwrap_or(title));
+			let name = match annotation.location() {
+				Some(location) => format!("{} {name}", escape_xml(&location)),
+				None => name,
+			};
+			writeln!(writer, r#"    <testcase name="{name}" classname="{file}">"#)?;
+			let message = escape_xml(annotation.message.trim());
+			if is_failure(annotation) {
+				writeln!(
+					writer,
+					r#"      <failure type="{}" message="{}">{message}</failure>"#,
+					annotation.kind.name(),
+					escape_xml(title)
+				)?;
+			} else {
+				writeln!(writer, "      <system-out>{message}</system-out>")?;
+			}
+			writeln!(writer, "    </testcase>")?;
+		}
+		writeln!(writer, "  </testsuite>")?;
+	}
+	writeln!(writer, "</testsuites>")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::report::tests::annotations;
+
+	#[test]
+	fn junit_report() {
+		let mut report = Vec::new();
+		write_junit(&annotations(), &mut report).unwrap();
+		assert_eq!(
+			String::from_utf8(report).unwrap(),
+			r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="cargo-ghannotate" tests="3" failures="2">
+  <testsuite name="lintfix/src/lib.rs" tests="3" failures="2" errors="0">
+    <testcase name="lintfix/src/lib.rs:1:15 clippy::ptr_arg" classname="lintfix/src/lib.rs">
+      <failure type="warning" message="writing `&amp;Vec` instead of `&amp;[_]` involves a new object where a slice will do [clippy::ptr_arg]">writing `&amp;Vec` instead of `&amp;[_]` involves a new object where a slice will do</failure>
+    </testcase>
+    <testcase name="lintfix/src/lib.rs:5:20 mismatched types" classname="lintfix/src/lib.rs">
+      <system-out>expected `u32` because of return type</system-out>
+    </testcase>
+    <testcase name="lintfix/src/lib.rs:6:5 E0308" classname="lintfix/src/lib.rs">
+      <failure type="error" message="mismatched types [E0308]">mismatched types</failure>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+		);
+	}
+}
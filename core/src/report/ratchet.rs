@@ -0,0 +1,227 @@
+//! Provides the structs to ratchet the counts of warnings, so that they can only decrease
+
+use crate::github::{Annotation, AnnotationKind};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fs::{self, File},
+	io::{self, BufWriter, Write},
+	path::Path,
+};
+
+/// Counts of warnings, as written to a ratchet file
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+struct WarningCounts {
+	/// Counts by lint
+	lints: BTreeMap<String, usize>,
+	/// Counts by file
+	files: BTreeMap<String, usize>,
+}
+impl WarningCounts {
+	/// Counts the warnings of `annotations`
+	fn count<'a>(annotations: impl IntoIterator<Item = &'a Annotation<'a>>) -> Self {
+		let mut counts = Self::default();
+		for annotation in annotations {
+			if annotation.kind != AnnotationKind::Warning {
+				continue;
+			}
+			if let Some(code) = &annotation.code {
+				*counts.lints.entry(code.clone().into_owned()).or_default() += 1;
+			}
+			if annotation.is_located() {
+				*counts
+					.files
+					.entry(annotation.file.clone().into_owned())
+					.or_default() += 1;
+			}
+		}
+		counts
+	}
+
+	/// Returns the total number of warnings counted by file
+	fn total(&self) -> usize {
+		self.files.values().sum()
+	}
+}
+
+/// Change of a count of warnings
+#[derive(Debug)]
+struct CountChange<'a> {
+	/// Counted lint or file
+	name: &'a str,
+	/// Previous count
+	before: usize,
+	/// Current count
+	after: usize,
+}
+
+/// Returns the changes between the `previous` and `current` counts
+fn changes<'a>(
+	previous: &'a BTreeMap<String, usize>,
+	current: &'a BTreeMap<String, usize>,
+) -> Vec<CountChange<'a>> {
+	previous
+		.keys()
+		.chain(current.keys())
+		.collect::<BTreeSet<_>>()
+		.into_iter()
+		.filter_map(|name| {
+			let before = previous.get(name).copied().unwrap_or_default();
+			let after = current.get(name).copied().unwrap_or_default();
+			(before != after).then_some(CountChange {
+				name,
+				before,
+				after,
+			})
+		})
+		.collect()
+}
+
+/// Compares the warnings of `annotations` with the counts of the ratchet file at `path`
+///
+/// The comparison is written to the `summary`.
+/// The ratchet file is updated unless a count has increased, and created if it does not exist.
+/// Returns `true` if a count has increased.
+///
+/// # Errors
+/// This function returns an error if the ratchet file could not be read or written, or if the summary could not be written.
+pub fn ratchet<'a>(
+	path: &Path,
+	annotations: impl IntoIterator<Item = &'a Annotation<'a>>,
+	summary: &mut dyn Write,
+) -> io::Result<bool> {
+	let current = WarningCounts::count(annotations);
+	let previous = match fs::read(path) {
+		Ok(content) => Some(serde_json::from_slice::<WarningCounts>(&content)?),
+		Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+		Err(err) => return Err(err),
+	};
+	let mut increased = false;
+	if let Some(previous) = &previous {
+		let changes: Vec<_> = changes(&previous.lints, &current.lints)
+			.into_iter()
+			.map(|change| ("Lint", change))
+			.chain(
+				changes(&previous.files, &current.files)
+					.into_iter()
+					.map(|change| ("File", change)),
+			)
+			.collect();
+		increased = changes
+			.iter()
+			.any(|(_, change)| change.after > change.before);
+		if increased {
+			writeln!(
+				summary,
+				"> :chart_with_upwards_trend: **RATCHET:** some counts of warnings increased ({} warnings, {} before)\n",
+				current.total(),
+				previous.total()
+			)?;
+		} else if !changes.is_empty() {
+			writeln!(
+				summary,
+				"> :tada: **RATCHET:** {} fewer warnings, from {} to {}\n",
+				previous.total().saturating_sub(current.total()),
+				previous.total(),
+				current.total()
+			)?;
+		}
+		if !changes.is_empty() {
+			writeln!(summary, "|Counted|Name|Before|After|")?;
+			writeln!(summary, "|:--|:--|--:|--:|")?;
+			for (counted, change) in changes {
+				writeln!(
+					summary,
+					"|{counted}|`{}`|{}|{}|",
+					change.name, change.before, change.after
+				)?;
+			}
+			writeln!(summary)?;
+		}
+	}
+	if !increased && previous.as_ref() != Some(&current) {
+		let mut file = BufWriter::new(File::create(path)?);
+		serde_json::to_writer_pretty(&mut file, &current)?;
+		writeln!(file)?;
+		file.flush()?;
+	}
+	Ok(increased)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::report::tests::annotations;
+	use std::env;
+
+	#[test]
+	fn ratchet_counts() {
+		let annotations = annotations();
+		let path = env::temp_dir().join(format!("ghannotate-ratchet-{}.json", std::process::id()));
+		let counts = r#"{
+  "lints": {
+    "clippy::ptr_arg": 1
+  },
+  "files": {
+    "lintfix/src/lib.rs": 1
+  }
+}
+"#;
+		let run = |annotations: &[&Annotation<'_>]| {
+			let mut summary = Vec::new();
+			let increased = ratchet(&path, annotations.iter().copied(), &mut summary).unwrap();
+			(
+				increased,
+				String::from_utf8(summary).unwrap(),
+				fs::read_to_string(&path).unwrap(),
+			)
+		};
+
+		// The ratchet file is created by the first run
+		let current: Vec<_> = annotations.iter().collect();
+		let first = run(&current);
+
+		let mut warning = annotations.first().unwrap().clone();
+		warning.line = 10;
+		let increased = run(&[current.as_slice(), &[&warning]].concat());
+
+		let decreased = run(&current[1..]);
+		fs::remove_file(&path).unwrap();
+
+		assert_eq!(first, (false, String::new(), counts.to_owned()));
+		// The ratchet file is not updated when a count increases
+		assert_eq!(
+			increased,
+			(
+				true,
+				"> :chart_with_upwards_trend: **RATCHET:** some counts of warnings increased (2 warnings, 1 before)
+
+|Counted|Name|Before|After|
+|:--|:--|--:|--:|
+|Lint|`clippy::ptr_arg`|1|2|
+|File|`lintfix/src/lib.rs`|1|2|
+
+"
+				.to_owned(),
+				counts.to_owned()
+			)
+		);
+		assert_eq!(
+			decreased,
+			(
+				false,
+				"> :tada: **RATCHET:** 1 fewer warnings, from 1 to 0
+
+|Counted|Name|Before|After|
+|:--|:--|--:|--:|
+|Lint|`clippy::ptr_arg`|1|0|
+|File|`lintfix/src/lib.rs`|1|0|
+
+"
+				.to_owned(),
+				"{\n  \"lints\": {},\n  \"files\": {}\n}\n".to_owned()
+			)
+		);
+	}
+}
@@ -0,0 +1,212 @@
+//! Provides the structs to write reports in the Reviewdog Diagnostic Format
+
+use super::title;
+use crate::{
+	github::{Annotation, AnnotationKind},
+	TOOL_NAME,
+};
+use serde::Serialize;
+use std::{
+	collections::BTreeSet,
+	io::{self, Write},
+};
+
+/// Result of a diagnostic tool
+#[derive(Debug, Serialize)]
+struct DiagnosticResult<'a> {
+	/// The tool
+	source: Source,
+	/// Diagnostics of the tool
+	diagnostics: Vec<RdDiagnostic<'a>>,
+}
+
+/// Diagnostic tool
+#[derive(Debug, Serialize)]
+struct Source {
+	/// Name of the tool
+	name: &'static str,
+	/// URL with more information about the tool
+	url: &'static str,
+}
+
+/// Diagnostic of a [`DiagnosticResult`]
+#[derive(Debug, Serialize)]
+struct RdDiagnostic<'a> {
+	/// Message of the diagnostic
+	message: &'a str,
+	/// Location of the diagnostic (absent if it is not located in a file)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	location: Option<RdLocation<'a>>,
+	/// Severity of the diagnostic
+	severity: &'static str,
+	/// Code of the diagnostic
+	#[serde(skip_serializing_if = "Option::is_none")]
+	code: Option<RdCode<'a>>,
+	/// Suggested fixes of the diagnostic
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	suggestions: Vec<RdSuggestion<'a>>,
+	/// Diagnostic as output by the tool
+	original_output: &'a str,
+}
+
+/// Location of a [`RdDiagnostic`]
+#[derive(Debug, Serialize)]
+struct RdLocation<'a> {
+	/// Path to the file, relative to the root of the repository
+	path: &'a str,
+	/// Range of the file
+	range: Range,
+}
+
+/// Range of a file
+#[derive(Debug, Clone, Copy, Serialize)]
+struct Range {
+	/// Start of the range (inclusive)
+	start: Position,
+	/// End of the range (exclusive)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	end: Option<Position>,
+}
+
+/// Position in a file
+#[derive(Debug, Clone, Copy, Serialize)]
+struct Position {
+	/// Line (1-based)
+	line: usize,
+	/// Column (1-based)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	column: Option<usize>,
+}
+
+/// Code of a [`RdDiagnostic`]
+#[derive(Debug, Serialize)]
+struct RdCode<'a> {
+	/// The code itself
+	value: &'a str,
+}
+
+/// Suggested fix of a [`RdDiagnostic`]
+#[derive(Debug, Serialize)]
+struct RdSuggestion<'a> {
+	/// Range to replace
+	range: Range,
+	/// Replacement of the range
+	text: &'a str,
+}
+
+/// Writes `annotations` as a report in the Reviewdog Diagnostic Format
+///
+/// # Errors
+/// This function returns an error if the report could not be written.
+pub fn write_rdjson(
+	annotations: &BTreeSet<Annotation<'_>>,
+	writer: &mut dyn Write,
+) -> io::Result<()> {
+	let diagnostics = annotations
+		.iter()
+		.map(|annotation| RdDiagnostic {
+			message: title(annotation),
+			location: annotation.is_located().then(|| RdLocation {
+				path: &annotation.file,
+				range: Range {
+					start: Position {
+						line: annotation.line,
+						column: annotation.col,
+					},
+					end: annotation
+						.end_line
+						.zip(annotation.col.and(annotation.end_column))
+						.map(|(line, column)| Position {
+							line,
+							column: Some(column),
+						}),
+				},
+			}),
+			severity: match annotation.kind {
+				AnnotationKind::Error => "ERROR",
+				AnnotationKind::Warning => "WARNING",
+				AnnotationKind::Notice => "INFO",
+			},
+			code: annotation.code.as_deref().map(|value| RdCode { value }),
+			suggestions: annotation
+				.suggestions
+				.iter()
+				.map(|suggestion| RdSuggestion {
+					range: Range {
+						start: Position {
+							line: suggestion.line,
+							column: Some(suggestion.col),
+						},
+						end: Some(Position {
+							line: suggestion.end_line,
+							column: Some(suggestion.end_column),
+						}),
+					},
+					text: &suggestion.replacement,
+				})
+				.collect(),
+			original_output: annotation.message.trim(),
+		})
+		.collect();
+	let result = DiagnosticResult {
+		source: Source {
+			name: TOOL_NAME,
+			url: env!("CARGO_PKG_REPOSITORY"),
+		},
+		diagnostics,
+	};
+	serde_json::to_writer_pretty(writer, &result).map_err(io::Error::from)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::report::tests::annotations;
+	use serde_json::json;
+
+	#[test]
+	fn diagnostic_result() {
+		let mut report = Vec::new();
+		write_rdjson(&annotations(), &mut report).unwrap();
+		let location = |line, start_column, end_column| {
+			json!({
+				"path": "lintfix/src/lib.rs",
+				"range": {
+					"start": { "line": line, "column": start_column },
+					"end": { "line": line, "column": end_column },
+				},
+			})
+		};
+		assert_eq!(
+			serde_json::from_slice::<serde_json::Value>(&report).unwrap(),
+			json!({
+				"source": {
+					"name": "cargo-ghannotate",
+					"url": "https://github.com/Dragonink/cargo-ghannotate",
+				},
+				"diagnostics": [
+					{
+						"message": "writing `&Vec` instead of `&[_]` involves a new object where a slice will do [clippy::ptr_arg]",
+						"location": location(1, 15, 24),
+						"severity": "WARNING",
+						"code": { "value": "clippy::ptr_arg" },
+						"original_output": "writing `&Vec` instead of `&[_]` involves a new object where a slice will do",
+					},
+					{
+						"message": "mismatched types",
+						"location": location(5, 20, 23),
+						"severity": "INFO",
+						"original_output": "expected `u32` because of return type",
+					},
+					{
+						"message": "mismatched types [E0308]",
+						"location": location(6, 5, 9),
+						"severity": "ERROR",
+						"code": { "value": "E0308" },
+						"original_output": "mismatched types",
+					},
+				],
+			})
+		);
+	}
+}
@@ -0,0 +1,272 @@
+//! Provides the structs to write SARIF logs
+
+use crate::{
+	github::{Annotation, AnnotationKind},
+	TOOL_NAME,
+};
+use serde::Serialize;
+use std::{
+	collections::BTreeSet,
+	io::{self, Write},
+};
+
+/// SARIF log
+#[derive(Debug, Serialize)]
+struct SarifLog<'a> {
+	/// JSON schema of the log
+	#[serde(rename = "$schema")]
+	schema: &'static str,
+	/// Version of the SARIF format
+	version: &'static str,
+	/// Runs of analysis tools
+	runs: [Run<'a>; 1],
+}
+
+/// Run of an analysis tool
+#[derive(Debug, Serialize)]
+struct Run<'a> {
+	/// Analysis tool
+	tool: Tool<'a>,
+	/// Results of the analysis
+	results: Vec<SarifResult<'a>>,
+}
+
+/// Analysis tool
+#[derive(Debug, Serialize)]
+struct Tool<'a> {
+	/// Main component of the tool
+	driver: ToolComponent<'a>,
+}
+
+/// Component of an analysis tool
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolComponent<'a> {
+	/// Name of the component
+	name: &'static str,
+	/// URL with more information about the component
+	information_uri: &'static str,
+	/// Version of the component
+	version: &'static str,
+	/// Rules checked by the component
+	rules: Vec<Rule<'a>>,
+}
+
+/// Rule checked by an analysis tool
+#[derive(Debug, Serialize)]
+struct Rule<'a> {
+	/// Identifier of the rule
+	id: &'a str,
+}
+
+/// Result of an analysis
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult<'a> {
+	/// Identifier of the violated rule
+	#[serde(skip_serializing_if = "Option::is_none")]
+	rule_id: Option<&'a str>,
+	/// Severity of the result
+	level: &'static str,
+	/// Message of the result
+	message: Message<'a>,
+	/// Locations of the result (none if it is not located in a file)
+	locations: Vec<Location<'a>>,
+	/// Fingerprints of the result, to track it across runs
+	partial_fingerprints: PartialFingerprints,
+}
+
+/// Fingerprints of a [`SarifResult`]
+#[derive(Debug, Serialize)]
+struct PartialFingerprints {
+	/// Fingerprint of the annotation
+	#[serde(rename = "ghannotate/v1")]
+	annotation: String,
+}
+
+/// Message of a [`SarifResult`]
+#[derive(Debug, Serialize)]
+struct Message<'a> {
+	/// Plain text of the message
+	text: &'a str,
+}
+
+/// Location of a [`SarifResult`]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location<'a> {
+	/// Location in a file
+	physical_location: PhysicalLocation<'a>,
+}
+
+/// Location in a file
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation<'a> {
+	/// The file
+	artifact_location: ArtifactLocation<'a>,
+	/// Region of the file
+	region: Region,
+}
+
+/// Location of a file
+#[derive(Debug, Serialize)]
+struct ArtifactLocation<'a> {
+	/// Path to the file, relative to the root of the repository
+	uri: &'a str,
+}
+
+/// Region of a file
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+	/// First line of the region (1-based, inclusive)
+	start_line: usize,
+	/// First column of the region (1-based, inclusive)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	start_column: Option<usize>,
+	/// Last line of the region (1-based, inclusive)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	end_line: Option<usize>,
+	/// End of the columns of the region (1-based, exclusive)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	end_column: Option<usize>,
+}
+
+/// Returns the identifier of the rule violated by `annotation`
+///
+/// Annotations without code are identified by their title.
+fn rule_id<'a>(annotation: &'a Annotation<'_>) -> Option<&'a str> {
+	annotation.code.as_deref().or(annotation.title.as_deref())
+}
+
+/// Writes `annotations` as a SARIF log
+///
+/// # Errors
+/// This function returns an error if the log could not be written.
+pub fn write_sarif(
+	annotations: &BTreeSet<Annotation<'_>>,
+	writer: &mut dyn Write,
+) -> io::Result<()> {
+	let rules: BTreeSet<_> = annotations.iter().filter_map(rule_id).collect();
+	let results = annotations
+		.iter()
+		.map(|annotation| SarifResult {
+			rule_id: rule_id(annotation),
+			level: match annotation.kind {
+				AnnotationKind::Error => "error",
+				AnnotationKind::Warning => "warning",
+				AnnotationKind::Notice => "note",
+			},
+			message: Message {
+				text: annotation.message.trim(),
+			},
+			locations: annotation
+				.is_located()
+				.then(|| Location {
+					physical_location: PhysicalLocation {
+						artifact_location: ArtifactLocation {
+							uri: &annotation.file,
+						},
+						region: Region {
+							start_line: annotation.line,
+							start_column: annotation.col,
+							end_line: annotation.end_line,
+							end_column: annotation.col.and(annotation.end_column),
+						},
+					},
+				})
+				.into_iter()
+				.collect(),
+			partial_fingerprints: PartialFingerprints {
+				annotation: annotation.fingerprint(),
+			},
+		})
+		.collect();
+	let log = SarifLog {
+		schema: "https://json.schemastore.org/sarif-2.1.0.json",
+		version: "2.1.0",
+		runs: [Run {
+			tool: Tool {
+				driver: ToolComponent {
+					name: TOOL_NAME,
+					information_uri: env!("CARGO_PKG_REPOSITORY"),
+					version: env!("CARGO_PKG_VERSION"),
+					rules: rules.into_iter().map(|id| Rule { id }).collect(),
+				},
+			},
+			results,
+		}],
+	};
+	serde_json::to_writer_pretty(writer, &log).map_err(io::Error::from)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::report::tests::annotations;
+	use serde_json::json;
+
+	#[test]
+	fn sarif_log() {
+		let mut log = Vec::new();
+		write_sarif(&annotations(), &mut log).unwrap();
+		let location = |start_line, start_column, end_column| {
+			json!([{
+				"physicalLocation": {
+					"artifactLocation": { "uri": "lintfix/src/lib.rs" },
+					"region": {
+						"startLine": start_line,
+						"startColumn": start_column,
+						"endLine": start_line,
+						"endColumn": end_column,
+					},
+				},
+			}])
+		};
+		assert_eq!(
+			serde_json::from_slice::<serde_json::Value>(&log).unwrap(),
+			json!({
+				"$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+				"version": "2.1.0",
+				"runs": [{
+					"tool": {
+						"driver": {
+							"name": "cargo-ghannotate",
+							"informationUri": "https://github.com/Dragonink/cargo-ghannotate",
+							"version": env!("CARGO_PKG_VERSION"),
+							"rules": [
+								{ "id": "E0308" },
+								{ "id": "clippy::ptr_arg" },
+								{ "id": "mismatched types" },
+							],
+						},
+					},
+					"results": [
+						{
+							"ruleId": "clippy::ptr_arg",
+							"level": "warning",
+							"message": { "text": "writing `&Vec` instead of `&[_]` involves a new object where a slice will do" },
+							"locations": location(1, 15, 24),
+							"partialFingerprints": { "ghannotate/v1": "985994fb683ce722" },
+						},
+						{
+							"ruleId": "mismatched types",
+							"level": "note",
+							"message": { "text": "expected `u32` because of return type" },
+							"locations": location(5, 20, 23),
+							"partialFingerprints": { "ghannotate/v1": "e1d0cd0ee5bbbc8b" },
+						},
+						{
+							"ruleId": "E0308",
+							"level": "error",
+							"message": { "text": "mismatched types" },
+							"locations": location(6, 5, 9),
+							"partialFingerprints": { "ghannotate/v1": "16a3f26475fabb58" },
+						},
+					],
+				}],
+			})
+		);
+	}
+}
@@ -0,0 +1,55 @@
+//! Provides the functions to render the job summary with a user-supplied template
+
+use super::{Counts, JsonAnnotation, RunInfo};
+use crate::github::Annotation;
+use minijinja::Environment;
+use serde::Serialize;
+use std::{collections::BTreeSet, fs, io, path::Path};
+
+/// Context given to the templates of the summary
+#[derive(Debug, Serialize)]
+struct TemplateContext<'a> {
+	/// Annotated command (e.g. `cargo clippy`)
+	command: &'a str,
+	/// Duration of the run, in seconds
+	duration: f64,
+	/// Counts of the annotations, by kind
+	counts: Counts,
+	/// The annotations
+	annotations: Vec<JsonAnnotation<'a>>,
+	/// The annotations of the pre-existing issues
+	preexisting: Vec<JsonAnnotation<'a>>,
+	/// The summary which would have been written without template
+	summary: &'a str,
+}
+
+/// Renders the summary with the [MiniJinja](https://docs.rs/minijinja) template at `path`
+///
+/// The template receives the `annotations` (and the `preexisting` ones), their counts,
+/// information about the `run` and the default `summary`.
+///
+/// # Errors
+/// This function returns an error if the template could not be read, or is invalid.
+pub fn render_summary(
+	path: &Path,
+	annotations: &BTreeSet<Annotation<'_>>,
+	preexisting: &BTreeSet<Annotation<'_>>,
+	run: &RunInfo,
+	summary: &str,
+) -> io::Result<String> {
+	let template = fs::read_to_string(path)?;
+	let mut env = Environment::new();
+	env.add_template("summary", &template)
+		.map_err(io::Error::other)?;
+	let context = TemplateContext {
+		command: &run.command,
+		duration: run.duration.as_secs_f64(),
+		counts: Counts::count(annotations),
+		annotations: annotations.iter().map(From::from).collect(),
+		preexisting: preexisting.iter().map(From::from).collect(),
+		summary,
+	};
+	env.get_template("summary")
+		.and_then(|template| template.render(context))
+		.map_err(io::Error::other)
+}
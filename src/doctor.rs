@@ -0,0 +1,307 @@
+//! Provides the checks of the environment run by the `doctor` subcommand
+
+use crate::{
+	cargo::{Rustfmt, RustfmtSource},
+	ci::CiPlatform,
+	github::GithubClient,
+};
+use clap::ValueEnum;
+use std::{
+	env,
+	ffi::OsStr,
+	fmt::{self, Display, Formatter},
+	fs::{self, File, OpenOptions},
+	io::{self, Write},
+	path::Path,
+	process::{Command, Stdio},
+};
+
+/// Status of a [`Check`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+	/// The environment is fit
+	Ok,
+	/// Some features will not work
+	Warning,
+	/// Annotating will fail or produce nothing
+	Error,
+}
+impl Display for Status {
+	#[inline]
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Ok => "ok",
+			Self::Warning => "warning",
+			Self::Error => "error",
+		})
+	}
+}
+
+/// Result of checking a part of the environment
+#[derive(Debug)]
+struct Check {
+	/// Checked part of the environment (e.g. `Cargo`)
+	name: &'static str,
+	/// Status of the check
+	status: Status,
+	/// What was found (e.g. the version of Cargo)
+	found: String,
+	/// What may be done to fix the environment, if the check did not pass
+	fix: Option<String>,
+}
+impl Check {
+	/// Creates a passed check
+	#[inline]
+	fn ok(name: &'static str, found: impl Into<String>) -> Self {
+		Self {
+			name,
+			status: Status::Ok,
+			found: found.into(),
+			fix: None,
+		}
+	}
+
+	/// Creates a check which did not pass with `status`
+	#[inline]
+	fn failed(
+		name: &'static str,
+		status: Status,
+		found: impl Into<String>,
+		fix: impl Into<String>,
+	) -> Self {
+		Self {
+			name,
+			status,
+			found: found.into(),
+			fix: Some(fix.into()),
+		}
+	}
+}
+impl Display for Check {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "[{}] {}: {}", self.status, self.name, self.found)?;
+		if let Some(fix) = &self.fix {
+			write!(f, "\n      help: {fix}")?;
+		}
+		Ok(())
+	}
+}
+
+/// Environment checked by the `doctor` subcommand
+#[derive(Debug)]
+pub(crate) struct Doctor<'a> {
+	/// Path to the `cargo` executable
+	pub(crate) cargo: &'a OsStr,
+	/// rustfmt with which `cargo fmt` is run
+	pub(crate) rustfmt: &'a Rustfmt,
+	/// CI platform running the job
+	pub(crate) ci: CiPlatform,
+	/// Whether the summary is written where the CI platform expects it
+	pub(crate) summary: bool,
+	/// Paths to the other files to be written (e.g. the reports)
+	pub(crate) outputs: Vec<&'a Path>,
+	/// Permissions of the token required by the requested features of the GitHub API (e.g. `checks: write`)
+	pub(crate) permissions: Vec<&'static str>,
+	/// Path to a bundle of PEM certificates to trust when calling the GitHub API
+	pub(crate) ca_bundle: Option<&'a Path>,
+}
+impl Doctor<'_> {
+	/// Checks the environment and writes the results, with the fixes of the failed checks
+	///
+	/// Returns `true` if a check failed with an error.
+	///
+	/// # Errors
+	/// This function returns an error if the results could not be written.
+	pub(crate) fn diagnose(&self, writer: &mut dyn Write) -> io::Result<bool> {
+		let mut checks = vec![self.check_cargo(), check_rustup(), self.check_rustfmt()];
+		if self.ci == CiPlatform::Github {
+			checks.push(check_workspace());
+		}
+		if self.summary {
+			checks.push(self.check_summary());
+		}
+		checks.extend(
+			self.outputs
+				.iter()
+				.map(|path| check_writable("Output", path)),
+		);
+		if !self.permissions.is_empty() {
+			checks.push(self.check_token());
+		}
+
+		if let Some(ci) = self.ci.to_possible_value() {
+			writeln!(writer, "CI platform: {}", ci.get_name())?;
+		}
+		for check in &checks {
+			writeln!(writer, "{check}")?;
+		}
+		Ok(checks.iter().any(|check| check.status == Status::Error))
+	}
+
+	/// Checks that Cargo can be invoked
+	fn check_cargo(&self) -> Check {
+		match version(Command::new(self.cargo).arg("--version")) {
+			Some(version) => Check::ok("Cargo", version),
+			None => Check::failed(
+				"Cargo",
+				Status::Error,
+				format!("`{}` could not be invoked", self.cargo.to_string_lossy()),
+				"Install Rust (e.g. with `dtolnay/rust-toolchain`), or give the path to Cargo with `--cargo`.",
+			),
+		}
+	}
+
+	/// Checks that the rustfmt selected for `cargo fmt` can be invoked
+	fn check_rustfmt(&self) -> Check {
+		if let Some(version) = &self.rustfmt.version {
+			return Check::ok("rustfmt", version.clone());
+		}
+		match &self.rustfmt.source {
+			RustfmtSource::Executable(executable) => Check::failed(
+				"rustfmt",
+				Status::Warning,
+				format!("`{}` could not be invoked", executable.display()),
+				"Check the path given with `--rustfmt` to use the `fmt` subcommand.",
+			),
+			RustfmtSource::Toolchain(toolchain) => Check::failed(
+				"rustfmt",
+				Status::Warning,
+				format!("no `{toolchain}` toolchain with `rustfmt`"),
+				format!("Install it with `rustup toolchain install {toolchain} --component rustfmt` to use the `fmt` subcommand."),
+			),
+			RustfmtSource::Cargo(cargo) => Check::failed(
+				"rustfmt",
+				Status::Warning,
+				format!("`{} fmt` could not be invoked", cargo.to_string_lossy()),
+				"Install rustfmt along with Cargo, or give the path to its executable with `--rustfmt`, to use the `fmt` subcommand.",
+			),
+		}
+	}
+
+	/// Checks that the summary file is set and writable
+	fn check_summary(&self) -> Check {
+		match self.ci.summary_path() {
+			Some(path) => check_writable("Summary", &path),
+			None if self.ci == CiPlatform::Github => Check::failed(
+				"Summary",
+				Status::Error,
+				"`GITHUB_STEP_SUMMARY` is not set",
+				"Run this program in a step of a GitHub Actions job, or pass `--no-summary`.",
+			),
+			None => Check::ok("Summary", "not written on this CI platform"),
+		}
+	}
+
+	/// Checks that the token gives access to the repository with the required permissions
+	fn check_token(&self) -> Check {
+		let permissions = self.permissions.join(", ");
+		let client = match GithubClient::from_env(self.ca_bundle) {
+			Ok(client) => client,
+			Err(err) => {
+				return Check::failed(
+					"GitHub token",
+					Status::Error,
+					err.to_string(),
+					format!(
+						"Set `GITHUB_TOKEN: ${{{{ secrets.GITHUB_TOKEN }}}}` in the `env` of the step, and grant `permissions` {permissions} to the job."
+					),
+				);
+			}
+		};
+		match client.scopes() {
+			Ok(None) => Check::ok(
+				"GitHub token",
+				format!("the repository is accessible (the job must be granted {permissions})"),
+			),
+			Ok(Some(scopes))
+				if scopes
+					.iter()
+					.any(|scope| scope == "repo" || scope == "public_repo") =>
+			{
+				Check::ok("GitHub token", format!("scopes {}", scopes.join(", ")))
+			}
+			Ok(Some(scopes)) => Check::failed(
+				"GitHub token",
+				Status::Error,
+				format!("scopes {}", scopes.join(", ")),
+				"Give the `repo` scope (or `public_repo` for a public repository) to the token.",
+			),
+			Err(err) => Check::failed(
+				"GitHub token",
+				Status::Error,
+				err.to_string(),
+				"Check that the token is valid and gives access to the repository, or pass `--ca-bundle` behind a proxy.",
+			),
+		}
+	}
+}
+
+/// Returns the version output by `command`, if it succeeded
+fn version(command: &mut Command) -> Option<String> {
+	let output = command
+		.stdin(Stdio::null())
+		.stderr(Stdio::null())
+		.output()
+		.ok()?;
+	output.status.success().then(|| {
+		String::from_utf8_lossy(&output.stdout)
+			.lines()
+			.next()
+			.unwrap_or_default()
+			.to_owned()
+	})
+}
+
+/// Checks that `rustup` can be invoked
+fn check_rustup() -> Check {
+	match version(Command::new("rustup").arg("--version")) {
+		Some(version) => Check::ok("rustup", version),
+		None => Check::failed(
+			"rustup",
+			Status::Warning,
+			"`rustup` could not be invoked",
+			"Install rustup from https://rustup.rs to use the `msrv`, `miri` and `udeps` subcommands.",
+		),
+	}
+}
+
+/// Checks that the workspace of GitHub Actions is set, as the annotated paths are made relative to it
+fn check_workspace() -> Check {
+	match env::var_os("GITHUB_WORKSPACE") {
+		Some(workspace) if Path::new(&workspace).is_dir() => {
+			Check::ok("Workspace", Path::new(&workspace).display().to_string())
+		}
+		Some(workspace) => Check::failed(
+			"Workspace",
+			Status::Error,
+			format!("`{}` is not a directory", workspace.to_string_lossy()),
+			"Check out the repository with `actions/checkout` before this step.",
+		),
+		None => Check::failed(
+			"Workspace",
+			Status::Warning,
+			"`GITHUB_WORKSPACE` is not set, the paths are made relative to the current directory",
+			"Run this program in a step of a GitHub Actions job, from the root of the repository.",
+		),
+	}
+}
+
+/// Checks that the file at `path` can be written, without changing it
+///
+/// A file which does not exist yet is created and removed.
+fn check_writable(name: &'static str, path: &Path) -> Check {
+	let result = if path.exists() {
+		OpenOptions::new().append(true).open(path).map(drop)
+	} else {
+		File::create_new(path).and_then(|_| fs::remove_file(path))
+	};
+	match result {
+		Ok(()) => Check::ok(name, format!("`{}` is writable", path.display())),
+		Err(err) => Check::failed(
+			name,
+			Status::Error,
+			format!("`{}` is not writable: {err}", path.display()),
+			"Check that the directory exists and that the user of the job may write to it.",
+		),
+	}
+}
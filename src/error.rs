@@ -0,0 +1,95 @@
+//! Provides the error type of the failures of this program
+
+use crate::github::{Annotation, AnnotationKind};
+use std::{
+	borrow::Cow,
+	error::Error as StdError,
+	fmt::{self, Display, Formatter},
+	io::{self, ErrorKind},
+};
+
+/// Failure of this program itself, as opposed to the issues it annotates
+#[derive(Debug)]
+pub(crate) struct Error {
+	/// What failed (e.g. `Cargo invocation failed`), absent if the cause tells enough
+	context: Option<&'static str>,
+	/// Cause of the failure
+	source: io::Error,
+}
+impl Error {
+	/// Creates an error caused by `source` while doing what `context` describes
+	#[inline]
+	pub(crate) fn new(context: &'static str, source: impl Into<io::Error>) -> Self {
+		Self {
+			context: Some(context),
+			source: source.into(),
+		}
+	}
+
+	/// Returns what may be done to fix the failure, if anything
+	pub(crate) fn hint(&self) -> Option<&'static str> {
+		let invocation = self
+			.context
+			.is_some_and(|context| context.contains("invocation"));
+		Some(match self.source.kind() {
+			ErrorKind::NotFound if invocation => {
+				"Check that the program is installed and in the `PATH`, or give the path to Cargo with `--cargo`."
+			}
+			ErrorKind::NotFound => "Check that the file exists and that the path is relative to the current directory.",
+			ErrorKind::PermissionDenied => "Check the permissions of the file or program.",
+			ErrorKind::InvalidData | ErrorKind::UnexpectedEof => {
+				"Check that the file is valid, or write it again."
+			}
+			ErrorKind::AlreadyExists => "Choose another path, or overwrite the file with `--force`.",
+			ErrorKind::BrokenPipe => "Check that the output of this program is not closed early (e.g. by `head`).",
+			_ => return None,
+		})
+	}
+
+	/// Converts the error into an annotation, so that the failure is shown in the CI platform
+	pub(crate) fn to_annotation(&self) -> Annotation<'static> {
+		let mut message = self.to_string();
+		if let Some(hint) = self.hint() {
+			message.push_str("\n\n");
+			message.push_str(hint);
+		}
+		Annotation {
+			kind: AnnotationKind::Error,
+			file: Cow::Borrowed(""),
+			line: 0,
+			end_line: None,
+			col: None,
+			end_column: None,
+			code: None,
+			suggestions: Vec::new(),
+			title: Some(Cow::Borrowed(concat!(env!("CARGO_PKG_NAME"), " failed"))),
+			message: Cow::Owned(message),
+		}
+	}
+}
+impl Display for Error {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		if let Some(context) = self.context {
+			write!(f, "{context}: ")?;
+		}
+		write!(f, "{}", self.source)
+	}
+}
+impl StdError for Error {
+	#[inline]
+	fn source(&self) -> Option<&(dyn StdError + 'static)> {
+		Some(&self.source)
+	}
+}
+impl From<io::Error> for Error {
+	#[inline]
+	fn from(source: io::Error) -> Self {
+		Self {
+			context: None,
+			source,
+		}
+	}
+}
+
+/// Result of an operation which may fail with an [`Error`]
+pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,113 @@
+//! Provides the generation of a GitHub Actions workflow by the `init` subcommand
+
+use crate::cargo::Metadata;
+use std::io::{self, Write};
+
+/// Names of the configuration files of Clippy
+const CLIPPY_CONFIGS: [&str; 2] = ["clippy.toml", ".clippy.toml"];
+/// Names of the configuration files of rustfmt
+const RUSTFMT_CONFIGS: [&str; 2] = ["rustfmt.toml", ".rustfmt.toml"];
+
+/// Steps of the workflow, chosen after the contents of the workspace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Workflow {
+	/// Whether `cargo fmt` is run, as rustfmt is configured
+	fmt: bool,
+	/// Whether `cargo clippy` is run instead of `cargo check`, as Clippy is configured
+	clippy: bool,
+	/// Whether `cargo test` is run, as the workspace has tests
+	test: bool,
+	/// Whether the minimum supported Rust version is checked, as it is declared
+	msrv: bool,
+	/// Whether the workspace has several members
+	workspace: bool,
+}
+impl Workflow {
+	/// Chooses the steps of the workflow after the contents of the workspace described by `metadata`
+	pub(crate) fn inspect(metadata: &Metadata) -> Self {
+		let has_file = |names: [&str; 2]| {
+			names
+				.iter()
+				.any(|name| metadata.workspace_root.join(name).is_file())
+		};
+		Self {
+			fmt: has_file(RUSTFMT_CONFIGS),
+			clippy: has_file(CLIPPY_CONFIGS),
+			test: metadata.packages.iter().any(|package| {
+				package
+					.targets
+					.iter()
+					.any(|target| target.kind.iter().any(|kind| kind == "test"))
+					|| package
+						.manifest_path
+						.parent()
+						.is_some_and(|root| root.join("tests").is_dir())
+			}),
+			msrv: metadata.rust_version().is_some(),
+			workspace: metadata.packages.len() > 1,
+		}
+	}
+
+	/// Returns the commands annotated by the workflow, in order
+	fn commands(&self) -> Vec<String> {
+		let workspace = if self.workspace { " --workspace" } else { "" };
+		let mut commands = Vec::new();
+		if self.fmt {
+			commands.push("fmt".to_owned());
+		}
+		if self.clippy {
+			commands.push(format!("clippy{workspace} --all-targets"));
+		} else {
+			commands.push(format!("check{workspace} --all-targets"));
+		}
+		if self.test {
+			commands.push(format!("test{workspace}"));
+		}
+		if self.msrv {
+			commands.push(format!("msrv{workspace}"));
+		}
+		commands
+	}
+
+	/// Writes the workflow, running each command with the action of this program
+	///
+	/// # Errors
+	/// This function returns an error if the workflow could not be written.
+	pub(crate) fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+		writeln!(writer, "# Generated by `cargo ghannotate init`")?;
+		writeln!(writer, "name: Rust")?;
+		writeln!(writer, "on:")?;
+		writeln!(writer, "  push:")?;
+		writeln!(writer, "  pull_request:")?;
+		writeln!(writer, "permissions:")?;
+		writeln!(writer, "  contents: read")?;
+		writeln!(writer, "jobs:")?;
+		writeln!(writer, "  ghannotate:")?;
+		writeln!(writer, "    runs-on: ubuntu-latest")?;
+		writeln!(writer, "    steps:")?;
+		writeln!(writer, "      - uses: actions/checkout@v4")?;
+		if self.fmt {
+			// The nightly toolchain is installed first, so that the stable one is the default
+			writeln!(writer, "      - uses: dtolnay/rust-toolchain@nightly")?;
+			writeln!(writer, "        with:")?;
+			writeln!(writer, "          components: rustfmt")?;
+		}
+		writeln!(writer, "      - uses: dtolnay/rust-toolchain@stable")?;
+		if self.clippy {
+			writeln!(writer, "        with:")?;
+			writeln!(writer, "          components: clippy")?;
+		}
+		for command in self.commands() {
+			writeln!(writer, "      - uses: Dragonink/cargo-ghannotate@v1")?;
+			// The following steps still run, so that all the issues are annotated at once
+			writeln!(writer, "        if: ${{{{ !cancelled() }}}}")?;
+			writeln!(writer, "        with:")?;
+			writeln!(writer, "          command: {command}")?;
+			writeln!(
+				writer,
+				"          GITHUB_TOKEN: ${{{{ secrets.GITHUB_TOKEN }}}}"
+			)?;
+		}
+		Ok(())
+	}
+}
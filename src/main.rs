@@ -8,13 +8,11 @@
 	unused,
 	unused_crate_dependencies,
 	unused_lifetimes,
-	unused_tuple_struct_fields,
 	clippy::dbg_macro,
 	clippy::empty_structs_with_brackets,
 	clippy::enum_glob_use,
 	clippy::float_cmp_const,
 	clippy::format_push_string,
-	clippy::match_on_vec_items,
 	clippy::missing_docs_in_private_items,
 	clippy::mod_module_files,
 	clippy::option_option,
@@ -50,8 +48,6 @@
 	clippy::suboptimal_flops,
 )]
 #![deny(
-	// Correctness
-	pointer_structural_match,
 	// Restriction
 	keyword_idents,
 	non_ascii_idents,
@@ -64,156 +60,1823 @@
 )]
 #![forbid(unsafe_code)]
 
-use clap::{Args, Parser, Subcommand, ValueHint};
+use clap::{ArgAction, Args, CommandFactory, Parser, Subcommand, ValueEnum, ValueHint};
+use clap_complete::Shell;
+use ghannotate_core::{cargo, ci, config, git, github, process, report};
 use std::{
+	borrow::Cow,
+	cell::Cell,
 	collections::BTreeSet,
-	ffi::OsString,
-	fs::File,
-	io::{self, BufRead, Cursor, Write as IoWrite},
-	process::{Command, ExitCode, Output, Stdio},
+	env,
+	ffi::{OsStr, OsString},
+	fmt::{self, Display, Formatter},
+	fs::{self, File},
+	io::{self, BufRead, IsTerminal, Read, Write as IoWrite},
+	path::{Path, PathBuf},
+	process::{Command, ExitCode, ExitStatus, Stdio},
+	thread,
+	time::{Duration, Instant, SystemTime},
 };
 
-/// Environment variable containing the path to the special summary file
-const SUMMARY_PATH_VAR: &str = "GITHUB_STEP_SUMMARY";
-/// Path to the summary file used in debug contexts
-const DEBUG_SUMMARY_PATH: &str = "SUMMARY.md";
-
-mod cargo;
-mod github;
+mod doctor;
+mod error;
+mod init;
 
 use cargo::{
-	Diagnostic, DiagnosticSummaryWriter, FormatMismatchSummaryWriter, FormatMismatches,
-	HandleMessage, SummaryWriter,
+	criterion_benchmarks, run_feature_sets, AdvisorySummaryWriter, AuditReport, BenchmarkResult,
+	BenchmarkSummaryWriter, CargoMessage, CoverageReport, CoverageSummaryWriter, CustomParser,
+	DenyMessage, DenySummaryWriter, Diagnostic, DiagnosticSummaryWriter, FeatureSetMessage,
+	FeatureSetSummaryWriter, FixPreview, FixSummaryWriter, FormatDiffParser,
+	FormatMismatchSummaryWriter, FormatMismatches, GeigerReport, GeigerSummaryWriter,
+	HandleMessage, MacheteReportParser, MessageStyle, Metadata, MiriMessage, MiriReportParser,
+	MsrvFailure, MsrvMessage, Options, OutdatedReport, OutdatedSummaryWriter, ReportedAnnotation,
+	RustdocSummaryWriter, Rustfmt, SemverReportParser, SemverSummaryWriter, SummaryWriter,
+	TestMessage, TestSummaryWriter, UdepsReport, UnusedDependencySummaryWriter,
+};
+use ci::CiPlatform;
+use config::{Config, PathMap};
+use doctor::Doctor;
+use error::Error;
+use git::ChangedLines;
+use github::{
+	Annotation, AnnotationKind, CheckRun, CommitStatus, GithubClient, PullRequestReview, SortOrder,
+	StickyComment,
 };
-use github::AnnotationKind;
+use init::Workflow;
+use process::{CommandOutput, Timeout};
+use report::{Baseline, Emit, ReportFormat, RunInfo};
+use tracing::{debug, info_span, trace, Event, Subscriber};
+use tracing_subscriber::{
+	filter::LevelFilter,
+	fmt::format::FmtSpan,
+	layer::{Context, SubscriberExt},
+	util::SubscriberInitExt,
+	EnvFilter, Layer,
+};
+
+/// Exit code of the runs which raised no annotation reaching the threshold
+const EXIT_CLEAN: u8 = 0;
+/// Exit code of the runs which raised annotations reaching the threshold
+const EXIT_FINDINGS: u8 = 1;
+/// Exit code of the runs which failed (e.g. Cargo could not be invoked), like the invalid command lines
+const EXIT_FAILURE: u8 = 2;
+
+/// Delay before the first retry of a Cargo command which failed because of the network, doubled at each retry
+const RETRY_DELAY: Duration = Duration::from_secs(5);
 
 fn main() -> ExitCode {
-	let cli = Cli::parse_from(std::env::args_os().filter(|arg| arg != "ghannotate"));
+	let cli = Cli::parse_from(env::args_os().filter(|arg| arg != "ghannotate"));
+	let ci = cli.ci.unwrap_or_else(CiPlatform::detect);
+	init_logging(cli.verbose, ci);
+	match run(cli) {
+		Ok(code) => ExitCode::from(code),
+		Err(err) => {
+			eprintln!("error: {err}");
+			if let Some(hint) = err.hint() {
+				eprintln!("help: {hint}");
+			}
+			// The failure is also shown in the CI platform, as the issues would be
+			let _ = ci.write_annotation(&err.to_annotation(), &mut io::stdout());
+			ExitCode::from(EXIT_FAILURE)
+		}
+	}
+}
 
-	let annotation_threshold = if cli.allow_warnings {
-		AnnotationKind::Error
-	} else {
-		AnnotationKind::Warning
+/// Logs on stderr according to the `verbose` level, or to the `RUST_LOG` environment variable if it is set
+///
+/// Nothing is logged by default. The time spent in each phase is logged when it ends.
+/// On GitHub Actions with the debug logging of the runner enabled,
+/// the debug messages (e.g. why lines or annotations are skipped) are also written as `::debug::` workflow commands.
+fn init_logging(verbose: u8, ci: CiPlatform) {
+	let filter = match env::var("RUST_LOG") {
+		Ok(directives) if !directives.is_empty() => Some(EnvFilter::new(directives)),
+		_ => match verbose {
+			0 => None,
+			1 => Some(EnvFilter::new("debug")),
+			_ => Some(EnvFilter::new("trace")),
+		},
 	};
-	let mut max_annotation = AnnotationKind::Notice;
+	let stderr = filter.map(|filter| {
+		tracing_subscriber::fmt::layer()
+			.with_writer(io::stderr)
+			.with_ansi(io::stderr().is_terminal())
+			.with_span_events(FmtSpan::CLOSE)
+			.with_filter(filter)
+	});
+	let runner_debug = (ci == CiPlatform::Github && github::is_runner_debug())
+		.then(|| DebugLayer.with_filter(LevelFilter::DEBUG));
+	if stderr.is_some() || runner_debug.is_some() {
+		tracing_subscriber::registry()
+			.with(stderr)
+			.with(runner_debug)
+			.init();
+	}
+}
+
+/// Layer writing the logged events as `::debug::` workflow commands (see [`github::write_debug`])
+#[derive(Debug, Default, Clone, Copy)]
+struct DebugLayer;
+impl<S: Subscriber> Layer<S> for DebugLayer {
+	#[inline]
+	fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+		github::write_debug(event);
+	}
+}
+
+/// Returns a function adding `context` to an error (e.g. `Cargo invocation failed: No such file or directory`)
+fn context<E: Into<io::Error>>(context: &'static str) -> impl FnOnce(E) -> Error {
+	move |err| Error::new(context, err)
+}
 
-	let cargo = cli.invoke_cargo().expect("Cargo invocation failed");
+/// Runs the program with the arguments of the command line
+///
+/// Returns the exit code of the program.
+///
+/// # Errors
+/// This function returns an error if the program failed (e.g. Cargo could not be invoked).
+fn run(mut cli: Cli) -> error::Result<u8> {
+	match cli.command {
+		CliCommand::Completions(ref args) => {
+			// The completions are buffered, as their generator panics if they cannot be written
+			let mut completions = Vec::new();
+			clap_complete::generate(
+				args.shell,
+				&mut Cli::command(),
+				env!("CARGO_PKG_NAME"),
+				&mut completions,
+			);
+			io::stdout()
+				.write_all(&completions)
+				.map_err(context("Completions could not be written"))?;
+			return Ok(EXIT_CLEAN);
+		}
+		CliCommand::Manpage => {
+			clap_mangen::Man::new(Cli::command())
+				.render(&mut io::stdout())
+				.map_err(context("Manual page could not be written"))?;
+			return Ok(EXIT_CLEAN);
+		}
+		_ => {}
+	}
+	let setup = info_span!("setup").entered();
+	cli.config = Config::load(cli.config_path.as_deref())
+		.map_err(context("Configuration could not be loaded"))?;
+	// The rewritings given on the command line take precedence over those of the configuration
+	cli.config.path_map.splice(0..0, cli.path_map.drain(..));
+	cli.annotation_level = cli.annotation_level.or(cli.config.annotation_level);
+	if let Some(path) = cli.report_json.take() {
+		cli.emit.push(Emit {
+			format: ReportFormat::Json,
+			path,
+		});
+	}
+	cli.config.ignore_lints.append(&mut cli.ignore_lint);
+	cli.config
+		.exclude
+		.extend(Config::load_ignore_file().map_err(context("Ignore file could not be loaded"))?);
+	// The toolchain given on the command line takes precedence over that of the configuration
+	cli.toolchain = cli.toolchain.take().or(cli.config.toolchain.take());
+	cli.cargo = match (cli.cargo_path.take(), &cli.toolchain) {
+		(Some(cargo), _) => cargo,
+		(None, Some(toolchain)) => {
+			// The proxies of rustup run the tools of this toolchain, as `rustup run` does,
+			// even when they are invoked by Cargo or by its subcommands (e.g. `cargo hack`)
+			env::set_var("RUSTUP_TOOLCHAIN", toolchain);
+			OsString::from("cargo")
+		}
+		// Cargo sets this variable when it runs this program as `cargo ghannotate`,
+		// to the executable of its own toolchain, which would ignore the selected one
+		(None, None) => env::var_os("CARGO").ok_or_else(|| {
+			Error::new(
+				"Cargo could not be found",
+				io::Error::new(
+					io::ErrorKind::InvalidInput,
+					"give its path with `--cargo` or `CARGO`, or select a toolchain with `--toolchain`",
+				),
+			)
+		})?,
+	};
+	// The rustfmt given on the command line takes precedence over that of the configuration
+	if cli.rustfmt_path.is_none() && cli.fmt_toolchain.is_none() {
+		cli.rustfmt_path = cli.config.rustfmt.take();
+		cli.fmt_toolchain = cli.config.fmt_toolchain.take();
+	}
+	// rustfmt is invoked for its version once, even if `cargo fmt` is run again
+	if matches!(cli.command, CliCommand::Fmt(_) | CliCommand::Doctor)
+		|| matches!(cli.command, CliCommand::Ci(ref args) if args.steps.contains(&CiStep::Fmt))
+	{
+		cli.rustfmt = Some(cli.resolve_rustfmt());
+	}
+	cli.rustc_bootstrap |= cli.config.rustc_bootstrap;
+	// Cargo is invoked for its version once, even if `cargo test` is run again
+	if matches!(cli.command, CliCommand::Test(_))
+		|| matches!(cli.command, CliCommand::Ci(ref args) if args.steps.contains(&CiStep::Test))
+	{
+		cli.nightly = cargo::is_nightly_cargo(&cli.cargo);
+	}
+	match cli.command {
+		CliCommand::Doctor => {
+			drop(setup);
+			return diagnose(&cli);
+		}
+		CliCommand::Init(ref args) => {
+			drop(setup);
+			return init(&cli.cargo, args);
+		}
+		_ => {}
+	}
+	cli.config.exclude.append(&mut cli.exclude);
+	cli.config.lints.extend(
+		cli.deny_lint
+			.drain(..)
+			.map(|code| (code, AnnotationKind::Error)),
+	);
+	if cli.dry_run {
+		// A dry run has no side effect
+		cli.checks = false;
+		cli.pr_review = false;
+		cli.pr_comment = false;
+		cli.commit_status = false;
+		cli.no_summary = true;
+		cli.summary_path.clear();
+		cli.emit.clear();
+		cli.write_baseline = None;
+		cli.ratchet = None;
+	}
+	cli.options = Options {
+		child_notices: cli.child_notices,
+		meta_diagnostics: cli.meta_diagnostics || cli.config.meta_diagnostics,
+		summary_snippets: cli.summary_snippets || cli.config.summary_snippets,
+		summary_charts: cli.summary_charts || cli.config.summary_charts,
+		message_style: cli
+			.message_style
+			.or(cli.config.message_style)
+			.unwrap_or_default(),
+		sort_order: cli.sort.or(cli.config.sort),
+		lint_levels: cli.config.lint_levels(),
+		workspace: None,
+	};
+	// The workspace is only inspected for the subcommands which need it, once for all their steps
+	if cli.command.needs_metadata() {
+		match Metadata::load(&cli.cargo, cli.command.as_ref().manifest_path(), cli.quiet) {
+			Ok(metadata) => cli.options.workspace = Some(metadata),
+			// Outside of a workspace, all the diagnostics are annotated
+			Err(err) => cli.metadata_error = Some((err.kind(), err.to_string())),
+		}
+	}
+
+	if cli.changed_only {
+		match github::base_revision().and_then(|base| ChangedLines::load(base.as_ref(), cli.quiet))
+		{
+			Ok(changed_lines) => cli.changed_lines = Some(changed_lines),
+			Err(err) => {
+				eprintln!(
+					"Changed lines could not be computed, annotating all the lines instead: {err}"
+				);
+			}
+		}
+	}
+	let mut baseline = cli
+		.baseline
+		.as_deref()
+		.map(Baseline::load)
+		.transpose()
+		.map_err(context("Baseline could not be loaded"))?;
 	let mut annotations_buf = BTreeSet::new();
-	let mut stdout = io::stdout().lock();
-	let mut summary_content = String::new();
-	/// Common code for all messages
-	macro_rules! handle_message {
-		($parse:expr, $summary_writer:ty) => {{
-			let mut summary_writer = <$summary_writer>::default();
-			for line in Cursor::new(cargo.stdout).lines() {
-				let line = line.unwrap();
-				let line = line.as_str();
-				if let Ok(message) = $parse(line) {
-					let summaries = message.summarize();
-					let mut write_summaries = false;
-					for annotation in message.into_annotations() {
-						if annotations_buf.insert(annotation.to_owned()) {
-							writeln!(stdout, "{annotation}").unwrap();
-							max_annotation = max_annotation.max(annotation.kind);
-							write_summaries = true;
-						}
-					}
-					if write_summaries {
-						summaries.into_iter().for_each(|summary| {
-							summary_writer
-								.write_summary(summary, &mut summary_content)
-								.unwrap();
-						});
-					}
-				}
+	let mut unemitted_buf = BTreeSet::new();
+	let mut preexisting_buf = BTreeSet::new();
+	// An unknown parser is reported before running the command
+	if let CliCommand::Exec(ExecArgs {
+		parser: Some(ref name),
+		..
+	})
+	| CliCommand::Replay(InputArgs {
+		parser: Some(ref name),
+		..
+	}) = cli.command
+	{
+		cli.parser(name)?;
+	}
+	let ci = *cli.ci.get_or_insert_with(CiPlatform::detect);
+	let check_run = if cli.checks {
+		let check_run = GithubClient::from_env(cli.ca_bundle.as_deref())
+			.and_then(|client| CheckRun::start(client, &format!("cargo {}", cli.command.name())));
+		match check_run {
+			Ok(check_run) => Some(check_run),
+			Err(err) => {
+				eprintln!("Check run could not be started, using workflow commands instead: {err}");
+				None
 			}
-			if let Some(mut file) = std::env::var_os(SUMMARY_PATH_VAR)
-				.or(cfg!(debug_assertions).then(|| OsString::from(DEBUG_SUMMARY_PATH)))
-				.and_then(|path| File::create(path).ok())
-			{
-				summary_writer.write_preamble(&mut file).unwrap();
-				file.write_all(summary_content.as_bytes()).unwrap();
-				summary_writer.write_postamble(&mut file).unwrap();
+		}
+	} else {
+		None
+	};
+	cli.checks = check_run.is_some();
+	let summary_file = (!cli.no_summary)
+		.then(|| ci.summary_path())
+		.flatten()
+		.and_then(|path| File::create(&path).ok().map(|file| (path, file)));
+	let summary_path = summary_file.as_ref().map(|(path, _)| path.clone());
+	let mut summary = Vec::new();
+
+	drop(setup);
+
+	let start_time = Instant::now();
+	let max_annotation = cli.annotate(
+		&cli.command,
+		&mut annotations_buf,
+		&mut unemitted_buf,
+		&mut preexisting_buf,
+		baseline.as_mut(),
+		&mut summary,
+	)?;
+	let run = RunInfo {
+		command: format!("cargo {}", cli.command.name()),
+		duration: start_time.elapsed(),
+	};
+	let _report = info_span!("report").entered();
+	if let Some(path) = &cli.write_baseline {
+		report::write_baseline(
+			path,
+			annotations_buf
+				.iter()
+				.chain(&unemitted_buf)
+				.chain(&preexisting_buf),
+		)
+		.map_err(context("Baseline could not be written"))?;
+	}
+	github::write_preexisting_annotations(&preexisting_buf, &mut summary)
+		.map_err(context("Summary could not be written"))?;
+	let warnings = annotations_buf
+		.iter()
+		.chain(&unemitted_buf)
+		.filter(|annotation| annotation.kind == AnnotationKind::Warning)
+		.count();
+	let max_warnings = cli.max_warnings.or(cli.config.max_warnings);
+	if let Some(max_warnings) = max_warnings {
+		writeln!(
+			summary,
+			"> **WARNING BUDGET:** {warnings} {}s out of {max_warnings} allowed\n",
+			AnnotationKind::Warning
+		)
+		.map_err(context("Summary could not be written"))?;
+	}
+	let ratchet_increased = cli
+		.ratchet
+		.as_deref()
+		.map(|path| {
+			report::ratchet(
+				path,
+				annotations_buf.iter().chain(&unemitted_buf),
+				&mut summary,
+			)
+		})
+		.transpose()
+		.map_err(context("Ratchet could not be updated"))?;
+	let annotation_threshold = annotation_threshold(
+		cli.allow_warnings
+			|| cli.config.allow_warnings
+			|| matches!(&cli.command, CliCommand::Outdated(args) if !args.deny_outdated),
+		ratchet_increased,
+		max_warnings,
+		warnings,
+	);
+	if (ci == CiPlatform::Github || cli.dry_run) && !cli.checks {
+		github::write_truncated_messages(&annotations_buf, &mut summary)
+			.map_err(context("Summary could not be written"))?;
+	}
+	if let Some(path) = cli
+		.summary_template
+		.as_ref()
+		.or(cli.config.summary_template.as_ref())
+	{
+		summary = report::render_summary(
+			path,
+			&annotations_buf,
+			&preexisting_buf,
+			&run,
+			&String::from_utf8_lossy(&summary),
+		)
+		.map_err(context("Summary template could not be rendered"))?
+		.into_bytes();
+	}
+	if ci == CiPlatform::Github && !cli.dry_run {
+		github::write_outputs(annotations_buf.iter().chain(&unemitted_buf))
+			.map_err(context("Step outputs could not be written"))?;
+	}
+	for path in &cli.summary_path {
+		fs::write(path, &summary).map_err(context("Summary could not be written"))?;
+	}
+	// The preview of a dry run tells whether the summary would be truncated
+	if ci == CiPlatform::Github && !cli.dry_run {
+		github::truncate_summary(&mut summary);
+	}
+	if let Some((_, mut file)) = summary_file {
+		file.write_all(&summary)
+			.map_err(context("Summary could not be written"))?;
+	}
+	if let Some(check_run) = check_run {
+		if let Err(err) = check_run.complete(
+			&annotations_buf,
+			&String::from_utf8_lossy(&summary),
+			annotation_threshold,
+		) {
+			eprintln!("Check run could not be completed, using workflow commands instead: {err}");
+			let mut stdout = io::stdout().lock();
+			for annotation in &annotations_buf {
+				ci.write_annotation(annotation, &mut stdout)
+					.map_err(context("Annotation could not be written"))?;
 			}
-		}};
+		}
 	}
-	match cli.command {
-		CliCommand::Check(_) | CliCommand::Clippy(_) | CliCommand::Build(_) => {
-			handle_message!(serde_json::from_str::<Diagnostic>, DiagnosticSummaryWriter);
+	if cli.pr_review {
+		let review = GithubClient::from_env(cli.ca_bundle.as_deref()).and_then(|client| {
+			match PullRequestReview::new(&client, &annotations_buf)? {
+				Some((number, review)) => review.post(&client, number),
+				None => Ok(()),
+			}
+		});
+		if let Err(err) = review {
+			eprintln!("Pull request review could not be posted: {err}");
 		}
-		CliCommand::Fmt(_) => {
-			handle_message!(
-				serde_json::from_str::<Vec<FormatMismatches>>,
-				FormatMismatchSummaryWriter
-			);
+	}
+	if cli.pr_comment {
+		let comment = StickyComment::new(
+			&format!("cargo {}", cli.command.name()),
+			&String::from_utf8_lossy(&summary),
+		);
+		if let Err(err) = GithubClient::from_env(cli.ca_bundle.as_deref())
+			.and_then(|client| comment.post(&client))
+		{
+			eprintln!("Pull request comment could not be posted: {err}");
+		}
+	}
+	if cli.commit_status {
+		let status = CommitStatus::new(
+			cli.command.name(),
+			&annotations_buf,
+			max_annotation,
+			annotation_threshold,
+		);
+		if let Err(err) =
+			GithubClient::from_env(cli.ca_bundle.as_deref()).and_then(|client| status.post(&client))
+		{
+			eprintln!("Commit status could not be set: {err}");
 		}
 	}
+	if cli.dry_run {
+		github::write_preview(&annotations_buf, &summary, &mut io::stdout())
+			.map_err(context("Preview could not be written"))?;
+	} else {
+		ci.write_report(&annotations_buf, &mut io::stdout())
+			.map_err(context("Report could not be written"))?;
+	}
+	if let Some(path) = &summary_path {
+		let outcome = (max_annotation > AnnotationKind::Notice || !annotations_buf.is_empty())
+			.then_some(max_annotation);
+		ci.publish_summary(path, outcome, &mut io::stdout())
+			.map_err(context("Summary could not be published"))?;
+	}
+	for emit in &cli.emit {
+		emit.write(&annotations_buf, &run)
+			.map_err(context("Report could not be written"))?;
+	}
+	Ok(exit_code(
+		// Cargo is killed by a signal if it has no exit code
+		cli.cargo_failure.get().map(|status| {
+			status
+				.code()
+				.and_then(|code| u8::try_from(code).ok())
+				.unwrap_or(EXIT_FAILURE)
+		}),
+		cli.propagate_exit_code || cli.config.propagate_exit_code,
+		cli.no_fail || cli.config.no_fail,
+		max_annotation >= annotation_threshold,
+	))
+}
+
+/// Returns the exit code of the run
+///
+/// `cargo_failure` holds the exit code of the first Cargo command which failed, if any,
+/// and `findings` tells if annotations reached the threshold.
+const fn exit_code(
+	cargo_failure: Option<u8>,
+	propagate_exit_code: bool,
+	no_fail: bool,
+	findings: bool,
+) -> u8 {
+	match cargo_failure {
+		_ if no_fail => EXIT_CLEAN,
+		Some(code) if propagate_exit_code => code,
+		_ if findings => EXIT_FINDINGS,
+		_ => EXIT_CLEAN,
+	}
+}
 
-	if max_annotation >= annotation_threshold {
-		ExitCode::FAILURE
+/// Returns the minimum kind of the annotations which cause the job to fail
+///
+/// The warnings do not if they are allowed, if the ratchet did not increase,
+/// or if there are no more than `max_warnings` of them.
+fn annotation_threshold(
+	allow_warnings: bool,
+	ratchet_increased: Option<bool>,
+	max_warnings: Option<usize>,
+	warnings: usize,
+) -> AnnotationKind {
+	if allow_warnings
+		|| ratchet_increased == Some(false)
+		|| max_warnings.is_some_and(|max_warnings| warnings <= max_warnings)
+	{
+		AnnotationKind::Error
 	} else {
-		ExitCode::SUCCESS
+		AnnotationKind::Warning
 	}
 }
 
+/// Returns `true` if Cargo failed because of the network (see [`cargo::is_spurious_failure`])
+///
+/// A command killed because it exceeded its time limit did not fail spuriously.
+fn spurious_failure(cargo: &mut CommandOutput, status: ExitStatus) -> bool {
+	!status.success()
+		&& !cargo.timed_out()
+		&& cargo
+			.stderr
+			.lines(false)
+			.any(|line| line.is_ok_and(|line| cargo::is_spurious_failure(&line)))
+}
+
+/// Returns an error annotation with `title` and `message`, for a failure which is not located in a file
+const fn error_annotation(title: &'static str, message: String) -> Annotation<'static> {
+	Annotation {
+		kind: AnnotationKind::Error,
+		file: Cow::Borrowed(""),
+		line: 0,
+		end_line: None,
+		col: None,
+		end_column: None,
+		code: None,
+		suggestions: Vec::new(),
+		title: Some(Cow::Borrowed(title)),
+		message: Cow::Owned(message),
+	}
+}
+
+/// Checks the environment of the job, as requested by the `doctor` subcommand
+///
+/// Returns [`EXIT_FINDINGS`] if the environment is not fit to annotate.
+///
+/// # Errors
+/// This function returns an error if the results could not be written.
+fn diagnose(cli: &Cli) -> error::Result<u8> {
+	let mut permissions = Vec::new();
+	if cli.checks {
+		permissions.push("`checks: write`");
+	}
+	if cli.pr_review || cli.pr_comment {
+		permissions.push("`pull-requests: write`");
+	}
+	if cli.commit_status {
+		permissions.push("`statuses: write`");
+	}
+	let rustfmt = cli.rustfmt();
+	let doctor = Doctor {
+		cargo: &cli.cargo,
+		rustfmt: &rustfmt,
+		ci: cli.ci.unwrap_or_else(CiPlatform::detect),
+		summary: !cli.no_summary,
+		outputs: cli
+			.summary_path
+			.iter()
+			.chain(cli.emit.iter().map(|emit| &emit.path))
+			.map(PathBuf::as_path)
+			.collect(),
+		permissions,
+		ca_bundle: cli.ca_bundle.as_deref(),
+	};
+	let failed = doctor
+		.diagnose(&mut io::stdout().lock())
+		.map_err(context("Diagnosis could not be written"))?;
+	Ok(if failed { EXIT_FINDINGS } else { EXIT_CLEAN })
+}
+
+/// Writes a workflow annotating the workspace, as requested by the `init` subcommand
+///
+/// # Errors
+/// This function returns an error if the workspace could not be inspected or if the workflow could not be written.
+fn init(cargo: &OsStr, args: &InitArgs) -> error::Result<u8> {
+	let metadata =
+		Metadata::load(cargo, None, false).map_err(context("Cargo metadata invocation failed"))?;
+	let workflow = Workflow::inspect(&metadata);
+	match &args.output {
+		Some(path) => {
+			if let Some(parent) = path.parent() {
+				fs::create_dir_all(parent).map_err(context("Workflow could not be written"))?;
+			}
+			let mut file = if args.force {
+				File::create(path)
+			} else {
+				File::create_new(path)
+			}
+			.map_err(context("Workflow could not be written"))?;
+			workflow
+				.write(&mut file)
+				.map_err(context("Workflow could not be written"))?;
+			eprintln!("Wrote the workflow to {}", path.display());
+		}
+		None => workflow
+			.write(&mut io::stdout().lock())
+			.map_err(context("Workflow could not be written"))?,
+	}
+	Ok(EXIT_CLEAN)
+}
+
 /// Annotates GitHub Actions from the output of Cargo subcommands
 #[derive(Debug, Clone, Parser)]
 #[command(author, version, about, long_about = None)]
 #[command(override_usage = "cargo ghannotate check [OPTIONS] [ARGS]...\n       \
 	cargo ghannotate clippy [OPTIONS] [ARGS]...\n       \
 	cargo ghannotate build [OPTIONS] [ARGS]...\n       \
-	cargo ghannotate fmt [OPTIONS] [ARGS]...")]
+	cargo ghannotate msrv [OPTIONS] [ARGS]...\n       \
+	cargo ghannotate doc [OPTIONS] [ARGS]...\n       \
+	cargo ghannotate test [OPTIONS] [ARGS]...\n       \
+	cargo ghannotate nextest [OPTIONS] [ARGS]...\n       \
+	cargo ghannotate coverage [OPTIONS] [ARGS]...\n       \
+	cargo ghannotate bench [OPTIONS] [ARGS]...\n       \
+	cargo ghannotate miri [OPTIONS] [ARGS]...\n       \
+	cargo ghannotate fmt [OPTIONS] [ARGS]...\n       \
+	cargo ghannotate outdated [OPTIONS] [ARGS]...\n       \
+	cargo ghannotate audit [OPTIONS] [ARGS]...\n       \
+	cargo ghannotate deny [OPTIONS] [ARGS]...\n       \
+	cargo ghannotate udeps [OPTIONS] [ARGS]...\n       \
+	cargo ghannotate machete [OPTIONS] [ARGS]...\n       \
+	cargo ghannotate geiger [OPTIONS] [ARGS]...\n       \
+	cargo ghannotate semver-checks [OPTIONS] [ARGS]...\n       \
+	cargo ghannotate exec [OPTIONS] -- <COMMAND>...\n       \
+	cargo ghannotate replay [OPTIONS] <--format <FORMAT>|--parser <NAME>> [PATH]\n       \
+	cargo ghannotate ci [OPTIONS]\n       \
+	cargo ghannotate merge [OPTIONS] <REPORTS>...\n       \
+	cargo ghannotate doctor [OPTIONS]\n       \
+	cargo ghannotate init [OPTIONS] [--output <PATH>]\n       \
+	cargo ghannotate completions <SHELL>\n       \
+	cargo ghannotate manpage")]
 struct Cli {
-	/// Path to the `cargo` executable
-	#[arg(long, env = "CARGO", value_name = "PATH", value_hint = ValueHint::ExecutablePath)]
-	cargo: OsString,
+	/// Path to the `cargo` executable, taking precedence over `--toolchain`
+	/// [default: the `cargo` of the toolchain if one is selected, or else `$CARGO`]
+	#[arg(long = "cargo", value_name = "PATH", value_hint = ValueHint::ExecutablePath)]
+	cargo_path: Option<OsString>,
+	/// Run Cargo and the tools it invokes with the rustup toolchain NAME (e.g. `beta` or `nightly-2024-06-01`)
+	/// [`miri` and `udeps` default: `nightly`]
+	#[arg(long, value_name = "NAME")]
+	toolchain: Option<String>,
+	/// Run `cargo fmt` with the rustup toolchain NAME [default: the nightly toolchain pinned by `rust-toolchain.toml`, or `nightly`]
+	#[arg(long, env = "GHANNOTATE_FMT_TOOLCHAIN", value_name = "NAME")]
+	fmt_toolchain: Option<String>,
+	/// Run `cargo fmt` with the rustfmt executable at PATH instead of a rustup toolchain, taking precedence over `--fmt-toolchain`
+	#[arg(long = "rustfmt", value_name = "PATH", value_hint = ValueHint::ExecutablePath)]
+	rustfmt_path: Option<PathBuf>,
+	/// Run `cargo test` with `RUSTC_BOOTSTRAP=1` on non-nightly toolchains, to annotate the failed tests from the unstable JSON output of libtest
+	///
+	/// This puts the toolchain in nightly mode, which build scripts probing for nightly features may notice.
+	#[arg(long)]
+	rustc_bootstrap: bool,
 	/// If warnings were to be raised, they would not cause the job to fail
 	#[arg(long)]
 	allow_warnings: bool,
+	/// Warnings would only cause the job to fail if there are more than N of them
+	#[arg(long, value_name = "N")]
+	max_warnings: Option<usize>,
+	/// Also print the annotated diagnostics as rendered by the tools, so that they can be read in the logs
+	#[arg(long)]
+	tee: bool,
+	/// Only print a preview of the annotations and of the summary, without emitting them nor writing any file
+	#[arg(long)]
+	dry_run: bool,
+	/// Do not pass the output of the tools through, nor print progress messages, so that only the annotations are printed
+	#[arg(long, conflicts_with = "tee")]
+	quiet: bool,
+	/// Log what is done on stderr, e.g. why lines or annotations are skipped (`-vv` to also log every parsed line and emitted annotation)
+	///
+	/// The `RUST_LOG` environment variable takes precedence if it is set.
+	#[arg(short, long, action = ArgAction::Count)]
+	verbose: u8,
+	/// Rewrite the prefix FROM of the annotated paths into TO (e.g. `/build/src=.` for builds in containers)
+	#[arg(long, value_name = "FROM=TO")]
+	path_map: Vec<PathMap>,
+	/// Also write a report of the annotations in FORMAT to PATH (FORMAT may be `sarif`, `junit`, `checkstyle`, `gitlab`, `rdjson`, `json` or `html`)
+	#[arg(long, value_name = "FORMAT=PATH")]
+	emit: Vec<Emit>,
+	/// Also write a JSON report of the annotations and of the run to PATH (same as `--emit json=PATH`)
+	#[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+	report_json: Option<PathBuf>,
+	/// CI platform running the job (detected from the environment otherwise)
+	#[arg(long, value_enum, value_name = "PLATFORM")]
+	ci: Option<CiPlatform>,
+	/// Attach the annotations to a check run instead of emitting workflow commands, which GitHub truncates (requires `GITHUB_TOKEN`)
+	#[arg(long)]
+	checks: bool,
+	/// Also comment the annotated lines of the pull request in a review, with the suggested fixes (requires `GITHUB_TOKEN`)
+	#[arg(long)]
+	pr_review: bool,
+	/// Also post the summary in a comment of the pull request, updated by subsequent runs (requires `GITHUB_TOKEN`)
+	#[arg(long)]
+	pr_comment: bool,
+	/// Also set a commit status named after the subcommand with the counts of annotations (requires `GITHUB_TOKEN`)
+	#[arg(long)]
+	commit_status: bool,
+	/// Also emit notices at the locations of the notes and help messages attached to the diagnostics of rustc
+	#[arg(long)]
+	child_notices: bool,
+	/// Also annotate the wrap-up diagnostics of rustc (e.g. `aborting due to 2 previous errors`)
+	#[arg(long)]
+	meta_diagnostics: bool,
+	/// Also write the diagnostics as rendered by rustc in collapsible sections of the summary
+	#[arg(long)]
+	summary_snippets: bool,
+	/// Also draw charts of the counts of diagnostics by level and of the most frequent lints in the summary
+	#[arg(long)]
+	summary_charts: bool,
+	/// Order of the emitted annotations and of the rows of the summary [default: as output by the tools]
+	#[arg(long, value_enum, value_name = "ORDER")]
+	sort: Option<SortOrder>,
+	/// Also write the summary to the file at PATH, may be repeated
+	#[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+	summary_path: Vec<PathBuf>,
+	/// Do not write the summary where the CI platform expects it (e.g. `GITHUB_STEP_SUMMARY`)
+	#[arg(long)]
+	no_summary: bool,
+	/// Run Cargo again up to N times if it failed because of the network, waiting longer each time
+	#[arg(long, value_name = "N")]
+	retries: Option<u32>,
+	/// Kill Cargo if it is still running after DURATION (e.g. `90s`, `30m` or `1h`)
+	#[arg(long, value_name = "DURATION")]
+	timeout: Option<Timeout>,
+	/// Stop Cargo as soon as an error is annotated
+	#[arg(long)]
+	fail_fast: bool,
+	/// Never fail because of the annotations, e.g. if another step makes the job fail
+	#[arg(long)]
+	no_fail: bool,
+	/// Fail with the exit code of Cargo if it failed, even if no annotation made the job fail
+	#[arg(long)]
+	propagate_exit_code: bool,
+	/// Render the summary with the Jinja template at PATH
+	#[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+	summary_template: Option<PathBuf>,
+	/// Do not emit the annotations of the issues recorded in the baseline file at PATH, nor fail the job because of them
+	#[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+	baseline: Option<PathBuf>,
+	/// Write the issues to the baseline file at PATH
+	#[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+	write_baseline: Option<PathBuf>,
+	/// Warnings would only cause the job to fail if their counts by lint or by file increased since the last run,
+	/// as recorded in the file at PATH
+	#[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+	ratchet: Option<PathBuf>,
+	/// Only emit the annotations of the lines changed by the pull request, and list the others in the summary
+	#[arg(long)]
+	changed_only: bool,
+	/// Do not emit the annotations of the paths matching GLOB (e.g. `tests/fixtures/**`), may be repeated
+	#[arg(long, value_name = "GLOB")]
+	exclude: Vec<String>,
+	/// Do not emit the annotations of the lint CODE (e.g. `clippy::todo`), may be repeated
+	#[arg(long, value_name = "CODE")]
+	ignore_lint: Vec<String>,
+	/// Emit the annotations of the lint CODE (e.g. `clippy::todo`) as errors, failing the job, may be repeated
+	#[arg(long, value_name = "CODE")]
+	deny_lint: Vec<String>,
+	/// Do not emit the annotations less severe than LEVEL, which are still counted in the summary [default: notice]
+	#[arg(long, value_enum, value_name = "LEVEL")]
+	annotation_level: Option<AnnotationKind>,
+	/// Style of the messages of the annotations of rustc's diagnostics [default: rendered]
+	#[arg(long, value_enum, value_name = "STYLE")]
+	message_style: Option<MessageStyle>,
+	/// Path to a bundle of PEM certificates to trust in addition to the usual ones when calling the GitHub API
+	#[arg(long, env = "SSL_CERT_FILE", value_name = "PATH", value_hint = ValueHint::FilePath)]
+	ca_bundle: Option<PathBuf>,
+	/// Path to the configuration file (`ghannotate.toml` or the metadata of `Cargo.toml` is read otherwise)
+	#[arg(long = "config", value_name = "PATH", value_hint = ValueHint::FilePath)]
+	config_path: Option<PathBuf>,
+	/// Configuration loaded from [`Cli.config_path`](Cli#structfield.config_path)
+	#[arg(skip)]
+	config: Config,
+	/// Path to the `cargo` executable, resolved from [`Cli.cargo_path`](Cli#structfield.cargo_path)
+	/// or [`Cli.toolchain`](Cli#structfield.toolchain)
+	#[arg(skip)]
+	cargo: OsString,
+	/// rustfmt resolved from [`Cli.rustfmt_path`](Cli#structfield.rustfmt_path)
+	/// or [`Cli.fmt_toolchain`](Cli#structfield.fmt_toolchain) for the subcommands running `cargo fmt`
+	#[arg(skip)]
+	rustfmt: Option<Rustfmt>,
+	/// Whether [`Cli.cargo`](Cli#structfield.cargo) belongs to a nightly toolchain, checked for the subcommands running `cargo test`
+	#[arg(skip)]
+	nightly: bool,
+	/// Options of the parsers and summary writers, resolved from the flags and the configuration
+	#[arg(skip)]
+	options: Options,
+	/// Kind and message of the error of `cargo metadata`, if the workspace could not be inspected by [`run`]
+	#[arg(skip)]
+	metadata_error: Option<(io::ErrorKind, String)>,
+	/// Lines changed by the pull request, loaded if [`Cli.changed_only`](Cli#structfield.changed_only) is set
+	#[arg(skip)]
+	changed_lines: Option<ChangedLines>,
+	/// Exit status of the first Cargo command which failed
+	#[arg(skip)]
+	cargo_failure: Cell<Option<ExitStatus>>,
 	/// Cargo subcommand
 	#[command(subcommand)]
 	command: CliCommand,
 }
 impl Cli {
-	/// Invokes Cargo with the passed arguments and returns its output
+	/// Resolves the rustfmt selected with [`Cli.rustfmt_path`](Cli#structfield.rustfmt_path)
+	/// or [`Cli.fmt_toolchain`](Cli#structfield.fmt_toolchain)
+	#[inline]
+	fn resolve_rustfmt(&self) -> Rustfmt {
+		Rustfmt::select(
+			self.rustfmt_path.clone(),
+			self.fmt_toolchain.clone(),
+			&self.cargo,
+		)
+	}
+
+	/// Returns the rustfmt resolved by [`run`], or resolves it if it was not
+	#[inline]
+	fn rustfmt(&self) -> Cow<'_, Rustfmt> {
+		self.rustfmt
+			.as_ref()
+			.map_or_else(|| Cow::Owned(self.resolve_rustfmt()), Cow::Borrowed)
+	}
+
+	/// Returns the toolchain with which the commands requiring a nightly toolchain are run
+	///
+	/// It is the one selected with [`Cli.toolchain`](Cli#structfield.toolchain), or else `nightly`.
+	#[inline]
+	fn nightly_toolchain(&self) -> &str {
+		self.toolchain.as_deref().unwrap_or("nightly")
+	}
+
+	/// Returns whether `cargo test` is run with the JSON output of libtest, from which the failed tests are annotated
+	///
+	/// This output is unstable, so it requires a nightly toolchain or [`Cli.rustc_bootstrap`](Cli#structfield.rustc_bootstrap).
+	#[inline]
+	const fn libtest_json(&self) -> bool {
+		self.nightly || self.rustc_bootstrap
+	}
+
+	/// Returns the metadata of the workspace, inspected once by [`run`] for the subcommands which [need it](CliCommand::needs_metadata)
+	///
+	/// # Errors
+	/// This function returns an error if `cargo metadata` failed.
+	fn metadata(&self) -> error::Result<&Metadata> {
+		self.options.workspace.as_ref().ok_or_else(|| {
+			let (kind, message) = self.metadata_error.clone().unwrap_or_else(|| {
+				(
+					io::ErrorKind::Other,
+					"the workspace was not inspected".to_owned(),
+				)
+			});
+			Error::new(
+				"Cargo metadata invocation failed",
+				io::Error::new(kind, message),
+			)
+		})
+	}
+
+	/// Returns the custom parser `name` of the configuration
+	///
+	/// # Errors
+	/// This function returns an error if the configuration has no such parser.
+	fn parser(&self, name: &str) -> error::Result<&CustomParser> {
+		self.config.parsers.get(name).ok_or_else(|| {
+			Error::new(
+				"Custom parser could not be found",
+				io::Error::new(
+					io::ErrorKind::InvalidInput,
+					format!("the configuration has no `parsers.{name}` table"),
+				),
+			)
+		})
+	}
+
+	/// Runs `command` and annotates from its output
+	///
+	/// The annotations already in `annotations_buf` are not emitted again.
+	/// The annotations less severe than [`Cli.annotation_level`](Cli#structfield.annotation_level)
+	/// are collected in `unemitted_buf` instead,
+	/// and those outside of the [`Cli.changed_lines`](Cli#structfield.changed_lines) or known by the `baseline` in `preexisting_buf`.
+	/// The exit status of the first command which failed is stored in [`Cli.cargo_failure`](Cli#structfield.cargo_failure).
+	/// If the command failed because of the network and nothing was annotated,
+	/// it is run again up to [`Cli.retries`](Cli#structfield.retries) times, and only the last attempt is summarized.
+	/// Returns the most severe kind of annotation raised.
+	///
+	/// # Errors
+	/// This function returns an error if the command could not be run, or its output could not be handled.
+	fn annotate(
+		&self,
+		command: &CliCommand,
+		annotations_buf: &mut BTreeSet<Annotation<'static>>,
+		unemitted_buf: &mut BTreeSet<Annotation<'static>>,
+		preexisting_buf: &mut BTreeSet<Annotation<'static>>,
+		mut baseline: Option<&mut Baseline>,
+		summary: &mut dyn IoWrite,
+	) -> error::Result<AnnotationKind> {
+		let retries = self.retries.or(self.config.retries).unwrap_or_default();
+		let mut attempt = 0;
+		loop {
+			let annotated = annotations_buf.len() + unemitted_buf.len() + preexisting_buf.len();
+			let cargo_failure = self.cargo_failure.get();
+			let mut attempt_summary = Vec::new();
+			let (max_annotation, spurious) = self.annotate_once(
+				command,
+				annotations_buf,
+				unemitted_buf,
+				preexisting_buf,
+				baseline.as_deref_mut(),
+				&mut attempt_summary,
+			)?;
+			if spurious
+				&& attempt < retries
+				&& annotated == annotations_buf.len() + unemitted_buf.len() + preexisting_buf.len()
+			{
+				attempt += 1;
+				let delay = RETRY_DELAY.saturating_mul(2_u32.saturating_pow(attempt - 1));
+				if !self.quiet {
+					eprintln!(
+					"Cargo failed because of the network, retrying in {}s ({attempt}/{retries})",
+					delay.as_secs()
+				);
+				}
+				self.cargo_failure.set(cargo_failure);
+				thread::sleep(delay);
+				continue;
+			}
+			summary.write_all(&attempt_summary)?;
+			return Ok(max_annotation);
+		}
+	}
+
+	/// Runs `command` once and annotates from its output
+	///
+	/// See [`Cli::annotate`] for the arguments.
+	/// Returns the most severe kind of annotation raised,
+	/// and whether the command failed because of the network (see [`cargo::is_spurious_failure`]).
+	///
+	/// # Errors
+	/// This function returns an error if the command could not be run, or its output could not be handled.
+	fn annotate_once(
+		&self,
+		command: &CliCommand,
+		annotations_buf: &mut BTreeSet<Annotation<'static>>,
+		unemitted_buf: &mut BTreeSet<Annotation<'static>>,
+		preexisting_buf: &mut BTreeSet<Annotation<'static>>,
+		mut baseline: Option<&mut Baseline>,
+		summary: &mut dyn IoWrite,
+	) -> error::Result<(AnnotationKind, bool)> {
+		let mut max_annotation = AnnotationKind::Notice;
+		let annotation_level = self.annotation_level.unwrap_or(AnnotationKind::Notice);
+
+		let mut command = command.clone();
+		let name = command.name();
+		if let Some(args) = command.args_mut() {
+			args.merge(self.config.args(name));
+		}
+		let command = &command;
+
+		let _span = info_span!("cargo", command = name).entered();
+		let mut stdout = io::stdout().lock();
+		// Without rustfmt, `cargo fmt` would fail without any diagnostic to annotate
+		if matches!(command, CliCommand::Fmt(_)) {
+			let rustfmt = self.rustfmt();
+			if !rustfmt.is_available() {
+				let message = format!(
+					"`cargo fmt` could not be run, as {} could not be invoked. \
+					Install rustfmt (e.g. with `rustup component add rustfmt`), \
+					or give the path to its executable with `--rustfmt`.",
+					rustfmt.source
+				);
+				writeln!(summary, "> :x: **RUSTFMT NOT FOUND:** {message}\n")?;
+				let annotation = error_annotation("rustfmt not found", message);
+				if !(self.checks || self.dry_run) {
+					self.ci
+						.unwrap_or_else(CiPlatform::detect)
+						.write_annotation(&annotation, &mut stdout)?;
+				}
+				annotations_buf.insert(annotation);
+				return Ok((AnnotationKind::Error, false));
+			}
+		}
+		// On GitHub Actions, the output passed through is collapsed in a group, out of which the annotations are emitted
+		let grouped = (self.tee || self.config.tee)
+			&& !self.quiet
+			&& self.ci.unwrap_or_else(CiPlatform::detect) == CiPlatform::Github
+			&& !matches!(command, CliCommand::Ci(_));
+		if grouped {
+			writeln!(stdout, "::group::cargo {name}")?;
+		}
+		let invocation_time = SystemTime::now();
+		let mut cargo = self.invoke_cargo(command)?;
+		let timeout = self.timeout.or(self.config.timeout);
+		if let Some(timeout) = timeout {
+			cargo.kill_after(timeout);
+		}
+		let mut summary_content = String::new();
+		let sort_order = self.options.sort_order;
+		let fail_fast = self.fail_fast || self.config.fail_fast;
+		// With a sort order or a group, the annotations are emitted once all of them are known
+		let mut deferred_buf = Vec::new();
+		/// Common code for all messages
+		macro_rules! handle_message {
+			($parse:expr, $summary_writer:ty) => {
+				handle_message!(cargo.stdout.by_ref().lines(), $parse, $summary_writer)
+			};
+			($lines:expr, $parse:expr, $summary_writer:ty) => {{
+				let mut summary_writer = <$summary_writer>::default();
+				let mut aborted = false;
+				for line in $lines {
+					let line = line.map_err(context("Output could not be read"))?;
+					let line = line.as_str();
+					let message = $parse(line);
+					match &message {
+						Ok(_) => trace!(line, "line parsed"),
+						Err(err) => debug!(line, ?err, "line skipped, as it could not be parsed"),
+					}
+					if let Ok(message) = message {
+						let summaries = message.summarize(&self.options);
+						let tee = self.tee || self.config.tee;
+						let mut rendered = if tee {
+							message.rendered().map(str::to_owned)
+						} else {
+							None
+						};
+						let is_rendered = rendered.is_some();
+						let annotations = message.into_annotations(&self.options);
+						let mut write_summaries = annotations.is_empty();
+						for annotation in annotations
+							.into_iter()
+							.filter_map(|annotation| self.config.apply(annotation))
+							.filter_map(|annotation| self.options.lint_levels.apply(annotation))
+						{
+							// The pre-existing issues do not cause the job to fail
+							if annotation.is_located()
+								&& self.changed_lines.as_ref().is_some_and(|changed_lines| {
+									!changed_lines.contains(
+										&annotation.file,
+										&(annotation.line
+											..=annotation.end_line.unwrap_or(annotation.line)),
+									)
+								}) {
+								debug!(file = %annotation.file, line = annotation.line, "annotation skipped, as it is outside of the changed lines");
+								preexisting_buf.insert(annotation.to_owned());
+								continue;
+							}
+							if !(annotations_buf.contains(&annotation)
+								|| preexisting_buf.contains(&annotation))
+								&& baseline
+									.as_deref_mut()
+									.is_some_and(|baseline| baseline.remove(&annotation))
+							{
+								debug!(file = %annotation.file, line = annotation.line, "annotation skipped, as it is known by the baseline");
+								preexisting_buf.insert(annotation.to_owned());
+								continue;
+							}
+							if annotation.kind < annotation_level {
+								debug!(file = %annotation.file, line = annotation.line, kind = annotation.kind.name(), "annotation not emitted, as it is below the annotation level");
+								if unemitted_buf.insert(annotation.to_owned()) {
+									max_annotation = max_annotation.max(annotation.kind);
+									write_summaries = true;
+								}
+								continue;
+							}
+							if annotations_buf.insert(annotation.to_owned()) {
+								trace!(file = %annotation.file, line = annotation.line, kind = annotation.kind.name(), "annotation emitted");
+								if tee {
+									// Messages rendered by the tools are printed once, with their colors
+									match rendered.take() {
+										Some(rendered) => {
+											writeln!(stdout, "{}", rendered.trim_end())?;
+										}
+										None if !is_rendered => {
+											writeln!(stdout, "{}", annotation.message.trim_end())?;
+										}
+										None => {}
+									}
+								}
+								if sort_order.is_some() || grouped {
+									deferred_buf.push(annotation.to_owned());
+								} else if !(self.checks || self.dry_run) {
+									self.ci
+										.unwrap_or_else(CiPlatform::detect)
+										.write_annotation(&annotation, &mut stdout)?;
+								}
+								max_annotation = max_annotation.max(annotation.kind);
+								write_summaries = true;
+							} else {
+								debug!(file = %annotation.file, line = annotation.line, "annotation deduplicated, as it has already been emitted");
+							}
+						}
+						if write_summaries {
+							for summary in summaries {
+								summary_writer
+									.write_summary(summary, &mut summary_content)
+									.map_err(io::Error::other)
+									.map_err(context("Summary could not be written"))?;
+							}
+						}
+						if fail_fast && max_annotation == AnnotationKind::Error {
+							process::kill(cargo.child.as_ref())
+								.map_err(context("Cargo could not be stopped"))?;
+							aborted = true;
+							break;
+						}
+					}
+				}
+				summary_writer.write_preamble(summary, &self.options)?;
+				summary.write_all(summary_content.as_bytes())?;
+				summary_writer.write_postamble(summary, &self.options)?;
+				if aborted {
+					writeln!(
+						summary,
+						"> :stop_sign: **FAIL-FAST:** `cargo {name}` was stopped at the first error\n"
+					)?;
+				}
+			}};
+		}
+		/// Reports a failure which is not located in a file, in an error annotation and in the summary
+		macro_rules! report_failure {
+			($heading:literal, $title:literal, $message:expr) => {{
+				let message = $message;
+				writeln!(summary, "> {}\n", format!($heading, message))?;
+				let annotation = error_annotation($title, message);
+				if sort_order.is_some() || grouped {
+					deferred_buf.push(annotation.clone());
+				} else if !(self.checks || self.dry_run) {
+					self.ci
+						.unwrap_or_else(CiPlatform::detect)
+						.write_annotation(&annotation, &mut stdout)?;
+				}
+				annotations_buf.insert(annotation);
+				max_annotation = AnnotationKind::Error;
+			}};
+		}
+		match *command {
+			CliCommand::Check(ref args)
+			| CliCommand::Clippy(ref args)
+			| CliCommand::Build(ref args)
+				if args.hack.is_enabled() =>
+			{
+				let mut command_list = String::new();
+				cargo
+					.stdout
+					.read_to_string(&mut command_list)
+					.map_err(context("Cargo invocation failed"))?;
+				let lines = run_feature_sets(&self.cargo, &command_list, self.quiet, |args| {
+					if !self.quiet {
+						eprintln!("Running `cargo {}`", args.join(" "));
+					}
+				})
+				.map_err(context("Cargo invocation failed"))?;
+				handle_message!(
+					lines.into_iter().map(io::Result::Ok),
+					serde_json::from_str::<FeatureSetMessage>,
+					FeatureSetSummaryWriter
+				);
+			}
+			CliCommand::Check(ref args)
+			| CliCommand::Clippy(ref args)
+			| CliCommand::Build(ref args)
+				if args.preview_fixes =>
+			{
+				handle_message!(
+					|line| serde_json::from_str::<CargoMessage>(line).map(FixPreview),
+					FixSummaryWriter
+				);
+			}
+			CliCommand::Check(_) | CliCommand::Clippy(_) | CliCommand::Build(_) => {
+				handle_message!(
+					serde_json::from_str::<CargoMessage>,
+					DiagnosticSummaryWriter
+				);
+			}
+			CliCommand::Msrv(ref args) => {
+				let metadata = self.metadata()?;
+				handle_message!(
+					cargo
+						.stdout
+						.by_ref()
+						.lines()
+						.chain(std::iter::once(Ok(String::new()))),
+					|line| serde_json::from_str::<CargoMessage>(line)
+						.map(MsrvMessage::Cargo)
+						.or_else(|_| {
+							if !line.is_empty() {
+								return Err(());
+							}
+							// All the messages have been read, so the build is over
+							let status = process::wait(cargo.child.as_ref()).map_err(|_| ())?;
+							(!status.success())
+								.then(|| metadata.rust_version())
+								.flatten()
+								.map(|rust_version| {
+									MsrvMessage::Failure(MsrvFailure {
+										toolchain: args.toolchain.as_ref().map_or_else(
+											|| rust_version.to_owned(),
+											|toolchain| toolchain.to_string_lossy().into_owned(),
+										),
+										locations: metadata.locate_rust_version(rust_version),
+									})
+								})
+								.ok_or(())
+						}),
+					DiagnosticSummaryWriter
+				);
+			}
+			CliCommand::Exec(ExecArgs {
+				parser: Some(ref name),
+				..
+			})
+			| CliCommand::Replay(InputArgs {
+				parser: Some(ref name),
+				..
+			}) => {
+				let parser = self.parser(name)?;
+				handle_message!(|line| parser.parse(line).ok_or(()), DiagnosticSummaryWriter);
+			}
+			CliCommand::Exec(_)
+			| CliCommand::Replay(InputArgs {
+				format: MessageFormat::Rustc,
+				..
+			}) => {
+				handle_message!(
+					|line| serde_json::from_str::<CargoMessage>(line).or_else(|_| {
+						serde_json::from_str::<Diagnostic>(line).map(|message| {
+							CargoMessage::CompilerMessage {
+								package_id: None,
+								target: None,
+								message,
+							}
+						})
+					}),
+					DiagnosticSummaryWriter
+				);
+			}
+			CliCommand::Doc(_)
+			| CliCommand::Replay(InputArgs {
+				format: MessageFormat::Rustdoc,
+				..
+			}) => {
+				handle_message!(serde_json::from_str::<CargoMessage>, RustdocSummaryWriter);
+			}
+			CliCommand::Test(_) if !self.libtest_json() => {
+				// The human-readable output of libtest is not parsed, so it is kept in the logs
+				handle_message!(
+					cargo.stdout.by_ref().lines().inspect(|line| {
+						if let (Ok(line), false) = (line, self.quiet) {
+							if !line.starts_with('{') {
+								eprintln!("{line}");
+							}
+						}
+					}),
+					serde_json::from_str::<CargoMessage>,
+					DiagnosticSummaryWriter
+				);
+			}
+			CliCommand::Test(_)
+			| CliCommand::Nextest(_)
+			| CliCommand::Replay(InputArgs {
+				format: MessageFormat::Test,
+				..
+			}) => {
+				handle_message!(serde_json::from_str::<TestMessage>, TestSummaryWriter);
+			}
+			CliCommand::Bench(ref args) => {
+				// Criterion's results are read from the target directory, so its output is kept in the logs
+				// (it is still read to its end in quiet mode, so that Cargo is not blocked by a full pipe)
+				if self.quiet {
+					io::copy(&mut cargo.stdout, &mut io::sink())?;
+				} else {
+					io::copy(&mut cargo.stdout, &mut io::stderr())?;
+				}
+				let metadata = self.metadata()?;
+				handle_message!(
+					criterion_benchmarks(metadata, invocation_time)
+						.into_iter()
+						.map(|dir| io::Result::Ok(dir.to_string_lossy().into_owned())),
+					|line| BenchmarkResult::load(Path::new(line))
+						.map(|result| result.locate(metadata, args.threshold)),
+					BenchmarkSummaryWriter
+				);
+			}
+			CliCommand::Fmt(_)
+			| CliCommand::Replay(InputArgs {
+				format: MessageFormat::Rustfmt | MessageFormat::RustfmtCheck,
+				..
+			}) => {
+				let diff = match *command {
+					CliCommand::Replay(ref args) => args.format == MessageFormat::RustfmtCheck,
+					_ => !self.rustfmt().is_nightly(),
+				};
+				if diff {
+					let mut parser = FormatDiffParser::default();
+					handle_message!(
+						cargo
+							.stdout
+							.by_ref()
+							.lines()
+							.chain(std::iter::once(Ok(String::new()))),
+						|line| parser
+							.feed(line)
+							.map(|mismatches| vec![mismatches])
+							.ok_or(()),
+						FormatMismatchSummaryWriter
+					);
+				} else {
+					handle_message!(
+						serde_json::from_str::<Vec<FormatMismatches>>,
+						FormatMismatchSummaryWriter
+					);
+				}
+			}
+			CliCommand::Coverage(ref args) => {
+				let changed_lines = args
+					.base
+					.as_deref()
+					.map(|base| ChangedLines::load(base, self.quiet))
+					.transpose()
+					.map_err(context("Git diff invocation failed"))?;
+				let mut percent = None;
+				handle_message!(
+					|line| serde_json::from_str::<CoverageReport>(line).map(|report| {
+						percent = report.percent();
+						report.locate(changed_lines.as_ref(), args.fail_under)
+					}),
+					CoverageSummaryWriter
+				);
+				if let (Some(percent), Some(fail_under)) = (percent, args.fail_under) {
+					if percent < fail_under {
+						report_failure!(
+							":x: **COVERAGE:** {}",
+							"Coverage below threshold",
+							format!(
+								"Line coverage ({percent:.2}%) is below the threshold of {fail_under}%"
+							)
+						);
+					}
+				}
+			}
+			CliCommand::Miri(_) => {
+				let mut parser = MiriReportParser::default();
+				handle_message!(
+					cargo
+						.stdout
+						.by_ref()
+						.lines()
+						.chain(cargo.stderr.lines(!self.quiet))
+						.chain(std::iter::once(Ok(String::new()))),
+					|line| serde_json::from_str::<CargoMessage>(line)
+						.map(MiriMessage::Cargo)
+						.or_else(|_| parser.feed(line).map(MiriMessage::Report).ok_or(())),
+					DiagnosticSummaryWriter
+				);
+			}
+			CliCommand::SemverChecks(_) => {
+				// The human-readable report is parsed, so it is kept in the logs
+				let mut parser = SemverReportParser::default();
+				handle_message!(
+					cargo
+						.stdout
+						.by_ref()
+						.lines()
+						.inspect(|line| {
+							if let (Ok(line), false) = (line, self.quiet) {
+								eprintln!("{line}");
+							}
+						})
+						.chain(std::iter::once(Ok(String::new())))
+						.chain(cargo.stderr.lines(!self.quiet)),
+					|line| parser.feed(line).ok_or(()),
+					SemverSummaryWriter
+				);
+			}
+			CliCommand::Audit(_)
+			| CliCommand::Replay(InputArgs {
+				format: MessageFormat::Audit,
+				..
+			}) => {
+				let metadata = self.metadata()?;
+				handle_message!(
+					|line| serde_json::from_str::<AuditReport>(line)
+						.map(|report| report.locate(metadata)),
+					AdvisorySummaryWriter
+				);
+			}
+			CliCommand::Deny(_)
+			| CliCommand::Replay(InputArgs {
+				format: MessageFormat::Deny,
+				..
+			}) => {
+				let metadata = self.metadata()?;
+				handle_message!(
+					cargo.stderr.lines(false),
+					|line| serde_json::from_str::<DenyMessage>(line)
+						.map(|message| message.locate(metadata)),
+					DenySummaryWriter
+				);
+			}
+			CliCommand::Udeps(_)
+			| CliCommand::Replay(InputArgs {
+				format: MessageFormat::Udeps,
+				..
+			}) => {
+				let metadata = self.metadata()?;
+				handle_message!(
+					|line| serde_json::from_str::<UdepsReport>(line)
+						.map(|report| report.locate(metadata)),
+					UnusedDependencySummaryWriter
+				);
+			}
+			CliCommand::Machete(_) => {
+				// The human-readable report is parsed, so it is kept in the logs
+				let metadata = self.metadata()?;
+				let mut parser = MacheteReportParser::default();
+				handle_message!(
+					cargo.stdout.by_ref().lines().inspect(|line| {
+						if let (Ok(line), false) = (line, self.quiet) {
+							eprintln!("{line}");
+						}
+					}),
+					|line| parser
+						.feed(line)
+						.map(|dependency| dependency.locate(metadata))
+						.ok_or(()),
+					UnusedDependencySummaryWriter
+				);
+			}
+			CliCommand::Geiger(ref args) => {
+				let metadata = self.metadata()?;
+				let baseline = args
+					.baseline
+					.as_ref()
+					.map(|path| {
+						serde_json::from_slice::<GeigerReport>(
+							&fs::read(path)
+								.map_err(context("Baseline report could not be read"))?,
+						)
+						.map_err(context("Baseline report is invalid"))
+					})
+					.transpose()?;
+				handle_message!(
+					|line| serde_json::from_str::<GeigerReport>(line)
+						.map(|report| report.locate(metadata, baseline.as_ref())),
+					GeigerSummaryWriter
+				);
+			}
+			CliCommand::Merge(_) => {
+				handle_message!(
+					serde_json::from_str::<ReportedAnnotation>,
+					DiagnosticSummaryWriter
+				);
+			}
+			CliCommand::Doctor
+			| CliCommand::Init(_)
+			| CliCommand::Completions(_)
+			| CliCommand::Manpage => {
+				// These subcommands are handled before annotating
+			}
+			CliCommand::Ci(ref args) => {
+				for step in &args.steps {
+					writeln!(summary, "## {step}\n")?;
+					max_annotation = max_annotation.max(self.annotate(
+						&CliCommand::from(*step),
+						annotations_buf,
+						unemitted_buf,
+						preexisting_buf,
+						baseline.as_deref_mut(),
+						summary,
+					)?);
+					writeln!(summary)?;
+					if fail_fast && max_annotation == AnnotationKind::Error {
+						break;
+					}
+				}
+			}
+			CliCommand::Outdated(ref args) => {
+				let metadata = self.metadata()?;
+				handle_message!(
+					|line| serde_json::from_str::<OutdatedReport>(line)
+						.map(|report| report.locate(metadata, args.major_level, args.major_only)),
+					OutdatedSummaryWriter
+				);
+			}
+		}
+		let status = cargo.wait().map_err(context("Cargo invocation failed"))?;
+		if !status.success() {
+			self.cargo_failure
+				.set(Some(self.cargo_failure.get().unwrap_or(status)));
+		}
+		if let Some(timeout) = timeout.filter(|_| cargo.timed_out()) {
+			report_failure!(
+				":hourglass: **TIMEOUT:** {}",
+				"Timeout",
+				format!("`cargo {name}` was killed after exceeding the time limit of {timeout}")
+			);
+		} else if matches!(command, CliCommand::Test(_))
+			&& !self.libtest_json()
+			&& !status.success()
+			&& max_annotation < AnnotationKind::Error
+		{
+			report_failure!(
+				":x: **TESTS FAILED:** {}",
+				"Tests failed",
+				"`cargo test` failed, but the failed tests could not be annotated, \
+				as the JSON output of libtest requires a nightly toolchain. \
+				See the logs for the failures, or pass `--rustc-bootstrap` to annotate them with this toolchain."
+					.to_owned()
+			);
+		}
+		if let Some(sort_order) = sort_order {
+			github::sort_annotations(&mut deferred_buf, sort_order);
+		}
+		if grouped {
+			writeln!(stdout, "::endgroup::")?;
+		}
+		if !(self.checks || self.dry_run) {
+			let ci = self.ci.unwrap_or_else(CiPlatform::detect);
+			for annotation in &deferred_buf {
+				ci.write_annotation(annotation, &mut stdout)?;
+			}
+		}
+		Ok((max_annotation, spurious_failure(&mut cargo, status)))
+	}
+
+	/// Returns the `--message-format` argument of Cargo
+	///
+	/// The diagnostics are rendered with colors if they are printed in logs supporting them.
+	fn message_format(&self) -> &'static str {
+		let color = (self.tee || self.config.tee)
+			&& env::var_os("NO_COLOR").is_none()
+			&& (self.ci != Some(CiPlatform::Terminal) || io::stdout().is_terminal());
+		if color {
+			"--message-format=json-diagnostic-rendered-ansi"
+		} else {
+			"--message-format=json"
+		}
+	}
+
+	/// Invokes Cargo with the arguments of `subcommand` and returns its output
+	///
+	/// The output is read while Cargo is running.
 	#[inline]
-	fn invoke_cargo(&self) -> io::Result<Output> {
+	fn invoke_cargo(&self, subcommand: &CliCommand) -> error::Result<CommandOutput> {
 		#[allow(clippy::enum_glob_use)]
 		use CliCommand::*;
 
-		match self.command {
-			Check(_) => {
+		let mut command = match *subcommand {
+			Check(ref args) => args.hack.command(
+				&self.cargo,
+				"check",
+				self.message_format(),
+				args.args.as_ref(),
+			),
+			Clippy(ref args) => args.hack.command(
+				&self.cargo,
+				"clippy",
+				self.message_format(),
+				args.args.as_ref(),
+			),
+			Build(ref args) => args.hack.command(
+				&self.cargo,
+				"build",
+				self.message_format(),
+				args.args.as_ref(),
+			),
+			Msrv(ref args) => {
+				let rust_version = match &args.toolchain {
+					Some(toolchain) => toolchain.clone(),
+					None => self
+						.metadata()?
+						.rust_version()
+						.map(OsString::from)
+						.ok_or_else(|| {
+							Error::new(
+								"Minimum supported Rust version could not be determined",
+								io::Error::other("no `rust-version` is declared in the workspace"),
+							)
+						})?,
+				};
+				let status = Command::new("rustup")
+					.args(["toolchain", "install", "--profile", "minimal"])
+					.arg(&rust_version)
+					.stdin(Stdio::null())
+					.stdout(Stdio::null())
+					.stderr(process::passthrough(self.quiet))
+					.status()
+					.map_err(context("Rustup invocation failed"))?;
+				if !status.success() {
+					return Err(Error::new(
+						"Rust toolchain installation failed",
+						io::Error::other(format!(
+							"`rustup toolchain install {}` exited with {status}",
+							rust_version.to_string_lossy()
+						)),
+					));
+				}
+				let mut command = Command::new("rustup");
+				command
+					.arg("run")
+					.arg(&rust_version)
+					.args(["cargo", "check", self.message_format()])
+					.args(subcommand.as_ref().as_ref());
+				command
+			}
+			Doc(ref args) => {
 				let mut command = Command::new(&self.cargo);
+				command.args(["doc", self.message_format()]);
+				if !args.with_deps {
+					command.arg("--no-deps");
+				}
+				command.args(subcommand.as_ref().as_ref());
 				command
-					.args(["check", "--message-format=json"])
-					.args(self.command.as_ref().as_ref());
+			}
+			Test(_) => {
+				let args = subcommand.as_ref().as_ref();
+				let mut command = Command::new(&self.cargo);
+				command.args(["test", self.message_format()]).args(args);
+				if self.libtest_json() {
+					if !args.iter().any(|arg| arg == "--") {
+						command.arg("--");
+					}
+					command.args(["-Z", "unstable-options", "--format=json"]);
+					// The JSON output of libtest is unstable, so other toolchains only output it in nightly mode
+					if !self.nightly {
+						command.env("RUSTC_BOOTSTRAP", "1");
+					}
+				}
 				command
 			}
-			Clippy(_) => {
+			Nextest(_) => {
 				let mut command = Command::new(&self.cargo);
 				command
-					.args(["clippy", "--message-format=json"])
-					.args(self.command.as_ref().as_ref());
+					.args([
+						"nextest",
+						"run",
+						"--cargo-message-format=json",
+						"--message-format=libtest-json",
+					])
+					.args(subcommand.as_ref().as_ref())
+					// The JSON output of nextest is experimental
+					.env("NEXTEST_EXPERIMENTAL_LIBTEST_JSON", "1");
 				command
 			}
-			Build(_) => {
+			Coverage(_) => {
 				let mut command = Command::new(&self.cargo);
 				command
-					.args(["build", "--message-format=json"])
-					.args(self.command.as_ref().as_ref());
+					.args(["llvm-cov", "--json"])
+					.args(subcommand.as_ref().as_ref());
+				command
+			}
+			Bench(ref args) => {
+				let mut command = Command::new(&self.cargo);
+				command.arg("bench").args(args.args.as_ref());
+				if let Some(baseline) = &args.baseline {
+					if !args.args.as_ref().iter().any(|arg| arg == "--") {
+						command.arg("--");
+					}
+					command.arg("--baseline").arg(baseline);
+				}
+				command
+			}
+			Miri(_) => {
+				let mut command = Command::new("rustup");
+				command
+					.args([
+						"run",
+						self.nightly_toolchain(),
+						"cargo",
+						"miri",
+						"test",
+						self.message_format(),
+					])
+					.args(subcommand.as_ref().as_ref());
 				command
 			}
 			Fmt(_) => {
+				let mut command = self.rustfmt().cargo_fmt(&self.cargo);
+				command.args(subcommand.as_ref().as_ref());
+				command
+			}
+			Audit(_) => {
+				let mut command = Command::new(&self.cargo);
+				command
+					.args(["audit", "--json"])
+					.args(subcommand.as_ref().as_ref());
+				command
+			}
+			Deny(_) => {
+				let mut command = Command::new(&self.cargo);
+				command
+					.args(["deny", "--format=json", "check"])
+					.args(subcommand.as_ref().as_ref());
+				command
+			}
+			Udeps(_) => {
 				let mut command = Command::new("rustup");
 				command
-					.args(["run", "nightly", "cargo", "fmt", "--message-format=json"])
-					.args(self.command.as_ref().as_ref());
+					.args([
+						"run",
+						self.nightly_toolchain(),
+						"cargo",
+						"udeps",
+						"--output=json",
+					])
+					.args(subcommand.as_ref().as_ref());
 				command
 			}
-		}
-		.stdin(Stdio::null())
-		.stderr(Stdio::inherit())
-		.output()
+			SemverChecks(_) => {
+				let mut command = Command::new(&self.cargo);
+				command
+					.args(["semver-checks", "check-release"])
+					.args(subcommand.as_ref().as_ref());
+				command
+			}
+			Machete(_) => {
+				let mut command = Command::new(&self.cargo);
+				command.arg("machete").args(subcommand.as_ref().as_ref());
+				command
+			}
+			Geiger(_) => {
+				let mut command = Command::new(&self.cargo);
+				command
+					.args(["geiger", "--output-format", "Json"])
+					.args(subcommand.as_ref().as_ref());
+				command
+			}
+			Replay(ref args) => {
+				let input = match &args.path {
+					Some(path) if path != Path::new("-") => {
+						fs::read(path).map_err(context("Replayed output could not be read"))?
+					}
+					_ => {
+						let mut input = Vec::new();
+						io::stdin()
+							.read_to_end(&mut input)
+							.map_err(context("Replayed output could not be read"))?;
+						input
+					}
+				};
+				return Ok(CommandOutput::from_input(input));
+			}
+			Ci(_) => {
+				// The steps are invoked separately
+				return Ok(CommandOutput::empty());
+			}
+			Doctor | Init(_) | Completions(_) | Manpage => {
+				// These subcommands are handled before annotating
+				return Ok(CommandOutput::empty());
+			}
+			Merge(ref args) => {
+				// The annotations of the reports are read as JSON lines
+				let mut input = Vec::new();
+				for path in &args.reports {
+					for annotation in
+						report::read_json(path).map_err(context("Report could not be read"))?
+					{
+						serde_json::to_writer(&mut input, &annotation)
+							.map_err(context("Report could not be read"))?;
+						input.push(b'\n');
+					}
+				}
+				return Ok(CommandOutput::from_input(input));
+			}
+			Exec(_) => {
+				let (program, args) =
+					subcommand.as_ref().as_ref().split_first().ok_or_else(|| {
+						io::Error::new(io::ErrorKind::InvalidInput, "no command to execute")
+					})?;
+				let mut command = Command::new(program);
+				command.args(args);
+				command
+			}
+			Outdated(_) => {
+				let mut command = Command::new(&self.cargo);
+				command
+					.args(["outdated", "--format", "json"])
+					.args(subcommand.as_ref().as_ref());
+				command
+			}
+		};
+		// Miri, cargo-deny and cargo-semver-checks report errors on stderr
+		let reports_on_stderr = matches!(*subcommand, Miri(_) | Deny(_) | SemverChecks(_));
+		// The other commands are retried if their standard error tells of a network failure
+		let retried = self.retries.or(self.config.retries).unwrap_or_default() > 0;
+		debug!(?command, "invoking Cargo");
+		CommandOutput::spawn(
+			&mut command,
+			// cargo-deny reports on stderr only
+			!matches!(*subcommand, Deny(_)),
+			reports_on_stderr || retried,
+			!reports_on_stderr && !self.quiet,
+			self.quiet,
+		)
+		.map_err(context("Cargo invocation failed"))
 	}
 }
 
@@ -221,27 +1884,518 @@ impl Cli {
 #[derive(Debug, Clone, Subcommand)]
 enum CliCommand {
 	/// Runs `cargo check` and annotates from its output
-	Check(CliCommandArgs),
+	Check(CompileArgs),
 	/// Runs `cargo clippy` and annotates from its output
-	Clippy(CliCommandArgs),
+	Clippy(CompileArgs),
 	/// Runs `cargo build` and annotates from its output
-	Build(CliCommandArgs),
+	Build(CompileArgs),
+	/// Runs `cargo check` with the minimum supported Rust version and annotates from its output
+	///
+	/// WARNING: This requires `rustup` to be installed!
+	Msrv(MsrvArgs),
+	/// Runs `cargo doc` and annotates from its output
+	Doc(DocArgs),
+	/// Runs `cargo test` and annotates from its output
+	///
+	/// The failed tests are annotated from the unstable JSON output of libtest,
+	/// which requires a nightly toolchain or `--rustc-bootstrap` (running Cargo with `RUSTC_BOOTSTRAP=1`).
+	/// Otherwise, only the compiler diagnostics are annotated, and the failure of the tests in a single error.
+	Test(CliCommandArgs),
+	/// Runs `cargo nextest run` and annotates from its output
+	///
+	/// WARNING: This requires `cargo-nextest` to be installed!
+	Nextest(CliCommandArgs),
+	/// Runs `cargo llvm-cov` and annotates from its output
+	///
+	/// WARNING: This requires `cargo-llvm-cov` to be installed!
+	Coverage(CoverageArgs),
+	/// Runs `cargo bench` and annotates from the results of criterion
+	Bench(BenchArgs),
+	/// Runs `cargo miri test` and annotates from its output
+	///
+	/// WARNING: This requires a nightly toolchain with the `miri` component!
+	Miri(CliCommandArgs),
 	/// Runs `cargo fmt` and annotates from its output
 	///
-	/// WARNING: This requires a nightly toolchain!
+	/// With a nightly rustfmt, the JSON output of `cargo fmt --message-format=json` is read;
+	/// with any other rustfmt (e.g. `--fmt-toolchain stable`), the diff output by `cargo fmt --check`.
 	Fmt(CliCommandArgs),
+	/// Runs `cargo outdated` and annotates from its output
+	///
+	/// WARNING: This requires `cargo-outdated` to be installed!
+	Outdated(OutdatedArgs),
+	/// Runs `cargo audit` and annotates from its output
+	///
+	/// WARNING: This requires `cargo-audit` to be installed!
+	Audit(CliCommandArgs),
+	/// Runs `cargo deny check` and annotates from its output
+	///
+	/// WARNING: This requires `cargo-deny` to be installed!
+	Deny(CliCommandArgs),
+	/// Runs `cargo udeps` and annotates from its output
+	///
+	/// WARNING: This requires a nightly toolchain and `cargo-udeps` to be installed!
+	Udeps(CliCommandArgs),
+	/// Runs `cargo machete` and annotates from its output
+	///
+	/// WARNING: This requires `cargo-machete` to be installed!
+	Machete(CliCommandArgs),
+	/// Runs `cargo geiger` and annotates from its output
+	///
+	/// WARNING: This requires `cargo-geiger` to be installed!
+	Geiger(GeigerArgs),
+	/// Runs `cargo semver-checks check-release` and annotates from its output
+	///
+	/// WARNING: This requires `cargo-semver-checks` to be installed!
+	SemverChecks(CliCommandArgs),
+	/// Runs any command outputting Cargo or rustc JSON messages (or parsed by a custom parser) and annotates from its output
+	Exec(ExecArgs),
+	/// Reads messages from a file or the standard input and annotates from them
+	#[command(visible_alias = "stdin")]
+	Replay(InputArgs),
+	/// Runs several subcommands one after the other and annotates from their outputs
+	Ci(CiArgs),
+	/// Reads the JSON reports of several jobs (e.g. of a matrix) and annotates from them, without duplicates
+	Merge(MergeArgs),
+	/// Checks that the environment is fit to annotate (e.g. Cargo, the summary file and the token), and tells how to fix it
+	Doctor,
+	/// Writes a GitHub Actions workflow annotating the workspace, with steps chosen after its contents
+	Init(InitArgs),
+	/// Writes the shell completions of this program
+	Completions(CompletionsArgs),
+	/// Writes the manual page of this program, in the roff format
+	Manpage,
 }
 impl AsRef<CliCommandArgs> for CliCommand {
 	#[inline]
 	fn as_ref(&self) -> &CliCommandArgs {
 		match self {
-			Self::Check(args) | Self::Clippy(args) | Self::Build(args) | Self::Fmt(args) => args,
+			Self::Test(args)
+			| Self::Nextest(args)
+			| Self::Miri(args)
+			| Self::Audit(args)
+			| Self::Deny(args)
+			| Self::Udeps(args)
+			| Self::Machete(args)
+			| Self::SemverChecks(args)
+			| Self::Fmt(args) => args,
+			Self::Check(args) | Self::Clippy(args) | Self::Build(args) => &args.args,
+			Self::Msrv(args) => &args.args,
+			Self::Doc(args) => &args.args,
+			Self::Coverage(args) => &args.args,
+			Self::Bench(args) => &args.args,
+			Self::Geiger(args) => &args.args,
+			Self::Outdated(args) => &args.args,
+			Self::Exec(args) => &args.args,
+			Self::Replay(_)
+			| Self::Ci(_)
+			| Self::Merge(_)
+			| Self::Doctor
+			| Self::Init(_)
+			| Self::Completions(_)
+			| Self::Manpage => &NO_ARGS,
 		}
 	}
 }
 
-/// Arguments to be passed down to Cargo
+impl CliCommand {
+	/// Returns the name of the subcommand
+	const fn name(&self) -> &'static str {
+		match self {
+			Self::Check(_) => "check",
+			Self::Clippy(_) => "clippy",
+			Self::Build(_) => "build",
+			Self::Msrv(_) => "msrv",
+			Self::Doc(_) => "doc",
+			Self::Test(_) => "test",
+			Self::Nextest(_) => "nextest",
+			Self::Coverage(_) => "coverage",
+			Self::Bench(_) => "bench",
+			Self::Miri(_) => "miri",
+			Self::Fmt(_) => "fmt",
+			Self::Outdated(_) => "outdated",
+			Self::Audit(_) => "audit",
+			Self::Deny(_) => "deny",
+			Self::Udeps(_) => "udeps",
+			Self::Machete(_) => "machete",
+			Self::Geiger(_) => "geiger",
+			Self::SemverChecks(_) => "semver-checks",
+			Self::Exec(_) => "exec",
+			Self::Replay(_) => "replay",
+			Self::Ci(_) => "ci",
+			Self::Merge(_) => "merge",
+			Self::Doctor => "doctor",
+			Self::Init(_) => "init",
+			Self::Completions(_) => "completions",
+			Self::Manpage => "manpage",
+		}
+	}
+
+	/// Returns `true` if the subcommand reads the metadata of the workspace
+	///
+	/// It tells the members of the workspace from their dependencies, and locates their manifests.
+	fn needs_metadata(&self) -> bool {
+		match self {
+			Self::Check(_)
+			| Self::Clippy(_)
+			| Self::Build(_)
+			| Self::Msrv(_)
+			| Self::Doc(_)
+			| Self::Test(_)
+			| Self::Nextest(_)
+			| Self::Bench(_)
+			| Self::Miri(_)
+			| Self::Outdated(_)
+			| Self::Audit(_)
+			| Self::Deny(_)
+			| Self::Udeps(_)
+			| Self::Machete(_)
+			| Self::Geiger(_)
+			| Self::Exec(ExecArgs { parser: None, .. }) => true,
+			Self::Replay(args) => {
+				args.parser.is_none()
+					&& !matches!(
+						args.format,
+						MessageFormat::Rustfmt | MessageFormat::RustfmtCheck
+					)
+			}
+			Self::Ci(args) => args
+				.steps
+				.iter()
+				.any(|step| Self::from(*step).needs_metadata()),
+			Self::Fmt(_)
+			| Self::Coverage(_)
+			| Self::SemverChecks(_)
+			| Self::Exec(_)
+			| Self::Merge(_)
+			| Self::Doctor
+			| Self::Init(_)
+			| Self::Completions(_)
+			| Self::Manpage => false,
+		}
+	}
+
+	/// Returns the arguments to be passed down to Cargo, if the subcommand invokes Cargo
+	const fn args_mut(&mut self) -> Option<&mut CliCommandArgs> {
+		match self {
+			Self::Test(args)
+			| Self::Nextest(args)
+			| Self::Miri(args)
+			| Self::Audit(args)
+			| Self::Deny(args)
+			| Self::Udeps(args)
+			| Self::Machete(args)
+			| Self::SemverChecks(args)
+			| Self::Fmt(args) => Some(args),
+			Self::Check(args) | Self::Clippy(args) | Self::Build(args) => Some(&mut args.args),
+			Self::Msrv(args) => Some(&mut args.args),
+			Self::Doc(args) => Some(&mut args.args),
+			Self::Coverage(args) => Some(&mut args.args),
+			Self::Bench(args) => Some(&mut args.args),
+			Self::Geiger(args) => Some(&mut args.args),
+			Self::Outdated(args) => Some(&mut args.args),
+			Self::Exec(_)
+			| Self::Replay(_)
+			| Self::Ci(_)
+			| Self::Merge(_)
+			| Self::Doctor
+			| Self::Init(_)
+			| Self::Completions(_)
+			| Self::Manpage => None,
+		}
+	}
+}
+
+/// Arguments of the `check`, `clippy` and `build` subcommands
+#[derive(Debug, Default, Clone, Args)]
+struct CompileArgs {
+	/// Feature combinations to run Cargo with
+	#[command(flatten)]
+	hack: HackArgs,
+	/// Preview the fixes which `cargo fix` would apply in the annotations and the summary
+	#[arg(long, conflicts_with_all = ["each_feature", "feature_powerset"])]
+	preview_fixes: bool,
+	/// Arguments to be passed down to Cargo
+	#[command(flatten)]
+	args: CliCommandArgs,
+}
+
+/// Feature combinations to run Cargo with
+#[derive(Debug, Default, Clone, Copy, Args)]
+struct HackArgs {
+	/// Run Cargo for each feature (requires `cargo-hack`)
+	#[arg(long, conflicts_with = "feature_powerset")]
+	each_feature: bool,
+	/// Run Cargo for each combination of features (requires `cargo-hack`)
+	#[arg(long)]
+	feature_powerset: bool,
+}
+impl HackArgs {
+	/// Returns `true` if Cargo is to be run with several feature combinations
+	#[inline]
+	const fn is_enabled(&self) -> bool {
+		self.each_feature || self.feature_powerset
+	}
+
+	/// Builds the command running the Cargo `subcommand`
+	///
+	/// If feature combinations are enabled, the command only lists the commands to run with `cargo hack`.
+	fn command(
+		&self,
+		cargo: &OsStr,
+		subcommand: &str,
+		message_format: &str,
+		args: &[OsString],
+	) -> Command {
+		let mut command = Command::new(cargo);
+		if self.each_feature {
+			command.args(["hack", subcommand, "--each-feature", "--print-command-list"]);
+		} else if self.feature_powerset {
+			command.args([
+				"hack",
+				subcommand,
+				"--feature-powerset",
+				"--print-command-list",
+			]);
+		} else {
+			command.args([subcommand, message_format]);
+		}
+		command.args(args);
+		command
+	}
+}
+
+/// Arguments of the `msrv` subcommand
+#[derive(Debug, Clone, Args)]
+struct MsrvArgs {
+	/// Toolchain to build with (the highest `rust-version` of the workspace members is used otherwise)
+	#[arg(long, value_name = "TOOLCHAIN")]
+	toolchain: Option<OsString>,
+	/// Arguments to be passed down to Cargo
+	#[command(flatten)]
+	args: CliCommandArgs,
+}
+
+/// Arguments of the `exec` subcommand
+#[derive(Debug, Default, Clone, Args)]
+struct ExecArgs {
+	/// Parse the output with the parser NAME of the configuration instead of as JSON messages
+	#[arg(long, value_name = "NAME")]
+	parser: Option<String>,
+	/// Command to execute
+	#[command(flatten)]
+	args: CliCommandArgs,
+}
+
+/// Arguments of the `doc` subcommand
+#[derive(Debug, Default, Clone, Args)]
+struct DocArgs {
+	/// Also document the dependencies (`--no-deps` is passed to Cargo otherwise)
+	#[arg(long)]
+	with_deps: bool,
+	/// Arguments to be passed down to Cargo
+	#[command(flatten)]
+	args: CliCommandArgs,
+}
+
+/// Arguments of the `coverage` subcommand
 #[derive(Debug, Clone, Args)]
+struct CoverageArgs {
+	/// Only annotate the uncovered code changed since the merge base with the revision REV (e.g. `origin/main`)
+	#[arg(long, value_name = "REV")]
+	base: Option<OsString>,
+	/// Fail if the percentage of covered lines is below PCT
+	#[arg(long, value_name = "PCT")]
+	fail_under: Option<f64>,
+	/// Arguments to be passed down to Cargo
+	#[command(flatten)]
+	args: CliCommandArgs,
+}
+
+/// Arguments of the `bench` subcommand
+#[derive(Debug, Clone, Args)]
+struct BenchArgs {
+	/// Name of the saved criterion baseline to compare against (the previous run is used otherwise)
+	#[arg(long, value_name = "NAME")]
+	baseline: Option<OsString>,
+	/// Percentage of slowdown beyond which a benchmark is annotated as a regression
+	#[arg(long, value_name = "PCT", default_value_t = 5.0)]
+	threshold: f64,
+	/// Arguments to be passed down to Cargo
+	#[command(flatten)]
+	args: CliCommandArgs,
+}
+
+/// Arguments of the `geiger` subcommand
+#[derive(Debug, Clone, Args)]
+struct GeigerArgs {
+	/// Path to a previous JSON report of cargo-geiger, to annotate the workspace members whose unsafe usage has increased
+	#[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+	baseline: Option<PathBuf>,
+	/// Arguments to be passed down to Cargo
+	#[command(flatten)]
+	args: CliCommandArgs,
+}
+
+/// Arguments of the `outdated` subcommand
+#[derive(Debug, Clone, Args)]
+struct OutdatedArgs {
+	/// Kind of annotation for dependencies lagging behind a new major version
+	#[arg(long, value_enum, default_value_t = AnnotationKind::Warning)]
+	major_level: AnnotationKind,
+	/// Only annotate dependencies lagging behind a new major version (the others are only listed in the summary)
+	#[arg(long)]
+	major_only: bool,
+	/// If outdated dependencies were to be annotated as warnings, they would cause the job to fail
+	#[arg(long)]
+	deny_outdated: bool,
+	/// Arguments to be passed down to Cargo
+	#[command(flatten)]
+	args: CliCommandArgs,
+}
+
+/// Arguments of the `replay` subcommand
+#[derive(Debug, Clone, Args)]
+struct InputArgs {
+	/// Format of the messages
+	#[arg(
+		long,
+		value_enum,
+		required_unless_present = "parser",
+		default_value_t = MessageFormat::Rustc,
+		hide_default_value = true
+	)]
+	format: MessageFormat,
+	/// Parse the messages with the parser NAME of the configuration instead
+	#[arg(long, value_name = "NAME", conflicts_with = "format")]
+	parser: Option<String>,
+	/// Path to a file containing the messages (the standard input is read if absent or `-`)
+	#[arg(value_hint = ValueHint::FilePath)]
+	path: Option<PathBuf>,
+}
+
+/// Format of the messages read by [`InputArgs`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum MessageFormat {
+	/// Cargo or rustc JSON diagnostics (`--message-format=json`)
+	Rustc,
+	/// Cargo JSON diagnostics of `cargo doc`
+	Rustdoc,
+	/// Cargo and libtest JSON messages
+	Test,
+	/// JSON output of `cargo fmt`
+	Rustfmt,
+	/// Diff output by `cargo fmt --check`
+	RustfmtCheck,
+	/// JSON output of `cargo audit`
+	Audit,
+	/// JSON output of `cargo deny`
+	Deny,
+	/// JSON output of `cargo udeps`
+	Udeps,
+}
+
+/// Arguments of the `ci` subcommand
+#[derive(Debug, Clone, Args)]
+struct CiArgs {
+	/// Subcommands to run, in order
+	#[arg(
+		long,
+		value_enum,
+		value_delimiter = ',',
+		value_name = "STEPS",
+		default_values_t = [CiStep::Fmt, CiStep::Clippy, CiStep::Test],
+	)]
+	steps: Vec<CiStep>,
+}
+
+/// Arguments of the `init` subcommand
+#[derive(Debug, Clone, Args)]
+struct InitArgs {
+	/// Write the workflow to PATH (e.g. `.github/workflows/rust.yml`) instead of the standard output
+	#[arg(long, short, value_name = "PATH", value_hint = ValueHint::FilePath)]
+	output: Option<PathBuf>,
+	/// Overwrite the file at PATH if it exists
+	#[arg(long, requires = "output")]
+	force: bool,
+}
+
+/// Arguments of the `completions` subcommand
+#[derive(Debug, Clone, Args)]
+struct CompletionsArgs {
+	/// Shell to write the completions for
+	#[arg(value_enum)]
+	shell: Shell,
+}
+
+/// Arguments of the `merge` subcommand
+#[derive(Debug, Clone, Args)]
+struct MergeArgs {
+	/// Paths to the JSON reports (written with `--report-json`)
+	#[arg(required = true, value_hint = ValueHint::FilePath)]
+	reports: Vec<PathBuf>,
+}
+
+/// Subcommand run by [`CiArgs`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CiStep {
+	/// `cargo fmt` (its JSON output with a nightly rustfmt, or the diff of `--check` otherwise)
+	Fmt,
+	/// `cargo check`
+	Check,
+	/// `cargo clippy`
+	Clippy,
+	/// `cargo build`
+	Build,
+	/// `cargo doc`
+	Doc,
+	/// `cargo test`
+	Test,
+	/// `cargo nextest run` (requires `cargo-nextest`)
+	Nextest,
+	/// `cargo audit` (requires `cargo-audit`)
+	Audit,
+	/// `cargo deny check` (requires `cargo-deny`)
+	Deny,
+	/// `cargo machete` (requires `cargo-machete`)
+	Machete,
+}
+impl Display for CiStep {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Fmt => write!(f, "`cargo fmt`"),
+			Self::Check => write!(f, "`cargo check`"),
+			Self::Clippy => write!(f, "`cargo clippy`"),
+			Self::Build => write!(f, "`cargo build`"),
+			Self::Doc => write!(f, "`cargo doc`"),
+			Self::Test => write!(f, "`cargo test`"),
+			Self::Nextest => write!(f, "`cargo nextest run`"),
+			Self::Audit => write!(f, "`cargo audit`"),
+			Self::Deny => write!(f, "`cargo deny check`"),
+			Self::Machete => write!(f, "`cargo machete`"),
+		}
+	}
+}
+impl From<CiStep> for CliCommand {
+	#[inline]
+	fn from(step: CiStep) -> Self {
+		match step {
+			CiStep::Fmt => Self::Fmt(CliCommandArgs::default()),
+			CiStep::Check => Self::Check(CompileArgs::default()),
+			CiStep::Clippy => Self::Clippy(CompileArgs::default()),
+			CiStep::Build => Self::Build(CompileArgs::default()),
+			CiStep::Doc => Self::Doc(DocArgs::default()),
+			CiStep::Test => Self::Test(CliCommandArgs::default()),
+			CiStep::Nextest => Self::Nextest(CliCommandArgs::default()),
+			CiStep::Audit => Self::Audit(CliCommandArgs::default()),
+			CiStep::Deny => Self::Deny(CliCommandArgs::default()),
+			CiStep::Machete => Self::Machete(CliCommandArgs::default()),
+		}
+	}
+}
+
+/// Arguments to be passed down to Cargo
+#[derive(Debug, Default, Clone, Args)]
 #[repr(transparent)]
 struct CliCommandArgs {
 	/// Arguments to be passed down to Cargo
@@ -252,6 +2406,59 @@ struct CliCommandArgs {
 	)]
 	args: Vec<OsString>,
 }
+impl CliCommandArgs {
+	/// Inserts the `defaults` arguments before these arguments
+	///
+	/// The arguments following `--` in `defaults` are inserted before those following `--` in these arguments.
+	fn merge(&mut self, defaults: &[String]) {
+		/// Splits `args` at the first `--`
+		fn split<T: PartialEq<str>>(args: &[T]) -> (&[T], Option<&[T]>) {
+			match args.iter().position(|arg| arg == "--") {
+				Some(index) => (&args[..index], Some(&args[index + 1..])),
+				None => (args, None),
+			}
+		}
+
+		if defaults.is_empty() {
+			return;
+		}
+		let (default_args, default_trailing) = split(defaults);
+		let (args, trailing) = split(&self.args);
+		let mut merged: Vec<OsString> = default_args
+			.iter()
+			.map(OsString::from)
+			.chain(args.iter().cloned())
+			.collect();
+		if default_trailing.is_some() || trailing.is_some() {
+			merged.push(OsString::from("--"));
+			merged.extend(
+				default_trailing
+					.unwrap_or_default()
+					.iter()
+					.map(OsString::from)
+					.chain(trailing.unwrap_or_default().iter().cloned()),
+			);
+		}
+		self.args = merged;
+	}
+
+	/// Returns the path given to the `--manifest-path` option of Cargo, if any
+	fn manifest_path(&self) -> Option<&Path> {
+		let mut args = self.args.iter().take_while(|arg| *arg != "--");
+		while let Some(arg) = args.next() {
+			if arg == "--manifest-path" {
+				return args.next().map(Path::new);
+			}
+			if let Some(path) = arg
+				.to_str()
+				.and_then(|arg| arg.strip_prefix("--manifest-path="))
+			{
+				return Some(Path::new(path));
+			}
+		}
+		None
+	}
+}
 impl AsRef<[OsString]> for CliCommandArgs {
 	#[inline]
 	fn as_ref(&self) -> &[OsString] {
@@ -259,6 +2466,9 @@ impl AsRef<[OsString]> for CliCommandArgs {
 	}
 }
 
+/// [`CliCommandArgs`] of the subcommands which do not invoke Cargo
+static NO_ARGS: CliCommandArgs = CliCommandArgs { args: Vec::new() };
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -268,4 +2478,86 @@ mod tests {
 	fn cli() {
 		Cli::command().debug_assert();
 	}
+
+	#[test]
+	fn propagate_exit_code() {
+		assert_eq!(exit_code(Some(101), false, false, false), EXIT_CLEAN);
+		assert_eq!(exit_code(Some(101), false, false, true), EXIT_FINDINGS);
+		// The exit code of Cargo takes precedence over the annotations
+		assert_eq!(exit_code(Some(101), true, false, true), 101);
+		assert_eq!(exit_code(None, true, false, true), EXIT_FINDINGS);
+		assert_eq!(exit_code(None, true, false, false), EXIT_CLEAN);
+	}
+
+	#[test]
+	fn no_fail() {
+		assert_eq!(exit_code(None, false, true, true), EXIT_CLEAN);
+		assert_eq!(exit_code(Some(101), false, true, true), EXIT_CLEAN);
+		// `--no-fail` takes precedence over `--propagate-exit-code`
+		assert_eq!(exit_code(Some(101), true, true, true), EXIT_CLEAN);
+		assert_eq!(exit_code(Some(101), true, true, false), EXIT_CLEAN);
+		assert_eq!(exit_code(None, false, false, true), EXIT_FINDINGS);
+	}
+
+	#[test]
+	#[cfg(unix)]
+	fn spurious_failure() {
+		use std::os::unix::process::ExitStatusExt;
+
+		let failure = ExitStatus::from_raw(101 << 8);
+		let mut cargo = CommandOutput::from_input(
+			b"{\"type\":\"log\",\"fields\":{\"message\":\"failed to fetch advisory database\"}}\n\
+			warning: spurious network error (2 tries remaining): [28] Timeout was reached\n"
+				.to_vec(),
+		);
+		// The standard error of `cargo deny` is read as its report before the failure is checked
+		assert_eq!(cargo.stderr.lines(false).count(), 2);
+		assert!(super::spurious_failure(&mut cargo, failure));
+		assert!(!super::spurious_failure(
+			&mut cargo,
+			ExitStatus::from_raw(0)
+		));
+	}
+
+	#[test]
+	fn ratchet() {
+		// The warnings only fail the job if a count increased
+		assert_eq!(
+			annotation_threshold(false, Some(true), None, 3),
+			AnnotationKind::Warning
+		);
+		assert_eq!(
+			annotation_threshold(false, Some(false), None, 3),
+			AnnotationKind::Error
+		);
+		assert_eq!(
+			annotation_threshold(false, Some(true), Some(3), 3),
+			AnnotationKind::Error
+		);
+	}
+
+	#[test]
+	fn max_warnings() {
+		assert_eq!(
+			annotation_threshold(false, None, None, 3),
+			AnnotationKind::Warning
+		);
+		// The budget is inclusive
+		assert_eq!(
+			annotation_threshold(false, None, Some(3), 3),
+			AnnotationKind::Error
+		);
+		assert_eq!(
+			annotation_threshold(false, None, Some(2), 3),
+			AnnotationKind::Warning
+		);
+		assert_eq!(
+			annotation_threshold(false, None, Some(0), 0),
+			AnnotationKind::Error
+		);
+		assert_eq!(
+			annotation_threshold(true, None, Some(2), 3),
+			AnnotationKind::Error
+		);
+	}
 }